@@ -0,0 +1,24 @@
+//! 基准测试共用的合成proc目录
+
+use room::test_util::{FakeProc, ProcFixture, ProcessGroup, RssDistribution, FIRST_PID};
+
+/// 构造包含 `count` 个进程的临时proc目录
+///
+/// 每个进程都由 `FakeProc` 生成，进程名、RSS和oom_score各不相同，
+/// 使选择器在所有进程上走完整的评分路径
+pub fn synthetic_proc(count: usize) -> ProcFixture {
+    // 可用内存只剩约5%，使压力检测器判定为压力状态
+    let mut proc = FakeProc::new().available_kb(300000);
+    for index in 0..count {
+        // RSS取值足够大，使每个进程都通过选择器的最小内存和1%释放量过滤，
+        // 从而覆盖完整的评分路径；按index变化以保证评分各不相同
+        let rss_kb = 64 * 1024 * 1024 + (index as u64 % 1024) * 256;
+        let process = ProcessGroup::new(1)
+            .name(&format!("worker-{}", index))
+            .rss(RssDistribution::Constant(rss_kb))
+            .oom_scores((index % 1000) as i32, 0);
+        proc = proc.process(FIRST_PID + index as i32, process);
+    }
+
+    proc.build().unwrap()
+}
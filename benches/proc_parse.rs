@@ -0,0 +1,86 @@
+//! /proc 解析的基准测试
+//!
+//! 运行方式：`cargo bench --features test-util --bench proc_parse`
+
+mod common;
+
+use std::path::Path;
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+use room::ffi::PidDir;
+use room::linux::proc::{get_all_processes_in, read_oom_scores_from, ProcessInfo};
+use room::linux::proc_stat::ProcessStat;
+use room::oom::pressure::MemoryStats;
+use room::test_util::FIRST_PID;
+use room::ProcessId;
+
+/// 仓库中采集的单进程夹具
+const STATUS_FIXTURE: &str = include_str!("../tests/fixtures/proc/status");
+const STAT_FIXTURE: &str = include_str!("../tests/fixtures/proc/stat");
+const MEMINFO_FIXTURE: &str = include_str!("../tests/fixtures/proc/meminfo");
+
+fn bench_fixture_parsing(c: &mut Criterion) {
+    let pid = ProcessId::new(2817).unwrap();
+
+    c.bench_function("parse_status", |b| {
+        b.iter(|| ProcessInfo::parse_status(pid, black_box(STATUS_FIXTURE)))
+    });
+    c.bench_function("parse_stat", |b| {
+        b.iter(|| ProcessStat::parse_stat(black_box(STAT_FIXTURE), pid).unwrap())
+    });
+    c.bench_function("parse_meminfo", |b| {
+        b.iter(|| MemoryStats::parse(black_box(MEMINFO_FIXTURE)))
+    });
+    c.bench_function("parse_meminfo_fast", |b| {
+        b.iter(|| MemoryStats::parse_fast(black_box(MEMINFO_FIXTURE.as_bytes())))
+    });
+}
+
+fn bench_process_enumeration(c: &mut Criterion) {
+    let mut group = c.benchmark_group("get_all_processes");
+
+    for count in [100, 1_000] {
+        let root = common::synthetic_proc(count);
+        group.bench_with_input(BenchmarkId::from_parameter(count), root.path(), |b, root: &Path| {
+            b.iter(|| get_all_processes_in(root).unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+/// 读取一个候选进程的status、stat和OOM分数
+///
+/// path：每个文件从proc根目录拼接路径并完整解析；
+/// dirfd：打开一次进程目录，子文件通过openat读取，缓冲区复用
+fn bench_candidate_read(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read_candidate");
+    let root = common::synthetic_proc(1);
+    let pid = ProcessId::new(FIRST_PID).unwrap();
+
+    group.bench_function("path", |b| {
+        b.iter(|| {
+            let info = ProcessInfo::from_pid_in(root.path(), pid).unwrap();
+            let stat = ProcessStat::from_pid_in(root.path(), pid).unwrap();
+            (info, stat)
+        })
+    });
+
+    let dir = PidDir::open(root.path()).unwrap();
+    let mut info = ProcessInfo::parse_status(pid, "");
+    let mut buf = String::with_capacity(4096);
+    group.bench_function("dirfd", |b| {
+        b.iter(|| {
+            let process = dir.open_process(pid).unwrap();
+            process.read_to_string("status", &mut buf).unwrap();
+            info.parse_status_into(pid, &buf);
+            let stat = ProcessStat::from_dir(&process, pid, &mut buf).unwrap();
+            let scores = read_oom_scores_from(&process).unwrap();
+            (stat.start_time, scores)
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_fixture_parsing, bench_process_enumeration, bench_candidate_read);
+criterion_main!(benches);
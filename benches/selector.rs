@@ -0,0 +1,42 @@
+//! 完整选择流程的基准测试
+//!
+//! 在合成proc目录上执行一次 `select_process`，包括压力检测、
+//! 进程枚举、解析和评分。运行方式：`cargo bench --features test-util --bench selector`
+
+mod common;
+
+use std::time::Duration;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use room::oom::pressure::PressureThresholds;
+use room::{OOMScorer, PressureDetector, ProcessSelector};
+
+fn bench_select_process(c: &mut Criterion) {
+    let mut group = c.benchmark_group("select_process");
+    group.sample_size(10);
+
+    for count in [1_000, 5_000, 10_000] {
+        let root = common::synthetic_proc(count);
+        let thresholds = PressureThresholds {
+            pressure_duration: Duration::ZERO,
+            ..PressureThresholds::default()
+        };
+        let mut selector = ProcessSelector::new(
+            None,
+            OOMScorer::new().with_proc_root(root.path()),
+            PressureDetector::new(Some(thresholds)).with_proc_root(root.path()),
+        )
+        .with_proc_root(root.path());
+
+        // 确认合成环境确实触发了完整的选择流程
+        assert!(selector.select_process().unwrap().is_some());
+
+        group.bench_function(BenchmarkId::from_parameter(count), |b| {
+            b.iter(|| selector.select_process().unwrap())
+        });
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_select_process);
+criterion_main!(benches);
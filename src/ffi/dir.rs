@@ -0,0 +1,378 @@
+use super::types::{ProcessId, SystemError, Result};
+use std::ffi::{CStr, CString};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// 单个proc文件读取的默认上限（字节）
+///
+/// status、stat、cgroup等文件正常只有几KB，上限只用于防止异常的文件占用大量内存
+pub const DEFAULT_MAX_PROC_FILE_BYTES: usize = 64 * 1024;
+
+static MAX_PROC_FILE_BYTES: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_PROC_FILE_BYTES);
+
+/// 设置单个proc文件读取的上限，对之后所有的读取生效
+pub fn set_max_proc_file_bytes(limit: usize) {
+    MAX_PROC_FILE_BYTES.store(limit, Ordering::Relaxed);
+}
+
+/// 当前单个proc文件读取的上限
+pub fn max_proc_file_bytes() -> usize {
+    MAX_PROC_FILE_BYTES.load(Ordering::Relaxed)
+}
+
+/// 把 `reader` 读入 `buf`（先清空），最多读取 `max_proc_file_bytes()` 字节
+///
+/// 超过上限时清空 `buf` 并返回 `ProcFileTooLarge`
+pub fn read_bounded(reader: impl Read, buf: &mut String) -> Result<()> {
+    let limit = max_proc_file_bytes();
+    buf.clear();
+    // 多读一个字节以区分恰好等于上限和超过上限
+    reader.take(limit as u64 + 1).read_to_string(buf).map_err(open_error)?;
+    if buf.len() > limit {
+        buf.clear();
+        return Err(SystemError::ProcFileTooLarge(limit));
+    }
+    Ok(())
+}
+
+/// openat 使用的相对路径的最大长度，例如 "4194304/oom_score_adj"
+const NAME_BUF_LEN: usize = 64;
+
+/// 遍历目录中以数字命名的条目（即 /proc 下的 PID 目录）
+///
+/// 与 `std::fs::read_dir` 不同，遍历时不会为每个条目分配内存，
+/// 并且可以通过 `rewind` 在多轮扫描之间复用同一个目录流
+#[derive(Debug)]
+pub struct PidDir {
+    dir: NonNull<libc::DIR>,
+}
+
+// 安全：目录流只通过 &mut self 访问，不会被并发使用
+unsafe impl Send for PidDir {}
+
+impl PidDir {
+    /// 打开目录
+    pub fn open(path: &Path) -> Result<Self> {
+        let c_path = CString::new(path.as_os_str().as_bytes()).map_err(|_| {
+            SystemError::SyscallError(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Path contains a NUL byte"
+            ))
+        })?;
+
+        let dir = unsafe { libc::opendir(c_path.as_ptr()) };
+        NonNull::new(dir)
+            .map(|dir| Self { dir })
+            .ok_or_else(|| SystemError::SyscallError(io::Error::last_os_error()))
+    }
+
+    /// 回到目录开头，下一轮遍历会看到最新的条目
+    pub fn rewind(&mut self) {
+        unsafe { libc::rewinddir(self.dir.as_ptr()) }
+    }
+
+    /// 打开进程的 /proc/<pid> 目录
+    pub fn open_process(&self, pid: ProcessId) -> Result<ProcessDir> {
+        let mut name = NameBuf::new();
+        name.push_pid(pid)?;
+        let fd = openat(self.fd(), &name, libc::O_RDONLY | libc::O_DIRECTORY)?;
+        Ok(ProcessDir { fd })
+    }
+
+    /// 读取进程目录下的文件 `<pid>/<name>`，不拼接完整路径，也不单独打开进程目录
+    pub fn read_process_file(&self, pid: ProcessId, name: &str, buf: &mut String) -> Result<()> {
+        let mut path = NameBuf::new();
+        path.push_pid(pid)?;
+        path.push(b"/")?;
+        path.push(name.as_bytes())?;
+        read_to_string(openat(self.fd(), &path, libc::O_RDONLY)?, buf)
+    }
+
+    fn fd(&self) -> RawFd {
+        unsafe { libc::dirfd(self.dir.as_ptr()) }
+    }
+
+    /// 返回下一个以数字命名的条目，遍历结束时返回 None
+    pub fn next_pid(&mut self) -> Option<i32> {
+        loop {
+            let entry = unsafe { libc::readdir(self.dir.as_ptr()) };
+            if entry.is_null() {
+                return None;
+            }
+
+            // 安全：readdir 返回的条目在下一次调用 readdir 之前有效
+            let name = unsafe { CStr::from_ptr((*entry).d_name.as_ptr()) };
+            if let Some(pid) = parse_pid(name.to_bytes()) {
+                return Some(pid);
+            }
+        }
+    }
+}
+
+impl Drop for PidDir {
+    fn drop(&mut self) {
+        unsafe {
+            libc::closedir(self.dir.as_ptr());
+        }
+    }
+}
+
+/// 单个进程的 /proc/<pid> 目录
+///
+/// 子文件通过 openat 相对于该目录打开，只需解析一个路径分量。
+/// 进程退出后目录随之失效，之后的读取都会失败，
+/// 而不会读到复用了同一pid的新进程的数据
+#[derive(Debug)]
+pub struct ProcessDir {
+    fd: OwnedFd,
+}
+
+impl ProcessDir {
+    /// 按路径打开进程目录，用于没有 `PidDir` 的场合
+    pub fn open(proc_root: &Path, pid: ProcessId) -> Result<Self> {
+        let path = proc_root.join(pid.as_raw().to_string());
+        let c_path = CString::new(path.as_os_str().as_bytes()).map_err(|_| {
+            SystemError::SyscallError(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Path contains a NUL byte"
+            ))
+        })?;
+
+        let fd = openat(libc::AT_FDCWD, &c_path, libc::O_RDONLY | libc::O_DIRECTORY)?;
+        Ok(Self { fd })
+    }
+
+    /// 把目录下的文件读入 `buf`（先清空）
+    pub fn read_to_string(&self, name: &str, buf: &mut String) -> Result<()> {
+        read_to_string(self.open_file(name)?, buf)
+    }
+
+    /// 把目录下的文件读入 `buf`，返回读取的字节数，超出 `buf` 的部分被忽略
+    pub fn read(&self, name: &str, buf: &mut [u8]) -> Result<usize> {
+        let mut file = File::from(self.open_file(name)?);
+        let mut len = 0;
+        while len < buf.len() {
+            match file.read(&mut buf[len..]) {
+                Ok(0) => break,
+                Ok(read) => len += read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(open_error(e)),
+            }
+        }
+        Ok(len)
+    }
+
+    /// 覆盖写入目录下的文件
+    pub fn write(&self, name: &str, contents: &[u8]) -> Result<()> {
+        let mut path = NameBuf::new();
+        path.push(name.as_bytes())?;
+        let mut file = File::from(openat(self.fd.as_raw_fd(), &path, libc::O_WRONLY | libc::O_TRUNC)?);
+        file.write_all(contents).map_err(open_error)
+    }
+
+    /// 读取进程当前的oom_score_adj
+    pub fn read_oom_score_adj(&self) -> Result<i32> {
+        let mut buf = [0u8; 16];
+        let len = self.read("oom_score_adj", &mut buf)?;
+        std::str::from_utf8(&buf[..len])
+            .ok()
+            .and_then(|content| content.trim().parse().ok())
+            .ok_or_else(|| SystemError::SyscallError(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid proc value"
+            )))
+    }
+
+    /// 写入进程的oom_score_adj
+    ///
+    /// 调高分数不需要特权，调低到低于进程原有的最小值需要 CAP_SYS_RESOURCE
+    pub fn write_oom_score_adj(&self, value: i32) -> Result<()> {
+        self.write("oom_score_adj", value.to_string().as_bytes())
+    }
+
+    /// 读取目录下的符号链接（例如 `exe`）的目标
+    pub fn read_link(&self, name: &str) -> Result<PathBuf> {
+        let mut path = NameBuf::new();
+        path.push(name.as_bytes())?;
+        let mut target = [0u8; libc::PATH_MAX as usize];
+        // 安全：path以NUL结尾，target的长度传给了readlinkat
+        let len = unsafe {
+            libc::readlinkat(self.fd.as_raw_fd(), path.as_ptr(), target.as_mut_ptr().cast(), target.len())
+        };
+        if len < 0 {
+            return Err(open_error(io::Error::last_os_error()));
+        }
+
+        Ok(PathBuf::from(std::ffi::OsStr::from_bytes(&target[..len as usize])))
+    }
+
+    fn open_file(&self, name: &str) -> Result<OwnedFd> {
+        let mut path = NameBuf::new();
+        path.push(name.as_bytes())?;
+        openat(self.fd.as_raw_fd(), &path, libc::O_RDONLY)
+    }
+}
+
+/// 栈上的以NUL结尾的相对路径
+struct NameBuf {
+    buf: [u8; NAME_BUF_LEN],
+    len: usize,
+}
+
+impl NameBuf {
+    fn new() -> Self {
+        Self { buf: [0; NAME_BUF_LEN], len: 0 }
+    }
+
+    fn push(&mut self, bytes: &[u8]) -> Result<()> {
+        // 保留结尾NUL的位置
+        if bytes.contains(&0) || self.len + bytes.len() >= NAME_BUF_LEN {
+            return Err(SystemError::SyscallError(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Invalid proc file name"
+            )));
+        }
+        self.buf[self.len..self.len + bytes.len()].copy_from_slice(bytes);
+        self.len += bytes.len();
+        Ok(())
+    }
+
+    fn push_pid(&mut self, pid: ProcessId) -> Result<()> {
+        let mut digits = [0u8; 10];
+        let mut value = pid.as_raw() as u32;
+        let mut start = digits.len();
+        loop {
+            start -= 1;
+            digits[start] = b'0' + (value % 10) as u8;
+            value /= 10;
+            if value == 0 {
+                break;
+            }
+        }
+        self.push(&digits[start..])
+    }
+}
+
+impl std::ops::Deref for NameBuf {
+    type Target = CStr;
+
+    fn deref(&self) -> &CStr {
+        // push 保证内容中没有NUL，且结尾总有一个NUL
+        CStr::from_bytes_with_nul(&self.buf[..=self.len]).unwrap_or_default()
+    }
+}
+
+fn openat(dir: RawFd, path: &CStr, flags: libc::c_int) -> Result<OwnedFd> {
+    let fd = unsafe { libc::openat(dir, path.as_ptr(), flags | libc::O_CLOEXEC) };
+    if fd < 0 {
+        return Err(open_error(io::Error::last_os_error()));
+    }
+    // 安全：fd 是刚刚打开的有效描述符，由 OwnedFd 负责关闭
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+fn read_to_string(fd: OwnedFd, buf: &mut String) -> Result<()> {
+    read_bounded(File::from(fd), buf)
+}
+
+/// 进程已退出（目录失效时为ESRCH）映射为 ProcessNotFound
+fn open_error(e: io::Error) -> SystemError {
+    match e.raw_os_error() {
+        Some(libc::ENOENT) | Some(libc::ESRCH) => SystemError::ProcessNotFound,
+        Some(libc::EACCES) | Some(libc::EPERM) => SystemError::PermissionDenied,
+        _ => SystemError::SyscallError(e),
+    }
+}
+
+/// 解析纯数字的目录名
+fn parse_pid(name: &[u8]) -> Option<i32> {
+    if name.is_empty() || !name.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+
+    std::str::from_utf8(name).ok()?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_pid() {
+        assert_eq!(parse_pid(b"1234"), Some(1234));
+        assert_eq!(parse_pid(b"self"), None);
+        assert_eq!(parse_pid(b"12a"), None);
+        assert_eq!(parse_pid(b""), None);
+    }
+
+    #[test]
+    fn test_iterate_and_rewind() {
+        let root = tempfile::tempdir().unwrap();
+        for name in ["10", "11", "self", "sys"] {
+            std::fs::create_dir(root.path().join(name)).unwrap();
+        }
+
+        let mut dir = PidDir::open(root.path()).unwrap();
+        let mut pids: Vec<i32> = std::iter::from_fn(|| dir.next_pid()).collect();
+        pids.sort();
+        assert_eq!(pids, vec![10, 11]);
+
+        // rewind 之后能看到新创建的条目
+        std::fs::create_dir(root.path().join("12")).unwrap();
+        dir.rewind();
+        assert_eq!(std::iter::from_fn(|| dir.next_pid()).count(), 3);
+    }
+
+    #[test]
+    fn test_read_relative_to_process_dir() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("42")).unwrap();
+        std::fs::write(root.path().join("42/status"), "Name:\tfirst\n").unwrap();
+        std::fs::write(root.path().join("42/oom_score_adj"), "-500\n").unwrap();
+
+        let dir = PidDir::open(root.path()).unwrap();
+        let pid = ProcessId::new(42).unwrap();
+        let mut buf = String::new();
+        dir.read_process_file(pid, "status", &mut buf).unwrap();
+        assert_eq!(buf, "Name:\tfirst\n");
+
+        let process = dir.open_process(pid).unwrap();
+        let mut small = [0u8; 3];
+        assert_eq!(process.read("oom_score_adj", &mut small).unwrap(), 3);
+        assert_eq!(&small, b"-50");
+        assert_eq!(process.read_oom_score_adj().unwrap(), -500);
+        // 写入覆盖原来更长的内容
+        process.write_oom_score_adj(7).unwrap();
+        assert_eq!(process.read_oom_score_adj().unwrap(), 7);
+
+        // 目录被替换（pid被复用）后，已打开的目录仍然指向原来的进程
+        std::fs::rename(root.path().join("42"), root.path().join("old")).unwrap();
+        std::fs::create_dir(root.path().join("42")).unwrap();
+        std::fs::write(root.path().join("42/status"), "Name:\tsecond\n").unwrap();
+        process.read_to_string("status", &mut buf).unwrap();
+        assert_eq!(buf, "Name:\tfirst\n");
+
+        // 原来的进程消失后读取失败，而不是读到新进程
+        std::fs::remove_dir_all(root.path().join("old")).unwrap();
+        assert!(matches!(process.read_to_string("status", &mut buf), Err(SystemError::ProcessNotFound)));
+        assert!(matches!(
+            dir.open_process(ProcessId::new(43).unwrap()),
+            Err(SystemError::ProcessNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_name_buf() {
+        let mut name = NameBuf::new();
+        name.push_pid(ProcessId::new(4194304).unwrap()).unwrap();
+        name.push(b"/stat").unwrap();
+        assert_eq!(name.to_bytes(), b"4194304/stat");
+        assert!(name.push(b"bad\0name").is_err());
+        assert!(name.push(&[b'x'; NAME_BUF_LEN]).is_err());
+    }
+}
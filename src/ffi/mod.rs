@@ -1,7 +1,15 @@
 mod bindings;
+mod dir;
+mod netlink;
 mod safe_wrapper;
 mod types;
 
+pub use dir::{
+    max_proc_file_bytes, read_bounded, set_max_proc_file_bytes, PidDir, ProcessDir,
+    DEFAULT_MAX_PROC_FILE_BYTES,
+};
+#[cfg(feature = "proc-connector")]
+pub use netlink::NetlinkSocket;
 pub use safe_wrapper::SafeProcessHandle;
 pub use types::{ProcessId, MemInfo, FfiError};
 
@@ -0,0 +1,143 @@
+#![cfg(feature = "proc-connector")]
+
+use super::types::{SystemError, Result};
+use std::io;
+use std::mem;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::raw::c_int;
+use std::time::Duration;
+
+/// netlink 数据报套接字的安全包装
+#[derive(Debug)]
+pub struct NetlinkSocket {
+    fd: OwnedFd,
+}
+
+impl NetlinkSocket {
+    /// 创建指定协议的 netlink 套接字并加入多播组
+    ///
+    /// # 错误
+    ///
+    /// 权限不足时返回 `SystemError::PermissionDenied`，
+    /// 内核不支持该协议时返回 `SystemError::SyscallError`
+    pub fn bind(protocol: c_int, groups: u32) -> Result<Self> {
+        let raw = unsafe {
+            libc::socket(libc::AF_NETLINK, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, protocol)
+        };
+        if raw < 0 {
+            return Err(last_error());
+        }
+        // 安全：socket 成功时返回一个新的、由我们独占的描述符
+        let fd = unsafe { OwnedFd::from_raw_fd(raw) };
+
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        addr.nl_pid = std::process::id();
+        addr.nl_groups = groups;
+
+        let result = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if result < 0 {
+            return Err(last_error());
+        }
+
+        Ok(Self { fd })
+    }
+
+    /// 向内核发送一条消息
+    pub fn send(&self, buf: &[u8]) -> Result<()> {
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+
+        let sent = unsafe {
+            libc::sendto(
+                self.fd.as_raw_fd(),
+                buf.as_ptr() as *const libc::c_void,
+                buf.len(),
+                0,
+                &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if sent < 0 {
+            return Err(last_error());
+        }
+
+        Ok(())
+    }
+
+    /// 接收一个数据报，返回写入 `buf` 的字节数
+    ///
+    /// 读超时时返回 `ErrorKind::WouldBlock`，接收缓冲区溢出时返回 `ENOBUFS`，
+    /// 两者都包装在 `SystemError::SyscallError` 中
+    pub fn recv(&self, buf: &mut [u8]) -> Result<usize> {
+        let received = unsafe {
+            libc::recv(
+                self.fd.as_raw_fd(),
+                buf.as_mut_ptr() as *mut libc::c_void,
+                buf.len(),
+                0,
+            )
+        };
+        if received < 0 {
+            return Err(SystemError::SyscallError(io::Error::last_os_error()));
+        }
+
+        Ok(received as usize)
+    }
+
+    /// 设置接收超时，使阻塞的接收线程能够定期检查停止标志
+    pub fn set_read_timeout(&self, timeout: Duration) -> Result<()> {
+        let tv = libc::timeval {
+            tv_sec: timeout.as_secs() as libc::time_t,
+            tv_usec: timeout.subsec_micros() as libc::suseconds_t,
+        };
+
+        let result = unsafe {
+            libc::setsockopt(
+                self.fd.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &tv as *const libc::timeval as *const libc::c_void,
+                mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        };
+        if result < 0 {
+            return Err(last_error());
+        }
+
+        Ok(())
+    }
+}
+
+/// 把 errno 转换为 SystemError，权限错误单独区分
+fn last_error() -> SystemError {
+    let err = io::Error::last_os_error();
+    match err.raw_os_error() {
+        Some(libc::EPERM) | Some(libc::EACCES) => SystemError::PermissionDenied,
+        _ => SystemError::SyscallError(err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recv_timeout() {
+        // NETLINK_ROUTE 不需要特权，不加入任何多播组时不会收到消息
+        let socket = NetlinkSocket::bind(libc::NETLINK_ROUTE, 0).unwrap();
+        socket.set_read_timeout(Duration::from_millis(10)).unwrap();
+
+        let mut buf = [0u8; 64];
+        match socket.recv(&mut buf) {
+            Err(SystemError::SyscallError(e)) => assert_eq!(e.kind(), io::ErrorKind::WouldBlock),
+            other => panic!("unexpected recv result: {:?}", other),
+        }
+    }
+}
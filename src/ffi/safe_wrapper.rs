@@ -1,7 +1,10 @@
 use super::bindings;
 use super::types::{ProcessId, SystemInfo, SystemError, Result};
+use std::ffi::CString;
 use std::mem::MaybeUninit;
 use std::os::raw::c_int;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
 use std::io;
 
 pub struct SystemInterface;
@@ -49,6 +52,63 @@ impl SystemInterface {
         }
     }
 
+    /// 获取路径所在文件系统对非特权用户可用的空间（字节）
+    ///
+    /// # 错误
+    ///
+    /// 如果路径无效或文件系统不可用，返回 `SystemError::SyscallError`
+    pub fn available_space(&self, path: &Path) -> Result<u64> {
+        let c_path = CString::new(path.as_os_str().as_bytes()).map_err(|_| {
+            SystemError::SyscallError(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Path contains a NUL byte"
+            ))
+        })?;
+        let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+
+        let result = unsafe {
+            libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr())
+        };
+
+        if result == 0 {
+            // 安全：statvfs成功时会完全初始化结构体
+            let stat = unsafe { stat.assume_init() };
+            Ok(stat.f_bavail * stat.f_frsize)
+        } else {
+            Err(SystemError::SyscallError(io::Error::last_os_error()))
+        }
+    }
+
+    /// 锁定值所在的内存页，使其常驻物理内存，不会被换出或回收
+    ///
+    /// 锁定一直持续到进程退出，值被释放后也不会自动解除
+    ///
+    /// # 错误
+    ///
+    /// * `SystemError::PermissionDenied` - 没有 CAP_IPC_LOCK 且 RLIMIT_MEMLOCK 为0
+    /// * `SystemError::SyscallError` - 例如超过 RLIMIT_MEMLOCK（ENOMEM）
+    pub fn lock_memory<T: ?Sized>(&self, value: &T) -> Result<()> {
+        let len = std::mem::size_of_val(value);
+        if len == 0 {
+            return Ok(());
+        }
+
+        let result = unsafe {
+            libc::mlock(value as *const T as *const libc::c_void, len)
+        };
+
+        match result {
+            0 => Ok(()),
+            _ => {
+                let err = io::Error::last_os_error();
+                match err.kind() {
+                    io::ErrorKind::PermissionDenied => Err(SystemError::PermissionDenied),
+                    _ => Err(SystemError::SyscallError(err)),
+                }
+            }
+        }
+    }
+
     /// 安全地发送信号给进程
     /// 
     /// # 参数
@@ -70,6 +130,10 @@ impl SystemInterface {
             0 => Ok(()),
             _ => {
                 let err = io::Error::last_os_error();
+                // 进程已经退出时kill返回ESRCH，io::ErrorKind没有对应的类型
+                if err.raw_os_error() == Some(libc::ESRCH) {
+                    return Err(SystemError::ProcessNotFound);
+                }
                 match err.kind() {
                     io::ErrorKind::PermissionDenied => Err(SystemError::PermissionDenied),
                     io::ErrorKind::NotFound => Err(SystemError::ProcessNotFound),
@@ -95,6 +159,25 @@ mod tests {
         assert!(info.procs > 0);
     }
 
+    #[test]
+    fn test_available_space() {
+        let sys = SystemInterface::new();
+        assert!(sys.available_space(Path::new("/")).is_ok());
+        assert!(sys.available_space(Path::new("/nonexistent/room")).is_err());
+    }
+
+    #[test]
+    fn test_lock_memory() {
+        let sys = SystemInterface::new();
+        let buffer = Box::new([0u8; 4096]);
+        // 默认的 RLIMIT_MEMLOCK 足以锁定一页；权限不足时只检查错误类型
+        match sys.lock_memory(&*buffer) {
+            Ok(()) | Err(SystemError::PermissionDenied) => {}
+            Err(e) => panic!("unexpected mlock error: {:?}", e),
+        }
+        assert!(sys.lock_memory(&[0u8; 0]).is_ok());
+    }
+
     #[test]
     fn test_invalid_pid() {
         let sys = SystemInterface::new();
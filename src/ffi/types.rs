@@ -2,7 +2,9 @@ use std::os::raw::{c_int, c_ulong};
 use std::fmt;
 
 /// 进程ID的安全包装
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+///
+/// 按原始pid比较和排序，可以用作 `HashMap`/`BTreeMap` 的键
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub struct ProcessId(pub(crate) c_int);
 
 impl ProcessId {
@@ -44,6 +46,33 @@ pub enum SystemError {
     PermissionDenied,
     #[error("Process not found")]
     ProcessNotFound,
+    #[error("Invalid cgroup data: {0}")]
+    InvalidCgroup(String),
+    #[error("Proc file exceeds {0} bytes")]
+    ProcFileTooLarge(usize),
+    #[error("Failed to spawn monitor thread after {attempts} attempt(s): {source}")]
+    ThreadSpawnFailed { attempts: u32, source: std::io::Error },
 }
 
-pub type Result<T> = std::result::Result<T, SystemError>; 
\ No newline at end of file
+pub type Result<T> = std::result::Result<T, SystemError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeSet, HashSet};
+
+    #[test]
+    fn test_process_id_in_sets() {
+        let pids = [42, 7, 1000, 7].map(|pid| ProcessId::new(pid).unwrap());
+
+        let hashed: HashSet<ProcessId> = pids.iter().copied().collect();
+        assert_eq!(hashed.len(), 3);
+        assert!(hashed.contains(&ProcessId::new(1000).unwrap()));
+        assert!(!hashed.contains(&ProcessId::new(8).unwrap()));
+
+        let ordered: BTreeSet<ProcessId> = pids.iter().copied().collect();
+        let raw: Vec<i32> = ordered.iter().map(ProcessId::as_raw).collect();
+        assert_eq!(raw, vec![7, 42, 1000]);
+        assert!(ProcessId::new(7).unwrap() < ProcessId::new(42).unwrap());
+    }
+}
@@ -8,6 +8,8 @@
 pub mod ffi;
 pub mod linux;
 pub mod oom;
+#[cfg(any(test, feature = "test-util"))]
+pub mod test_util;
 
 // 重新导出常用类型，使其可以直接从 crate 根访问
 pub use crate::ffi::types::{ProcessId, Result, SystemError};
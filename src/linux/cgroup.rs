@@ -0,0 +1,312 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::ffi::types::{ProcessId, SystemError, Result};
+use crate::linux::proc::{read_proc_file, DEFAULT_PROC_ROOT};
+
+/// cgroup 文件系统的默认挂载点（v2 统一层级）
+pub const DEFAULT_CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// 进程所在 memory cgroup 的层级类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CgroupVersion {
+    /// cgroup v1，memory 控制器挂载在独立的层级上
+    V1,
+    /// cgroup v2 统一层级
+    V2,
+}
+
+/// 从 /proc/[pid]/cgroup 解析出的 memory cgroup 信息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CgroupInfo {
+    pub version: CgroupVersion,
+    /// memory cgroup 相对于层级根目录的路径，例如 "/system.slice/foo.service"
+    pub memory_path: String,
+}
+
+impl CgroupInfo {
+    /// 读取进程的 memory cgroup
+    pub fn from_pid(pid: ProcessId) -> Result<Self> {
+        Self::from_pid_in(Path::new(DEFAULT_PROC_ROOT), pid)
+    }
+
+    /// 读取本进程（/proc/self/cgroup）的 memory cgroup
+    pub fn current() -> Result<Self> {
+        let content = read_proc_file(&Path::new(DEFAULT_PROC_ROOT).join("self/cgroup"))?;
+        Self::parse(&content)
+    }
+
+    /// 从指定proc根目录下的[pid]/cgroup读取进程的 memory cgroup
+    pub fn from_pid_in(proc_root: &Path, pid: ProcessId) -> Result<Self> {
+        let path = proc_root.join(pid.as_raw().to_string()).join("cgroup");
+        let content = read_proc_file(&path)?;
+
+        Self::parse(&content)
+    }
+
+    /// 解析 /proc/[pid]/cgroup 的内容
+    ///
+    /// 每行格式为 `hierarchy-ID:controller-list:cgroup-path`。
+    /// v1 下每个层级一行，取控制器列表中包含 `memory` 的那一行；
+    /// v2 下只有一行 `0::/path`。混合模式（systemd hybrid）两者都有，
+    /// 此时 memory 控制器仍在 v1 层级上，优先使用 v1 的行
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut unified = None;
+
+        for line in content.lines().filter(|line| !line.is_empty()) {
+            let mut fields = line.splitn(3, ':');
+            let (Some(id), Some(controllers), Some(path)) = (fields.next(), fields.next(), fields.next()) else {
+                return Err(SystemError::InvalidCgroup(format!("malformed line: {:?}", line)));
+            };
+            if id.parse::<u32>().is_err() {
+                return Err(SystemError::InvalidCgroup(format!("invalid hierarchy ID: {:?}", line)));
+            }
+
+            if controllers.split(',').any(|controller| controller == "memory") {
+                return Ok(Self {
+                    version: CgroupVersion::V1,
+                    memory_path: path.to_string(),
+                });
+            }
+            if id == "0" && controllers.is_empty() {
+                unified = Some(path);
+            }
+        }
+
+        unified
+            .map(|path| Self {
+                version: CgroupVersion::V2,
+                memory_path: path.to_string(),
+            })
+            .ok_or_else(|| SystemError::InvalidCgroup("no memory controller found".to_string()))
+    }
+}
+
+/// memory cgroup 的 memory.stat 中与回收相关的字段（字节）
+///
+/// 以 cgroup v2 的字段为准；v1 没有 `anon`/`file` 时分别使用 `rss`/`cache`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CgroupMemStat {
+    /// 匿名内存，只能换出或终止进程才能释放
+    pub anon: u64,
+    /// 页缓存，包含 `shmem`
+    pub file: u64,
+    /// 内核为该 cgroup 分配的内存；旧内核没有 `kernel` 字段时为 kernel_stack、pagetables 和 slab 之和
+    pub kernel: u64,
+    pub slab: u64,
+    pub slab_reclaimable: u64,
+    /// tmpfs 和共享内存，计入 `file` 但不能像缓存一样直接回收
+    pub shmem: u64,
+}
+
+impl CgroupMemStat {
+    /// 读取 cgroup 目录下的 memory.stat
+    pub fn read(cgroup_dir: &Path) -> Result<Self> {
+        let content = fs::read_to_string(cgroup_dir.join("memory.stat")).map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => SystemError::InvalidCgroup(format!("no memory.stat in {}", cgroup_dir.display())),
+            io::ErrorKind::PermissionDenied => SystemError::PermissionDenied,
+            _ => SystemError::SyscallError(e),
+        })?;
+
+        Ok(Self::parse(&content))
+    }
+
+    /// 解析 memory.stat 的内容，每行为 `key value`，未出现的字段为0
+    pub fn parse(content: &str) -> Self {
+        let mut stat = Self::default();
+        let (mut kernel, mut kernel_parts) = (None, 0u64);
+        let (mut slab, mut slab_unreclaimable) = (None, 0u64);
+        let (mut rss, mut cache) = (0u64, 0u64);
+        let (mut has_anon, mut has_file) = (false, false);
+
+        for line in content.lines() {
+            let mut parts = line.split_whitespace();
+            let (Some(key), Some(value)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            let value: u64 = value.parse().unwrap_or(0);
+
+            match key {
+                "anon" => (stat.anon, has_anon) = (value, true),
+                "file" => (stat.file, has_file) = (value, true),
+                "kernel" => kernel = Some(value),
+                "kernel_stack" | "pagetables" => kernel_parts = kernel_parts.saturating_add(value),
+                "slab" => slab = Some(value),
+                "slab_reclaimable" => stat.slab_reclaimable = value,
+                "slab_unreclaimable" => slab_unreclaimable = value,
+                "shmem" => stat.shmem = value,
+                // cgroup v1
+                "rss" => rss = value,
+                "cache" => cache = value,
+                _ => {}
+            }
+        }
+
+        if !has_anon {
+            stat.anon = rss;
+        }
+        if !has_file {
+            stat.file = cache;
+        }
+        stat.slab = slab.unwrap_or(stat.slab_reclaimable.saturating_add(slab_unreclaimable));
+        stat.kernel = kernel.unwrap_or(kernel_parts.saturating_add(stat.slab));
+
+        stat
+    }
+}
+
+/// 读取 cgroup 的内存上限，没有限制时返回 `None`
+///
+/// v2 读取 memory.max（无限制时为 `max`），v1 读取 memory.limit_in_bytes
+/// （无限制时为接近 i64::MAX 的页对齐值）
+pub fn read_memory_limit(cgroup_dir: &Path) -> Result<Option<u64>> {
+    /// v1 中不小于该值的上限视为没有限制
+    const V1_UNLIMITED: u64 = 1 << 62;
+
+    let (content, v1) = match fs::read_to_string(cgroup_dir.join("memory.max")) {
+        Ok(content) => (content, false),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            (fs::read_to_string(cgroup_dir.join("memory.limit_in_bytes"))?, true)
+        }
+        Err(e) => return Err(SystemError::SyscallError(e)),
+    };
+
+    let content = content.trim();
+    if content == "max" {
+        return Ok(None);
+    }
+    let limit: u64 = content
+        .parse()
+        .map_err(|_| SystemError::InvalidCgroup(format!("invalid memory limit: {:?}", content)))?;
+
+    Ok(if v1 && limit >= V1_UNLIMITED { None } else { Some(limit) })
+}
+
+/// 读取 cgroup v2 的 memory.low（字节），`max` 时为 `u64::MAX`
+///
+/// 根cgroup和v1层级没有 memory.low，文件不存在时返回0
+pub fn read_memory_low(cgroup_dir: &Path) -> Result<u64> {
+    let content = match fs::read_to_string(cgroup_dir.join("memory.low")) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(SystemError::SyscallError(e)),
+    };
+
+    let content = content.trim();
+    if content == "max" {
+        return Ok(u64::MAX);
+    }
+    content
+        .parse()
+        .map_err(|_| SystemError::InvalidCgroup(format!("invalid memory.low: {:?}", content)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const V1_FIXTURE: &str = include_str!("../../tests/fixtures/cgroup/v1");
+    const V2_FIXTURE: &str = include_str!("../../tests/fixtures/cgroup/v2");
+    const MEMORY_STAT_FIXTURE: &str = include_str!("../../tests/fixtures/cgroup/memory.stat");
+
+    #[test]
+    fn test_v1_and_v2_extract_same_path() {
+        let v1 = CgroupInfo::parse(V1_FIXTURE).unwrap();
+        let v2 = CgroupInfo::parse(V2_FIXTURE).unwrap();
+
+        assert_eq!(v1.version, CgroupVersion::V1);
+        assert_eq!(v2.version, CgroupVersion::V2);
+        assert_eq!(v1.memory_path, "/system.slice/postgresql.service");
+        assert_eq!(v1.memory_path, v2.memory_path);
+    }
+
+    #[test]
+    fn test_hybrid_prefers_v1_memory_line() {
+        let content = "0::/user.slice\n5:cpu,memory:/docker/abc\n1:name=systemd:/user.slice\n";
+        let info = CgroupInfo::parse(content).unwrap();
+        assert_eq!(info.version, CgroupVersion::V1);
+        assert_eq!(info.memory_path, "/docker/abc");
+    }
+
+    #[test]
+    fn test_invalid_content() {
+        // v1 中没有挂载 memory 控制器
+        assert!(matches!(
+            CgroupInfo::parse("3:cpu:/\n1:name=systemd:/init.scope\n"),
+            Err(SystemError::InvalidCgroup(_))
+        ));
+        assert!(matches!(CgroupInfo::parse(""), Err(SystemError::InvalidCgroup(_))));
+        assert!(matches!(CgroupInfo::parse("garbage"), Err(SystemError::InvalidCgroup(_))));
+        assert!(matches!(CgroupInfo::parse("x::/foo"), Err(SystemError::InvalidCgroup(_))));
+    }
+
+    #[test]
+    fn test_read_from_proc_root() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("42");
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("cgroup"), V2_FIXTURE).unwrap();
+
+        let pid = ProcessId::new(42).unwrap();
+        let info = CgroupInfo::from_pid_in(root.path(), pid).unwrap();
+        assert_eq!(info.memory_path, "/system.slice/postgresql.service");
+
+        let missing = ProcessId::new(43).unwrap();
+        assert!(matches!(
+            CgroupInfo::from_pid_in(root.path(), missing),
+            Err(SystemError::ProcessNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_parse_memory_stat() {
+        let stat = CgroupMemStat::parse(MEMORY_STAT_FIXTURE);
+        assert_eq!(stat.anon, 1073741824);
+        assert_eq!(stat.file, 2147483648);
+        assert_eq!(stat.kernel, 113246208);
+        assert_eq!(stat.slab, 73400320);
+        assert_eq!(stat.slab_reclaimable, 62914560);
+        assert_eq!(stat.shmem, 16777216);
+
+        // 没有kernel和slab汇总字段的旧内核
+        let stat = CgroupMemStat::parse("anon 100\nkernel_stack 10\npagetables 20\nslab_reclaimable 30\nslab_unreclaimable 40\n");
+        assert_eq!(stat.slab, 70);
+        assert_eq!(stat.kernel, 100);
+
+        // cgroup v1
+        let stat = CgroupMemStat::parse("cache 4096\nrss 8192\nshmem 1024\ntotal_rss 16384\n");
+        assert_eq!(stat.anon, 8192);
+        assert_eq!(stat.file, 4096);
+        assert_eq!(stat.shmem, 1024);
+    }
+
+    #[test]
+    fn test_read_memory_limit() {
+        let v2 = tempfile::tempdir().unwrap();
+        fs::write(v2.path().join("memory.max"), "max\n").unwrap();
+        assert_eq!(read_memory_limit(v2.path()).unwrap(), None);
+        fs::write(v2.path().join("memory.max"), "4294967296\n").unwrap();
+        assert_eq!(read_memory_limit(v2.path()).unwrap(), Some(4294967296));
+
+        let v1 = tempfile::tempdir().unwrap();
+        fs::write(v1.path().join("memory.limit_in_bytes"), "9223372036854771712\n").unwrap();
+        assert_eq!(read_memory_limit(v1.path()).unwrap(), None);
+        fs::write(v1.path().join("memory.limit_in_bytes"), "1073741824\n").unwrap();
+        assert_eq!(read_memory_limit(v1.path()).unwrap(), Some(1073741824));
+
+        fs::write(v2.path().join("memory.max"), "lots\n").unwrap();
+        assert!(matches!(read_memory_limit(v2.path()), Err(SystemError::InvalidCgroup(_))));
+    }
+
+    #[test]
+    fn test_read_memory_low() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(read_memory_low(dir.path()).unwrap(), 0);
+        fs::write(dir.path().join("memory.low"), "1073741824\n").unwrap();
+        assert_eq!(read_memory_low(dir.path()).unwrap(), 1073741824);
+        fs::write(dir.path().join("memory.low"), "max\n").unwrap();
+        assert_eq!(read_memory_low(dir.path()).unwrap(), u64::MAX);
+        fs::write(dir.path().join("memory.low"), "lots\n").unwrap();
+        assert!(matches!(read_memory_low(dir.path()), Err(SystemError::InvalidCgroup(_))));
+    }
+}
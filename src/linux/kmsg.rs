@@ -0,0 +1,346 @@
+#![cfg(feature = "kmsg")]
+
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::os::unix::io::AsRawFd;
+use std::time::Duration;
+use crate::ffi::types::{ProcessId, SystemError, Result};
+
+/// 内核日志设备路径
+const KMSG_PATH: &str = "/dev/kmsg";
+
+/// 单条 kmsg 记录的最大长度（内核限制为 1024 字节左右，这里留足余量）
+const KMSG_RECORD_MAX: usize = 8192;
+
+/// 等待新记录的超时，读取线程至少以此间隔检查停止标志
+const READ_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// 从内核日志中重建的一次内核 OOM Killer 终止记录
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KernelOomRecord {
+    pub pid: ProcessId,
+    pub comm: String,
+    pub uid: Option<u32>,
+    pub total_vm: u64,       // 虚拟内存总量（字节）
+    pub anon_rss: u64,       // 匿名页常驻内存（字节）
+    pub file_rss: u64,       // 文件页常驻内存（字节）
+    pub shmem_rss: u64,      // 共享内存常驻内存（字节）
+    pub oom_score_adj: Option<i32>,
+    pub invoked_by: Option<String>,   // 触发 OOM 的进程名
+    pub constraint: Option<String>,   // CONSTRAINT_NONE / CONSTRAINT_MEMCG 等
+    pub task_memcg: Option<String>,   // 受害进程所在的 memory cgroup
+    pub timestamp_us: Option<u64>,    // 自系统启动以来的微秒数
+}
+
+impl KernelOomRecord {
+    /// 内核报告的受害进程常驻内存总量（字节）
+    pub fn total_rss(&self) -> u64 {
+        self.anon_rss
+            .saturating_add(self.file_rss)
+            .saturating_add(self.shmem_rss)
+    }
+}
+
+/// 一次 OOM 事件中尚未看到 "Killed process" 行之前收集到的上下文
+#[derive(Debug, Default)]
+struct PendingEpisode {
+    invoked_by: Option<String>,
+    constraint: Option<String>,
+    task_memcg: Option<String>,
+    uid: Option<u32>,
+}
+
+/// 内核 OOM 报告的增量解析器
+///
+/// 逐行输入 `/dev/kmsg` 记录或 `dmesg` 输出，在遇到 "Killed process" 行时
+/// 产出一条 `KernelOomRecord`。
+///
+/// # 为什么按行做状态机？
+///
+/// 1. 不同内核版本的报告格式差异很大（4.x 的 "Kill process ... or sacrifice child"，
+///    5.x 之后的 "oom-kill:" 汇总行，memcg 的 "Memory cgroup out of memory"）
+/// 2. 唯一稳定的是 "Killed process <pid> (<comm>)" 这一行，其余行只用于补充上下文
+#[derive(Debug, Default)]
+pub struct KmsgParser {
+    pending: PendingEpisode,
+}
+
+impl KmsgParser {
+    /// 创建新的解析器实例
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 输入一行日志，若该行完成了一次内核终止报告则返回记录
+    pub fn feed_line(&mut self, line: &str) -> Option<KernelOomRecord> {
+        // kmsg 的续行（以空格开头的 KEY=VALUE 字典）不包含报告内容
+        if line.starts_with(' ') || line.starts_with('\t') {
+            return None;
+        }
+
+        let (timestamp_us, message) = split_prefix(line);
+        let message = message.trim_end();
+
+        if let Some(idx) = message.find(" invoked oom-killer:") {
+            // 新的 OOM 事件开始，丢弃之前未完成的上下文
+            self.pending = PendingEpisode {
+                invoked_by: Some(message[..idx].to_string()),
+                ..Default::default()
+            };
+        } else if let Some(summary) = message.strip_prefix("oom-kill:") {
+            self.parse_summary(summary);
+        } else if let Some(idx) = message.find("Killed process ") {
+            let record = parse_killed_line(&message[idx + "Killed process ".len()..], timestamp_us)
+                .map(|mut record| {
+                    let pending = std::mem::take(&mut self.pending);
+                    record.invoked_by = pending.invoked_by;
+                    record.constraint = pending.constraint;
+                    record.task_memcg = pending.task_memcg;
+                    if record.uid.is_none() {
+                        record.uid = pending.uid;
+                    }
+                    record
+                });
+            return record;
+        }
+
+        None
+    }
+
+    /// 解析 5.x 之后的 "oom-kill:constraint=...,task=...,pid=...,uid=..." 汇总行
+    fn parse_summary(&mut self, summary: &str) {
+        for field in summary.split(',') {
+            if let Some((key, value)) = field.split_once('=') {
+                match key {
+                    "constraint" => self.pending.constraint = Some(value.to_string()),
+                    "task_memcg" => self.pending.task_memcg = Some(value.to_string()),
+                    "uid" => self.pending.uid = value.parse().ok(),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// 去掉 kmsg 记录头（"prio,seq,usec,flags;"）或 dmesg 时间戳（"[ 123.456789] "）
+fn split_prefix(line: &str) -> (Option<u64>, &str) {
+    if let Some((header, message)) = line.split_once(';') {
+        let fields: Vec<&str> = header.split(',').collect();
+        if fields.len() >= 3 && fields[..3].iter().all(|f| f.parse::<u64>().is_ok()) {
+            return (fields[2].parse().ok(), message);
+        }
+    }
+
+    if let Some(rest) = line.strip_prefix('[') {
+        if let Some((stamp, message)) = rest.split_once(']') {
+            let timestamp_us = stamp.trim().split_once('.').and_then(|(secs, frac)| {
+                let secs: u64 = secs.parse().ok()?;
+                let micros: u64 = format!("{:0<6}", frac).get(..6)?.parse().ok()?;
+                Some(secs * 1_000_000 + micros)
+            });
+            return (timestamp_us, message.trim_start());
+        }
+    }
+
+    (None, line)
+}
+
+/// 解析 "Killed process" 之后的部分：
+/// "<pid> (<comm>) total-vm:NkB, anon-rss:NkB, file-rss:NkB, shmem-rss:NkB, UID:N pgtables:NkB oom_score_adj:N"
+///
+/// 旧内核只有 total-vm/anon-rss/file-rss 三项，缺失的字段保持默认值
+fn parse_killed_line(rest: &str, timestamp_us: Option<u64>) -> Option<KernelOomRecord> {
+    let (pid_str, rest) = rest.split_once(' ')?;
+    let pid = ProcessId::new(pid_str.parse().ok()?)?;
+
+    // 进程名可能包含空格和括号，取统计字段之前的最后一个 ')'
+    let rest = rest.strip_prefix('(')?;
+    let stats_start = rest.find(") total-vm:").map(|i| i + 1).unwrap_or(rest.len());
+    let comm_end = rest[..stats_start].rfind(')')?;
+    let comm = rest[..comm_end].to_string();
+
+    let mut record = KernelOomRecord {
+        pid,
+        comm,
+        uid: None,
+        total_vm: 0,
+        anon_rss: 0,
+        file_rss: 0,
+        shmem_rss: 0,
+        oom_score_adj: None,
+        invoked_by: None,
+        constraint: None,
+        task_memcg: None,
+        timestamp_us,
+    };
+
+    for token in rest[comm_end + 1..].split(|c: char| c == ',' || c.is_whitespace()) {
+        let Some((key, value)) = token.split_once(':') else {
+            continue;
+        };
+        match key {
+            "total-vm" => record.total_vm = parse_kb(value),
+            "anon-rss" => record.anon_rss = parse_kb(value),
+            "file-rss" => record.file_rss = parse_kb(value),
+            "shmem-rss" => record.shmem_rss = parse_kb(value),
+            "UID" => record.uid = value.parse().ok(),
+            "oom_score_adj" => record.oom_score_adj = value.parse().ok(),
+            _ => {}
+        }
+    }
+
+    Some(record)
+}
+
+/// 解析 "1024kB" 形式的值并转换为字节
+fn parse_kb(value: &str) -> u64 {
+    value.trim_end_matches("kB")
+        .parse::<u64>()
+        .unwrap_or(0)
+        .saturating_mul(1024)
+}
+
+/// `/dev/kmsg` 监视器
+///
+/// 需要 CAP_SYSLOG 或 root 权限。打开后会跳过已有的历史记录，只报告之后发生的内核 OOM。
+pub struct KmsgWatcher {
+    file: File,
+    parser: KmsgParser,
+    buf: Vec<u8>,
+}
+
+impl KmsgWatcher {
+    /// 打开 `/dev/kmsg` 并定位到末尾
+    pub fn open() -> Result<Self> {
+        let mut file = File::open(KMSG_PATH).map_err(|e| {
+            if e.kind() == io::ErrorKind::PermissionDenied {
+                SystemError::PermissionDenied
+            } else {
+                SystemError::SyscallError(e)
+            }
+        })?;
+        file.seek(SeekFrom::End(0))?;
+
+        Ok(Self {
+            file,
+            parser: KmsgParser::new(),
+            buf: vec![0; KMSG_RECORD_MAX],
+        })
+    }
+
+    /// 读取下一条日志记录，超时或这条记录没有完成一次内核终止报告时返回 `None`
+    pub fn next_record(&mut self) -> Result<Option<KernelOomRecord>> {
+        if !self.wait_readable()? {
+            return Ok(None);
+        }
+
+        // /dev/kmsg 每次 read 恰好返回一条记录
+        let n = match self.file.read(&mut self.buf) {
+            Ok(n) => n,
+            // 读取速度跟不上时内核覆盖了旧记录，跳过即可
+            Err(e) if e.raw_os_error() == Some(libc::EPIPE) => return Ok(None),
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => return Ok(None),
+            Err(e) => return Err(SystemError::SyscallError(e)),
+        };
+
+        let text = String::from_utf8_lossy(&self.buf[..n]);
+        Ok(text.lines().find_map(|line| self.parser.feed_line(line)))
+    }
+
+    /// 等待最多 `READ_TIMEOUT` 直到有新记录可读
+    fn wait_readable(&self) -> Result<bool> {
+        let mut fd = libc::pollfd {
+            fd: self.file.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        let ready = unsafe { libc::poll(&mut fd, 1, READ_TIMEOUT.as_millis() as libc::c_int) };
+        if ready < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::Interrupted {
+                return Ok(false);
+            }
+            return Err(SystemError::SyscallError(err));
+        }
+
+        Ok(ready > 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_fixture(content: &str) -> Vec<KernelOomRecord> {
+        let mut parser = KmsgParser::new();
+        content.lines().filter_map(|line| parser.feed_line(line)).collect()
+    }
+
+    #[test]
+    fn test_parse_linux_4_19() {
+        let records = parse_fixture(include_str!("../../tests/fixtures/kmsg/linux-4.19-global.txt"));
+        assert_eq!(records.len(), 1);
+
+        let record = &records[0];
+        assert_eq!(record.pid.as_raw(), 4242);
+        assert_eq!(record.comm, "stress");
+        assert_eq!(record.anon_rss, 7_700_000 * 1024);
+        assert_eq!(record.file_rss, 4 * 1024);
+        assert_eq!(record.invoked_by.as_deref(), Some("stress"));
+        // 旧内核没有 UID 和 oom_score_adj 字段
+        assert_eq!(record.uid, None);
+        assert_eq!(record.oom_score_adj, None);
+        assert_eq!(record.timestamp_us, Some(1_234_567_123_800));
+    }
+
+    #[test]
+    fn test_parse_linux_5_10() {
+        let records = parse_fixture(include_str!("../../tests/fixtures/kmsg/linux-5.10-global.txt"));
+        assert_eq!(records.len(), 1);
+
+        let record = &records[0];
+        assert_eq!(record.pid.as_raw(), 1777);
+        assert_eq!(record.comm, "python3");
+        assert_eq!(record.uid, Some(1000));
+        assert_eq!(record.anon_rss, 6_123_456 * 1024);
+        assert_eq!(record.file_rss, 2048 * 1024);
+        assert_eq!(record.oom_score_adj, Some(0));
+        assert_eq!(record.constraint.as_deref(), Some("CONSTRAINT_NONE"));
+        assert_eq!(
+            record.task_memcg.as_deref(),
+            Some("/user.slice/user-1000.slice/session-3.scope")
+        );
+    }
+
+    #[test]
+    fn test_parse_raw_kmsg_memcg() {
+        let records = parse_fixture(include_str!("../../tests/fixtures/kmsg/linux-6.1-memcg-kmsg.txt"));
+        assert_eq!(records.len(), 1);
+
+        let record = &records[0];
+        assert_eq!(record.pid.as_raw(), 31337);
+        assert_eq!(record.comm, "node");
+        assert_eq!(record.shmem_rss, 128 * 1024);
+        assert_eq!(record.oom_score_adj, Some(900));
+        assert_eq!(record.constraint.as_deref(), Some("CONSTRAINT_MEMCG"));
+        assert_eq!(record.timestamp_us, Some(123_456_789_510));
+        assert_eq!(record.total_rss(), (512_000 + 16_384 + 128) * 1024);
+    }
+
+    #[test]
+    fn test_parse_comm_with_spaces() {
+        let records = parse_fixture(include_str!("../../tests/fixtures/kmsg/linux-6.5-comm-with-spaces.txt"));
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].comm, "Isolated Web Co");
+        assert_eq!(records[0].invoked_by.as_deref(), Some("Isolated Web Co"));
+        assert_eq!(records[0].anon_rss, 1_048_576 * 1024);
+    }
+
+    #[test]
+    fn test_ignores_unrelated_lines() {
+        let mut parser = KmsgParser::new();
+        assert!(parser.feed_line("[    0.000000] Linux version 6.1.0-13-amd64").is_none());
+        assert!(parser.feed_line("6,1,2,-;usb 1-1: new high-speed USB device").is_none());
+        assert!(parser.feed_line("Killed process garbage").is_none());
+    }
+}
@@ -0,0 +1,110 @@
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use serde::Serialize;
+use crate::ffi::types::{SystemError, Result};
+use crate::linux::proc::DEFAULT_PROC_ROOT;
+
+/// 读取loadavg的缓冲区大小，完整的一行不超过几十字节
+const LOADAVG_BUF_LEN: usize = 128;
+
+/// /proc/loadavg 中的系统负载
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct SystemLoad {
+    /// 1、5、15分钟平均负载
+    pub load1: f64,
+    pub load5: f64,
+    pub load15: f64,
+    /// 当前可运行的调度实体数
+    pub runnable: u32,
+    /// 系统中的调度实体总数
+    pub total: u32,
+}
+
+impl SystemLoad {
+    /// 读取当前的 /proc/loadavg
+    pub fn read() -> Result<Self> {
+        Self::read_in(Path::new(DEFAULT_PROC_ROOT))
+    }
+
+    /// 读取指定proc根目录下的loadavg
+    pub fn read_in(proc_root: &Path) -> Result<Self> {
+        Self::read_from(&proc_root.join("loadavg"))
+    }
+
+    /// 读取指定路径的loadavg文件，读取缓冲区在栈上，不分配内存
+    pub fn read_from(path: &Path) -> Result<Self> {
+        let mut buf = [0u8; LOADAVG_BUF_LEN];
+        let mut file = File::open(path)?;
+        let mut len = 0;
+        while len < buf.len() {
+            match file.read(&mut buf[len..]) {
+                Ok(0) => break,
+                Ok(read) => len += read,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        std::str::from_utf8(&buf[..len])
+            .map_err(|_| invalid_loadavg())
+            .and_then(Self::parse)
+    }
+
+    /// 解析loadavg的内容，例如 `0.52 0.58 0.59 2/1191 47213`
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut fields = content.split_whitespace();
+        let mut load = || -> Result<f64> {
+            fields.next()
+                .and_then(|field| field.parse().ok())
+                .ok_or_else(invalid_loadavg)
+        };
+        let (load1, load5, load15) = (load()?, load()?, load()?);
+
+        let (runnable, total) = fields.next()
+            .and_then(|field| field.split_once('/'))
+            .and_then(|(runnable, total)| Some((runnable.parse().ok()?, total.parse().ok()?)))
+            .ok_or_else(invalid_loadavg)?;
+
+        Ok(Self { load1, load5, load15, runnable, total })
+    }
+
+    /// 每个CPU的1分钟平均负载
+    pub fn load1_per_cpu(&self, cpus: usize) -> f64 {
+        self.load1 / cpus.max(1) as f64
+    }
+}
+
+fn invalid_loadavg() -> SystemError {
+    SystemError::SyscallError(io::Error::new(
+        io::ErrorKind::InvalidData,
+        "Invalid loadavg content"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_loadavg() {
+        let load = SystemLoad::parse("0.52 1.58 2.59 3/1191 47213\n").unwrap();
+
+        assert_eq!(load.load1, 0.52);
+        assert_eq!(load.load5, 1.58);
+        assert_eq!(load.load15, 2.59);
+        assert_eq!(load.runnable, 3);
+        assert_eq!(load.total, 1191);
+        assert_eq!(load.load1_per_cpu(4), 0.13);
+
+        assert!(SystemLoad::parse("").is_err());
+        assert!(SystemLoad::parse("0.52 1.58 2.59 3 47213").is_err());
+    }
+
+    #[test]
+    fn test_read_live_loadavg() {
+        let load = SystemLoad::read().unwrap();
+        assert!(load.total >= load.runnable);
+        assert!(load.load1 >= 0.0);
+    }
+}
@@ -1,27 +1,162 @@
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
-use std::path::Path;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
 use crate::ffi::types::{ProcessId, SystemError, Result};
+use crate::ffi::{read_bounded, ProcessDir};
+use crate::linux::proc_stat::ProcessKey;
+
+/// 默认的proc文件系统挂载点
+pub const DEFAULT_PROC_ROOT: &str = "/proc";
 
 /// 进程的内存统计信息
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct ProcessMemInfo {
     pub vm_peak: u64,      // 进程使用的虚拟内存峰值
     pub vm_size: u64,      // 当前虚拟内存使用量
     pub vm_rss: u64,       // 物理内存使用量
+    pub rss_anon: u64,     // 匿名页的物理内存
+    pub rss_file: u64,     // 文件映射页的物理内存
+    pub rss_shmem: u64,    // 共享内存的物理内存
     pub vm_swap: u64,      // swap使用量
     pub oom_score: i32,    // 系统计算的OOM分数
     pub oom_score_adj: i32, // OOM分数调整值
 }
 
+/// 构造 `ProcessInfo` 时各部分是否读取成功
+///
+/// 权限不足时（例如读取特权进程的文件），status可读而OOM分数不可读的情况并不少见。
+/// 读取失败的字段保持为0，由使用者决定是否信任这样的记录
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Completeness {
+    /// /proc/[pid]/status 已解析
+    pub status_ok: bool,
+    /// /proc/[pid]/oom_score 已读取
+    pub oom_score_ok: bool,
+    /// /proc/[pid]/oom_score_adj 已读取
+    pub oom_adj_ok: bool,
+}
+
+impl Completeness {
+    /// 所有字段都已读取
+    pub const COMPLETE: Self = Self {
+        status_ok: true,
+        oom_score_ok: true,
+        oom_adj_ok: true,
+    };
+
+    /// 只解析了status，还没有读取OOM分数
+    pub const STATUS_ONLY: Self = Self {
+        status_ok: true,
+        oom_score_ok: false,
+        oom_adj_ok: false,
+    };
+
+    /// 是否所有字段都已读取
+    pub fn is_complete(&self) -> bool {
+        *self == Self::COMPLETE
+    }
+}
+
+/// 总占用（RSS加swap）低于该值（kB）的进程归为 `MemoryProfile::Tiny`
+const TINY_PROFILE_KB: u64 = 16 * 1024;
+
+/// 按内存构成对进程的粗略分类，用于报告和解释选择结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MemoryProfile {
+    /// RSS加swap不到16MB，终止它释放不了多少内存
+    Tiny,
+    /// 大部分内存在swap中，终止它主要释放swap
+    SwappedOut,
+    /// 常驻内存以文件映射页为主，这部分内核本来就可以回收
+    CacheHeavy,
+    /// 常驻内存以匿名页和共享内存为主，只有终止才能释放
+    AnonHeavy,
+    /// status中没有RssAnon/RssFile（4.5之前的内核），无法区分
+    Unknown,
+}
+
+/// /proc/[pid]/limits 中与内存有关的软限制（字节），`None` 表示unlimited
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// RLIMIT_RSS（"Max resident set"）
+    pub rss: Option<u64>,
+    /// RLIMIT_MEMLOCK（"Max locked memory"）
+    pub memlock: Option<u64>,
+}
+
+impl ResourceLimits {
+    /// 解析/proc/[pid]/limits的内容，缺少的行按unlimited处理
+    pub fn parse(content: &str) -> Self {
+        let mut limits = Self::default();
+        for line in content.lines() {
+            if let Some(rest) = line.strip_prefix("Max resident set") {
+                limits.rss = parse_soft_limit(rest);
+            } else if let Some(rest) = line.strip_prefix("Max locked memory") {
+                limits.memlock = parse_soft_limit(rest);
+            }
+        }
+        limits
+    }
+}
+
+/// limits中一行去掉名称后的第一列（软限制），"unlimited"或无法解析时为None
+fn parse_soft_limit(rest: &str) -> Option<u64> {
+    rest.split_whitespace().next()?.parse().ok()
+}
+
 /// 进程的基本信息
+///
+/// 相等、哈希和排序都只看pid：同一个pid在不同时刻读取的两份信息视为同一个进程，
+/// 便于在集合中按进程去重或比较两次扫描。需要区分pid复用时使用 `ProcessKey`
 #[derive(Debug, Clone)]
 pub struct ProcessInfo {
     pub pid: ProcessId,
     pub name: String,
     pub state: String,
     pub ppid: i32,
+    pub uid: u32,
     pub mem_info: ProcessMemInfo,
+    /// /proc/[pid]/exe 指向的可执行文件名（不含目录）
+    ///
+    /// `name` 来自status中的comm，进程可以通过 prctl(PR_SET_NAME) 修改它；
+    /// 可执行文件名不受影响。没有读取、没有权限或内核线程时为None
+    pub exe_name: Option<String>,
+    /// /proc/[pid]/limits 中与内存有关的资源限制，没有读取或无法读取时为None
+    pub limits: Option<ResourceLimits>,
+    /// 所在cgroup（v2）的 memory.low（字节），没有读取时为None
+    pub cgroup_memory_low: Option<u64>,
+    /// 所在的 memory cgroup 路径，例如 "/system.slice/foo.service"，没有读取时为None
+    pub memory_cgroup: Option<String>,
+    /// 各部分是否读取成功
+    pub completeness: Completeness,
+}
+
+impl PartialEq for ProcessInfo {
+    fn eq(&self, other: &Self) -> bool {
+        self.pid == other.pid
+    }
+}
+
+impl Eq for ProcessInfo {}
+
+impl std::hash::Hash for ProcessInfo {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.pid.hash(state);
+    }
+}
+
+impl PartialOrd for ProcessInfo {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ProcessInfo {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.pid.cmp(&other.pid)
+    }
 }
 
 impl ProcessInfo {
@@ -35,79 +170,209 @@ impl ProcessInfo {
     /// 
     /// 返回包含进程信息的 ProcessInfo 结构体
     pub fn from_pid(pid: ProcessId) -> Result<Self> {
-        let status_path = format!("/proc/{}/status", pid.as_raw());
-        let oom_score_path = format!("/proc/{}/oom_score", pid.as_raw());
-        let oom_adj_path = format!("/proc/{}/oom_score_adj", pid.as_raw());
+        Self::from_pid_in(Path::new(DEFAULT_PROC_ROOT), pid)
+    }
+
+    /// 从指定的proc根目录读取进程信息（用于测试夹具和基准测试）
+    ///
+    /// status必须可读；OOM分数读取失败时保持为0，并记录在 `completeness` 中
+    pub fn from_pid_in(proc_root: &Path, pid: ProcessId) -> Result<Self> {
+        Self::from_pid_with(proc_root, pid, &mut String::new())
+    }
+
+    /// 与 `from_pid_in` 相同，文件内容读入 `buf`
+    ///
+    /// 扫描多个进程时传入同一个缓冲区，每个文件不再单独分配
+    pub fn from_pid_with(proc_root: &Path, pid: ProcessId, buf: &mut String) -> Result<Self> {
+        let mut info = Self::from_status_with(proc_root, pid, buf)?;
+        let pid_dir = proc_root.join(pid.as_raw().to_string());
+        if let Ok(oom_score) = read_proc_value_with(&pid_dir.join("oom_score"), buf) {
+            info.mem_info.oom_score = oom_score;
+            info.completeness.oom_score_ok = true;
+        }
+        if let Ok(oom_score_adj) = read_proc_value_with(&pid_dir.join("oom_score_adj"), buf) {
+            info.mem_info.oom_score_adj = oom_score_adj;
+            info.completeness.oom_adj_ok = true;
+        }
+        info.exe_name = read_exe_name_in(proc_root, pid);
+        info.limits = read_proc_file_into(&pid_dir.join("limits"), buf)
+            .ok()
+            .map(|()| ResourceLimits::parse(buf));
+
+        Ok(info)
+    }
+
+    /// 只读取[pid]/status，不读取OOM分数
+    ///
+    /// 返回值中的oom_score和oom_score_adj为0，需要时通过 `OomScoreCache` 补充
+    pub fn from_status_in(proc_root: &Path, pid: ProcessId) -> Result<Self> {
+        Self::from_status_with(proc_root, pid, &mut String::new())
+    }
+
+    /// 与 `from_status_in` 相同，status读入 `buf`
+    pub fn from_status_with(proc_root: &Path, pid: ProcessId, buf: &mut String) -> Result<Self> {
+        let status_path = proc_root.join(pid.as_raw().to_string()).join("status");
 
         // 读取进程状态信息
-        let mut name = String::new();
-        let mut state = String::new();
-        let mut ppid = 0;
-        let mut vm_peak = 0;
-        let mut vm_size = 0;
-        let mut vm_rss = 0;
-        let mut vm_swap = 0;
-
-        let file = File::open(&status_path).map_err(|e| {
-            if e.kind() == io::ErrorKind::NotFound {
-                SystemError::ProcessNotFound
-            } else {
-                SystemError::SyscallError(e)
-            }
-        })?;
+        read_proc_file_into(&status_path, buf)?;
+
+        Ok(Self::parse_status(pid, buf))
+    }
+
+    /// 解析/proc/[pid]/status的内容
+    ///
+    /// OOM分数不在status中，返回值中的oom_score和oom_score_adj为0
+    pub fn parse_status(pid: ProcessId, content: &str) -> Self {
+        let mut info = ProcessInfo {
+            pid,
+            name: String::new(),
+            state: String::new(),
+            ppid: 0,
+            uid: 0,
+            mem_info: ProcessMemInfo::default(),
+            exe_name: None,
+            limits: None,
+            cgroup_memory_low: None,
+            memory_cgroup: None,
+            completeness: Completeness::STATUS_ONLY,
+        };
+        info.parse_status_into(pid, content);
+
+        info
+    }
+
+    /// 解析status内容并覆盖当前的值，复用name和state的缓冲区
+    pub fn parse_status_into(&mut self, pid: ProcessId, content: &str) {
+        self.pid = pid;
+        self.name.clear();
+        self.state.clear();
+        self.ppid = 0;
+        self.uid = 0;
+        self.mem_info = ProcessMemInfo::default();
+        self.exe_name = None;
+        self.limits = None;
+        self.cgroup_memory_low = None;
+        self.memory_cgroup = None;
+        self.completeness = Completeness::STATUS_ONLY;
 
-        let reader = BufReader::new(file);
-        for line in reader.lines() {
-            let line = line?;
-            let parts: Vec<&str> = line.split(':').collect();
-            if parts.len() < 2 {
+        for line in content.lines() {
+            let Some((key, value)) = line.split_once(':') else {
                 continue;
-            }
+            };
+            let value = value.trim();
 
-            let key = parts[0].trim();
-            let value = parts[1].trim();
-
-            match key {
-                "Name" => name = value.to_string(),
-                "State" => state = value.to_string(),
-                "PPid" => ppid = value.parse().unwrap_or(0),
-                "VmPeak" => vm_peak = parse_kb_value(value),
-                "VmSize" => vm_size = parse_kb_value(value),
-                "VmRSS" => vm_rss = parse_kb_value(value),
-                "VmSwap" => vm_swap = parse_kb_value(value),
+            match key.trim() {
+                "Name" => self.name.push_str(value),
+                "State" => self.state.push_str(value),
+                "PPid" => self.ppid = value.parse().unwrap_or(0),
+                // Uid行包含real/effective/saved/fs四个值，取real uid
+                "Uid" => self.uid = value.split_whitespace()
+                    .next()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0),
+                "VmPeak" => self.mem_info.vm_peak = parse_kb_value(value),
+                "VmSize" => self.mem_info.vm_size = parse_kb_value(value),
+                "VmRSS" => self.mem_info.vm_rss = parse_kb_value(value),
+                "RssAnon" => self.mem_info.rss_anon = parse_kb_value(value),
+                "RssFile" => self.mem_info.rss_file = parse_kb_value(value),
+                "RssShmem" => self.mem_info.rss_shmem = parse_kb_value(value),
+                "VmSwap" => self.mem_info.vm_swap = parse_kb_value(value),
                 _ => {}
             }
         }
+    }
 
-        // 读取OOM分数
-        let oom_score = read_proc_value(&oom_score_path)?;
-        let oom_score_adj = read_proc_value(&oom_adj_path)?;
-
-        Ok(ProcessInfo {
+    /// 构造用于测试的进程信息
+    #[cfg(test)]
+    pub fn new_test(pid: ProcessId, name: &str, rss: u64, oom_score_adj: i32) -> Self {
+        ProcessInfo {
             pid,
-            name,
-            state,
-            ppid,
+            name: name.to_string(),
+            state: "S".to_string(),
+            ppid: 1,
+            uid: 0,
             mem_info: ProcessMemInfo {
-                vm_peak,
-                vm_size,
-                vm_rss,
-                vm_swap,
-                oom_score,
+                vm_peak: rss,
+                vm_size: rss,
+                vm_rss: rss,
+                rss_anon: rss,
+                rss_file: 0,
+                rss_shmem: 0,
+                vm_swap: 0,
+                oom_score: 0,
                 oom_score_adj,
             },
-        })
+            exe_name: None,
+            limits: None,
+            cgroup_memory_low: None,
+            memory_cgroup: None,
+            completeness: Completeness::COMPLETE,
+        }
+    }
+
+    /// RSS是否已经达到RLIMIT_RSS软限制，没有读取limits或没有限制时为false
+    ///
+    /// 这样的进程无法继续增长，通常不是压力持续上升的原因
+    pub fn at_rss_limit(&self) -> bool {
+        match self.limits.and_then(|limits| limits.rss) {
+            Some(limit) => self.mem_info.vm_rss.saturating_mul(1024) >= limit,
+            None => false,
+        }
+    }
+
+    /// 按RSS的匿名/文件构成和swap对进程分类
+    ///
+    /// 依次判断：总占用很小为Tiny，swap多于RSS为SwappedOut，
+    /// 文件映射页多于匿名页和共享内存之和为CacheHeavy，否则为AnonHeavy
+    pub fn memory_profile(&self) -> MemoryProfile {
+        let mem = &self.mem_info;
+        if mem.vm_rss.saturating_add(mem.vm_swap) < TINY_PROFILE_KB {
+            return MemoryProfile::Tiny;
+        }
+        if mem.vm_swap > mem.vm_rss {
+            return MemoryProfile::SwappedOut;
+        }
+
+        let anon = mem.rss_anon.saturating_add(mem.rss_shmem);
+        if anon == 0 && mem.rss_file == 0 {
+            MemoryProfile::Unknown
+        } else if mem.rss_file > anon {
+            MemoryProfile::CacheHeavy
+        } else {
+            MemoryProfile::AnonHeavy
+        }
     }
 
     /// 判断进程是否可以被OOM killer终止
     pub fn is_oomable(&self) -> bool {
         // 系统进程通常不应该被OOM killer终止
-        !self.name.starts_with('[') && 
-        self.oom_score_adj > -1000 &&
-        self.state != "Z" // 不终止僵尸进程
+        !self.name.starts_with('[') &&
+        self.mem_info.oom_score_adj > OOM_SCORE_ADJ_MIN &&
+        !self.state.starts_with('Z') // 不终止僵尸进程，state形如 "Z (zombie)"
     }
 }
 
+/// 读取进程的可执行文件名，没有权限、进程已退出或没有可执行文件（内核线程）时为None
+pub fn read_exe_name_in(proc_root: &Path, pid: ProcessId) -> Option<String> {
+    let target = std::fs::read_link(proc_root.join(pid.as_raw().to_string()).join("exe")).ok()?;
+    exe_basename(&target)
+}
+
+/// 读取进程的资源限制，没有权限或进程已退出时为None
+pub fn read_limits_in(proc_root: &Path, pid: ProcessId) -> Option<ResourceLimits> {
+    let content = read_proc_file(&proc_root.join(pid.as_raw().to_string()).join("limits")).ok()?;
+    Some(ResourceLimits::parse(&content))
+}
+
+/// 从 /proc/[pid]/exe 的链接目标中取出文件名
+///
+/// 可执行文件被替换或删除后，内核在目标后面附加 " (deleted)"，这里去掉该后缀
+pub fn exe_basename(target: &Path) -> Option<String> {
+    let name = target.file_name()?.to_string_lossy();
+    let name = name.strip_suffix(" (deleted)").unwrap_or(&name);
+    (!name.is_empty()).then(|| name.to_string())
+}
+
 /// 解析/proc中的KB值（例如："1024 kB"）
 fn parse_kb_value(value: &str) -> u64 {
     value.split_whitespace()
@@ -116,28 +381,112 @@ fn parse_kb_value(value: &str) -> u64 {
         .unwrap_or(0)
 }
 
-/// 读取/proc中的单个数值
-fn read_proc_value(path: &str) -> Result<i32> {
-    let content = std::fs::read_to_string(path).map_err(|e| {
-        if e.kind() == io::ErrorKind::NotFound {
-            SystemError::ProcessNotFound
-        } else {
-            SystemError::SyscallError(e)
-        }
+/// 解析/proc中的单个数值
+fn parse_proc_value(content: &[u8]) -> Result<i32> {
+    std::str::from_utf8(content)
+        .ok()
+        .and_then(|content| content.trim().parse().ok())
+        .ok_or_else(|| SystemError::SyscallError(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "Invalid proc value"
+        )))
+}
+
+/// 读取/proc下的文件，大小受 `max_proc_file_bytes()` 限制
+pub(crate) fn read_proc_file(path: &Path) -> Result<String> {
+    let mut content = String::new();
+    read_proc_file_into(path, &mut content)?;
+    Ok(content)
+}
+
+/// 读取/proc下的文件到 `buf`，先清空 `buf`，复用其容量
+fn read_proc_file_into(path: &Path, buf: &mut String) -> Result<()> {
+    let file = std::fs::File::open(path).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => SystemError::ProcessNotFound,
+        io::ErrorKind::PermissionDenied => SystemError::PermissionDenied,
+        _ => SystemError::SyscallError(e),
     })?;
-    
-    content.trim().parse().map_err(|_| {
+    read_bounded(file, buf)
+}
+
+/// 读取/proc中的单个数值
+fn read_proc_value(path: &Path) -> Result<i32> {
+    read_proc_value_with(path, &mut String::new())
+}
+
+/// 读取/proc中的单个数值，内容读入 `buf`
+fn read_proc_value_with(path: &Path, buf: &mut String) -> Result<i32> {
+    read_proc_file_into(path, buf)?;
+
+    let value = buf.trim().parse().map_err(|_| {
         SystemError::SyscallError(io::Error::new(
             io::ErrorKind::InvalidData,
             "Invalid proc value"
         ))
-    })
+    })?;
+    let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    Ok(clamp_oom_value(name, value))
 }
 
 /// 获取系统中所有进程的列表
 pub fn get_all_processes() -> Result<Vec<ProcessInfo>> {
-    let proc_dir = Path::new("/proc");
+    get_all_processes_in(Path::new(DEFAULT_PROC_ROOT))
+}
+
+/// 获取指定proc根目录下所有进程的列表
+pub fn get_all_processes_in(proc_dir: &Path) -> Result<Vec<ProcessInfo>> {
+    scan_processes(proc_dir, ProcessInfo::from_pid_with)
+}
+
+/// 进程列表的来源
+///
+/// 选择器默认直接扫描 /proc；测试、沙箱环境或监控其他命名空间中的目标时，
+/// 可以通过 `ProcessSelector::with_process_source` 提供其他来源。
+/// 返回的记录应当已经包含OOM分数，`completeness` 表示哪些字段可信
+pub trait ProcessSource: Send {
+    /// 列出当前的所有进程
+    fn processes(&self) -> Result<Vec<ProcessInfo>>;
+}
+
+/// 从proc文件系统读取完整进程信息的来源
+#[derive(Debug, Clone)]
+pub struct ProcfsSource {
+    proc_root: PathBuf,
+}
+
+impl ProcfsSource {
+    /// 使用指定的proc根目录
+    pub fn new(proc_root: impl Into<PathBuf>) -> Self {
+        Self { proc_root: proc_root.into() }
+    }
+}
+
+impl Default for ProcfsSource {
+    fn default() -> Self {
+        Self::new(DEFAULT_PROC_ROOT)
+    }
+}
+
+impl ProcessSource for ProcfsSource {
+    fn processes(&self) -> Result<Vec<ProcessInfo>> {
+        get_all_processes_in(&self.proc_root)
+    }
+}
+
+/// 获取指定proc根目录下所有进程的列表，只读取status
+///
+/// 用于先做廉价的过滤，再对剩余进程补充OOM分数
+pub fn list_processes_in(proc_dir: &Path) -> Result<Vec<ProcessInfo>> {
+    scan_processes(proc_dir, ProcessInfo::from_status_with)
+}
+
+/// 逐个读取pid目录，所有进程共用一个文件内容缓冲区
+fn scan_processes(
+    proc_dir: &Path,
+    read: impl Fn(&Path, ProcessId, &mut String) -> Result<ProcessInfo>,
+) -> Result<Vec<ProcessInfo>> {
     let mut processes = Vec::new();
+    let mut buf = String::with_capacity(4096);
 
     for entry in proc_dir.read_dir().map_err(SystemError::SyscallError)? {
         let entry = entry.map_err(SystemError::SyscallError)?;
@@ -147,7 +496,7 @@ pub fn get_all_processes() -> Result<Vec<ProcessInfo>> {
         if let Some(pid_str) = file_name.to_str() {
             if let Ok(pid_num) = pid_str.parse::<i32>() {
                 if let Some(pid) = ProcessId::new(pid_num) {
-                    if let Ok(info) = ProcessInfo::from_pid(pid) {
+                    if let Ok(info) = read(proc_dir, pid, &mut buf) {
                         processes.push(info);
                     }
                 }
@@ -158,6 +507,116 @@ pub fn get_all_processes() -> Result<Vec<ProcessInfo>> {
     Ok(processes)
 }
 
+/// 读取进程的oom_score和oom_score_adj
+pub fn read_oom_scores_in(proc_root: &Path, pid: ProcessId) -> Result<(i32, i32)> {
+    let pid_dir = proc_root.join(pid.as_raw().to_string());
+    let oom_score = read_proc_value(&pid_dir.join("oom_score"))?;
+    let oom_score_adj = read_proc_value(&pid_dir.join("oom_score_adj"))?;
+
+    Ok((oom_score, oom_score_adj))
+}
+
+/// 通过已打开的进程目录读取oom_score和oom_score_adj，不分配内存
+pub fn read_oom_scores_from(dir: &ProcessDir) -> Result<(i32, i32)> {
+    Ok((read_value_from(dir, "oom_score")?, read_value_from(dir, "oom_score_adj")?))
+}
+
+/// 通过已打开的进程目录读取单个数值，不分配内存
+fn read_value_from(dir: &ProcessDir, name: &str) -> Result<i32> {
+    let mut buf = [0u8; 16];
+    let len = dir.read(name, &mut buf)?;
+    Ok(clamp_oom_value(name, parse_proc_value(&buf[..len])?))
+}
+
+/// 最低的oom_score_adj，内核永远不会终止该进程
+pub const OOM_SCORE_ADJ_MIN: i32 = -1000;
+/// oom_score_adj的有效范围
+const OOM_SCORE_ADJ_RANGE: (i32, i32) = (OOM_SCORE_ADJ_MIN, 1000);
+/// oom_score的有效范围：oom_score_adj为正时内核报告的值可以超过1000
+const OOM_SCORE_RANGE: (i32, i32) = (0, 2000);
+
+/// 把读到的oom_score和oom_score_adj限制在有效范围内，超出时记录警告
+///
+/// 超出范围的值只可能来自损坏的读取，不限制会让评分失真。其他文件的值原样返回
+fn clamp_oom_value(name: &str, value: i32) -> i32 {
+    let (min, max) = match name {
+        "oom_score" => OOM_SCORE_RANGE,
+        "oom_score_adj" => OOM_SCORE_ADJ_RANGE,
+        _ => return value,
+    };
+    let clamped = value.clamp(min, max);
+    if clamped != value {
+        log::warn!("Read {} = {} outside [{}, {}], clamped to {}", name, value, min, max, clamped);
+    }
+    clamped
+}
+
+/// 按(pid, start_time)缓存的OOM分数
+///
+/// oom_score_adj很少变化，因此每个进程只读取一次；pid被复用时
+/// start_time不同，不会用到旧进程的值
+#[derive(Debug, Default)]
+pub struct OomScoreCache {
+    entries: HashMap<ProcessKey, (i32, i32)>,
+    reads: u64,
+}
+
+impl OomScoreCache {
+    /// 创建空缓存
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 为进程填充oom_score和oom_score_adj，缓存未命中时才通过进程目录读取
+    ///
+    /// `key` 应当读自同一个 `dir`，保证分数属于同一个进程实例。
+    /// 只有一个文件无法读取（例如权限不足）时仍然返回Ok，读取结果记录在 `process.completeness` 中，
+    /// 这样的结果不缓存；进程已经退出时返回错误
+    pub fn enrich(&mut self, dir: &ProcessDir, key: ProcessKey, process: &mut ProcessInfo) -> Result<()> {
+        let (oom_score, oom_score_adj) = match self.entries.get(&key) {
+            Some(&(oom_score, oom_score_adj)) => (Ok(oom_score), Ok(oom_score_adj)),
+            None => {
+                let scores = (read_value_from(dir, "oom_score"), read_value_from(dir, "oom_score_adj"));
+                self.reads += 1;
+                if let (Ok(oom_score), Ok(oom_score_adj)) = &scores {
+                    self.entries.insert(key, (*oom_score, *oom_score_adj));
+                }
+                scores
+            }
+        };
+
+        if let (Err(SystemError::ProcessNotFound), _) | (_, Err(SystemError::ProcessNotFound)) = (&oom_score, &oom_score_adj) {
+            return Err(SystemError::ProcessNotFound);
+        }
+        process.completeness.oom_score_ok = oom_score.is_ok();
+        process.completeness.oom_adj_ok = oom_score_adj.is_ok();
+        process.mem_info.oom_score = oom_score.unwrap_or(0);
+        process.mem_info.oom_score_adj = oom_score_adj.unwrap_or(0);
+
+        Ok(())
+    }
+
+    /// 丢弃不在 `live` 中的进程（已退出或不再是候选）
+    pub fn retain(&mut self, live: &HashSet<ProcessKey>) {
+        self.entries.retain(|key, _| live.contains(key));
+    }
+
+    /// 从proc读取OOM分数的次数
+    pub fn reads(&self) -> u64 {
+        self.reads
+    }
+
+    /// 缓存的进程数
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 缓存是否为空
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -169,6 +628,283 @@ mod tests {
         assert_eq!(parse_kb_value("invalid"), 0);
     }
 
+    #[test]
+    fn test_parse_status_fixture() {
+        let pid = ProcessId::new(2817).unwrap();
+        let info = ProcessInfo::parse_status(pid, include_str!("../../tests/fixtures/proc/status"));
+
+        assert_eq!(info.name, "postgres");
+        assert_eq!(info.state, "S (sleeping)");
+        assert_eq!(info.ppid, 1);
+        assert_eq!(info.uid, 999);
+        assert_eq!(info.mem_info.vm_peak, 221488);
+        assert_eq!(info.mem_info.vm_size, 219364);
+        assert_eq!(info.mem_info.vm_rss, 28604);
+        assert_eq!(info.mem_info.rss_anon, 104);
+        assert_eq!(info.mem_info.rss_file, 1144);
+        assert_eq!(info.mem_info.rss_shmem, 0);
+        assert_eq!(info.mem_info.vm_swap, 1536);
+    }
+
+    #[test]
+    fn test_memory_profile() {
+        let pid = ProcessId::new(100).unwrap();
+        let profile = |status: &str| ProcessInfo::parse_status(pid, status).memory_profile();
+
+        assert_eq!(
+            profile("VmRSS:	4096 kB
+RssAnon:	4000 kB
+RssFile:	96 kB
+VmSwap:	0 kB
+"),
+            MemoryProfile::Tiny
+        );
+        assert_eq!(
+            profile("VmRSS:	1024 kB
+RssAnon:	512 kB
+RssFile:	512 kB
+VmSwap:	819200 kB
+"),
+            MemoryProfile::SwappedOut
+        );
+        assert_eq!(
+            profile("VmRSS:	524288 kB
+RssAnon:	102400 kB
+RssFile:	409600 kB
+RssShmem:	12288 kB
+"),
+            MemoryProfile::CacheHeavy
+        );
+        assert_eq!(
+            profile("VmRSS:	524288 kB
+RssAnon:	409600 kB
+RssFile:	102400 kB
+RssShmem:	12288 kB
+VmSwap:	1024 kB
+"),
+            MemoryProfile::AnonHeavy
+        );
+        // 共享内存和匿名页一样只有终止才能释放
+        assert_eq!(
+            profile("VmRSS:	524288 kB
+RssAnon:	12288 kB
+RssFile:	204800 kB
+RssShmem:	307200 kB
+"),
+            MemoryProfile::AnonHeavy
+        );
+        assert_eq!(profile("VmRSS:	524288 kB
+"), MemoryProfile::Unknown);
+
+        assert_eq!(serde_json::to_string(&MemoryProfile::SwappedOut).unwrap(), "\"swapped-out\"");
+    }
+
+    #[test]
+    fn test_is_oomable() {
+        let pid = ProcessId::new(100).unwrap();
+        let process = |name: &str, state: &str, oom_score_adj: i32| ProcessInfo {
+            state: state.to_string(),
+            ..ProcessInfo::new_test(pid, name, 4096, oom_score_adj)
+        };
+
+        assert!(process("worker", "S (sleeping)", 0).is_oomable());
+        assert!(process("worker", "R (running)", OOM_SCORE_ADJ_MIN + 1).is_oomable());
+        assert!(!process("worker", "S (sleeping)", OOM_SCORE_ADJ_MIN).is_oomable());
+        assert!(!process("worker", "Z (zombie)", 0).is_oomable());
+        assert!(!process("[kworker/0:1]", "I (idle)", 0).is_oomable());
+    }
+
+    #[test]
+    fn test_read_current_exe_name() {
+        let pid = ProcessId::new(std::process::id() as i32).unwrap();
+        let expected = std::env::current_exe().unwrap();
+        let expected = expected.file_name().unwrap().to_str().unwrap();
+
+        assert_eq!(read_exe_name_in(Path::new(DEFAULT_PROC_ROOT), pid).as_deref(), Some(expected));
+        assert_eq!(ProcessInfo::from_pid(pid).unwrap().exe_name.as_deref(), Some(expected));
+
+        assert_eq!(exe_basename(Path::new("/usr/bin/python3.11 (deleted)")).as_deref(), Some("python3.11"));
+        assert_eq!(exe_basename(Path::new("/")), None);
+        // 链接不存在时没有可执行文件名
+        let root = tempfile::tempdir().unwrap();
+        assert_eq!(read_exe_name_in(root.path(), pid), None);
+    }
+
+    #[test]
+    fn test_parse_limits() {
+        let content = "\
+Limit                     Soft Limit           Hard Limit           Units     
+Max cpu time              unlimited            unlimited            seconds   
+Max resident set          536870912            unlimited            bytes     
+Max processes             63421                63421                processes 
+Max locked memory         unlimited            unlimited            bytes     
+";
+        let limits = ResourceLimits::parse(content);
+        assert_eq!(limits.rss, Some(512 * 1024 * 1024));
+        assert_eq!(limits.memlock, None);
+
+        // RSS（kB）达到软限制时才算到达上限
+        let mut process = ProcessInfo::new_test(ProcessId::new(100).unwrap(), "capped", 256 * 1024, 0);
+        assert!(!process.at_rss_limit());
+        process.limits = Some(limits);
+        assert!(!process.at_rss_limit());
+        process.mem_info.vm_rss = 512 * 1024;
+        assert!(process.at_rss_limit());
+        process.limits = Some(ResourceLimits::default());
+        assert!(!process.at_rss_limit());
+
+        let root = tempfile::tempdir().unwrap();
+        let pid = ProcessId::new(100).unwrap();
+        assert_eq!(read_limits_in(root.path(), pid), None);
+        std::fs::create_dir(root.path().join("100")).unwrap();
+        std::fs::write(root.path().join("100").join("limits"), content).unwrap();
+        assert_eq!(read_limits_in(root.path(), pid), Some(limits));
+    }
+
+    #[test]
+    fn test_partial_reads_tracked_in_completeness() {
+        use crate::linux::proc_stat::ProcessKey;
+
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("300");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("status"), "Name:\tdaemon\nVmRSS:\t4096 kB\n").unwrap();
+        let pid = ProcessId::new(300).unwrap();
+
+        // status可读，OOM分数都读不到
+        let info = ProcessInfo::from_pid_in(root.path(), pid).unwrap();
+        assert_eq!(info.name, "daemon");
+        assert_eq!(info.completeness, Completeness::STATUS_ONLY);
+        assert!(!info.completeness.is_complete());
+
+        // 只有oom_score_adj可读
+        std::fs::write(dir.join("oom_score_adj"), "-500\n").unwrap();
+        let info = ProcessInfo::from_pid_in(root.path(), pid).unwrap();
+        assert!(info.completeness.status_ok && info.completeness.oom_adj_ok);
+        assert!(!info.completeness.oom_score_ok);
+        assert_eq!(info.mem_info.oom_score_adj, -500);
+
+        // 通过缓存读取：无法解析的oom_score记为缺失且不缓存
+        std::fs::write(dir.join("oom_score"), "unreadable\n").unwrap();
+        let process_dir = ProcessDir::open(root.path(), pid).unwrap();
+        let key = ProcessKey { pid, start_time: 1 };
+        let mut cache = OomScoreCache::new();
+        let mut info = ProcessInfo::parse_status(pid, "Name:\tdaemon\n");
+        assert_eq!(info.completeness, Completeness::STATUS_ONLY);
+        cache.enrich(&process_dir, key, &mut info).unwrap();
+        assert!(info.completeness.oom_adj_ok && !info.completeness.oom_score_ok);
+        assert_eq!(cache.len(), 0);
+
+        std::fs::write(dir.join("oom_score"), "700\n").unwrap();
+        cache.enrich(&process_dir, key, &mut info).unwrap();
+        assert!(info.completeness.is_complete());
+        assert_eq!(info.mem_info.oom_score, 700);
+        assert_eq!(cache.len(), 1);
+
+        // 文件消失视为进程已退出
+        let gone = ProcessKey { pid, start_time: 2 };
+        std::fs::remove_file(dir.join("oom_score_adj")).unwrap();
+        assert!(matches!(cache.enrich(&process_dir, gone, &mut info), Err(SystemError::ProcessNotFound)));
+    }
+
+    #[test]
+    fn test_process_info_keyed_on_pid() {
+        use std::collections::{BTreeSet, HashSet};
+
+        let pid = |raw| ProcessId::new(raw).unwrap();
+        let before = ProcessInfo::parse_status(pid(20), "Name:\tworker\nVmRSS:\t100 kB\n");
+        let after = ProcessInfo::parse_status(pid(20), "Name:\tworker\nVmRSS:\t900 kB\n");
+        let other = ProcessInfo::parse_status(pid(3), "Name:\tworker\nVmRSS:\t100 kB\n");
+
+        // 同一pid的两次读取视为同一个进程
+        assert_eq!(before, after);
+        assert_ne!(before, other);
+
+        let hashed: HashSet<ProcessInfo> = [before.clone(), after, other.clone()].into_iter().collect();
+        assert_eq!(hashed.len(), 2);
+
+        let ordered: Vec<i32> = [before, other].into_iter()
+            .collect::<BTreeSet<_>>()
+            .iter()
+            .map(|process| process.pid.as_raw())
+            .collect();
+        assert_eq!(ordered, vec![3, 20]);
+    }
+
+    #[test]
+    fn test_parse_proc_value() {
+        assert_eq!(parse_proc_value(b"-500\n").unwrap(), -500);
+        assert!(parse_proc_value(b"").is_err());
+        assert!(parse_proc_value(b"not-a-number\n").is_err());
+    }
+
+    #[test]
+    fn test_out_of_range_oom_values_clamped() {
+        use crate::linux::proc_stat::ProcessKey;
+
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("400");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("status"), "Name:\tcorrupt\nVmRSS:\t4096 kB\n").unwrap();
+        std::fs::write(dir.join("oom_score"), "-7\n").unwrap();
+        std::fs::write(dir.join("oom_score_adj"), "31337\n").unwrap();
+        let pid = ProcessId::new(400).unwrap();
+
+        let info = ProcessInfo::from_pid_in(root.path(), pid).unwrap();
+        assert_eq!(info.mem_info.oom_score, 0);
+        assert_eq!(info.mem_info.oom_score_adj, 1000);
+        assert_eq!(read_oom_scores_in(root.path(), pid).unwrap(), (0, 1000));
+
+        std::fs::write(dir.join("oom_score"), "2500\n").unwrap();
+        std::fs::write(dir.join("oom_score_adj"), "-1001\n").unwrap();
+        let process_dir = ProcessDir::open(root.path(), pid).unwrap();
+        assert_eq!(read_oom_scores_from(&process_dir).unwrap(), (2000, -1000));
+        let mut info = ProcessInfo::parse_status(pid, "Name:\tcorrupt\n");
+        OomScoreCache::new()
+            .enrich(&process_dir, ProcessKey { pid, start_time: 1 }, &mut info)
+            .unwrap();
+        assert_eq!((info.mem_info.oom_score, info.mem_info.oom_score_adj), (2000, -1000));
+
+        // 范围内的值和其他文件不受影响
+        assert_eq!(clamp_oom_value("oom_score_adj", -500), -500);
+        assert_eq!(clamp_oom_value("oom_score", 1500), 1500);
+        assert_eq!(clamp_oom_value("oom_adj", 99), 99);
+    }
+
+    #[test]
+    fn test_oversized_proc_file_rejected() {
+        use crate::ffi::DEFAULT_MAX_PROC_FILE_BYTES;
+        use crate::linux::proc_stat::ProcessStat;
+
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("77");
+        std::fs::create_dir(&dir).unwrap();
+        // 有效内容之后跟着超过上限的填充
+        let padding = "x".repeat(DEFAULT_MAX_PROC_FILE_BYTES);
+        std::fs::write(dir.join("status"), format!("Name:\tbloat\nVmRSS:\t1024 kB\n{}", padding)).unwrap();
+        std::fs::write(dir.join("stat"), format!("77 (bloat) S 1 77 77 0 -1 0 {}", padding)).unwrap();
+        let pid = ProcessId::new(77).unwrap();
+
+        assert!(matches!(
+            ProcessInfo::from_status_in(root.path(), pid),
+            Err(SystemError::ProcFileTooLarge(DEFAULT_MAX_PROC_FILE_BYTES))
+        ));
+        assert!(matches!(
+            ProcessStat::from_pid_in(root.path(), pid),
+            Err(SystemError::ProcFileTooLarge(_))
+        ));
+
+        let process = ProcessDir::open(root.path(), pid).unwrap();
+        let mut buf = String::new();
+        assert!(matches!(process.read_to_string("status", &mut buf), Err(SystemError::ProcFileTooLarge(_))));
+        assert!(buf.is_empty());
+
+        // 恰好等于上限的文件可以读取
+        std::fs::write(dir.join("status"), "x".repeat(DEFAULT_MAX_PROC_FILE_BYTES)).unwrap();
+        process.read_to_string("status", &mut buf).unwrap();
+        assert_eq!(buf.len(), DEFAULT_MAX_PROC_FILE_BYTES);
+    }
+
     #[test]
     fn test_get_current_process_info() {
         let current_pid = std::process::id() as i32;
@@ -0,0 +1,536 @@
+#![cfg(feature = "proc-connector")]
+
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use crate::ffi::types::{ProcessId, SystemError, Result};
+use crate::ffi::NetlinkSocket;
+use crate::linux::proc::ProcessInfo;
+use crate::linux::process_table::ProcessTable;
+
+// <linux/netlink.h>
+const NLMSG_HDRLEN: usize = 16;
+const NLMSG_DONE: u16 = 3;
+const NLMSG_OVERRUN: u16 = 4;
+
+// <linux/connector.h>
+const CN_IDX_PROC: u32 = 1;
+const CN_VAL_PROC: u32 = 1;
+/// struct cn_msg 的头部长度（不含数据）
+const CN_MSG_HDRLEN: usize = 20;
+
+// <linux/cn_proc.h>
+const PROC_CN_MCAST_LISTEN: u32 = 1;
+const PROC_CN_MCAST_IGNORE: u32 = 2;
+/// struct proc_event 中 event_data 之前的字段：what、cpu、timestamp_ns
+const PROC_EVENT_HDRLEN: usize = 16;
+const PROC_EVENT_FORK: u32 = 0x0000_0001;
+const PROC_EVENT_EXEC: u32 = 0x0000_0002;
+const PROC_EVENT_COMM: u32 = 0x0000_0200;
+const PROC_EVENT_EXIT: u32 = 0x8000_0000;
+
+/// 接收缓冲区大小，足够容纳一个数据报中的多条消息
+const RECV_BUFFER_SIZE: usize = 8192;
+
+/// 接收线程检查停止标志的间隔
+const RECV_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// 我们关心的 proc connector 事件
+///
+/// pid 为线程ID，tgid 为线程组（进程）ID
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProcEvent {
+    Fork { parent_tgid: i32, child_pid: i32, child_tgid: i32 },
+    Exec { pid: i32, tgid: i32 },
+    Comm { pid: i32, tgid: i32 },
+    Exit { pid: i32, tgid: i32, exit_code: u32 },
+}
+
+impl ProcEvent {
+    /// 事件涉及的进程，线程的事件返回 None
+    pub fn leader(&self) -> Option<ProcessId> {
+        let (pid, tgid) = match *self {
+            ProcEvent::Fork { child_pid, child_tgid, .. } => (child_pid, child_tgid),
+            ProcEvent::Exec { pid, tgid }
+            | ProcEvent::Comm { pid, tgid }
+            | ProcEvent::Exit { pid, tgid, .. } => (pid, tgid),
+        };
+
+        if pid == tgid {
+            ProcessId::new(pid)
+        } else {
+            None
+        }
+    }
+}
+
+/// 一个数据报的解析结果
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Datagram {
+    pub events: Vec<ProcEvent>,
+    /// 内核报告了消息丢失，需要全量对账
+    pub overrun: bool,
+}
+
+/// 解析一个 netlink 数据报，其中可能包含多条消息
+///
+/// 长度字段不合法的消息及其后的内容会被丢弃，不会越界读取
+pub fn parse_datagram(buf: &[u8]) -> Datagram {
+    let mut datagram = Datagram::default();
+    let mut offset = 0;
+
+    while offset + NLMSG_HDRLEN <= buf.len() {
+        let len = read_u32(buf, offset) as usize;
+        let msg_type = read_u16(buf, offset + 4);
+        if len < NLMSG_HDRLEN || offset + len > buf.len() {
+            break;
+        }
+
+        match msg_type {
+            NLMSG_DONE => {
+                if let Some(event) = parse_cn_msg(&buf[offset + NLMSG_HDRLEN..offset + len]) {
+                    datagram.events.push(event);
+                }
+            }
+            NLMSG_OVERRUN => datagram.overrun = true,
+            // NLMSG_NOOP、NLMSG_ERROR 等不携带事件
+            _ => {}
+        }
+
+        // 消息按4字节对齐
+        offset += (len + 3) & !3;
+    }
+
+    datagram
+}
+
+/// 解析 struct cn_msg，只接受来自 proc connector 的消息
+fn parse_cn_msg(payload: &[u8]) -> Option<ProcEvent> {
+    if payload.len() < CN_MSG_HDRLEN {
+        return None;
+    }
+    if read_u32(payload, 0) != CN_IDX_PROC || read_u32(payload, 4) != CN_VAL_PROC {
+        return None;
+    }
+
+    let data_len = read_u16(payload, 16) as usize;
+    let data = payload.get(CN_MSG_HDRLEN..CN_MSG_HDRLEN + data_len)?;
+    parse_proc_event(data)
+}
+
+/// 解析 struct proc_event
+fn parse_proc_event(data: &[u8]) -> Option<ProcEvent> {
+    if data.len() < PROC_EVENT_HDRLEN {
+        return None;
+    }
+    let what = read_u32(data, 0);
+    let event = &data[PROC_EVENT_HDRLEN..];
+    // 取 event_data 中第 index 个 32 位字段
+    let field = |index: usize| event.get(index * 4..index * 4 + 4).map(|_| read_u32(event, index * 4));
+
+    match what {
+        PROC_EVENT_FORK => Some(ProcEvent::Fork {
+            parent_tgid: field(1)? as i32,
+            child_pid: field(2)? as i32,
+            child_tgid: field(3)? as i32,
+        }),
+        PROC_EVENT_EXEC => Some(ProcEvent::Exec {
+            pid: field(0)? as i32,
+            tgid: field(1)? as i32,
+        }),
+        PROC_EVENT_COMM => Some(ProcEvent::Comm {
+            pid: field(0)? as i32,
+            tgid: field(1)? as i32,
+        }),
+        PROC_EVENT_EXIT => Some(ProcEvent::Exit {
+            pid: field(0)? as i32,
+            tgid: field(1)? as i32,
+            exit_code: field(2)?,
+        }),
+        _ => None,
+    }
+}
+
+/// 构造订阅或取消订阅的控制消息
+fn control_message(op: u32) -> Vec<u8> {
+    let data_len = 4;
+    let total = NLMSG_HDRLEN + CN_MSG_HDRLEN + data_len;
+    let mut msg = Vec::with_capacity(total);
+
+    // struct nlmsghdr
+    msg.extend_from_slice(&(total as u32).to_ne_bytes());
+    msg.extend_from_slice(&NLMSG_DONE.to_ne_bytes());
+    msg.extend_from_slice(&0u16.to_ne_bytes());
+    msg.extend_from_slice(&0u32.to_ne_bytes());
+    msg.extend_from_slice(&std::process::id().to_ne_bytes());
+    // struct cn_msg
+    msg.extend_from_slice(&CN_IDX_PROC.to_ne_bytes());
+    msg.extend_from_slice(&CN_VAL_PROC.to_ne_bytes());
+    msg.extend_from_slice(&0u32.to_ne_bytes());
+    msg.extend_from_slice(&0u32.to_ne_bytes());
+    msg.extend_from_slice(&(data_len as u16).to_ne_bytes());
+    msg.extend_from_slice(&0u16.to_ne_bytes());
+    // enum proc_cn_mcast_op
+    msg.extend_from_slice(&op.to_ne_bytes());
+
+    msg
+}
+
+fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_ne_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+fn read_u16(buf: &[u8], offset: usize) -> u16 {
+    u16::from_ne_bytes(buf[offset..offset + 2].try_into().unwrap())
+}
+
+/// 根据事件更新进程表
+pub fn apply_event(table: &mut ProcessTable, event: &ProcEvent) {
+    let Some(pid) = event.leader() else {
+        return;
+    };
+
+    match event {
+        // exec 和 comm 会改变进程名，fork 产生新进程，都重新读取status
+        ProcEvent::Fork { .. } | ProcEvent::Exec { .. } | ProcEvent::Comm { .. } => table.upsert(pid),
+        ProcEvent::Exit { .. } => table.remove(pid),
+    }
+}
+
+/// proc connector 的订阅连接
+pub struct ProcConnector {
+    socket: NetlinkSocket,
+    buf: Vec<u8>,
+}
+
+impl ProcConnector {
+    /// 连接 proc connector 并订阅进程事件
+    ///
+    /// # 错误
+    ///
+    /// 没有 CAP_NET_ADMIN 时返回 `SystemError::PermissionDenied`，
+    /// 内核未启用 CONFIG_PROC_EVENTS 时返回 `SystemError::SyscallError`
+    pub fn open() -> Result<Self> {
+        let socket = NetlinkSocket::bind(libc::NETLINK_CONNECTOR, CN_IDX_PROC)?;
+        socket.send(&control_message(PROC_CN_MCAST_LISTEN))?;
+        socket.set_read_timeout(RECV_TIMEOUT)?;
+
+        Ok(Self {
+            socket,
+            buf: vec![0; RECV_BUFFER_SIZE],
+        })
+    }
+
+    /// 接收下一个数据报，超时返回空结果
+    pub fn recv(&mut self) -> Result<Datagram> {
+        match self.socket.recv(&mut self.buf) {
+            Ok(n) => Ok(parse_datagram(&self.buf[..n])),
+            Err(SystemError::SyscallError(e)) => match e.raw_os_error() {
+                Some(libc::EAGAIN) | Some(libc::EINTR) => Ok(Datagram::default()),
+                // 接收缓冲区溢出，事件已经丢失
+                Some(libc::ENOBUFS) => Ok(Datagram { events: Vec::new(), overrun: true }),
+                _ => Err(SystemError::SyscallError(e)),
+            },
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl Drop for ProcConnector {
+    fn drop(&mut self) {
+        let _ = self.socket.send(&control_message(PROC_CN_MCAST_IGNORE));
+    }
+}
+
+/// 进程跟踪配置
+#[derive(Debug, Clone)]
+pub struct ProcTrackerConfig {
+    /// 即使连接正常也定期全量扫描对账的间隔
+    pub reconcile_interval: Duration,
+    /// 重新读取进程内存信息的间隔
+    pub refresh_interval: Duration,
+}
+
+impl Default for ProcTrackerConfig {
+    fn default() -> Self {
+        Self {
+            reconcile_interval: Duration::from_secs(60),
+            refresh_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// 通过 proc connector 增量维护进程表
+///
+/// connector 不可用（权限不足、内核不支持）或连接中断时，
+/// 退化为每次获取进程列表都全量扫描 /proc
+#[derive(Debug)]
+pub struct ProcessTracker {
+    config: ProcTrackerConfig,
+    table: Arc<Mutex<ProcessTable>>,
+    connected: Arc<AtomicBool>,
+    needs_rescan: Arc<AtomicBool>,
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl ProcessTracker {
+    /// 创建跟踪器，尝试连接 proc connector 并启动接收线程
+    pub fn start(proc_root: impl Into<PathBuf>, config: ProcTrackerConfig) -> Self {
+        let mut tracker = Self {
+            config,
+            table: Arc::new(Mutex::new(ProcessTable::new(proc_root))),
+            connected: Arc::new(AtomicBool::new(false)),
+            needs_rescan: Arc::new(AtomicBool::new(true)),
+            stop: Arc::new(AtomicBool::new(false)),
+            worker: None,
+        };
+
+        match ProcConnector::open() {
+            Ok(connector) => tracker.spawn_listener(connector),
+            Err(e) => log::info!(
+                "Process events unavailable ({:?}), falling back to full /proc scans",
+                e
+            ),
+        }
+
+        tracker
+    }
+
+    fn spawn_listener(&mut self, mut connector: ProcConnector) {
+        let table = Arc::clone(&self.table);
+        let connected = Arc::clone(&self.connected);
+        let needs_rescan = Arc::clone(&self.needs_rescan);
+        let stop = Arc::clone(&self.stop);
+
+        // 先订阅再扫描：订阅之后发生的变化都会以事件的形式到达
+        connected.store(true, Ordering::SeqCst);
+        let spawned = thread::Builder::new()
+            .name("oom-proc-events".to_string())
+            .spawn(move || {
+                while !stop.load(Ordering::SeqCst) {
+                    match connector.recv() {
+                        Ok(datagram) => {
+                            if datagram.overrun {
+                                log::debug!("Process event overrun, scheduling a full rescan");
+                                needs_rescan.store(true, Ordering::SeqCst);
+                            }
+                            if !datagram.events.is_empty() {
+                                let mut table = lock(&table);
+                                for event in &datagram.events {
+                                    apply_event(&mut table, event);
+                                }
+                            }
+                        }
+                        Err(e) => {
+                            log::warn!("Process event connection failed: {:?}", e);
+                            connected.store(false, Ordering::SeqCst);
+                            break;
+                        }
+                    }
+                }
+            });
+
+        match spawned {
+            Ok(worker) => self.worker = Some(worker),
+            Err(e) => {
+                log::warn!("Failed to start process event listener: {}", e);
+                self.connected.store(false, Ordering::SeqCst);
+            }
+        }
+    }
+
+    /// 是否正在接收进程事件
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::SeqCst)
+    }
+
+    /// 获取当前的进程列表（只包含status中的信息）
+    ///
+    /// 未连接、发生过事件丢失或到达对账间隔时全量扫描，
+    /// 否则只重新读取内存信息过期的进程
+    pub fn processes(&self) -> Result<Vec<ProcessInfo>> {
+        let mut table = lock(&self.table);
+
+        let reconcile_due = !matches!(
+            table.last_full_scan(),
+            Some(at) if at.elapsed() < self.config.reconcile_interval
+        );
+        if !self.is_connected() || reconcile_due || self.needs_rescan.swap(false, Ordering::SeqCst) {
+            table.full_scan()?;
+        } else {
+            table.refresh_stale(self.config.refresh_interval);
+        }
+
+        Ok(table.processes().cloned().collect())
+    }
+
+    /// 最近一次全量扫描距今的时间
+    pub fn since_full_scan(&self) -> Option<Duration> {
+        lock(&self.table).last_full_scan().map(|at| Instant::now().duration_since(at))
+    }
+}
+
+impl Drop for ProcessTracker {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // 接收线程最多在一个超时周期后退出
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// 获取进程表的锁，接收线程 panic 时继续使用其中的数据
+fn lock(table: &Mutex<ProcessTable>) -> MutexGuard<'_, ProcessTable> {
+    table.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+    use crate::linux::proc::DEFAULT_PROC_ROOT;
+
+    /// 读取十六进制编码的事件流夹具，每行一个数据报
+    fn load_fixture(content: &str) -> Vec<Vec<u8>> {
+        content.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                (0..line.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&line[i..i + 2], 16).unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn recorded_stream() -> Vec<Vec<u8>> {
+        load_fixture(include_str!("../../tests/fixtures/proc_connector/fork-exec-exit.hex"))
+    }
+
+    fn write_status(root: &Path, pid: i32, name: &str) {
+        let dir = root.join(pid.to_string());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("status"),
+            format!("Name:\t{}\nState:\tS (sleeping)\nVmRSS:\t1024 kB\n", name),
+        ).unwrap();
+    }
+
+    #[test]
+    fn test_parse_recorded_stream() {
+        let events: Vec<ProcEvent> = recorded_stream()
+            .iter()
+            .flat_map(|datagram| parse_datagram(datagram).events)
+            .collect();
+
+        assert_eq!(events, vec![
+            ProcEvent::Fork { parent_tgid: 1000, child_pid: 4242, child_tgid: 4242 },
+            ProcEvent::Exec { pid: 4242, tgid: 4242 },
+            ProcEvent::Comm { pid: 4242, tgid: 4242 },
+            ProcEvent::Fork { parent_tgid: 4242, child_pid: 4243, child_tgid: 4242 },
+            ProcEvent::Exit { pid: 4243, tgid: 4242, exit_code: 0 },
+            ProcEvent::Exit { pid: 4242, tgid: 4242, exit_code: 0 },
+        ]);
+
+        // 线程事件不对应进程
+        assert_eq!(events[3].leader(), None);
+        assert_eq!(events[0].leader(), ProcessId::new(4242));
+    }
+
+    #[test]
+    fn test_multiple_messages_in_one_datagram() {
+        let stream = recorded_stream();
+        let mut combined = stream[1].clone();
+        combined.extend_from_slice(&stream[2]);
+
+        let datagram = parse_datagram(&combined);
+        assert_eq!(datagram.events.len(), 2);
+        assert!(!datagram.overrun);
+    }
+
+    #[test]
+    fn test_truncated_datagram() {
+        let stream = recorded_stream();
+
+        // 任意位置截断都不会 panic，也不会产生半条事件
+        for datagram in &stream {
+            for len in 0..datagram.len() {
+                assert!(parse_datagram(&datagram[..len]).events.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn test_overrun_message() {
+        let mut msg = Vec::new();
+        msg.extend_from_slice(&(NLMSG_HDRLEN as u32).to_ne_bytes());
+        msg.extend_from_slice(&NLMSG_OVERRUN.to_ne_bytes());
+        msg.extend_from_slice(&[0; 10]);
+
+        assert!(parse_datagram(&msg).overrun);
+    }
+
+    #[test]
+    fn test_control_message_layout() {
+        let msg = control_message(PROC_CN_MCAST_LISTEN);
+
+        assert_eq!(msg.len(), 40);
+        assert_eq!(read_u32(&msg, 0), 40);
+        assert_eq!(read_u16(&msg, 4), NLMSG_DONE);
+        assert_eq!(read_u32(&msg, NLMSG_HDRLEN), CN_IDX_PROC);
+        assert_eq!(read_u32(&msg, NLMSG_HDRLEN + 4), CN_VAL_PROC);
+        assert_eq!(read_u16(&msg, NLMSG_HDRLEN + 16), 4);
+        assert_eq!(read_u32(&msg, NLMSG_HDRLEN + CN_MSG_HDRLEN), PROC_CN_MCAST_LISTEN);
+    }
+
+    #[test]
+    fn test_replay_stream_into_table() {
+        let root = tempfile::tempdir().unwrap();
+        write_status(root.path(), 1000, "bash");
+        let mut table = ProcessTable::new(root.path());
+        table.full_scan().unwrap();
+
+        let stream = recorded_stream();
+        let replay = |table: &mut ProcessTable, index: usize| {
+            for event in parse_datagram(&stream[index]).events {
+                apply_event(table, &event);
+            }
+        };
+
+        // fork 时子进程还是 bash，exec 之后变为 python3
+        write_status(root.path(), 4242, "bash");
+        replay(&mut table, 1);
+        write_status(root.path(), 4242, "python3");
+        replay(&mut table, 2);
+        replay(&mut table, 3);
+
+        // 线程的 fork 和 exit 不会影响进程表
+        replay(&mut table, 4);
+        replay(&mut table, 6);
+        assert_eq!(table.len(), 2);
+        let mut names: Vec<String> = table.processes().map(|p| p.name.clone()).collect();
+        names.sort();
+        assert_eq!(names, vec!["bash", "python3"]);
+
+        replay(&mut table, 7);
+        assert!(!table.contains(ProcessId::new(4242).unwrap()));
+        assert!(table.contains(ProcessId::new(1000).unwrap()));
+    }
+
+    #[test]
+    fn test_tracker_lists_processes() {
+        // 无论 connector 是否可用，跟踪器都应返回完整的进程列表
+        let tracker = ProcessTracker::start(DEFAULT_PROC_ROOT, ProcTrackerConfig::default());
+        let processes = tracker.processes().unwrap();
+
+        let current_pid = std::process::id() as i32;
+        assert!(processes.iter().any(|p| p.pid.as_raw() == current_pid));
+        assert!(tracker.since_full_scan().is_some());
+    }
+}
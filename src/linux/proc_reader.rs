@@ -0,0 +1,338 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+use crate::ffi::types::{ProcessId, Result};
+use crate::ffi::{read_bounded, PidDir, ProcessDir};
+use crate::linux::cgroup::CgroupInfo;
+use crate::linux::proc::ProcessInfo;
+use crate::linux::proc_stat::ProcessStat;
+
+/// 在多轮扫描之间复用缓冲区的 /proc 读取器
+///
+/// 目录流和文件内容的缓冲区都在第一轮扫描后保留下来，
+/// 稳定状态下的扫描不再为每个进程分配内存。
+/// 进程文件通过 openat 相对于proc根目录打开，不拼接路径
+#[derive(Debug)]
+pub struct ProcReader {
+    proc_root: PathBuf,
+    dir: Option<PidDir>,
+    content: String,
+}
+
+impl ProcReader {
+    /// 创建读取指定proc根目录的读取器
+    pub fn new(proc_root: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+            dir: None,
+            content: String::with_capacity(4096),
+        }
+    }
+
+    /// 读取器使用的proc根目录
+    pub fn proc_root(&self) -> &Path {
+        &self.proc_root
+    }
+
+    /// 打开进程的 /proc/<pid> 目录，用于对同一个进程实例读取多个文件
+    pub fn open_process(&mut self, pid: ProcessId) -> Result<ProcessDir> {
+        let dir = match &mut self.dir {
+            Some(dir) => dir,
+            None => self.dir.insert(PidDir::open(&self.proc_root)?),
+        };
+        dir.open_process(pid)
+    }
+
+    /// 打开进程目录并通过它读取status到 `info`
+    ///
+    /// 返回的目录用于继续读取同一个进程实例的其他文件
+    pub fn read_process(&mut self, pid: ProcessId, info: &mut ProcessInfo) -> Result<ProcessDir> {
+        let dir = self.open_process(pid)?;
+        dir.read_to_string("status", &mut self.content)?;
+        info.parse_status_into(pid, &self.content);
+        Ok(dir)
+    }
+
+    /// 通过进程目录读取stat
+    pub fn read_stat(&mut self, dir: &ProcessDir, pid: ProcessId) -> Result<ProcessStat> {
+        ProcessStat::from_dir(dir, pid, &mut self.content)
+    }
+
+    /// 通过进程目录读取 memory cgroup
+    pub fn read_cgroup(&mut self, dir: &ProcessDir) -> Result<CgroupInfo> {
+        dir.read_to_string("cgroup", &mut self.content)?;
+        CgroupInfo::parse(&self.content)
+    }
+
+    /// 枚举所有进程的pid到 `out` 中，`priority` 中的pid排在最前
+    ///
+    /// 只读取目录项，不打开任何进程文件。`priority` 中的pid不检查是否存在
+    pub fn scan_pids(&mut self, out: &mut Vec<ProcessId>, priority: &[ProcessId]) -> Result<()> {
+        let dir = rewound_dir(&mut self.dir, &self.proc_root)?;
+
+        out.clear();
+        out.extend_from_slice(priority);
+        while let Some(raw_pid) = dir.next_pid() {
+            let Some(pid) = ProcessId::new(raw_pid) else {
+                continue;
+            };
+            if !priority.contains(&pid) {
+                out.push(pid);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 把 `roots` 中的pid写入 `out`，`descendants` 为true时同时写入它们的所有后代
+    ///
+    /// 不扫描整个 /proc：后代从每个线程的 task/<tid>/children 中查找，
+    /// 已经退出或无法读取的进程没有后代。`roots` 中的pid不检查是否存在
+    pub fn collect_pids(&mut self, out: &mut Vec<ProcessId>, roots: &[ProcessId], descendants: bool) {
+        out.clear();
+        for &pid in roots {
+            if !out.contains(&pid) {
+                out.push(pid);
+            }
+        }
+        if !descendants {
+            return;
+        }
+
+        let mut next = 0;
+        while next < out.len() {
+            let pid = out[next];
+            next += 1;
+
+            let Ok(tasks) = fs::read_dir(self.proc_root.join(pid.as_raw().to_string()).join("task")) else {
+                continue;
+            };
+            for task in tasks.flatten() {
+                let Ok(file) = File::open(task.path().join("children")) else {
+                    continue;
+                };
+                if read_bounded(file, &mut self.content).is_err() {
+                    continue;
+                }
+                let children = self.content
+                    .split_whitespace()
+                    .filter_map(|raw| raw.parse().ok())
+                    .filter_map(ProcessId::new);
+                for child in children {
+                    if !out.contains(&child) {
+                        out.push(child);
+                    }
+                }
+            }
+        }
+    }
+
+    /// 读取所有进程的status到 `out` 中
+    ///
+    /// `out` 中已有的元素会被原地覆盖以复用其字符串缓冲区，
+    /// 多余的元素被截断。读取失败的进程（例如已经退出）会被跳过
+    pub fn read_processes(&mut self, out: &mut Vec<ProcessInfo>) -> Result<()> {
+        self.read_processes_with(out, &[], None).map(|_| ())
+    }
+
+    /// 先读取 `priority` 中的进程，再扫描其余进程，超过 `deadline` 时提前结束
+    ///
+    /// `priority` 中的进程总是会被读取，期限只在扫描目录时检查。
+    /// 返回是否完整扫描了所有进程
+    pub fn read_processes_with(
+        &mut self,
+        out: &mut Vec<ProcessInfo>,
+        priority: &[ProcessId],
+        deadline: Option<Instant>,
+    ) -> Result<bool> {
+        let Self { proc_root, dir, content } = self;
+        let dir = rewound_dir(dir, proc_root)?;
+
+        let mut count = 0;
+        for &pid in priority {
+            if read_status_into(dir, content, pid, out, count) {
+                count += 1;
+            }
+        }
+
+        let mut complete = true;
+        while let Some(raw_pid) = dir.next_pid() {
+            let Some(pid) = ProcessId::new(raw_pid) else {
+                continue;
+            };
+            if priority.contains(&pid) {
+                continue;
+            }
+
+            // 相比读取status，取当前时间的开销可以忽略
+            if deadline.map(|deadline| Instant::now() >= deadline).unwrap_or(false) {
+                complete = false;
+                break;
+            }
+
+            if read_status_into(dir, content, pid, out, count) {
+                count += 1;
+            }
+        }
+        out.truncate(count);
+
+        Ok(complete)
+    }
+}
+
+/// 从头开始读取的proc根目录，第一次使用时打开
+fn rewound_dir<'a>(dir: &'a mut Option<PidDir>, proc_root: &Path) -> Result<&'a mut PidDir> {
+    match dir {
+        Some(existing) => {
+            existing.rewind();
+            Ok(existing)
+        }
+        None => Ok(dir.insert(PidDir::open(proc_root)?)),
+    }
+}
+
+/// 读取一个进程的status到 `out[index]`（不存在时追加），返回是否成功
+fn read_status_into(
+    dir: &PidDir,
+    content: &mut String,
+    pid: ProcessId,
+    out: &mut Vec<ProcessInfo>,
+    index: usize,
+) -> bool {
+    if dir.read_process_file(pid, "status", content).is_err() {
+        return false;
+    }
+
+    match out.get_mut(index) {
+        Some(info) => info.parse_status_into(pid, content),
+        None => out.push(ProcessInfo::parse_status(pid, content)),
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write_status(root: &Path, pid: i32, name: &str, rss_kb: u64) {
+        let dir = root.join(pid.to_string());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("status"),
+            format!("Name:\t{}\nState:\tS (sleeping)\nVmRSS:\t{} kB\n", name, rss_kb),
+        ).unwrap();
+    }
+
+    #[test]
+    fn test_read_processes_reuses_output() {
+        let root = tempfile::tempdir().unwrap();
+        write_status(root.path(), 10, "first", 100);
+        write_status(root.path(), 11, "second", 200);
+
+        let mut reader = ProcReader::new(root.path());
+        let mut processes = Vec::new();
+        reader.read_processes(&mut processes).unwrap();
+        processes.sort_by_key(|p| p.pid.as_raw());
+        assert_eq!(processes.len(), 2);
+        assert_eq!(processes[0].name, "first");
+        assert_eq!(processes[1].mem_info.vm_rss, 200);
+
+        // 进程退出后输出被截断，已有元素被覆盖
+        fs::remove_dir_all(root.path().join("10")).unwrap();
+        write_status(root.path(), 11, "renamed", 300);
+        reader.read_processes(&mut processes).unwrap();
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].pid.as_raw(), 11);
+        assert_eq!(processes[0].name, "renamed");
+        assert_eq!(processes[0].mem_info.vm_rss, 300);
+    }
+
+    #[test]
+    fn test_priority_first_and_deadline() {
+        let root = tempfile::tempdir().unwrap();
+        for pid in 100..200 {
+            write_status(root.path(), pid, "worker", 100);
+        }
+
+        let mut reader = ProcReader::new(root.path());
+        let mut processes = Vec::new();
+        let priority = [ProcessId::new(150).unwrap(), ProcessId::new(999).unwrap()];
+
+        // 期限已过：只读取优先进程，不存在的被跳过
+        let complete = reader
+            .read_processes_with(&mut processes, &priority, Some(Instant::now()))
+            .unwrap();
+        assert!(!complete);
+        assert_eq!(processes.len(), 1);
+        assert_eq!(processes[0].pid.as_raw(), 150);
+
+        // 没有期限时完整扫描，优先进程不会重复出现
+        let complete = reader.read_processes_with(&mut processes, &priority, None).unwrap();
+        assert!(complete);
+        assert_eq!(processes.len(), 100);
+        assert_eq!(processes.iter().filter(|p| p.pid.as_raw() == 150).count(), 1);
+    }
+
+    #[test]
+    fn test_scan_pids_and_read_process() {
+        let root = tempfile::tempdir().unwrap();
+        for pid in 100..110 {
+            write_status(root.path(), pid, "worker", 100);
+        }
+
+        let mut reader = ProcReader::new(root.path());
+        let mut pids = Vec::new();
+        let priority = [ProcessId::new(105).unwrap(), ProcessId::new(999).unwrap()];
+        reader.scan_pids(&mut pids, &priority).unwrap();
+        assert_eq!(pids.len(), 11);
+        assert_eq!(&pids[..2], &priority);
+        assert_eq!(pids.iter().filter(|p| p.as_raw() == 105).count(), 1);
+
+        let mut info = ProcessInfo::parse_status(pids[0], "");
+        reader.read_process(pids[0], &mut info).unwrap();
+        assert_eq!(info.name, "worker");
+        assert_eq!(info.mem_info.vm_rss, 100);
+        assert!(matches!(
+            reader.read_process(pids[1], &mut info),
+            Err(crate::ffi::types::SystemError::ProcessNotFound)
+        ));
+    }
+
+    #[test]
+    fn test_collect_pids_follows_children() {
+        let root = tempfile::tempdir().unwrap();
+        let children = |pid: i32, tid: i32, children: &str| {
+            let task = root.path().join(format!("{}/task/{}", pid, tid));
+            fs::create_dir_all(&task).unwrap();
+            fs::write(task.join("children"), children).unwrap();
+        };
+        // 10 -> 11 -> 13；12由10的另一个线程创建；20不受监控
+        children(10, 10, "11 ");
+        children(10, 15, "12 ");
+        children(11, 11, "13 ");
+        children(20, 20, "21 ");
+
+        let mut reader = ProcReader::new(root.path());
+        let mut pids = Vec::new();
+        let roots = [ProcessId::new(10).unwrap(), ProcessId::new(10).unwrap()];
+
+        reader.collect_pids(&mut pids, &roots, false);
+        assert_eq!(pids, vec![ProcessId::new(10).unwrap()]);
+
+        reader.collect_pids(&mut pids, &roots, true);
+        let mut raw: Vec<i32> = pids.iter().map(|pid| pid.as_raw()).collect();
+        raw.sort();
+        assert_eq!(raw, vec![10, 11, 12, 13]);
+    }
+
+    #[test]
+    fn test_read_live_proc() {
+        let mut reader = ProcReader::new(crate::linux::proc::DEFAULT_PROC_ROOT);
+        let mut processes = Vec::new();
+        reader.read_processes(&mut processes).unwrap();
+
+        let current_pid = std::process::id() as i32;
+        assert!(processes.iter().any(|p| p.pid.as_raw() == current_pid));
+    }
+}
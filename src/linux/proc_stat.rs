@@ -1,7 +1,55 @@
-use std::fs::File;
-use std::io::{self, Read};
+use std::io;
+use std::path::Path;
 use std::time::Duration;
 use crate::ffi::types::{ProcessId, SystemError, Result};
+use crate::ffi::ProcessDir;
+use crate::linux::proc::{read_proc_file, DEFAULT_PROC_ROOT};
+
+// /proc/[pid]/stat 中 comm 之后各字段的下标
+//
+// proc(5) 中字段从1开始编号：(1) pid、(2) comm、(3) state ...
+// parse_stat 在最后一个 ')' 之后重新分割，因此下标 = proc(5) 编号 - 3
+const STAT_STATE: usize = 0;       // (3) state
+const STAT_PPID: usize = 1;        // (4) ppid
+const STAT_TTY_NR: usize = 4;      // (7) tty_nr
+const STAT_UTIME: usize = 11;      // (14) utime
+const STAT_STIME: usize = 12;      // (15) stime
+const STAT_CUTIME: usize = 13;     // (16) cutime
+const STAT_CSTIME: usize = 14;     // (17) cstime
+const STAT_STARTTIME: usize = 19;  // (22) starttime
+const STAT_POLICY: usize = 38;     // (41) policy，Linux 2.5.19 之前没有
+
+/// comm 之后至少需要的字段数
+const STAT_MIN_FIELDS: usize = STAT_STARTTIME + 1;
+/// comm 之后解析的字段数
+const STAT_PARSED_FIELDS: usize = STAT_POLICY + 1;
+
+/// 实时调度策略（sched(7)）
+const SCHED_FIFO: u32 = 1;
+const SCHED_RR: u32 = 2;
+
+/// 跨pid复用也能唯一标识进程的键
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProcessKey {
+    pub pid: ProcessId,
+    pub start_time: u64,
+}
+
+impl ProcessKey {
+    /// 打开进程目录，并通过同一个目录确认启动时间与键一致
+    ///
+    /// 进程已退出或pid已被复用时返回 `ProcessNotFound`；之后通过返回的目录读写，
+    /// 不会作用到复用了同一pid的新进程
+    pub fn open_dir(&self, proc_root: &Path) -> Result<ProcessDir> {
+        let dir = ProcessDir::open(proc_root, self.pid)?;
+        let mut buf = String::new();
+        dir.read_to_string("stat", &mut buf)?;
+        if ProcessStat::parse_start_time(&buf) != Some(self.start_time) {
+            return Err(SystemError::ProcessNotFound);
+        }
+        Ok(dir)
+    }
+}
 
 /// 进程的统计信息
 #[derive(Debug, Clone)]
@@ -10,44 +58,38 @@ pub struct ProcessStat {
     pub comm: String,
     pub state: char,
     pub ppid: i32,
+    pub tty_nr: i32,         // 控制终端的设备号，0表示没有控制终端
     pub start_time: u64,     // 进程启动时间（自系统启动以来的时钟滴答数）
     pub utime: u64,          // 用户态CPU时间
     pub stime: u64,          // 内核态CPU时间
     pub cutime: u64,         // 子进程用户态CPU时间
     pub cstime: u64,         // 子进程内核态CPU时间
+    pub policy: Option<u32>, // 调度策略（SCHED_*），内核不提供该字段时为None
 }
 
 impl ProcessStat {
     /// 从/proc/[pid]/stat获取进程统计信息
     pub fn from_pid(pid: ProcessId) -> Result<Self> {
-        let path = format!("/proc/{}/stat", pid.as_raw());
-        let mut content = String::new();
-        File::open(&path)
-            .and_then(|mut file| file.read_to_string(&mut content))
-            .map_err(|e| {
-                if e.kind() == io::ErrorKind::NotFound {
-                    SystemError::ProcessNotFound
-                } else {
-                    SystemError::SyscallError(e)
-                }
-            })?;
+        Self::from_pid_in(Path::new(DEFAULT_PROC_ROOT), pid)
+    }
+
+    /// 从指定proc根目录下的[pid]/stat获取进程统计信息
+    pub fn from_pid_in(proc_root: &Path, pid: ProcessId) -> Result<Self> {
+        let path = proc_root.join(pid.as_raw().to_string()).join("stat");
+        let content = read_proc_file(&path)?;
 
         Self::parse_stat(&content, pid)
     }
 
+    /// 通过已打开的进程目录读取stat，`buf` 用于复用读取缓冲区
+    pub fn from_dir(dir: &ProcessDir, pid: ProcessId, buf: &mut String) -> Result<Self> {
+        dir.read_to_string("stat", buf)?;
+        Self::parse_stat(buf, pid)
+    }
+
     /// 解析stat文件内容
-    fn parse_stat(content: &str, pid: ProcessId) -> Result<Self> {
+    pub fn parse_stat(content: &str, pid: ProcessId) -> Result<Self> {
         // stat文件格式较复杂，特别是进程名可能包含空格和括号
-        let mut parts: Vec<&str> = content.split_whitespace().collect();
-        
-        // 确保至少有最小数量的字段
-        if parts.len() < 24 {
-            return Err(SystemError::SyscallError(io::Error::new(
-                io::ErrorKind::InvalidData,
-                "Invalid stat file format"
-            )));
-        }
-
         // 处理进程名（可能包含空格）
         let comm_start = content.find('(').ok_or_else(|| {
             SystemError::SyscallError(io::Error::new(
@@ -63,23 +105,64 @@ impl ProcessStat {
         })?;
         let comm = content[comm_start + 1..comm_end].to_string();
 
-        // 重新分割剩余部分
+        // 重新分割剩余部分，下标见 STAT_* 常量
+        // 使用定长数组而不是Vec，避免每个进程一次堆分配
         let remainder = &content[comm_end + 1..];
-        parts = remainder.split_whitespace().collect();
+        let mut parts = [""; STAT_PARSED_FIELDS];
+        let mut count = 0;
+        for (slot, field) in parts.iter_mut().zip(remainder.split_whitespace()) {
+            *slot = field;
+            count += 1;
+        }
+
+        // 确保至少包含到starttime为止的字段
+        if count < STAT_MIN_FIELDS {
+            return Err(SystemError::SyscallError(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Invalid stat file format"
+            )));
+        }
 
         Ok(ProcessStat {
             pid,
             comm,
-            state: parts[0].chars().next().unwrap_or('?'),
-            ppid: parts[2].parse().unwrap_or(0),
-            utime: parts[11].parse().unwrap_or(0),
-            stime: parts[12].parse().unwrap_or(0),
-            cutime: parts[13].parse().unwrap_or(0),
-            cstime: parts[14].parse().unwrap_or(0),
-            start_time: parts[19].parse().unwrap_or(0),
+            state: parts[STAT_STATE].chars().next().unwrap_or('?'),
+            ppid: parts[STAT_PPID].parse().unwrap_or(0),
+            tty_nr: parts[STAT_TTY_NR].parse().unwrap_or(0),
+            utime: parts[STAT_UTIME].parse().unwrap_or(0),
+            stime: parts[STAT_STIME].parse().unwrap_or(0),
+            cutime: parts[STAT_CUTIME].parse().unwrap_or(0),
+            cstime: parts[STAT_CSTIME].parse().unwrap_or(0),
+            start_time: parts[STAT_STARTTIME].parse().unwrap_or(0),
+            policy: parts[STAT_POLICY].parse().ok(),
         })
     }
 
+    /// 只解析stat内容中的starttime，不分配内存
+    ///
+    /// 用于在不能分配内存的路径上确认pid没有被复用
+    pub fn parse_start_time(content: &str) -> Option<u64> {
+        let comm_end = content.rfind(')')?;
+        content[comm_end + 1..]
+            .split_whitespace()
+            .nth(STAT_STARTTIME)?
+            .parse()
+            .ok()
+    }
+
+    /// 进程的唯一标识
+    pub fn key(&self) -> ProcessKey {
+        ProcessKey {
+            pid: self.pid,
+            start_time: self.start_time,
+        }
+    }
+
+    /// 是否使用实时调度策略（SCHED_FIFO 或 SCHED_RR）
+    pub fn is_realtime(&self) -> bool {
+        matches!(self.policy, Some(SCHED_FIFO | SCHED_RR))
+    }
+
     /// 获取进程的总CPU时间
     pub fn total_cpu_time(&self) -> Duration {
         let ticks = self.utime + self.stime + self.cutime + self.cstime;
@@ -90,24 +173,17 @@ impl ProcessStat {
 
     /// 获取进程的运行时长
     pub fn running_time(&self) -> Duration {
-        // 读取系统启动时间
-        let uptime = Self::get_system_uptime()
-            .unwrap_or_else(|_| Duration::from_secs(0));
-        
-        // 计算进程运行时间
-        let process_uptime = Duration::from_secs_f64(
-            self.start_time as f64 / 100.0  // 转换启动时间的时钟滴答数
-        );
-        
-        uptime.saturating_sub(process_uptime)
+        self.running_time_in(Path::new(DEFAULT_PROC_ROOT))
+    }
+
+    /// 根据指定proc根目录下的uptime计算进程的运行时长
+    pub fn running_time_in(&self, proc_root: &Path) -> Duration {
+        running_time_since(self.start_time, proc_root)
     }
 
     /// 获取系统运行时间
-    fn get_system_uptime() -> Result<Duration> {
-        let mut content = String::new();
-        File::open("/proc/uptime")
-            .and_then(|mut file| file.read_to_string(&mut content))
-            .map_err(SystemError::SyscallError)?;
+    fn get_system_uptime(proc_root: &Path) -> Result<Duration> {
+        let content = read_proc_file(&proc_root.join("uptime"))?;
 
         let uptime: f64 = content
             .split_whitespace()
@@ -119,12 +195,30 @@ impl ProcessStat {
     }
 }
 
+/// 根据进程的启动时间（时钟滴答）和指定proc根目录下的uptime计算运行时长
+pub fn running_time_since(start_time: u64, proc_root: &Path) -> Duration {
+    // 读取系统启动时间
+    let uptime = ProcessStat::get_system_uptime(proc_root)
+        .unwrap_or_else(|_| Duration::from_secs(0));
+
+    // 计算进程运行时间
+    let process_uptime = Duration::from_secs_f64(
+        start_time as f64 / 100.0  // 转换启动时间的时钟滴答数
+    );
+
+    uptime.saturating_sub(process_uptime)
+}
+
 /// 现在我们可以更新 OOMScorer 中的 calculate_runtime_score 方法
 pub fn calculate_runtime_score(process_stat: &ProcessStat) -> f64 {
+    score_runtime(process_stat.running_time())
+}
+
+/// 根据进程的运行时长计算运行时间分数（0-1）
+pub fn score_runtime(runtime: Duration) -> f64 {
     const HOUR: u64 = 3600;
     const DAY: u64 = HOUR * 24;
     
-    let runtime = process_stat.running_time();
     let runtime_secs = runtime.as_secs();
 
     // 根据运行时间计算分数：
@@ -158,6 +252,77 @@ mod tests {
         assert!(stat.start_time > 0);
     }
 
+    #[test]
+    fn test_parse_full_stat_line() {
+        // 按 proc(5) 构造的完整stat行，每个字段的值都不相同
+        let line = "4321 (my (odd) proc) S 77 4321 4321 34816 4321 4194560 \
+                    1000 2000 3 4 111 222 333 444 20 0 5 0 98765 123456789 2048 \
+                    18446744073709551615 1 1 0 0 0 0 0 4096 0 0 0 0 17 2 0 0 0 0 0";
+        let pid = ProcessId::new(4321).unwrap();
+        let stat = ProcessStat::parse_stat(line, pid).unwrap();
+
+        assert_eq!(stat.pid, pid);
+        assert_eq!(stat.comm, "my (odd) proc");
+        assert_eq!(stat.state, 'S');
+        assert_eq!(stat.ppid, 77);
+        assert_eq!(stat.tty_nr, 34816);
+        assert_eq!(stat.utime, 111);
+        assert_eq!(stat.stime, 222);
+        assert_eq!(stat.cutime, 333);
+        assert_eq!(stat.cstime, 444);
+        assert_eq!(stat.start_time, 98765);
+        assert_eq!(stat.policy, Some(0));
+        assert!(!stat.is_realtime());
+    }
+
+    #[test]
+    fn test_parse_realtime_policy() {
+        let pid = ProcessId::new(612).unwrap();
+        // rt_priority 50，policy 1（SCHED_FIFO）
+        let line = "612 (pipewire) S 1 612 612 0 -1 4194560 0 0 0 0 0 0 0 0 -51 0 4 0 2048 0 0 \
+                    18446744073709551615 1 1 0 0 0 0 0 4096 0 0 0 0 17 3 50 1 0 0 0";
+        let stat = ProcessStat::parse_stat(line, pid).unwrap();
+        assert_eq!(stat.policy, Some(1));
+        assert!(stat.is_realtime());
+
+        let stat = ProcessStat::parse_stat(&line.replace(" 50 1 ", " 50 2 "), pid).unwrap();
+        assert!(stat.is_realtime());
+        // SCHED_BATCH
+        let stat = ProcessStat::parse_stat(&line.replace(" 50 1 ", " 0 3 "), pid).unwrap();
+        assert!(!stat.is_realtime());
+
+        // 旧内核没有policy字段
+        let stat = ProcessStat::parse_stat("612 (old) S 1 612 612 0 -1 0 0 0 0 0 0 0 0 0 20 0 1 0 2048 0 0", pid).unwrap();
+        assert_eq!(stat.policy, None);
+        assert!(!stat.is_realtime());
+    }
+
+    #[test]
+    fn test_parse_stat_fixture() {
+        let pid = ProcessId::new(2817).unwrap();
+        let stat = ProcessStat::parse_stat(include_str!("../../tests/fixtures/proc/stat"), pid).unwrap();
+
+        assert_eq!(stat.comm, "postgres");
+        assert_eq!(stat.ppid, 1);
+        assert_eq!(stat.utime, 431);
+        assert_eq!(stat.start_time, 4821);
+    }
+
+    #[test]
+    fn test_parse_start_time_only() {
+        let content = include_str!("../../tests/fixtures/proc/stat");
+        assert_eq!(ProcessStat::parse_start_time(content), Some(4821));
+        // 进程名中的括号和空格不影响字段定位
+        assert_eq!(ProcessStat::parse_start_time("7 (a) b) S 1 7 7 0 -1 0 0 0 0 0 0 0 0 0 20 0 1 0 99 0"), Some(99));
+        assert_eq!(ProcessStat::parse_start_time("7 (short) S 1"), None);
+    }
+
+    #[test]
+    fn test_parse_truncated_stat_line() {
+        let pid = ProcessId::new(1).unwrap();
+        assert!(ProcessStat::parse_stat("1 (init) S 0 1 1", pid).is_err());
+    }
+
     #[test]
     fn test_process_times() {
         let pid = ProcessId::new(std::process::id() as i32).unwrap();
@@ -187,11 +352,13 @@ mod tests {
             comm: String::from("test"),
             state: 'R',
             ppid: 0,
+            tty_nr: 0,
             start_time: 0,
             utime: 0,
             stime: 0,
             cutime: 0,
             cstime: 0,
+            policy: None,
         };
 
         // 测试新进程（运行时间小于1小时）
@@ -0,0 +1,188 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use crate::ffi::types::{ProcessId, SystemError, Result};
+use crate::linux::proc::{list_processes_in, ProcessInfo};
+
+/// 表中的一个进程及其最近一次读取status的时间
+#[derive(Debug, Clone)]
+struct TrackedProcess {
+    info: ProcessInfo,
+    refreshed_at: Instant,
+}
+
+/// 增量维护的进程表
+///
+/// 由外部事件（例如 proc connector 的 fork/exec/exit）逐个更新，
+/// 并定期通过全量扫描与 /proc 对账。表中的进程信息只来自status，
+/// 不包含OOM分数
+#[derive(Debug)]
+pub struct ProcessTable {
+    proc_root: PathBuf,
+    processes: HashMap<ProcessId, TrackedProcess>,
+    last_full_scan: Option<Instant>,
+}
+
+impl ProcessTable {
+    /// 创建空的进程表
+    pub fn new(proc_root: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+            processes: HashMap::new(),
+            last_full_scan: None,
+        }
+    }
+
+    /// 表使用的proc根目录
+    pub fn proc_root(&self) -> &Path {
+        &self.proc_root
+    }
+
+    /// 全量扫描 /proc，用扫描结果替换整个表
+    pub fn full_scan(&mut self) -> Result<()> {
+        let now = Instant::now();
+        let processes = list_processes_in(&self.proc_root)?;
+
+        self.processes = processes.into_iter()
+            .map(|info| (info.pid, TrackedProcess { info, refreshed_at: now }))
+            .collect();
+        self.last_full_scan = Some(now);
+
+        Ok(())
+    }
+
+    /// 重新读取单个进程，进程已不存在时从表中移除
+    pub fn upsert(&mut self, pid: ProcessId) {
+        match ProcessInfo::from_status_in(&self.proc_root, pid) {
+            Ok(info) => {
+                self.processes.insert(pid, TrackedProcess { info, refreshed_at: Instant::now() });
+            }
+            Err(SystemError::ProcessNotFound) => {
+                self.processes.remove(&pid);
+            }
+            Err(e) => log::debug!("Failed to read process {}: {:?}", pid.as_raw(), e),
+        }
+    }
+
+    /// 从表中移除进程
+    pub fn remove(&mut self, pid: ProcessId) {
+        self.processes.remove(&pid);
+    }
+
+    /// 重新读取超过 `max_age` 未刷新的进程
+    ///
+    /// 事件只说明进程的创建和退出，RSS等内存信息需要定期重新读取
+    pub fn refresh_stale(&mut self, max_age: Duration) {
+        let now = Instant::now();
+        let stale: Vec<ProcessId> = self.processes.iter()
+            .filter(|(_, tracked)| now.duration_since(tracked.refreshed_at) >= max_age)
+            .map(|(pid, _)| *pid)
+            .collect();
+
+        for pid in stale {
+            self.upsert(pid);
+        }
+    }
+
+    /// 最近一次全量扫描的时间
+    pub fn last_full_scan(&self) -> Option<Instant> {
+        self.last_full_scan
+    }
+
+    /// 表中的所有进程
+    pub fn processes(&self) -> impl Iterator<Item = &ProcessInfo> {
+        self.processes.values().map(|tracked| &tracked.info)
+    }
+
+    /// 表中是否包含该进程
+    pub fn contains(&self, pid: ProcessId) -> bool {
+        self.processes.contains_key(&pid)
+    }
+
+    /// 表中的进程数
+    pub fn len(&self) -> usize {
+        self.processes.len()
+    }
+
+    /// 表是否为空
+    pub fn is_empty(&self) -> bool {
+        self.processes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn write_status(root: &Path, pid: i32, rss_kb: u64) {
+        let dir = root.join(pid.to_string());
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("status"),
+            format!("Name:\tproc-{}\nState:\tS (sleeping)\nVmRSS:\t{} kB\n", pid, rss_kb),
+        ).unwrap();
+    }
+
+    fn pid(raw: i32) -> ProcessId {
+        ProcessId::new(raw).unwrap()
+    }
+
+    #[test]
+    fn test_full_scan_replaces_table() {
+        let root = tempfile::tempdir().unwrap();
+        write_status(root.path(), 10, 100);
+        write_status(root.path(), 11, 200);
+
+        let mut table = ProcessTable::new(root.path());
+        table.full_scan().unwrap();
+        assert_eq!(table.len(), 2);
+        assert!(table.last_full_scan().is_some());
+
+        // 扫描之间退出的进程在下一次扫描时被移除
+        fs::remove_dir_all(root.path().join("11")).unwrap();
+        write_status(root.path(), 12, 300);
+        table.full_scan().unwrap();
+        assert!(table.contains(pid(10)));
+        assert!(!table.contains(pid(11)));
+        assert!(table.contains(pid(12)));
+    }
+
+    #[test]
+    fn test_upsert_and_remove() {
+        let root = tempfile::tempdir().unwrap();
+        write_status(root.path(), 20, 100);
+
+        let mut table = ProcessTable::new(root.path());
+        table.upsert(pid(20));
+        assert!(table.contains(pid(20)));
+
+        // 已经不存在的进程不会被加入
+        table.upsert(pid(21));
+        assert!(!table.contains(pid(21)));
+
+        table.remove(pid(20));
+        assert!(table.is_empty());
+    }
+
+    #[test]
+    fn test_refresh_stale_rereads_memory() {
+        let root = tempfile::tempdir().unwrap();
+        write_status(root.path(), 30, 100);
+        write_status(root.path(), 31, 100);
+
+        let mut table = ProcessTable::new(root.path());
+        table.full_scan().unwrap();
+
+        write_status(root.path(), 30, 5000);
+        fs::remove_dir_all(root.path().join("31")).unwrap();
+
+        // 未过期的条目保持不变
+        table.refresh_stale(Duration::from_secs(60));
+        assert_eq!(table.len(), 2);
+
+        table.refresh_stale(Duration::ZERO);
+        let rss: Vec<u64> = table.processes().map(|p| p.mem_info.vm_rss).collect();
+        assert_eq!(rss, vec![5000]);
+    }
+}
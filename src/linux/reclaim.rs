@@ -0,0 +1,157 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::linux::proc::DEFAULT_PROC_ROOT;
+
+/// 终止进程之前尝试的非破坏性回收
+#[derive(Debug, Clone)]
+pub struct ReclaimConfig {
+    /// 写入该 cgroup v2 目录下的 memory.reclaim，`None` 表示不请求cgroup回收
+    pub cgroup: Option<PathBuf>,
+    /// 每次向cgroup请求回收的字节数
+    pub amount: u64,
+    /// 是否写入 /proc/sys/vm/drop_caches 丢弃全系统的页缓存
+    ///
+    /// 需要root权限，并且会让所有进程的缓存失效，必须显式开启
+    pub drop_caches: bool,
+}
+
+impl Default for ReclaimConfig {
+    fn default() -> Self {
+        Self {
+            cgroup: None,
+            amount: 256 * 1024 * 1024, // 256MB
+            drop_caches: false,
+        }
+    }
+}
+
+/// 回收请求的写入方式，测试中可以替换为不写入真实文件的实现
+pub trait ReclaimWriter: Send {
+    /// 把 `contents` 写入 `path`
+    fn write(&mut self, path: &Path, contents: &str) -> io::Result<()>;
+}
+
+impl<F> ReclaimWriter for F
+where
+    F: FnMut(&Path, &str) -> io::Result<()> + Send,
+{
+    fn write(&mut self, path: &Path, contents: &str) -> io::Result<()> {
+        self(path, contents)
+    }
+}
+
+/// 直接写入文件
+#[derive(Debug, Default)]
+pub struct FsWriter;
+
+impl ReclaimWriter for FsWriter {
+    fn write(&mut self, path: &Path, contents: &str) -> io::Result<()> {
+        fs::write(path, contents)
+    }
+}
+
+/// 按配置发出回收请求
+pub struct Reclaimer {
+    config: ReclaimConfig,
+    proc_root: PathBuf,
+    writer: Box<dyn ReclaimWriter>,
+}
+
+impl Reclaimer {
+    /// 创建回收器，写入真实的文件
+    pub fn new(config: ReclaimConfig) -> Self {
+        Self {
+            config,
+            proc_root: PathBuf::from(DEFAULT_PROC_ROOT),
+            writer: Box::new(FsWriter),
+        }
+    }
+
+    /// 在指定的proc根目录下写入 sys/vm/drop_caches
+    pub fn with_proc_root(mut self, proc_root: impl Into<PathBuf>) -> Self {
+        self.proc_root = proc_root.into();
+        self
+    }
+
+    /// 使用指定的写入方式
+    pub fn with_writer(mut self, writer: impl ReclaimWriter + 'static) -> Self {
+        self.writer = Box::new(writer);
+        self
+    }
+
+    /// 发出所有已配置的回收请求，返回是否至少有一个请求被内核接受
+    ///
+    /// memory.reclaim 未能回收全部请求的字节数时返回EAGAIN，此时已经回收了一部分，
+    /// 同样视为接受。失败的请求只记录警告
+    pub fn attempt(&mut self) -> bool {
+        let mut accepted = false;
+
+        if let Some(cgroup) = &self.config.cgroup {
+            let path = cgroup.join("memory.reclaim");
+            match self.writer.write(&path, &self.config.amount.to_string()) {
+                Ok(()) => accepted = true,
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => {
+                    log::debug!("Partial reclaim from {}", path.display());
+                    accepted = true;
+                }
+                Err(e) => log::warn!("Failed to request reclaim via {}: {}", path.display(), e),
+            }
+        }
+
+        if self.config.drop_caches {
+            let path = self.proc_root.join("sys/vm/drop_caches");
+            match self.writer.write(&path, "1") {
+                Ok(()) => accepted = true,
+                Err(e) => log::warn!("Failed to drop caches via {}: {}", path.display(), e),
+            }
+        }
+
+        accepted
+    }
+}
+
+impl fmt::Debug for Reclaimer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Reclaimer")
+            .field("config", &self.config)
+            .field("proc_root", &self.proc_root)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_attempt_writes_configured_targets() {
+        let writes = Arc::new(Mutex::new(Vec::new()));
+        let recorded = Arc::clone(&writes);
+        let mut reclaimer = Reclaimer::new(ReclaimConfig {
+            cgroup: Some(PathBuf::from("/sys/fs/cgroup/app")),
+            amount: 1024,
+            drop_caches: true,
+        })
+        .with_proc_root("/fake/proc")
+        .with_writer(move |path: &Path, contents: &str| {
+            recorded.lock().unwrap().push((path.to_path_buf(), contents.to_string()));
+            // 只回收了一部分
+            Err(io::Error::from(io::ErrorKind::WouldBlock))
+        });
+
+        // 部分回收仍然视为接受；drop_caches失败不影响结果
+        assert!(reclaimer.attempt());
+        assert_eq!(*writes.lock().unwrap(), vec![
+            (PathBuf::from("/sys/fs/cgroup/app/memory.reclaim"), "1024".to_string()),
+            (PathBuf::from("/fake/proc/sys/vm/drop_caches"), "1".to_string()),
+        ]);
+
+        // 默认配置不写入任何文件
+        let mut idle = Reclaimer::new(ReclaimConfig::default())
+            .with_writer(|_: &Path, _: &str| -> io::Result<()> { panic!("unexpected write") });
+        assert!(!idle.attempt());
+    }
+}
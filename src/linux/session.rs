@@ -0,0 +1,168 @@
+#![cfg(feature = "desktop-session")]
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::ffi::dir::ProcessDir;
+use crate::linux::proc::DEFAULT_PROC_ROOT;
+use crate::linux::proc_stat::{ProcessKey, ProcessStat};
+
+/// logind 记录会话状态的目录
+pub const DEFAULT_SESSIONS_DIR: &str = "/run/systemd/sessions";
+
+/// 图形会话的类型（XDG_SESSION_TYPE / logind 的 TYPE）
+const GRAPHICAL_SESSION_TYPES: [&str; 3] = ["x11", "wayland", "mir"];
+
+/// 桌面会话集成的配置
+#[derive(Debug, Clone, Default)]
+pub struct DesktopSessionConfig {
+    /// 先发送SIGTERM让应用保存，这段时间之后仍未退出时发送SIGKILL；`None` 表示只警告、直接SIGKILL
+    pub grace: Option<Duration>,
+}
+
+/// 进程所属的活动图形会话
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GraphicalSession {
+    /// logind 会话ID（XDG_SESSION_ID），环境变量中没有时为None
+    pub id: Option<String>,
+    /// 会话类型，例如 "x11" 或 "wayland"
+    pub session_type: String,
+}
+
+/// 判断进程是否属于活动的图形会话，测试中可以替换为模拟实现
+pub trait SessionDetector: Send {
+    /// 进程所属的活动图形会话，不属于图形会话、无法判断或pid已被复用时为None
+    fn graphical_session(&self, key: ProcessKey) -> Option<GraphicalSession>;
+}
+
+/// 从 /proc/[pid]/environ 读取会话信息，有会话ID时以logind记录的状态为准
+///
+/// 读取其他用户进程的environ需要root或CAP_SYS_PTRACE，无法读取时按不属于图形会话处理。
+/// stat和environ通过同一个进程目录读取，启动时间不符时说明pid已被复用；
+/// environ与其他proc文件一样受 `max_proc_file_bytes` 限制，超过上限或不是UTF-8时同样按无法读取处理
+pub struct LogindSessionDetector {
+    proc_root: PathBuf,
+    sessions_dir: PathBuf,
+}
+
+impl LogindSessionDetector {
+    /// 使用默认的 /proc 和 logind 会话目录
+    pub fn new() -> Self {
+        Self {
+            proc_root: PathBuf::from(DEFAULT_PROC_ROOT),
+            sessions_dir: PathBuf::from(DEFAULT_SESSIONS_DIR),
+        }
+    }
+
+    /// 从指定目录读取进程信息，用于测试
+    pub fn with_proc_root(mut self, proc_root: impl Into<PathBuf>) -> Self {
+        self.proc_root = proc_root.into();
+        self
+    }
+
+    /// 从指定目录读取logind会话状态，用于测试
+    pub fn with_sessions_dir(mut self, sessions_dir: impl Into<PathBuf>) -> Self {
+        self.sessions_dir = sessions_dir.into();
+        self
+    }
+
+    /// logind记录的会话类型，会话不活动时为None；没有记录时退回环境变量
+    fn session_type(&self, id: &str, environ: &[u8]) -> Option<String> {
+        let Ok(content) = fs::read_to_string(self.sessions_dir.join(id)) else {
+            return environ_var(environ, "XDG_SESSION_TYPE");
+        };
+        // 进程继承的环境变量不会随会话切换更新，logind的状态更可靠
+        let mut active = false;
+        let mut session_type = None;
+        for line in content.lines() {
+            match line.split_once('=') {
+                Some(("ACTIVE", value)) => active = value == "1",
+                Some(("TYPE", value)) => session_type = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        session_type.filter(|_| active)
+    }
+}
+
+impl Default for LogindSessionDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SessionDetector for LogindSessionDetector {
+    fn graphical_session(&self, key: ProcessKey) -> Option<GraphicalSession> {
+        let dir = ProcessDir::open(&self.proc_root, key.pid).ok()?;
+        let mut buf = String::new();
+        dir.read_to_string("stat", &mut buf).ok()?;
+        if ProcessStat::parse_start_time(&buf)? != key.start_time {
+            return None;
+        }
+        dir.read_to_string("environ", &mut buf).ok()?;
+
+        let environ = buf.as_bytes();
+        let id = environ_var(environ, "XDG_SESSION_ID");
+        let session_type = match &id {
+            Some(id) => self.session_type(id, environ),
+            None => environ_var(environ, "XDG_SESSION_TYPE"),
+        }?;
+
+        GRAPHICAL_SESSION_TYPES
+            .contains(&session_type.as_str())
+            .then_some(GraphicalSession { id, session_type })
+    }
+}
+
+/// 在 /proc/[pid]/environ 的内容（以NUL分隔的 KEY=VALUE）中查找变量
+pub fn environ_var(environ: &[u8], name: &str) -> Option<String> {
+    environ.split(|&byte| byte == 0).find_map(|entry| {
+        let value = entry.strip_prefix(name.as_bytes())?.strip_prefix(b"=")?;
+        Some(String::from_utf8_lossy(value).into_owned())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffi::types::ProcessId;
+
+    const START_TIME: u64 = 500;
+
+    #[test]
+    fn test_detect_graphical_session() {
+        let proc_root = tempfile::tempdir().unwrap();
+        let sessions = tempfile::tempdir().unwrap();
+        let write_environ = |pid: i32, environ: &str| {
+            let dir = proc_root.path().join(pid.to_string());
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(
+                dir.join("stat"),
+                format!("{} (app) S 1 0 0 0 -1 0 0 0 0 0 0 0 0 0 20 0 1 0 {} 0 0\n", pid, START_TIME),
+            )
+            .unwrap();
+            fs::write(dir.join("environ"), environ.replace(' ', "\0")).unwrap();
+        };
+        write_environ(1001, "HOME=/home/alice XDG_SESSION_TYPE=wayland");
+        write_environ(1002, "HOME=/root XDG_SESSION_TYPE=tty");
+        write_environ(1003, "XDG_SESSION_ID=3 XDG_SESSION_TYPE=x11");
+        write_environ(1004, "XDG_SESSION_ID=4 XDG_SESSION_TYPE=x11");
+        fs::write(sessions.path().join("3"), "# This is private data.\nUID=1000\nTYPE=x11\nACTIVE=1\n").unwrap();
+        // 已经切换到其他会话，环境变量仍然是x11
+        fs::write(sessions.path().join("4"), "UID=1000\nTYPE=x11\nACTIVE=0\n").unwrap();
+
+        let detector = LogindSessionDetector::new()
+            .with_proc_root(proc_root.path())
+            .with_sessions_dir(sessions.path());
+        let key = |pid: i32, start_time: u64| ProcessKey { pid: ProcessId::new(pid).unwrap(), start_time };
+        let session = |pid: i32| detector.graphical_session(key(pid, START_TIME));
+        assert_eq!(session(1001), Some(GraphicalSession { id: None, session_type: "wayland".to_string() }));
+        assert_eq!(session(1002), None);
+        assert_eq!(session(1003), Some(GraphicalSession { id: Some("3".to_string()), session_type: "x11".to_string() }));
+        assert_eq!(session(1004), None);
+        // 没有environ（进程已退出或没有权限）
+        assert_eq!(session(1005), None);
+        // pid已被另一个进程复用
+        assert_eq!(detector.graphical_session(key(1001, START_TIME + 1)), None);
+    }
+}
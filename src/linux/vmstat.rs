@@ -0,0 +1,119 @@
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::ffi::types::{SystemError, Result};
+
+/// /proc/vmstat 中与内存回收相关的累计计数器
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VmStat {
+    pub pgscan: u64,       // 所有 pgscan_* 计数之和
+    pub pgsteal: u64,      // 所有 pgsteal_* 计数之和
+    pub pswpin: u64,       // 换入的页数
+    pub pswpout: u64,      // 换出的页数
+    pub pgmajfault: u64,   // 主缺页次数
+    pub oom_kill: u64,     // 内核 OOM 终止次数
+}
+
+/// 两次采样之间计数器的每秒变化率
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VmStatRates {
+    pub pgscan_per_sec: f64,
+    pub pgsteal_per_sec: f64,
+    pub pswpin_per_sec: f64,
+    pub pswpout_per_sec: f64,
+    pub pgmajfault_per_sec: f64,
+    pub oom_kill_per_sec: f64,
+}
+
+impl VmStat {
+    /// 读取当前的 /proc/vmstat
+    pub fn read() -> Result<Self> {
+        let content = std::fs::read_to_string("/proc/vmstat")
+            .map_err(SystemError::SyscallError)?;
+        Ok(Self::parse(&content))
+    }
+
+    /// 解析 vmstat 内容，不同内核版本的 pgscan/pgsteal 细分字段不同，统一求和
+    pub fn parse(content: &str) -> Self {
+        let mut stat = VmStat::default();
+
+        for line in content.lines() {
+            let mut parts = line.split_whitespace();
+            let (Some(key), Some(value)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+            let value: u64 = value.parse().unwrap_or(0);
+
+            match key {
+                "pswpin" => stat.pswpin = value,
+                "pswpout" => stat.pswpout = value,
+                "pgmajfault" => stat.pgmajfault = value,
+                "oom_kill" => stat.oom_kill = value,
+                k if k.starts_with("pgscan_") => stat.pgscan += value,
+                k if k.starts_with("pgsteal_") => stat.pgsteal += value,
+                _ => {}
+            }
+        }
+
+        stat
+    }
+
+    /// 计算相对于较早一次采样的每秒变化率
+    pub fn rates_since(&self, earlier: &VmStat, elapsed: Duration) -> VmStatRates {
+        let secs = elapsed.as_secs_f64();
+        if secs <= 0.0 {
+            return VmStatRates::default();
+        }
+        let rate = |now: u64, before: u64| now.saturating_sub(before) as f64 / secs;
+
+        VmStatRates {
+            pgscan_per_sec: rate(self.pgscan, earlier.pgscan),
+            pgsteal_per_sec: rate(self.pgsteal, earlier.pgsteal),
+            pswpin_per_sec: rate(self.pswpin, earlier.pswpin),
+            pswpout_per_sec: rate(self.pswpout, earlier.pswpout),
+            pgmajfault_per_sec: rate(self.pgmajfault, earlier.pgmajfault),
+            oom_kill_per_sec: rate(self.oom_kill, earlier.oom_kill),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vmstat() {
+        let content = "nr_free_pages 12345\n\
+                       pswpin 10\n\
+                       pswpout 20\n\
+                       pgmajfault 30\n\
+                       pgscan_kswapd 100\n\
+                       pgscan_direct 50\n\
+                       pgsteal_kswapd 80\n\
+                       pgsteal_direct 40\n\
+                       oom_kill 2\n";
+        let stat = VmStat::parse(content);
+
+        assert_eq!(stat.pgscan, 150);
+        assert_eq!(stat.pgsteal, 120);
+        assert_eq!(stat.pswpin, 10);
+        assert_eq!(stat.pswpout, 20);
+        assert_eq!(stat.pgmajfault, 30);
+        assert_eq!(stat.oom_kill, 2);
+    }
+
+    #[test]
+    fn test_rates() {
+        let earlier = VmStat { pgscan: 100, pswpout: 10, ..Default::default() };
+        let later = VmStat { pgscan: 300, pswpout: 30, ..Default::default() };
+
+        let rates = later.rates_since(&earlier, Duration::from_secs(2));
+        assert_eq!(rates.pgscan_per_sec, 100.0);
+        assert_eq!(rates.pswpout_per_sec, 10.0);
+        assert_eq!(rates.pgmajfault_per_sec, 0.0);
+    }
+
+    #[test]
+    fn test_read_vmstat() {
+        assert!(VmStat::read().is_ok());
+    }
+}
@@ -0,0 +1,224 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::ffi::types::ProcessId;
+use crate::oom::killer::KillSource;
+use crate::oom::pressure::{PressureLevel, ProcessSummary};
+
+/// 审计日志使用的 log target
+pub const AUDIT_TARGET: &str = "room::audit";
+
+/// 审计事件
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AuditEvent {
+    /// 一次进程终止（rOOM 自身或内核）
+    Kill {
+        pid: i32,
+        name: String,
+        memory_freed: u64,
+        source: KillSource,
+    },
+    /// 处于内存压力下，但因终止间隔限制而没有终止进程
+    RateLimited {
+        remaining_ms: u64,
+    },
+    /// 内存压力级别发生变化
+    PressureTransition {
+        from: PressureLevel,
+        to: PressureLevel,
+    },
+    /// 监控循环连续多轮超过检查间隔
+    LoopOverrun {
+        consecutive: u32,
+        cycle_ms: u64,
+        interval_ms: u64,
+    },
+    /// 一轮检查的决定，启用 `log_every_decision` 时每轮记录
+    Decision(Decision),
+    /// 启用 `periodic_snapshot_interval` 时定期记录的RSS最高的进程，与是否终止无关
+    TopConsumers {
+        consumers: Vec<ProcessSummary>,
+    },
+    /// 启用 `desktop_session` 时，属于活动图形会话的进程进入终止计划，在终止之前记录
+    GraphicalSessionWarning {
+        pid: i32,
+        name: String,
+        session_id: Option<String>,
+        session_type: String,
+        /// 终止前留给应用保存的时间（毫秒），`None` 表示直接SIGKILL
+        grace_ms: Option<u64>,
+    },
+}
+
+/// 一轮检查的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DecisionOutcome {
+    /// 没有持续的内存压力
+    NoPressure,
+    /// 处于持续压力，但因终止间隔限制没有终止
+    RateLimited,
+    /// 处于持续压力，但没有可以终止的候选
+    NoCandidate,
+    /// 终止了候选
+    Killed,
+    /// 把候选委托给内核终止
+    Delegated,
+    /// 终止之前的回收消除了压力，没有终止
+    Reclaimed,
+    /// 处于持续压力，但本次压力过程的回收量已达到上限，没有终止
+    EpisodeCapped,
+    /// 受害者在发送信号前已经自行退出，重新检查后压力已经消除
+    TargetGone,
+}
+
+/// 一轮检查中得分最高的候选
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DecisionCandidate {
+    pub pid: i32,
+    pub name: String,
+    pub score: f64,
+    /// 是否因为严格过滤没有足够的候选而使用了放宽的阈值
+    #[serde(default)]
+    pub relaxed: bool,
+}
+
+/// 一轮检查的完整决定
+///
+/// 监控循环每轮记录一个，`KillerConfig::evaluate` 和回放对记录的场景给出同样的决定
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Decision {
+    pub level: PressureLevel,
+    /// 压力是否已经持续足够时间，即是否触发终止
+    pub sustained: bool,
+    /// 终止的进程，没有终止时为计划中得分最高的候选
+    pub candidate: Option<DecisionCandidate>,
+    pub outcome: DecisionOutcome,
+}
+
+impl Decision {
+    /// 压力没有持续足够时间、不做选择的决定
+    pub fn no_pressure(level: PressureLevel) -> Self {
+        Self {
+            level,
+            sustained: false,
+            candidate: None,
+            outcome: DecisionOutcome::NoPressure,
+        }
+    }
+
+    /// 被终止（或委托给内核终止）的进程，不终止时为None
+    pub fn victim(&self) -> Option<ProcessId> {
+        match self.outcome {
+            DecisionOutcome::Killed | DecisionOutcome::Delegated => {
+                self.candidate.as_ref().and_then(|candidate| ProcessId::new(candidate.pid))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// 一条带时间戳的审计记录
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditRecord {
+    pub timestamp_ms: u64,
+    #[serde(flatten)]
+    pub event: AuditEvent,
+}
+
+impl AuditRecord {
+    /// 以当前时间创建审计记录
+    pub fn new(event: AuditEvent) -> Self {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+
+        Self { timestamp_ms, event }
+    }
+
+    /// 序列化为单行JSON
+    pub fn to_json(&self) -> String {
+        // 审计记录只包含基本类型，序列化不会失败
+        serde_json::to_string(self).unwrap_or_default()
+    }
+
+    /// 写入审计日志
+    pub fn log(&self) {
+        self.log_at(log::Level::Info);
+    }
+
+    /// 以指定级别写入审计日志
+    pub fn log_at(&self, level: log::Level) {
+        log::log!(target: AUDIT_TARGET, level, "{}", self.to_json());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kill_record_json() {
+        let record = AuditRecord::new(AuditEvent::Kill {
+            pid: 1234,
+            name: "stress".to_string(),
+            memory_freed: 4096,
+            source: KillSource::Kernel,
+        });
+
+        let value: serde_json::Value = serde_json::from_str(&record.to_json()).unwrap();
+        assert_eq!(value["event"], "kill");
+        assert_eq!(value["pid"], 1234);
+        assert_eq!(value["source"], "kernel");
+        assert!(value["timestamp_ms"].as_u64().unwrap() > 0);
+    }
+
+    #[test]
+    fn test_pressure_transition_json() {
+        let record = AuditRecord::new(AuditEvent::PressureTransition {
+            from: PressureLevel::Warning,
+            to: PressureLevel::Critical,
+        });
+
+        let value: serde_json::Value = serde_json::from_str(&record.to_json()).unwrap();
+        assert_eq!(value["event"], "pressure_transition");
+        assert_eq!(value["from"], "Warning");
+        assert_eq!(value["to"], "Critical");
+    }
+
+    #[test]
+    fn test_loop_overrun_json() {
+        let record = AuditRecord::new(AuditEvent::LoopOverrun {
+            consecutive: 6,
+            cycle_ms: 800,
+            interval_ms: 100,
+        });
+
+        let value: serde_json::Value = serde_json::from_str(&record.to_json()).unwrap();
+        assert_eq!(value["event"], "loop_overrun");
+        assert_eq!(value["consecutive"], 6);
+        assert_eq!(value["cycle_ms"], 800);
+    }
+
+    #[test]
+    fn test_decision_json() {
+        let record = AuditRecord::new(AuditEvent::Decision(Decision {
+            level: PressureLevel::Critical,
+            sustained: true,
+            candidate: Some(DecisionCandidate {
+                pid: 1234,
+                name: "stress".to_string(),
+                score: 0.5,
+                relaxed: false,
+            }),
+            outcome: DecisionOutcome::RateLimited,
+        }));
+
+        let value: serde_json::Value = serde_json::from_str(&record.to_json()).unwrap();
+        assert_eq!(value["event"], "decision");
+        assert_eq!(value["level"], "Critical");
+        assert_eq!(value["candidate"]["pid"], 1234);
+        assert_eq!(value["outcome"], "rate_limited");
+    }
+}
@@ -0,0 +1,432 @@
+use std::fmt::{self, Write as _};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use crate::ffi::safe_wrapper::SystemInterface;
+use crate::ffi::types::{ProcessId, SystemError, Result};
+use crate::linux::proc_stat::{ProcessKey, ProcessStat};
+
+/// 终止计划中最多保留的候选受害者数
+pub const MAX_PLANNED_VICTIMS: usize = 8;
+
+/// 进程名的最大长度（内核的 TASK_COMM_LEN 为16，包含结尾的NUL）
+const COMM_LEN: usize = 15;
+
+/// 读取 /proc/[pid]/stat 的缓冲区大小，足以容纳到starttime为止的字段
+const STAT_BUF_LEN: usize = 1024;
+
+/// 拼接 /proc/[pid]/stat 路径的栈缓冲区大小
+const PATH_BUF_LEN: usize = 256;
+
+/// 栈上的定长字符串缓冲区，写满后的写入返回错误而不是扩容
+pub struct StackBuf<const N: usize> {
+    buf: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> StackBuf<N> {
+    /// 创建空的缓冲区
+    pub const fn new() -> Self {
+        Self { buf: [0; N], len: 0 }
+    }
+
+    /// 已写入的内容
+    pub fn as_str(&self) -> &str {
+        // 只通过 write_str 写入完整的 &str，内容始终是合法的UTF-8
+        std::str::from_utf8(&self.buf[..self.len]).unwrap_or_default()
+    }
+
+    /// 清空缓冲区
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl<const N: usize> Default for StackBuf<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> fmt::Write for StackBuf<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if end > N {
+            return Err(fmt::Error);
+        }
+        self.buf[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+impl<const N: usize> fmt::Debug for StackBuf<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+/// 定长的进程名，超出内核长度的部分在字符边界处截断
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub struct Comm {
+    bytes: [u8; COMM_LEN],
+    len: u8,
+}
+
+impl Comm {
+    /// 从进程名创建
+    pub fn new(name: &str) -> Self {
+        let mut len = name.len().min(COMM_LEN);
+        while !name.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        let mut bytes = [0; COMM_LEN];
+        bytes[..len].copy_from_slice(&name.as_bytes()[..len]);
+        Self { bytes, len: len as u8 }
+    }
+
+    /// 进程名
+    pub fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.len as usize]).unwrap_or_default()
+    }
+}
+
+impl fmt::Debug for Comm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+/// 预先选出的候选受害者
+///
+/// 只包含定长字段，终止时不需要再读取或分配任何东西
+#[derive(Debug, Clone, Copy)]
+pub struct PlannedVictim {
+    pub key: ProcessKey,
+    pub comm: Comm,
+    /// 分散终止时区分应用的名称：通常与comm相同，启用 `match_exe_name` 时为可执行文件名
+    pub app: Comm,
+    /// 计划时的RSS（kB）
+    pub rss_kb: u64,
+    pub score: f64,
+    /// 是否来自因时间预算用尽而提前结束的选择
+    pub partial: bool,
+}
+
+/// 按得分降序排列的定长候选数组
+///
+/// 分散终止时，选择器可以把一个候选提前到第一位
+#[derive(Debug)]
+pub struct KillPlan {
+    victims: [Option<PlannedVictim>; MAX_PLANNED_VICTIMS],
+    len: usize,
+}
+
+impl KillPlan {
+    /// 创建空的计划
+    pub const fn new() -> Self {
+        Self {
+            victims: [None; MAX_PLANNED_VICTIMS],
+            len: 0,
+        }
+    }
+
+    /// 清空计划
+    pub fn clear(&mut self) {
+        self.victims = [None; MAX_PLANNED_VICTIMS];
+        self.len = 0;
+    }
+
+    /// 按得分插入候选，计划已满时丢弃得分最低的一个
+    pub fn insert(&mut self, victim: PlannedVictim) {
+        let position = self.victims().position(|v| v.score < victim.score).unwrap_or(self.len);
+        if position >= MAX_PLANNED_VICTIMS {
+            return;
+        }
+
+        let last = self.len.min(MAX_PLANNED_VICTIMS - 1);
+        self.victims.copy_within(position..last, position + 1);
+        self.victims[position] = Some(victim);
+        self.len = (self.len + 1).min(MAX_PLANNED_VICTIMS);
+    }
+
+    /// 把 `position` 处的候选移到第一位，其余候选的顺序不变
+    pub fn move_to_front(&mut self, position: usize) {
+        if position >= self.len {
+            return;
+        }
+        self.victims[..=position].rotate_right(1);
+    }
+
+    /// 只保留得分最高的 `len` 个候选
+    pub fn truncate(&mut self, len: usize) {
+        while self.len > len {
+            self.len -= 1;
+            self.victims[self.len] = None;
+        }
+    }
+
+    /// 从计划中移除进程
+    pub fn remove(&mut self, key: ProcessKey) {
+        let Some(position) = self.victims().position(|v| v.key == key) else {
+            return;
+        };
+
+        self.victims.copy_within(position + 1..self.len, position);
+        self.len -= 1;
+        self.victims[self.len] = None;
+    }
+
+    /// 按得分降序遍历候选
+    pub fn victims(&self) -> impl Iterator<Item = &PlannedVictim> {
+        self.victims[..self.len].iter().flatten()
+    }
+
+    /// 得分最高的候选
+    pub fn first(&self) -> Option<PlannedVictim> {
+        self.victims[0]
+    }
+
+    /// 候选数
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// 计划是否为空
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+impl Default for KillPlan {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 关键路径使用的全部状态
+///
+/// 从“检测到持续压力”到“信号送达”之间只使用这里预先分配的内存：
+/// 受害者在压力上升阶段（Warning或尚未持续的Critical）就已经选好，
+/// 终止时只需用栈上拼接的路径读取一次stat确认pid没有被复用，然后发送信号。
+/// 启用自我保护时整个结构被 mlock，不会因为被换出而在回收时阻塞
+#[derive(Debug)]
+pub struct CriticalState {
+    pub plan: KillPlan,
+    stat_buf: [u8; STAT_BUF_LEN],
+    /// 发送信号时已经退出（ESRCH）的受害者
+    vanished: Option<PlannedVictim>,
+}
+
+impl CriticalState {
+    /// 在堆上创建，使其地址在所有者移动后保持不变，便于锁定
+    pub fn new() -> Box<Self> {
+        Box::new(Self {
+            plan: KillPlan::new(),
+            stat_buf: [0; STAT_BUF_LEN],
+            vanished: None,
+        })
+    }
+
+    /// 锁定关键路径状态所在的内存页
+    pub fn lock(&self) -> Result<()> {
+        SystemInterface::new().lock_memory(self)
+    }
+
+    /// 按得分从高到低终止计划中第一个仍然存活且未被复用的进程
+    ///
+    /// 已经退出或pid被复用的候选会被跳过；终止成功的进程从计划中移除。
+    /// 确认存活后、发送信号时才退出的候选同样从计划中移除并立即返回
+    /// `None`，由调用方通过 [`take_vanished`](Self::take_vanished) 取出后
+    /// 决定是否继续。整个过程不分配堆内存
+    pub fn kill_first<F>(&mut self, proc_root: &Path, mut kill: F) -> Result<Option<PlannedVictim>>
+    where
+        F: FnMut(ProcessId) -> Result<()>,
+    {
+        for index in 0..self.plan.len() {
+            let Some(victim) = self.plan.victims[index] else {
+                continue;
+            };
+            if !self.still_running(proc_root, victim.key) {
+                continue;
+            }
+
+            match kill(victim.key.pid) {
+                Ok(()) => {
+                    self.plan.remove(victim.key);
+                    return Ok(Some(victim));
+                }
+                // 在确认和发送信号之间退出
+                Err(SystemError::ProcessNotFound) => {
+                    self.plan.remove(victim.key);
+                    self.vanished = Some(victim);
+                    return Ok(None);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// 取出最近一次终止时已经自行退出的受害者
+    pub fn take_vanished(&mut self) -> Option<PlannedVictim> {
+        self.vanished.take()
+    }
+
+    /// 进程是否仍然存在且启动时间与计划时一致
+    fn still_running(&mut self, proc_root: &Path, key: ProcessKey) -> bool {
+        let mut path = StackBuf::<PATH_BUF_LEN>::new();
+        let Some(root) = proc_root.to_str() else {
+            return false;
+        };
+        if write!(path, "{}/{}/stat", root, key.pid.as_raw()).is_err() {
+            return false;
+        }
+
+        // 路径短于标准库的栈上缓冲区，打开时不会分配内存
+        let Ok(mut file) = File::open(path.as_str()) else {
+            return false;
+        };
+        let mut len = 0;
+        while len < self.stat_buf.len() {
+            match file.read(&mut self.stat_buf[len..]) {
+                Ok(0) => break,
+                Ok(read) => len += read,
+                Err(_) => return false,
+            }
+        }
+
+        std::str::from_utf8(&self.stat_buf[..len])
+            .ok()
+            .and_then(ProcessStat::parse_start_time)
+            == Some(key.start_time)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn victim(pid: i32, score: f64) -> PlannedVictim {
+        PlannedVictim {
+            key: ProcessKey {
+                pid: ProcessId::new(pid).unwrap(),
+                start_time: 100,
+            },
+            comm: Comm::new("worker"),
+            app: Comm::new("worker"),
+            rss_kb: 1024,
+            score,
+            partial: false,
+        }
+    }
+
+    fn plan_pids(plan: &KillPlan) -> Vec<i32> {
+        plan.victims().map(|v| v.key.pid.as_raw()).collect()
+    }
+
+    #[test]
+    fn test_plan_keeps_highest_scores_in_order() {
+        let mut plan = KillPlan::new();
+        for pid in 1..=MAX_PLANNED_VICTIMS as i32 + 2 {
+            plan.insert(victim(pid, pid as f64));
+        }
+
+        assert_eq!(plan.len(), MAX_PLANNED_VICTIMS);
+        let pids = plan_pids(&plan);
+        assert_eq!(pids[0], MAX_PLANNED_VICTIMS as i32 + 2);
+        assert_eq!(*pids.last().unwrap(), 3);
+
+        plan.remove(victim(5, 0.0).key);
+        assert_eq!(plan.len(), MAX_PLANNED_VICTIMS - 1);
+        assert!(!plan_pids(&plan).contains(&5));
+
+        plan.move_to_front(2);
+        assert_eq!(&plan_pids(&plan)[..4], &[8, 10, 9, 7]);
+        plan.move_to_front(MAX_PLANNED_VICTIMS);
+        assert_eq!(plan_pids(&plan)[0], 8);
+    }
+
+    #[test]
+    fn test_comm_truncates_at_char_boundary() {
+        assert_eq!(Comm::new("postgres").as_str(), "postgres");
+        assert_eq!(Comm::new("a-very-long-process-name").as_str(), "a-very-long-pro");
+        // 第15个字节落在多字节字符中间
+        assert_eq!(Comm::new("abcdefghijklmn数据").as_str(), "abcdefghijklmn");
+    }
+
+    #[test]
+    fn test_stack_buf_rejects_overflow() {
+        let mut buf = StackBuf::<8>::new();
+        assert!(write!(buf, "{}", 1234).is_ok());
+        assert!(write!(buf, "{}", 56789).is_err());
+        assert_eq!(buf.as_str(), "1234");
+    }
+
+    #[test]
+    fn test_kill_first_skips_reused_pids() {
+        let root = tempfile::tempdir().unwrap();
+        let stat = include_str!("../../tests/fixtures/proc/stat");
+        // pid 10 已被复用（启动时间不同），pid 11 已经退出，pid 12 仍然是计划中的进程
+        for (pid, start_time) in [(10, 999), (12, 4821)] {
+            let dir = root.path().join(pid.to_string());
+            fs::create_dir(&dir).unwrap();
+            fs::write(dir.join("stat"), stat.replace(" 4821 ", &format!(" {} ", start_time))).unwrap();
+        }
+
+        let mut state = CriticalState::new();
+        for (pid, score) in [(10, 3.0), (11, 2.0), (12, 1.0)] {
+            let mut planned = victim(pid, score);
+            planned.key.start_time = 4821;
+            state.plan.insert(planned);
+        }
+
+        let mut killed = Vec::new();
+        let victim = state
+            .kill_first(root.path(), |pid| {
+                killed.push(pid.as_raw());
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(victim.unwrap().key.pid.as_raw(), 12);
+        assert_eq!(killed, vec![12]);
+        assert!(!plan_pids(&state.plan).contains(&12));
+    }
+
+    #[test]
+    fn test_kill_first_records_vanished_victim() {
+        let root = tempfile::tempdir().unwrap();
+        let stat = include_str!("../../tests/fixtures/proc/stat");
+        for pid in [10, 11] {
+            let dir = root.path().join(pid.to_string());
+            fs::create_dir(&dir).unwrap();
+            fs::write(dir.join("stat"), stat).unwrap();
+        }
+
+        let mut state = CriticalState::new();
+        for (pid, score) in [(10, 2.0), (11, 1.0)] {
+            let mut planned = victim(pid, score);
+            planned.key.start_time = 4821;
+            state.plan.insert(planned);
+        }
+
+        // pid 10 在发送信号时已经退出，不继续终止下一个候选
+        let mut killed = Vec::new();
+        let victim = state
+            .kill_first(root.path(), |pid| {
+                killed.push(pid.as_raw());
+                Err(SystemError::ProcessNotFound)
+            })
+            .unwrap();
+
+        assert!(victim.is_none());
+        assert_eq!(killed, vec![10]);
+        assert_eq!(state.take_vanished().unwrap().key.pid.as_raw(), 10);
+        assert!(state.take_vanished().is_none());
+        assert_eq!(plan_pids(&state.plan), vec![11]);
+    }
+}
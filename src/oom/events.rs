@@ -0,0 +1,257 @@
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+use crate::oom::audit::AuditRecord;
+
+/// 事件队列已满时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverflowPolicy {
+    /// 丢弃最旧的事件，监控循环从不阻塞
+    #[default]
+    DropOldest,
+    /// 丢弃新的事件，保留已经排队的
+    DropNewest,
+    /// 最多等待这段时间，仍然没有空位时丢弃新的事件
+    ///
+    /// 等待发生在监控循环中，会推迟下一轮检查
+    Block(Duration),
+}
+
+/// 事件订阅的配置
+#[derive(Debug, Clone)]
+pub struct EventStreamConfig {
+    /// 队列容量
+    pub capacity: usize,
+    /// 队列已满时的处理方式
+    pub overflow: OverflowPolicy,
+}
+
+impl Default for EventStreamConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 256,
+            overflow: OverflowPolicy::DropOldest,
+        }
+    }
+}
+
+/// 监控循环和订阅者之间的有界事件队列
+#[derive(Debug)]
+pub(crate) struct EventQueue {
+    items: Mutex<VecDeque<AuditRecord>>,
+    // 有新事件时通知订阅者，有空位时通知等待的监控循环
+    not_empty: Condvar,
+    not_full: Condvar,
+    capacity: usize,
+    overflow: OverflowPolicy,
+    dropped: AtomicU64,
+    // 订阅者已经drop，不再入队
+    closed: AtomicBool,
+}
+
+impl EventQueue {
+    pub(crate) fn new(config: EventStreamConfig) -> Self {
+        let capacity = config.capacity.max(1);
+        Self {
+            items: Mutex::new(VecDeque::with_capacity(capacity)),
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            capacity,
+            overflow: config.overflow,
+            dropped: AtomicU64::new(0),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    /// 入队，队列已满时按 `overflow` 处理
+    pub(crate) fn push(&self, record: AuditRecord) {
+        if self.closed.load(Ordering::SeqCst) {
+            return;
+        }
+
+        let mut items = self.lock();
+        if items.len() >= self.capacity {
+            match self.overflow {
+                OverflowPolicy::DropOldest => {
+                    items.pop_front();
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                OverflowPolicy::DropNewest => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+                OverflowPolicy::Block(timeout) => {
+                    let deadline = Instant::now() + timeout;
+                    while items.len() >= self.capacity && !self.closed.load(Ordering::SeqCst) {
+                        let remaining = deadline.saturating_duration_since(Instant::now());
+                        if remaining.is_zero() {
+                            self.dropped.fetch_add(1, Ordering::Relaxed);
+                            return;
+                        }
+                        items = self.not_full.wait_timeout(items, remaining)
+                            .unwrap_or_else(|e| e.into_inner())
+                            .0;
+                    }
+                    // 订阅者已经drop
+                    if items.len() >= self.capacity {
+                        return;
+                    }
+                }
+            }
+        }
+        items.push_back(record);
+        self.not_empty.notify_one();
+    }
+
+    /// 取出下一个事件，`timeout` 为None时一直等待
+    fn pop(&self, timeout: Option<Duration>) -> Option<AuditRecord> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut items = self.lock();
+        loop {
+            if let Some(record) = items.pop_front() {
+                self.not_full.notify_one();
+                return Some(record);
+            }
+            match deadline {
+                None => items = self.not_empty.wait(items).unwrap_or_else(|e| e.into_inner()),
+                Some(deadline) => {
+                    let remaining = deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        return None;
+                    }
+                    items = self.not_empty.wait_timeout(items, remaining)
+                        .unwrap_or_else(|e| e.into_inner())
+                        .0;
+                }
+            }
+        }
+    }
+
+    /// 因队列已满而丢弃的事件数
+    pub(crate) fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        let _guard = self.lock();
+        self.not_full.notify_all();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, VecDeque<AuditRecord>> {
+        self.items.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// 审计事件的订阅端，见 `OOMKiller::subscribe_events`
+///
+/// drop之后监控循环不再入队，`Block` 策略也不会再等待
+#[derive(Debug)]
+pub struct EventReceiver {
+    queue: Arc<EventQueue>,
+}
+
+impl EventReceiver {
+    pub(crate) fn new(queue: Arc<EventQueue>) -> Self {
+        Self { queue }
+    }
+
+    /// 阻塞等待下一个事件
+    pub fn recv(&self) -> AuditRecord {
+        loop {
+            if let Some(record) = self.queue.pop(None) {
+                return record;
+            }
+        }
+    }
+
+    /// 最多等待 `timeout`，没有事件时返回None
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<AuditRecord> {
+        self.queue.pop(Some(timeout))
+    }
+
+    /// 不等待，没有事件时返回None
+    pub fn try_recv(&self) -> Option<AuditRecord> {
+        self.queue.pop(Some(Duration::ZERO))
+    }
+
+    /// 因队列已满而丢弃的事件数
+    pub fn dropped(&self) -> u64 {
+        self.queue.dropped()
+    }
+}
+
+impl Drop for EventReceiver {
+    fn drop(&mut self) {
+        self.queue.close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oom::audit::AuditEvent;
+
+    fn record(remaining_ms: u64) -> AuditRecord {
+        AuditRecord::new(AuditEvent::RateLimited { remaining_ms })
+    }
+
+    fn remaining(record: AuditRecord) -> u64 {
+        match record.event {
+            AuditEvent::RateLimited { remaining_ms } => remaining_ms,
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    fn filled(overflow: OverflowPolicy) -> EventReceiver {
+        let queue = Arc::new(EventQueue::new(EventStreamConfig { capacity: 2, overflow }));
+        for i in 1..=3 {
+            queue.push(record(i));
+        }
+        EventReceiver::new(queue)
+    }
+
+    #[test]
+    fn test_overflow_policies() {
+        let receiver = filled(OverflowPolicy::DropOldest);
+        assert_eq!(receiver.dropped(), 1);
+        assert_eq!(remaining(receiver.try_recv().unwrap()), 2);
+        assert_eq!(remaining(receiver.try_recv().unwrap()), 3);
+        assert!(receiver.try_recv().is_none());
+
+        let receiver = filled(OverflowPolicy::DropNewest);
+        assert_eq!(receiver.dropped(), 1);
+        assert_eq!(remaining(receiver.try_recv().unwrap()), 1);
+        assert_eq!(remaining(receiver.try_recv().unwrap()), 2);
+
+        // 没有人取出时等待超时后丢弃新的事件
+        let start = Instant::now();
+        let receiver = filled(OverflowPolicy::Block(Duration::from_millis(50)));
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        assert_eq!(receiver.dropped(), 1);
+        assert_eq!(remaining(receiver.try_recv().unwrap()), 1);
+    }
+
+    #[test]
+    fn test_block_waits_for_consumer() {
+        let queue = Arc::new(EventQueue::new(EventStreamConfig {
+            capacity: 1,
+            overflow: OverflowPolicy::Block(Duration::from_secs(5)),
+        }));
+        let receiver = EventReceiver::new(Arc::clone(&queue));
+        queue.push(record(1));
+
+        let consumer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(50));
+            let first = remaining(receiver.recv());
+            (first, receiver)
+        });
+        // 订阅者取出一个之后有了空位，不丢弃
+        queue.push(record(2));
+        let (first, receiver) = consumer.join().unwrap();
+        assert_eq!(first, 1);
+        assert_eq!(remaining(receiver.recv()), 2);
+        assert_eq!(receiver.dropped(), 0);
+    }
+}
@@ -1,10 +1,28 @@
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
-use std::time::{Duration, Instant};
+use std::collections::{BTreeMap, HashSet};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::fmt::{self, Write as _};
+use std::os::raw::c_int;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
 use crate::ffi::types::{ProcessId, SystemError, Result};
+use serde::Serialize;
+use crate::ffi::safe_wrapper::SystemInterface;
+use crate::linux::proc::DEFAULT_PROC_ROOT;
+use crate::linux::proc_stat::{ProcessKey, ProcessStat};
+use crate::linux::reclaim::{ReclaimConfig, Reclaimer};
+use crate::oom::audit::{AuditEvent, AuditRecord, Decision, DecisionCandidate, DecisionOutcome};
+use crate::oom::critical::{CriticalState, PlannedVictim, StackBuf};
+use crate::oom::events::{EventQueue, EventReceiver, EventStreamConfig};
+use crate::oom::latency::{LatencySummary, LoopLatency, Phase};
+use crate::oom::metrics;
 use crate::oom::score::OOMScorer;
-use crate::oom::pressure::{PressureDetector, PressureThresholds};
+use crate::oom::pressure::{PressureDetector, PressureLevel, PressureThresholds};
 use crate::oom::selector::{ProcessSelector, SelectorConfig};
+use crate::oom::replay::{Recorder, RecorderConfig};
+use crate::oom::report::KillerStatusReport;
+use crate::oom::snapshot::{SnapshotConfig, SnapshotWriter};
+use crate::oom::state::{top_offenders, OffenderStats, PersistedStats};
 use std::thread;
 
 /// OOM Killer的配置
@@ -14,10 +32,88 @@ pub struct KillerConfig {
     pub selector: SelectorConfig,
     /// 内存压力阈值配置
     pub pressure: PressureThresholds,
+    /// 容器模式：按该 memory cgroup 目录的 memory.stat 判断压力，`None` 表示使用 /proc/meminfo
+    ///
+    /// 压力基于匿名内存相对于cgroup上限的比例，可回收的页缓存不计入
+    pub container_cgroup: Option<PathBuf>,
     /// 两次终止进程之间的最小间隔
     pub min_kill_interval: Duration,
+    /// 上一次终止每释放1GiB内存，终止间隔增加的时间，为0时间隔固定为 `min_kill_interval`
+    ///
+    /// 释放大量内存之后，内核需要时间回收页面、拆除页表，这期间的压力读数并不可靠
+    pub kill_interval_per_gib: Duration,
+    /// 按释放内存延长后的终止间隔上限，不小于 `min_kill_interval`
+    pub max_kill_interval: Duration,
+    /// 一次压力过程中终止进程估算回收的内存上限（字节），`None` 表示不限制
+    ///
+    /// 回收滞后于终止时，持续的压力可能导致连续终止远多于需要的进程。
+    /// 达到上限后不再终止，直到压力回到Normal、开始新的一次压力过程
+    pub max_reclaim_per_episode: Option<u64>,
     /// 检查内存压力的间隔
     pub check_interval: Duration,
+    /// 连续超过 `check_interval` 的轮数超过该值时发出警告，0表示不警告
+    pub overrun_alert_after: u32,
+    /// 终止后等待确认实际回收量的时间窗口
+    pub confirm_window: Duration,
+    /// 受害者进入僵尸（Z）或死亡（X）状态时即确认终止，不必等到它被回收
+    ///
+    /// 父进程尚未回收的子进程会一直留在 /proc 中，只等待消失会把它们误报为未确认
+    pub confirm_zombie: bool,
+    /// 终止进程的方式
+    pub kill_mode: KillMode,
+    /// 持续压力下先尝试非破坏性回收，再检查一次压力，压力消除时不终止；`None` 表示直接终止
+    ///
+    /// 回收请求在关键路径上执行，写入文件时会分配内存
+    pub attempt_reclaim_first: Option<ReclaimConfig>,
+    /// 压力进入Critical时写入诊断快照，`None` 表示禁用
+    pub snapshot: Option<SnapshotConfig>,
+    /// 定期记录内存状态供离线回放，`None` 表示禁用
+    pub recorder: Option<RecorderConfig>,
+    /// 每隔这段时间在审计日志中记录一次RSS最高的进程，`None` 表示禁用
+    ///
+    /// 与是否终止和 `check_interval` 无关，用于在OOM之前发现内存占用的趋势。
+    /// 只写入日志，不发送到webhook
+    pub periodic_snapshot_interval: Option<Duration>,
+    /// 定期记录的进程数
+    pub periodic_snapshot_top: usize,
+    /// 保存终止次数、回收量和按进程名统计的终止排行的文件，`None` 表示不保存
+    ///
+    /// 创建时读取并累加到新的统计中，之后每隔 `state_save_interval` 在统计变化时
+    /// 原子地写入（先写临时文件再重命名），停止时再写入一次
+    pub state_file: Option<PathBuf>,
+    /// 写入状态文件的最小间隔
+    pub state_save_interval: Duration,
+    /// `start` 创建监控线程失败后重试的次数，0表示不重试
+    ///
+    /// 内存或线程数耗尽时创建线程可能失败，而这正是需要监控的时候
+    pub spawn_retries: u32,
+    /// 第一次重试前等待的时间，之后每次加倍
+    pub spawn_backoff: Duration,
+    /// 每轮记录一次决定（是否触发、得分最高的候选、是否终止），即使没有终止进程
+    pub log_every_decision: bool,
+    /// 记录每轮决定使用的日志级别
+    pub decision_log_level: log::Level,
+    /// 自我保护：锁定关键路径使用的内存（mlock），需要 CAP_IPC_LOCK 或足够的 RLIMIT_MEMLOCK
+    pub self_protection: bool,
+    /// 是否监视 /dev/kmsg 并记录内核自身的 OOM 终止
+    #[cfg(feature = "kmsg")]
+    pub watch_kmsg: bool,
+    /// 将审计记录发送到 webhook，`None` 表示禁用
+    ///
+    /// 与 `statsd`、`track_processes` 和 `watch_kmsg` 一样，由 `start` 创建的监控线程启动
+    #[cfg(feature = "webhook")]
+    pub webhook: Option<crate::oom::webhook::WebhookConfig>,
+    /// 定期向 statsd 发送指标，`None` 表示禁用
+    #[cfg(feature = "statsd")]
+    pub statsd: Option<crate::oom::statsd::StatsdConfig>,
+    /// 通过 proc connector 增量跟踪进程，`None` 表示每轮扫描 /proc
+    #[cfg(feature = "proc-connector")]
+    pub track_processes: Option<crate::linux::proc_connector::ProcTrackerConfig>,
+    /// 终止属于活动图形会话的进程之前发出警告，`None` 表示禁用
+    ///
+    /// 会话通过进程环境中的 XDG_SESSION_ID（logind）或 XDG_SESSION_TYPE 判断
+    #[cfg(feature = "desktop-session")]
+    pub desktop_session: Option<crate::linux::session::DesktopSessionConfig>,
 }
 
 impl Default for KillerConfig {
@@ -25,221 +121,2634 @@ impl Default for KillerConfig {
         Self {
             selector: SelectorConfig::default(),
             pressure: PressureThresholds::default(),
+            container_cgroup: None,
             min_kill_interval: Duration::from_secs(5),
+            kill_interval_per_gib: Duration::ZERO,
+            max_kill_interval: Duration::from_secs(30),
+            max_reclaim_per_episode: None,
             check_interval: Duration::from_millis(100),
+            overrun_alert_after: 5,
+            confirm_window: Duration::from_secs(2),
+            confirm_zombie: true,
+            kill_mode: KillMode::Signal,
+            attempt_reclaim_first: None,
+            snapshot: None,
+            recorder: None,
+            periodic_snapshot_interval: None,
+            periodic_snapshot_top: 5,
+            state_file: None,
+            state_save_interval: Duration::from_secs(60),
+            spawn_retries: 0,
+            spawn_backoff: Duration::from_millis(100),
+            log_every_decision: false,
+            decision_log_level: log::Level::Info,
+            self_protection: false,
+            #[cfg(feature = "kmsg")]
+            watch_kmsg: false,
+            #[cfg(feature = "webhook")]
+            webhook: None,
+            #[cfg(feature = "statsd")]
+            statsd: None,
+            #[cfg(feature = "proc-connector")]
+            track_processes: None,
+            #[cfg(feature = "desktop-session")]
+            desktop_session: None,
         }
     }
 }
 
+/// 终止进程的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KillMode {
+    /// 直接发送SIGKILL
+    Signal,
+    /// 将受害进程的oom_score_adj设为1000，由内核OOM Killer在真正的压力下执行终止
+    ///
+    /// 如果进程在 `revert_after` 之后仍然存在，恢复其原有的oom_score_adj
+    KernelDelegate { revert_after: Duration },
+    /// 先发送SIGTERM让进程自行清理，`grace` 之后仍未退出时发送SIGKILL
+    ///
+    /// 不在发送SIGTERM后阻塞等待：每轮开始时检查已到期的进程并统一发送SIGKILL
+    Graceful { grace: Duration },
+}
+
+impl KillMode {
+    /// 直接发送给受害者的信号，委托给内核时为None
+    fn signal(&self) -> Option<c_int> {
+        match self {
+            KillMode::Signal => Some(libc::SIGKILL),
+            KillMode::Graceful { .. } => Some(libc::SIGTERM),
+            KillMode::KernelDelegate { .. } => None,
+        }
+    }
+}
+
+/// 最高的oom_score_adj，内核会优先终止该进程
+const OOM_SCORE_ADJ_MAX: i32 = 1000;
+
+/// 已委托给内核终止、等待观察结果的进程
+#[derive(Debug, Clone)]
+struct DelegatedVictim {
+    // 带启动时间，恢复前确认pid没有被新进程复用
+    key: ProcessKey,
+    original_adj: i32,
+    revert_at: Instant,
+}
+
+/// 已发送SIGTERM、等待其在期限前退出的进程
+#[derive(Debug, Clone)]
+struct TerminatingVictim {
+    pid: ProcessId,
+    // 计划时的启动时间，用于识别pid复用
+    start_time: u64,
+    kill_at: Instant,
+}
+
+/// 已终止、等待确认实际回收量的进程
+#[derive(Debug, Clone)]
+struct PendingConfirmation {
+    pid: ProcessId,
+    // 计划时的启动时间，用于识别pid复用
+    start_time: u64,
+    estimated: u64,
+    available_before: u64,
+    killed_at: Instant,
+}
+
+/// 终止历史中保留的最大记录数
+const MAX_KILL_HISTORY: usize = 256;
+
+/// 终止操作的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum KillSource {
+    /// 由 rOOM 自身选择并终止
+    Room,
+    /// 由内核 OOM Killer 终止（通过 /dev/kmsg 观察到）
+    Kernel,
+}
+
+impl fmt::Display for KillSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KillSource::Room => write!(f, "room"),
+            KillSource::Kernel => write!(f, "kernel"),
+        }
+    }
+}
+
+/// 一次终止操作的历史记录
+#[derive(Debug, Clone)]
+pub struct KillRecord {
+    pub pid: ProcessId,
+    pub name: String,
+    pub memory_freed: u64,
+    pub timestamp: SystemTime,
+    pub source: KillSource,
+    /// 受害者是否来自因时间预算用尽而提前结束的选择
+    pub partial: bool,
+}
+
 /// OOM Killer的运行状态
 #[derive(Debug, Clone)]
 pub struct KillerStatus {
     pub last_kill_time: Option<Instant>,
     pub total_kills: u64,
-    pub total_memory_reclaimed: u64,
+    /// 按终止前RSS估算的回收量（字节）
+    pub estimated_reclaimed: u64,
+    /// 终止后实际观察到的回收量（字节）
+    pub confirmed_reclaimed: u64,
     pub running_since: Instant,
+    /// 最近一轮检查的耗时，还没有完成任何一轮时为None
+    pub last_cycle_duration: Option<Duration>,
+    /// 最近128轮检查的平均耗时
+    pub average_cycle_duration: Duration,
+    /// 最近的监控循环及各阶段耗时
+    pub latency: LatencySummary,
+    /// 实际使用的检查间隔，见 `OOMKiller::effective_interval`
+    pub effective_interval: Duration,
+    /// 监控循环中出错的轮数
+    pub error_count: u64,
+    /// 因订阅队列已满而丢弃的审计事件数，见 `OOMKiller::subscribe_events`
+    pub events_dropped: u64,
+}
+
+/// 终止统计、按进程名的统计、监控循环耗时和终止历史，由 `OOMKiller` 和 `start` 创建的监控线程共享
+///
+/// 监控线程运行时统计由线程更新，句柄上的 `get_status`、`kill_history` 和 `reset_stats` 都作用于同一份
+#[derive(Debug)]
+struct SharedStats {
+    last_kill_time: Option<Instant>,
+    total_kills: u64,
+    estimated_reclaimed: u64,
+    confirmed_reclaimed: u64,
+    running_since: Instant,
+    // 记录阶段耗时时加锁，不分配内存，可以在关键路径上使用
+    latency: LoopLatency,
+    history: Vec<KillRecord>,
+    // 按进程名统计的终止次数和回收量，启用 state_file 时跨重启保留
+    offenders: BTreeMap<String, OffenderStats>,
+}
+
+impl SharedStats {
+    fn new() -> Self {
+        Self {
+            last_kill_time: None,
+            total_kills: 0,
+            estimated_reclaimed: 0,
+            confirmed_reclaimed: 0,
+            running_since: Instant::now(),
+            latency: LoopLatency::new(),
+            history: Vec::new(),
+            offenders: BTreeMap::new(),
+        }
+    }
 }
 
+/// 监控循环出错时调用的回调
+type ErrorHandler = Box<dyn FnMut(&SystemError) + Send>;
+type DecisionWriter = Box<dyn std::io::Write + Send>;
+/// 移交给监控线程的回调和写入端，线程创建失败时可以取回
+type MonitorHandoff = Arc<Mutex<Option<(Option<ErrorHandler>, Option<DecisionWriter>)>>>;
+
 /// OOM Killer的主要实现
+///
+/// drop时停止 `start` 创建的监控线程并等待它退出
 pub struct OOMKiller {
     config: KillerConfig,
     selector: ProcessSelector,
+    // 读取进程和内存信息的proc根目录，监控线程按它创建自己的选择器
+    proc_root: PathBuf,
     running: Arc<AtomicBool>,
-    last_kill_time: Option<Instant>,
-    total_kills: u64,
-    total_memory_reclaimed: u64,
-    running_since: Instant,
+    // `start` 创建的监控线程，drop时等待它退出
+    monitor: Option<thread::JoinHandle<()>>,
+    // 与监控线程中的实例共享，状态中可以看到线程里的出错次数
+    error_count: Arc<AtomicU64>,
+    // 终止统计和历史，与监控线程共享
+    stats: Arc<Mutex<SharedStats>>,
+    error_handler: Option<ErrorHandler>,
+    // 每轮的决定以JSON行写入这里，见 `stream_decisions_to`
+    decision_writer: Option<DecisionWriter>,
+    // 上一次写入是否失败，连续失败时只警告一次
+    decision_write_failed: bool,
+    // 审计事件的订阅队列，与监控线程共享，见 `subscribe_events`
+    events: Option<Arc<EventQueue>>,
+    // 上一次终止估算释放的内存（字节），用于计算终止间隔
+    last_kill_freed: u64,
+    // 上一次定期记录RSS最高进程的时间
+    last_top_consumers: Option<Instant>,
+    // 本轮发送信号前已经自行退出的受害者（最近一个）及其数量，在关键路径之后记录
+    vanished: Option<PlannedVictim>,
+    vanished_count: u32,
+    // 最近一次读取或写入状态文件的内容和写入时间，统计没有变化时不再写入
+    saved_state: Option<PersistedStats>,
+    last_state_save: Option<Instant>,
+    pending_confirmations: Vec<PendingConfirmation>,
+    delegated: Vec<DelegatedVictim>,
+    terminating: Vec<TerminatingVictim>,
+    snapshots: Option<SnapshotWriter>,
+    recorder: Option<Recorder>,
+    reclaimer: Option<Reclaimer>,
+    // 关键路径使用的预分配状态，包括预先选好的受害者
+    critical: Box<CriticalState>,
+    last_level: PressureLevel,
+    rate_limit_reported: bool,
+    // 本次压力过程中终止进程估算回收的内存，以及是否已经记录过达到上限
+    episode_reclaimed: u64,
+    episode_cap_reported: bool,
+    // 本次压力过程中已经终止或委托给内核的进程，压力回到Normal之前不再选择
+    episode_victims: HashSet<ProcessKey>,
+    // 本次连续超过检查间隔是否已经警告过
+    overrun_reported: bool,
+    // 启用 log_every_decision 时最近一轮的决定
+    last_decision: Option<Decision>,
+    #[cfg(feature = "webhook")]
+    webhook: Option<crate::oom::webhook::WebhookSink>,
+    #[cfg(feature = "statsd")]
+    _statsd: Option<crate::oom::statsd::StatsdSink>,
+    #[cfg(feature = "kmsg")]
+    kernel_records: Option<std::sync::mpsc::Receiver<crate::linux::kmsg::KernelOomRecord>>,
+    // 读取 /dev/kmsg 的线程，drop时等待它退出
+    #[cfg(feature = "kmsg")]
+    kmsg_watcher: Option<thread::JoinHandle<()>>,
+    #[cfg(feature = "desktop-session")]
+    session_detector: Option<Box<dyn crate::linux::session::SessionDetector>>,
+    // 当前计划中属于活动图形会话的受害者，刷新计划时更新
+    #[cfg(feature = "desktop-session")]
+    graphical_victims: Vec<ProcessKey>,
+    // 测试中模拟创建监控线程失败的次数
+    #[cfg(test)]
+    spawn_failures: u32,
 }
 
 impl OOMKiller {
     /// 创建新的OOM Killer实例
     pub fn new(config: Option<KillerConfig>) -> Self {
-        let config = config.unwrap_or_default();
-        let scorer = OOMScorer::new();
-        let pressure_detector = PressureDetector::new(Some(config.pressure.clone()));
-        let selector = ProcessSelector::new(
-            Some(config.selector.clone()),
-            scorer,
-            pressure_detector,
-        );
+        let mut killer = Self::build(config.unwrap_or_default(), PathBuf::from(DEFAULT_PROC_ROOT));
+        killer.restore_state();
+
+        killer
+    }
+
+    /// 从指定的proc根目录读取进程和内存信息，`start` 创建的监控线程使用同一个目录
+    pub fn with_proc_root(mut self, proc_root: impl Into<PathBuf>) -> Self {
+        self.proc_root = proc_root.into();
+        self.selector = build_selector(&self.config, &self.proc_root);
+        self
+    }
+
+    /// 创建实例，不读取状态文件
+    fn build(config: KillerConfig, proc_root: PathBuf) -> Self {
+        let selector = build_selector(&config, &proc_root);
+        let snapshots = config.snapshot.clone().map(SnapshotWriter::new);
+        let recorder = config.recorder.clone().map(Recorder::new);
+        let reclaimer = config.attempt_reclaim_first.clone().map(Reclaimer::new);
+        #[cfg(feature = "desktop-session")]
+        let session_detector = config.desktop_session.as_ref().map(|_| {
+            Box::new(crate::linux::session::LogindSessionDetector::new())
+                as Box<dyn crate::linux::session::SessionDetector>
+        });
 
         Self {
             config,
             selector,
+            proc_root,
             running: Arc::new(AtomicBool::new(false)),
-            last_kill_time: None,
-            total_kills: 0,
-            total_memory_reclaimed: 0,
-            running_since: Instant::now(),
+            monitor: None,
+            error_count: Arc::new(AtomicU64::new(0)),
+            stats: Arc::new(Mutex::new(SharedStats::new())),
+            error_handler: None,
+            decision_writer: None,
+            decision_write_failed: false,
+            events: None,
+            last_kill_freed: 0,
+            last_top_consumers: None,
+            vanished: None,
+            vanished_count: 0,
+            saved_state: None,
+            last_state_save: None,
+            pending_confirmations: Vec::new(),
+            delegated: Vec::new(),
+            terminating: Vec::new(),
+            snapshots,
+            recorder,
+            reclaimer,
+            critical: CriticalState::new(),
+            last_level: PressureLevel::Normal,
+            rate_limit_reported: false,
+            episode_reclaimed: 0,
+            episode_cap_reported: false,
+            episode_victims: HashSet::new(),
+            overrun_reported: false,
+            last_decision: None,
+            #[cfg(feature = "webhook")]
+            webhook: None,
+            #[cfg(feature = "statsd")]
+            _statsd: None,
+            #[cfg(feature = "kmsg")]
+            kernel_records: None,
+            #[cfg(feature = "kmsg")]
+            kmsg_watcher: None,
+            #[cfg(feature = "desktop-session")]
+            session_detector,
+            #[cfg(feature = "desktop-session")]
+            graphical_victims: Vec::new(),
+            #[cfg(test)]
+            spawn_failures: 0,
         }
     }
 
     /// 启动OOM Killer
+    ///
+    /// 创建监控线程失败时按 `spawn_retries` 和 `spawn_backoff` 重试，仍然失败时
+    /// 返回 `SystemError::ThreadSpawnFailed`，`running` 保持为false，回调和写入端保留到下一次 `start`
     pub fn start(&mut self) -> Result<()> {
         if self.running.load(Ordering::SeqCst) {
             return Ok(());
         }
+        // `stop` 之后再次启动时，先等上一个监控线程退出，否则它会看到running重新变为true继续运行
+        self.join_monitor();
 
         self.running.store(true, Ordering::SeqCst);
+        let handoff: MonitorHandoff =
+            Arc::new(Mutex::new(Some((self.error_handler.take(), self.decision_writer.take()))));
+        let mut backoff = self.config.spawn_backoff;
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let error = match self.spawn_monitor(Arc::clone(&handoff)) {
+                Ok(()) => return Ok(()),
+                Err(e) => e,
+            };
+
+            if attempts > self.config.spawn_retries {
+                self.running.store(false, Ordering::SeqCst);
+                let handoff = handoff.lock().unwrap_or_else(PoisonError::into_inner).take();
+                if let Some((error_handler, decision_writer)) = handoff {
+                    self.error_handler = error_handler;
+                    self.decision_writer = decision_writer;
+                }
+                log::error!("Failed to spawn monitor thread after {} attempt(s): {}", attempts, error);
+                return Err(SystemError::ThreadSpawnFailed { attempts, source: error });
+            }
+            log::warn!(
+                "Failed to spawn monitor thread (attempt {}): {}, retrying in {:?}",
+                attempts,
+                error,
+                backoff
+            );
+            thread::sleep(backoff);
+            backoff = backoff.saturating_mul(2);
+        }
+    }
+
+    /// 创建运行监控循环的线程，回调和写入端在线程启动后从 `handoff` 中取出
+    fn spawn_monitor(&mut self, handoff: MonitorHandoff) -> std::io::Result<()> {
+        #[cfg(test)]
+        if self.spawn_failures > 0 {
+            self.spawn_failures -= 1;
+            return Err(std::io::Error::from_raw_os_error(libc::EAGAIN));
+        }
+
         let running = Arc::clone(&self.running);
+        let error_count = Arc::clone(&self.error_count);
+        let stats = Arc::clone(&self.stats);
+        let events = self.events.clone();
         let config = self.config.clone();
+        let proc_root = self.proc_root.clone();
+        // 状态文件只在创建句柄时读取一次，线程从共享的统计继续
+        let saved_state = self.saved_state.clone();
 
         // 在新线程中运行监控循环
         thread::Builder::new()
             .name("oom-killer".to_string())
             .spawn(move || {
-                let mut killer = OOMKiller::new(Some(config));
-                while running.load(Ordering::SeqCst) {
-                    if let Err(e) = killer.check_and_kill() {
-                        eprintln!("OOM Killer error: {:?}", e);
-                    }
-                    thread::sleep(killer.config.check_interval);
+                let (error_handler, decision_writer) =
+                    handoff.lock().unwrap_or_else(PoisonError::into_inner).take().unwrap_or_default();
+                let mut killer = OOMKiller::build(config, proc_root);
+                // 与句柄共享运行标志，线程中的实例启动的kmsg监视随之停止
+                killer.running = running;
+                killer.saved_state = saved_state;
+                killer.error_count = error_count;
+                killer.stats = stats;
+                killer.events = events;
+                killer.error_handler = error_handler;
+                killer.decision_writer = decision_writer;
+                if killer.config.self_protection {
+                    killer.lock_critical_path();
+                }
+                killer.start_integrations();
+                while killer.running.load(Ordering::SeqCst) {
+                    let pause = killer.run_cycle();
+                    thread::sleep(pause);
                 }
             })
-            .map_err(|e| SystemError::SyscallError(e))?;
+            .map(|handle| self.monitor = Some(handle))
+    }
 
-        Ok(())
+    /// 按配置启动外部接收端、进程跟踪器和kmsg监视，只在监控线程中调用
+    ///
+    /// 它们各自运行后台线程，statsd读取的是全局的指标，因此每个运行中的killer只启动一份
+    fn start_integrations(&mut self) {
+        #[cfg(feature = "webhook")]
+        if let Some(webhook) = self.config.webhook.clone() {
+            self.webhook = crate::oom::webhook::WebhookSink::new(webhook)
+                .map_err(|e| log::warn!("Failed to start webhook sink: {:?}", e))
+                .ok();
+        }
+        #[cfg(feature = "statsd")]
+        if let Some(statsd) = self.config.statsd.clone() {
+            self._statsd = crate::oom::statsd::StatsdSink::new(statsd, metrics::registry())
+                .map_err(|e| log::warn!("Failed to start statsd sink: {:?}", e))
+                .ok();
+        }
+        #[cfg(feature = "proc-connector")]
+        if let Some(tracker_config) = self.config.track_processes.clone() {
+            let tracker = crate::linux::proc_connector::ProcessTracker::start(
+                self.selector.proc_root().to_path_buf(),
+                tracker_config,
+            );
+            self.selector.set_process_tracker(tracker);
+        }
+        #[cfg(feature = "kmsg")]
+        if self.config.watch_kmsg {
+            self.spawn_kmsg_watcher();
+        }
+    }
+
+    /// 设置监控循环出错时调用的回调，每一轮出错调用一次
+    ///
+    /// 用于在持续出错（例如权限不足）时告警或退避。回调在 `start` 时
+    /// 移交给监控线程，之后再设置的回调只对下一次 `start` 生效
+    pub fn set_error_handler(&mut self, handler: impl FnMut(&SystemError) + Send + 'static) {
+        self.error_handler = Some(Box::new(handler));
+    }
+
+    /// 把每一轮的决定以一行JSON（`Decision`）写入 `writer`，例如文件、socket或管道
+    ///
+    /// 不需要启用 `log_every_decision`。每行写完后flush；写入失败时记录警告，
+    /// 监控循环照常运行。与错误回调一样在 `start` 时移交给监控线程
+    pub fn stream_decisions_to(&mut self, writer: impl std::io::Write + Send + 'static) {
+        self.decision_writer = Some(Box::new(writer));
+        self.decision_write_failed = false;
+    }
+
+    /// 订阅审计事件，返回有界队列的接收端，替换之前的订阅
+    ///
+    /// 队列已满时按 `overflow` 处理，默认丢弃最旧的事件，订阅者处理慢时监控循环不会阻塞，
+    /// 丢弃的数量见 `KillerStatus::events_dropped`。与错误回调一样在 `start` 时交给监控线程，
+    /// 需要在 `start` 之前订阅
+    pub fn subscribe_events(&mut self, config: Option<EventStreamConfig>) -> EventReceiver {
+        let queue = Arc::new(EventQueue::new(config.unwrap_or_default()));
+        self.events = Some(Arc::clone(&queue));
+        EventReceiver::new(queue)
     }
 
     /// 停止OOM Killer
+    ///
+    /// 不等待监控线程退出，线程在当前一轮结束后停止
     pub fn stop(&mut self) {
         self.running.store(false, Ordering::SeqCst);
     }
 
+    /// 等待已经停止的监控线程退出，返回是否有这样的线程
+    fn join_monitor(&mut self) -> bool {
+        let Some(monitor) = self.monitor.take() else {
+            return false;
+        };
+        if monitor.join().is_err() {
+            log::warn!("Monitor thread panicked");
+        }
+        true
+    }
+
+    /// 清零终止次数、回收量和出错次数，并把 `running_since` 设为现在，不影响监控循环
+    ///
+    /// 可以在监控线程运行时调用：统计由句柄和线程共享，清零立即生效。
+    /// 终止间隔、单次压力过程的回收上限和终止历史不受影响，全局指标中的计数器也不会清零
+    pub fn reset_stats(&mut self) {
+        let mut stats = self.stats();
+        stats.total_kills = 0;
+        stats.estimated_reclaimed = 0;
+        stats.confirmed_reclaimed = 0;
+        stats.running_since = Instant::now();
+        drop(stats);
+        self.error_count.store(0, Ordering::Relaxed);
+    }
+
+    /// 与监控线程共享的统计
+    fn stats(&self) -> MutexGuard<'_, SharedStats> {
+        self.stats.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// 执行一轮检查并记录耗时，返回到下一轮开始之前应等待的时间
+    fn run_cycle(&mut self) -> Duration {
+        let start = Instant::now();
+        if let Err(e) = self.check_and_kill() {
+            log::error!("OOM Killer error: {:?}", e);
+            self.error_count.fetch_add(1, Ordering::Relaxed);
+            if let Some(handler) = &mut self.error_handler {
+                handler(&e);
+            }
+        }
+        self.maybe_log_top_consumers();
+        self.maybe_save_state(false);
+        let elapsed = start.elapsed();
+        self.finish_cycle(elapsed);
+
+        self.effective_interval().saturating_sub(elapsed)
+    }
+
+    /// 实际使用的检查间隔
+    ///
+    /// 间隔从每轮开始时计算，不小于配置的 `check_interval`，也不小于最近整轮耗时的p90：
+    /// 循环做不到配置的间隔时，按它最近实际能做到的节奏安排下一轮
+    pub fn effective_interval(&self) -> Duration {
+        self.config.check_interval.max(self.stats().latency.cycle_percentiles().p90)
+    }
+
+    /// 记录一轮的耗时，返回是否因超过检查间隔发出了警告
+    ///
+    /// 连续超时的第一轮警告一次；连续超时的轮数超过阈值时再发出一次告警并写入审计记录
+    fn finish_cycle(&mut self, elapsed: Duration) -> bool {
+        let interval = self.config.check_interval;
+        let mut stats = self.stats();
+        let overran = stats.latency.finish_cycle(elapsed, interval);
+        let p99 = stats.latency.cycle_percentiles().p99;
+        let consecutive = stats.latency.consecutive_overruns();
+        drop(stats);
+        metrics::registry().record_cycle(elapsed, overran);
+        metrics::registry().set_cycle_p99(p99);
+
+        if !overran {
+            self.overrun_reported = false;
+            return false;
+        }
+
+        if consecutive == 1 {
+            log::warn!("Monitor cycle took {:?}, longer than the {:?} check interval", elapsed, interval);
+            return true;
+        }
+
+        let threshold = self.config.overrun_alert_after;
+        if threshold == 0 || consecutive <= threshold || self.overrun_reported {
+            return false;
+        }
+
+        self.overrun_reported = true;
+        log::warn!(
+            "Monitor loop overran the {:?} check interval {} times in a row, last cycle took {:?}",
+            interval,
+            consecutive,
+            elapsed
+        );
+        self.audit(AuditEvent::LoopOverrun {
+            consecutive,
+            cycle_ms: elapsed.as_millis() as u64,
+            interval_ms: interval.as_millis() as u64,
+        });
+        true
+    }
+
+    /// 记录本轮一个阶段的耗时
+    fn record_phase(&mut self, phase: Phase, duration: Duration) {
+        self.stats().latency.record(phase, duration);
+        metrics::registry().record_phase(phase, duration);
+    }
+
+    /// 检查内存压力并记录耗时
+    fn check_pressure(&mut self) -> Result<bool> {
+        let start = Instant::now();
+        let sustained = self.selector.check_pressure();
+        self.record_phase(Phase::PressureCheck, start.elapsed());
+        sustained
+    }
+
     /// 检查内存状态并在必要时终止进程
+    ///
+    /// 持续压力下，从检查压力到信号送达是关键路径：只读取meminfo并终止
+    /// 预先计划好的受害者，不分配堆内存。快照、审计、历史和日志都推迟到
+    /// 信号送达之后
     fn check_and_kill(&mut self) -> Result<()> {
+        #[cfg(feature = "kmsg")]
+        self.drain_kernel_records();
+
+        self.revert_expired_delegations();
+        self.escalate_expired_terminations();
+        self.confirm_pending_kills();
+
         // 检查是否需要等待kill间隔
-        if let Some(last_time) = self.last_kill_time {
-            if last_time.elapsed() < self.config.min_kill_interval {
+        let last_kill_time = self.stats().last_kill_time;
+        if let Some(last_time) = last_kill_time {
+            if last_time.elapsed() < self.effective_kill_interval() {
+                let sustained = self.note_rate_limit(last_time)?;
+                let outcome = if sustained { DecisionOutcome::RateLimited } else { DecisionOutcome::NoPressure };
+                self.log_decision(sustained, outcome, None);
                 return Ok(());
             }
         }
 
-        // 选择进程
-        if let Some(pid) = self.selector.select_process()? {
-            // 获取进程信息（用于记录）
-            let process = crate::linux::proc::ProcessInfo::from_pid(pid)?;
-            let memory_freed = process.mem_info.vm_rss;
+        // 关键路径开始
+        let mut sustained = self.check_pressure()?;
+        let capped = self.episode_capped(sustained);
+        let mut reclaimed = false;
+        if sustained && !capped && self.reclaimer.is_some() {
+            reclaimed = self.reclaim_before_kill()?;
+            sustained = !reclaimed;
+        }
+        let mut killed = None;
+        let mut delegated = false;
+        if sustained && !capped {
+            if let Some(signal) = self.config.kill_mode.signal() {
+                killed = self.kill_planned(&mut sustained, signal)?;
+            }
+        }
+        // 关键路径结束
 
-            // 终止进程
-            self.kill_process(pid)?;
+        // 计划为空或已全部失效时重新评分；压力上升阶段也在这里提前准备计划
+        if killed.is_none() {
+            self.refresh_plan()?;
 
-            // 更新统计信息
-            self.last_kill_time = Some(Instant::now());
-            self.total_kills += 1;
-            self.total_memory_reclaimed += memory_freed;
+            if sustained && !capped {
+                match self.config.kill_mode {
+                    KillMode::Signal => killed = self.kill_planned(&mut sustained, libc::SIGKILL)?,
+                    KillMode::Graceful { .. } => killed = self.kill_planned(&mut sustained, libc::SIGTERM)?,
+                    KillMode::KernelDelegate { revert_after } => {
+                        if let Some(victim) = self.critical.plan.first() {
+                            let start = Instant::now();
+                            self.delegate_to_kernel(victim.key, revert_after)?;
+                            self.selector.note_chosen(victim.key);
+                            self.episode_victims.insert(victim.key);
+                            self.record_phase(Phase::Kill, start.elapsed());
+                            self.stats().last_kill_time = Some(Instant::now());
+                            // 内核何时终止、释放多少都不确定，按固定间隔处理
+                            self.last_kill_freed = 0;
+                            delegated = true;
+                        }
+                    }
+                }
+            }
+        }
 
-            // 记录操作
-            self.log_kill(&process);
+        self.track_pressure_level();
+        self.maybe_snapshot();
+        self.maybe_record();
+        let outcome = if killed.is_some() {
+            DecisionOutcome::Killed
+        } else if delegated {
+            DecisionOutcome::Delegated
+        } else if reclaimed {
+            DecisionOutcome::Reclaimed
+        } else if self.vanished.is_some() && !sustained {
+            DecisionOutcome::TargetGone
+        } else if capped {
+            DecisionOutcome::EpisodeCapped
+        } else if sustained {
+            DecisionOutcome::NoCandidate
+        } else {
+            DecisionOutcome::NoPressure
+        };
+        self.log_decision(sustained, outcome, killed);
+        if let Some(victim) = self.vanished.take() {
+            log::info!(
+                "{} planned victim(s) exited before they could be killed, last was {} ({})",
+                std::mem::take(&mut self.vanished_count),
+                victim.key.pid.as_raw(),
+                victim.comm.as_str()
+            );
+        }
+        if let Some(victim) = killed {
+            if let KillMode::Graceful { grace } = self.config.kill_mode {
+                self.track_termination(&victim, grace);
+            }
+            #[cfg(feature = "desktop-session")]
+            if let Some(grace) = self.desktop_grace(victim.key) {
+                self.track_termination(&victim, grace);
+            }
+            self.record_kill(victim);
         }
 
         Ok(())
     }
 
-    /// 终止指定的进程
-    fn kill_process(&self, pid: ProcessId) -> Result<()> {
-        use crate::ffi::safe_wrapper::SystemInterface;
-        
-        let system = SystemInterface::new();
-        // 发送SIGKILL信号
-        system.kill(pid, libc::SIGKILL)
+    /// 本次压力过程是否已经达到 `max_reclaim_per_episode`，压力回到Normal时重新计数
+    fn episode_capped(&mut self, sustained: bool) -> bool {
+        if self.selector.pressure_level() == PressureLevel::Normal {
+            self.episode_reclaimed = 0;
+            self.episode_cap_reported = false;
+            self.episode_victims.clear();
+            return false;
+        }
+        let Some(cap) = self.config.max_reclaim_per_episode else {
+            return false;
+        };
+        if !sustained || self.episode_reclaimed < cap {
+            return false;
+        }
+
+        if !self.episode_cap_reported {
+            log::warn!(
+                "Reclaimed {} bytes in this pressure episode, reaching the cap of {}; not killing until pressure clears",
+                self.episode_reclaimed,
+                cap
+            );
+            self.episode_cap_reported = true;
+        }
+        true
     }
 
-    /// 记录终止进程的操作
-    fn log_kill(&self, process: &crate::linux::proc::ProcessInfo) {
-        // TODO: 实现更好的日志系统
-        println!(
-            "OOM Killer terminated process {} ({}), freed {} MB of memory",
-            process.pid.as_raw(),
-            process.name,
-            process.mem_info.vm_rss / 1024 / 1024
-        );
+    /// 发出回收请求并重新检查压力，返回压力是否因此消除
+    fn reclaim_before_kill(&mut self) -> Result<bool> {
+        let Some(reclaimer) = &mut self.reclaimer else {
+            return Ok(false);
+        };
+        if !reclaimer.attempt() {
+            return Ok(false);
+        }
+
+        let resolved = !self.check_pressure()?;
+        if resolved {
+            log::info!("Reclaim relieved memory pressure, skipping kill");
+        }
+        Ok(resolved)
     }
 
-    /// 获取当前状态
-    pub fn get_status(&self) -> KillerStatus {
-        KillerStatus {
-            last_kill_time: self.last_kill_time,
-            total_kills: self.total_kills,
-            total_memory_reclaimed: self.total_memory_reclaimed,
-            running_since: self.running_since,
+    /// 启用 `log_every_decision` 或 `stream_decisions_to` 时记录本轮的决定，都未启用时只有一次判断
+    ///
+    /// `killed` 为本轮终止的受害者；没有终止时记录计划中得分最高的候选。
+    /// 每轮都会记录，因此只写入日志和决定流，不发送到webhook
+    fn log_decision(&mut self, sustained: bool, outcome: DecisionOutcome, killed: Option<PlannedVictim>) {
+        if !self.config.log_every_decision && self.decision_writer.is_none() {
+            return;
+        }
+
+        let candidate = killed.or_else(|| self.critical.plan.first()).map(|victim| DecisionCandidate {
+            pid: victim.key.pid.as_raw(),
+            name: victim.comm.as_str().to_string(),
+            score: victim.score,
+            relaxed: self.selector.last_selection_relaxed(),
+        });
+        let decision = Decision {
+            level: self.selector.pressure_level(),
+            sustained,
+            candidate,
+            outcome,
+        };
+        if self.config.log_every_decision {
+            AuditRecord::new(AuditEvent::Decision(decision.clone())).log_at(self.config.decision_log_level);
         }
+        self.stream_decision(&decision);
+        self.last_decision = Some(decision);
     }
-}
 
-/// 用于测试的模拟进程终止器
-#[cfg(test)]
-pub struct MockKiller {
-    killed_processes: Vec<ProcessId>,
-}
+    /// 把决定写入决定流，失败时只在第一次失败时警告
+    fn stream_decision(&mut self, decision: &Decision) {
+        let Some(writer) = &mut self.decision_writer else {
+            return;
+        };
+        let written = serde_json::to_writer(&mut *writer, decision)
+            .map_err(std::io::Error::from)
+            .and_then(|()| writer.write_all(b"\n"))
+            .and_then(|()| writer.flush());
+        match written {
+            Ok(()) => self.decision_write_failed = false,
+            Err(e) => {
+                if !self.decision_write_failed {
+                    log::warn!("Failed to write decision to stream: {}", e);
+                }
+                self.decision_write_failed = true;
+            }
+        }
+    }
 
-#[cfg(test)]
-impl MockKiller {
-    pub fn new() -> Self {
-        Self {
-            killed_processes: Vec::new(),
+    /// 启用 `log_every_decision` 或 `stream_decisions_to` 时最近一轮记录的决定
+    pub fn last_decision(&self) -> Option<&Decision> {
+        self.last_decision.as_ref()
+    }
+
+    /// 终止计划中第一个仍然有效的受害者，不分配堆内存
+    ///
+    /// 发送信号时受害者已经退出（ESRCH）不算终止也不算错误：它释放的内存
+    /// 可能已经缓解了压力，因此立即重新检查，压力仍然持续才继续终止下一个
+    /// 候选。`sustained` 更新为重新检查的结果
+    fn kill_planned(&mut self, sustained: &mut bool, signal: c_int) -> Result<Option<PlannedVictim>> {
+        let system = SystemInterface::new();
+        loop {
+            // 启用桌面会话宽限期时，图形会话中的应用先收到SIGTERM，有机会保存
+            #[cfg(feature = "desktop-session")]
+            let graphical: &[ProcessKey] = match &self.config.desktop_session {
+                Some(desktop) if desktop.grace.is_some() => &self.graphical_victims,
+                _ => &[],
+            };
+            #[cfg(not(feature = "desktop-session"))]
+            let graphical: &[ProcessKey] = &[];
+
+            let start = Instant::now();
+            let killed = self.critical.kill_first(self.selector.proc_root(), |pid| {
+                let signal = if graphical.iter().any(|key| key.pid == pid) { libc::SIGTERM } else { signal };
+                system.kill(pid, signal)
+            });
+            self.record_phase(Phase::Kill, start.elapsed());
+            let killed = killed?;
+
+            // 每次退出的受害者都从计划中移除，循环最多执行计划长度次
+            let Some(victim) = self.critical.take_vanished() else {
+                return Ok(killed);
+            };
+            self.vanished = Some(victim);
+            self.vanished_count += 1;
+            *sustained = self.check_pressure()?;
+            if !*sustained {
+                return Ok(None);
+            }
         }
     }
 
-    pub fn kill(&mut self, pid: ProcessId) -> Result<()> {
-        self.killed_processes.push(pid);
+    /// 刷新终止计划：无压力时清空，压力上升或持续时重新评分
+    fn refresh_plan(&mut self) -> Result<()> {
+        if self.selector.pressure_level() == PressureLevel::Normal {
+            self.critical.plan.clear();
+            return Ok(());
+        }
+
+        let selection_start = Instant::now();
+        self.selector.plan_victims(&mut self.critical.plan)?;
+        // 已经处理过的进程可能还没有退出（例如等待确认或宽限期内），不再重复终止和计数
+        for &key in &self.episode_victims {
+            self.critical.plan.remove(key);
+        }
+        self.selector.confirm_top(&mut self.critical.plan);
+        metrics::registry().record_selection(selection_start.elapsed());
+        #[cfg(feature = "desktop-session")]
+        self.check_graphical_sessions();
+
+        let timings = self.selector.last_selection_timings();
+        self.record_phase(Phase::Enumeration, timings.enumeration);
+        self.record_phase(Phase::Scoring, timings.scoring);
         Ok(())
     }
 
-    pub fn get_killed_processes(&self) -> &[ProcessId] {
-        &self.killed_processes
+    /// 信号送达之后更新统计信息并记录
+    fn record_kill(&mut self, victim: PlannedVictim) {
+        let pid = victim.key.pid;
+        let memory_freed = victim.rss_kb.saturating_mul(1024);
+        let available_before = self.selector.last_memory_stats()
+            .map(|stats| stats.available_memory)
+            .unwrap_or(0);
+
+        // 更新统计信息
+        let mut stats = self.stats();
+        stats.last_kill_time = Some(Instant::now());
+        stats.total_kills += 1;
+        let offender = stats.offenders.entry(victim.comm.as_str().to_string()).or_default();
+        offender.kills += 1;
+        offender.reclaimed = offender.reclaimed.saturating_add(memory_freed);
+        drop(stats);
+        self.last_kill_freed = memory_freed;
+        self.rate_limit_reported = false;
+        self.account_estimated(memory_freed);
+        self.episode_reclaimed = self.episode_reclaimed.saturating_add(memory_freed);
+        self.episode_victims.insert(victim.key);
+        metrics::registry().record_kill(memory_freed);
+        self.pending_confirmations.push(PendingConfirmation {
+            pid,
+            start_time: victim.key.start_time,
+            estimated: memory_freed,
+            available_before,
+            killed_at: Instant::now(),
+        });
+
+        // 计划中剩余的候选在下一轮直接终止，需要在这里按轮换和分散终止调整
+        self.selector.note_kill(victim.app);
+        self.selector.note_chosen(victim.key);
+        self.selector.rotate_ties(&mut self.critical.plan);
+        self.selector.diversify_plan(&mut self.critical.plan);
+
+        // 记录操作
+        self.log_kill(&victim);
+        self.push_history(KillRecord {
+            pid,
+            name: victim.comm.as_str().to_string(),
+            memory_freed,
+            timestamp: SystemTime::now(),
+            source: KillSource::Room,
+            partial: victim.partial,
+        });
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::thread;
+    /// 读取状态文件并累加到本实例的统计中
+    ///
+    /// 文件不存在时从零开始；无法读取或解析时记录警告，之后的写入会覆盖它
+    fn restore_state(&mut self) {
+        let Some(path) = &self.config.state_file else {
+            return;
+        };
+        let loaded = match PersistedStats::load(path) {
+            Ok(loaded) => loaded.unwrap_or_default(),
+            Err(e) => {
+                log::warn!("Failed to load killer state from {}: {}", path.display(), e);
+                return;
+            }
+        };
 
-    #[test]
-    fn test_oom_killer_lifecycle() {
-        let mut killer = OOMKiller::new(None);
-        
-        // 测试启动
-        assert!(killer.start().is_ok());
-        assert!(killer.running.load(Ordering::SeqCst));
+        let mut stats = self.persisted_stats();
+        stats.merge(&loaded);
+        let mut shared = self.stats();
+        shared.total_kills = stats.total_kills;
+        shared.estimated_reclaimed = stats.estimated_reclaimed;
+        shared.confirmed_reclaimed = stats.confirmed_reclaimed;
+        shared.offenders = stats.offenders.clone();
+        drop(shared);
+        self.saved_state = Some(stats);
+    }
 
-        // 等待一段时间
-        thread::sleep(Duration::from_secs(1));
+    /// 需要保存到状态文件的统计
+    fn persisted_stats(&self) -> PersistedStats {
+        let stats = self.stats();
+        PersistedStats {
+            total_kills: stats.total_kills,
+            estimated_reclaimed: stats.estimated_reclaimed,
+            confirmed_reclaimed: stats.confirmed_reclaimed,
+            offenders: stats.offenders.clone(),
+        }
+    }
 
-        // 测试停止
-        killer.stop();
-        assert!(!killer.running.load(Ordering::SeqCst));
+    /// 启用 `state_file` 时，距上一次写入超过 `state_save_interval`（或 `force`）且统计有变化时写入
+    fn maybe_save_state(&mut self, force: bool) {
+        let Some(path) = &self.config.state_file else {
+            return;
+        };
+        let interval = self.config.state_save_interval;
+        if !force && self.last_state_save.is_some_and(|last| last.elapsed() < interval) {
+            return;
+        }
+        self.last_state_save = Some(Instant::now());
 
-        // 验证状态
-        let status = killer.get_status();
-        assert!(status.running_since <= Instant::now());
+        let stats = self.persisted_stats();
+        if self.saved_state.as_ref() == Some(&stats) {
+            return;
+        }
+        match stats.save(path) {
+            Ok(()) => self.saved_state = Some(stats),
+            Err(e) => log::warn!("Failed to save killer state to {}: {}", path.display(), e),
+        }
     }
 
-    #[test]
-    fn test_kill_interval() {
-        let config = KillerConfig {
-            min_kill_interval: Duration::from_millis(100),
-            ..Default::default()
+    /// 终止次数最多的 `n` 个进程名，启用 `state_file` 时包括之前运行中的终止
+    pub fn top_offenders(&self, n: usize) -> Vec<(String, OffenderStats)> {
+        top_offenders(&self.stats().offenders, n)
+    }
+
+    /// 锁定关键路径使用的内存，使其不会在回收时被换出
+    fn lock_critical_path(&self) {
+        let locked = self.critical.lock().and_then(|()| self.selector.lock_buffers());
+        if let Err(e) = locked {
+            log::warn!("Failed to lock critical path memory: {:?}", e);
+        }
+    }
+
+    /// 累加估算的回收量
+    fn account_estimated(&mut self, bytes: u64) {
+        let mut stats = self.stats();
+        stats.estimated_reclaimed = stats.estimated_reclaimed.saturating_add(bytes);
+    }
+
+    /// 累加确认的回收量
+    fn account_confirmed(&mut self, bytes: u64) {
+        let mut stats = self.stats();
+        stats.confirmed_reclaimed = stats.confirmed_reclaimed.saturating_add(bytes);
+    }
+
+    /// 检查已终止进程的实际回收量
+    ///
+    /// 进程消失（或启用 `confirm_zombie` 时进入Z/X状态）后，以可用内存的增量
+    /// （不超过估算值）作为确认的回收量；超过确认窗口仍未退出的进程不计入确认回收。
+    /// 同一轮中退出的多个进程共用一个增量：从其中最早终止的进程的基线算起，
+    /// 不超过它们估算值之和，同一份内存不会被重复计入
+    fn confirm_pending_kills(&mut self) {
+        if self.pending_confirmations.is_empty() {
+            return;
+        }
+
+        // 在本轮压力检查之前执行，重新读取meminfo得到当前的可用内存
+        let available_now = match self.selector.pressure_detector().get_memory_stats() {
+            Ok(stats) => stats.available_memory,
+            Err(_) => return,
         };
+        let window = self.config.confirm_window;
+        let confirm_zombie = self.config.confirm_zombie;
+        let proc_root = self.selector.proc_root();
 
-        let mut killer = OOMKiller::new(Some(config));
-        
-        // 第一次检查应该可以执行
-        assert!(killer.check_and_kill().is_ok());
+        // 本轮退出的进程中最早终止的一个的基线，以及估算值之和
+        let mut baseline: Option<(Instant, u64)> = None;
+        let mut estimated = 0u64;
+        self.pending_confirmations.retain(|pending| {
+            if process_exited(proc_root, pending.pid, pending.start_time, confirm_zombie) {
+                if baseline.is_none_or(|(killed_at, _)| pending.killed_at < killed_at) {
+                    baseline = Some((pending.killed_at, pending.available_before));
+                }
+                estimated = estimated.saturating_add(pending.estimated);
+                return false;
+            }
+            if pending.killed_at.elapsed() >= window {
+                log::warn!(
+                    "Process {} still present {:?} after kill, reclaim unconfirmed",
+                    pending.pid.as_raw(),
+                    window
+                );
+                return false;
+            }
+            true
+        });
 
-        // 立即再次检查应该被间隔限制
-        if let Some(last_time) = killer.last_kill_time {
-            assert!(last_time.elapsed() < killer.config.min_kill_interval);
+        if let Some((_, available_before)) = baseline {
+            self.account_confirmed(available_now.saturating_sub(available_before).min(estimated));
         }
     }
 
-    #[test]
-    fn test_mock_killer() {
-        let mut mock = MockKiller::new();
-        let pid = ProcessId::new(1234).unwrap();
+    /// 压力进入Critical时写入诊断快照
+    fn maybe_snapshot(&mut self) {
+        let Some(writer) = self.snapshots.as_mut() else {
+            return;
+        };
+        writer.observe_vmstat();
 
-        assert!(mock.kill(pid).is_ok());
-        assert_eq!(mock.get_killed_processes(), &[pid]);
+        let level = self.selector.pressure_level();
+        let Some(stats) = self.selector.last_memory_stats() else {
+            return;
+        };
+        if let Err(e) = writer.maybe_write(level, stats, self.selector.scorer()) {
+            log::warn!("Failed to write diagnostic snapshot: {:?}", e);
+        }
+    }
+
+    /// 到了 `periodic_snapshot_interval` 时在审计日志中记录RSS最高的进程
+    ///
+    /// 在每轮检查之后调用，终止间隔内或本轮出错时同样记录
+    fn maybe_log_top_consumers(&mut self) {
+        let Some(interval) = self.config.periodic_snapshot_interval else {
+            return;
+        };
+        if self.last_top_consumers.is_some_and(|last| last.elapsed() < interval) {
+            return;
+        }
+
+        self.last_top_consumers = Some(Instant::now());
+        match self.selector.top_consumers(self.config.periodic_snapshot_top) {
+            Ok(consumers) => AuditRecord::new(AuditEvent::TopConsumers { consumers }).log(),
+            Err(e) => log::warn!("Failed to list top memory consumers: {:?}", e),
+        }
+    }
+
+    /// 到了记录间隔时记录一帧内存状态
+    fn maybe_record(&mut self) {
+        let Some(recorder) = self.recorder.as_mut() else {
+            return;
+        };
+        if !recorder.due() {
+            return;
+        }
+
+        let Some(stats) = self.selector.last_memory_stats() else {
+            return;
+        };
+        if let Err(e) = recorder.record(stats) {
+            log::warn!("Failed to record memory state: {:?}", e);
+        }
+    }
+
+    /// 将受害进程标记为内核优先终止的对象
+    fn delegate_to_kernel(&mut self, key: ProcessKey, revert_after: Duration) -> Result<()> {
+        // 已经委托过的进程无需重复写入，避免把1000当作原值记录
+        if self.delegated.iter().any(|d| d.key == key) {
+            return Ok(());
+        }
+        let pid = key.pid;
+
+        // 读写都通过确认过启动时间的同一个目录，pid被复用时不会标记无关的进程
+        let dir = key.open_dir(&self.proc_root)?;
+        let original_adj = dir.read_oom_score_adj()?;
+        dir.write_oom_score_adj(OOM_SCORE_ADJ_MAX)?;
+
+        log::info!(
+            "Delegated process {} to the kernel OOM killer (oom_score_adj {} -> {})",
+            pid.as_raw(),
+            original_adj,
+            OOM_SCORE_ADJ_MAX
+        );
+
+        self.delegated.push(DelegatedVictim {
+            key,
+            original_adj,
+            revert_at: Instant::now() + revert_after,
+        });
+        Ok(())
+    }
+
+    /// 恢复那些在观察窗口结束后仍然存活的委托进程的oom_score_adj
+    ///
+    /// 进程已经退出或pid已被新进程复用时跳过，不把原值写给无关的进程
+    fn revert_expired_delegations(&mut self) {
+        let now = Instant::now();
+        let proc_root = &self.proc_root;
+        self.delegated.retain(|victim| {
+            if now < victim.revert_at {
+                return true;
+            }
+            let pid = victim.key.pid;
+            // 检查和写入通过同一个目录，检查之后退出的进程的写入会失败，而不是写给新进程
+            match victim.key.open_dir(proc_root).and_then(|dir| dir.write_oom_score_adj(victim.original_adj)) {
+                Ok(()) => log::info!(
+                    "Process {} survived delegation, restored oom_score_adj to {}",
+                    pid.as_raw(),
+                    victim.original_adj
+                ),
+                // 进程已经被内核终止，或pid已被复用
+                Err(SystemError::ProcessNotFound) => {
+                    log::debug!("Delegated process {} is gone, not restoring oom_score_adj", pid.as_raw());
+                }
+                Err(e) => log::warn!(
+                    "Failed to restore oom_score_adj for process {}: {:?}",
+                    pid.as_raw(),
+                    e
+                ),
+            }
+            false
+        });
+    }
+
+    /// 记录已发送SIGTERM的受害者，`grace` 之后由 `escalate_expired_terminations` 检查
+    fn track_termination(&mut self, victim: &PlannedVictim, grace: Duration) {
+        // 宽限期内再次选中同一进程时不延后原来的期限
+        if self.terminating.iter().any(|t| t.pid == victim.key.pid && t.start_time == victim.key.start_time) {
+            return;
+        }
+        self.terminating.push(TerminatingVictim {
+            pid: victim.key.pid,
+            start_time: victim.key.start_time,
+            kill_at: Instant::now() + grace,
+        });
+    }
+
+    /// 检查计划中的受害者是否属于活动的图形会话，新进入计划的在终止之前发出警告
+    ///
+    /// 在刷新计划时执行，读取environ会分配内存，不能放在关键路径上
+    #[cfg(feature = "desktop-session")]
+    fn check_graphical_sessions(&mut self) {
+        let Some(detector) = &self.session_detector else {
+            return;
+        };
+        let grace = self.config.desktop_session.as_ref().and_then(|desktop| desktop.grace);
+
+        let previous = std::mem::take(&mut self.graphical_victims);
+        let mut warnings = Vec::new();
+        for victim in self.critical.plan.victims() {
+            let Some(session) = detector.graphical_session(victim.key) else {
+                continue;
+            };
+            self.graphical_victims.push(victim.key);
+            if !previous.contains(&victim.key) {
+                warnings.push(AuditEvent::GraphicalSessionWarning {
+                    pid: victim.key.pid.as_raw(),
+                    name: victim.comm.as_str().to_string(),
+                    session_id: session.id,
+                    session_type: session.session_type,
+                    grace_ms: grace.map(|grace| grace.as_millis() as u64),
+                });
+            }
+        }
+        for warning in warnings {
+            self.audit(warning);
+        }
+    }
+
+    /// 按 `desktop_session` 先收到SIGTERM的受害者的宽限期，`KillMode::Signal` 以外的方式不适用
+    #[cfg(feature = "desktop-session")]
+    fn desktop_grace(&self, key: ProcessKey) -> Option<Duration> {
+        if self.config.kill_mode != KillMode::Signal || !self.graphical_victims.contains(&key) {
+            return None;
+        }
+        self.config.desktop_session.as_ref()?.grace
+    }
+
+    /// 对已过宽限期仍未退出的受害者统一发送SIGKILL
+    ///
+    /// 每轮开始时调用，宽限期内不阻塞监控循环。已经退出或pid被复用的进程直接移除
+    fn escalate_expired_terminations(&mut self) {
+        if self.terminating.is_empty() {
+            return;
+        }
+
+        let now = Instant::now();
+        let proc_root = self.selector.proc_root();
+        let system = SystemInterface::new();
+        self.terminating.retain(|victim| {
+            if process_exited(proc_root, victim.pid, victim.start_time, true) {
+                return false;
+            }
+            if now < victim.kill_at {
+                return true;
+            }
+
+            match system.kill(victim.pid, libc::SIGKILL) {
+                Ok(()) => log::warn!(
+                    "Process {} ignored SIGTERM past its grace period, sent SIGKILL",
+                    victim.pid.as_raw()
+                ),
+                Err(SystemError::ProcessNotFound) => {}
+                Err(e) => log::warn!("Failed to SIGKILL process {}: {:?}", victim.pid.as_raw(), e),
+            }
+            false
+        });
+    }
+
+    /// 记录终止进程的操作
+    fn log_kill(&self, victim: &PlannedVictim) {
+        // TODO: 实现更好的日志系统
+        // 在栈上格式化，日志行本身不需要堆内存
+        let mut line = StackBuf::<128>::new();
+        let _ = write!(
+            line,
+            "OOM Killer terminated process {} ({}), freed {} MB of memory",
+            victim.key.pid.as_raw(),
+            victim.comm.as_str(),
+            victim.rss_kb / 1024
+        );
+        println!("{}", line.as_str());
+    }
+
+    /// 写入审计日志并转发到已配置的外部接收端
+    fn audit(&mut self, event: AuditEvent) {
+        let record = AuditRecord::new(event);
+        record.log();
+
+        #[cfg(feature = "webhook")]
+        if let Some(webhook) = &self.webhook {
+            webhook.send(&record);
+        }
+        if let Some(events) = &self.events {
+            events.push(record);
+        }
+    }
+
+    /// 当前的终止间隔：`min_kill_interval` 加上按上一次终止释放的内存延长的时间，不超过 `max_kill_interval`
+    pub fn effective_kill_interval(&self) -> Duration {
+        let min = self.config.min_kill_interval;
+        if self.config.kill_interval_per_gib.is_zero() || self.last_kill_freed == 0 {
+            return min;
+        }
+        let max = self.config.max_kill_interval.max(min);
+        let gib = self.last_kill_freed as f64 / (1u64 << 30) as f64;
+        let extra = self.config.kill_interval_per_gib.as_secs_f64() * gib;
+        // 先按上限截断再转换，避免超出Duration的范围
+        Duration::from_secs_f64((min.as_secs_f64() + extra).min(max.as_secs_f64()))
+    }
+
+    /// 在终止间隔内仍处于内存压力时记录一次限流（每个间隔只记录一次）
+    ///
+    /// 返回是否处于持续压力
+    fn note_rate_limit(&mut self, last_kill: Instant) -> Result<bool> {
+        let under_pressure = self.check_pressure()?;
+        self.track_pressure_level();
+        // 限流期间不会终止进程，趁此刷新计划，使下一次终止使用最新的评分
+        self.refresh_plan()?;
+
+        if under_pressure && !self.rate_limit_reported {
+            self.rate_limit_reported = true;
+            let remaining = self.effective_kill_interval().saturating_sub(last_kill.elapsed());
+            self.audit(AuditEvent::RateLimited {
+                remaining_ms: remaining.as_millis() as u64,
+            });
+        }
+        Ok(under_pressure)
+    }
+
+    /// 压力级别变化时记录审计事件
+    fn track_pressure_level(&mut self) {
+        let level = self.selector.pressure_level();
+        if level != self.last_level {
+            let from = self.last_level;
+            self.last_level = level;
+            self.audit(AuditEvent::PressureTransition { from, to: level });
+        }
+    }
+
+    /// 将终止记录追加到历史和审计日志中
+    fn push_history(&mut self, record: KillRecord) {
+        self.audit(AuditEvent::Kill {
+            pid: record.pid.as_raw(),
+            name: record.name.clone(),
+            memory_freed: record.memory_freed,
+            source: record.source,
+        });
+
+        let mut stats = self.stats();
+        if stats.history.len() >= MAX_KILL_HISTORY {
+            stats.history.remove(0);
+        }
+        stats.history.push(record);
+    }
+
+    /// 获取终止历史（包括内核自身的终止）的副本，监控线程运行时包括线程中的终止
+    pub fn kill_history(&self) -> Vec<KillRecord> {
+        self.stats().history.clone()
+    }
+
+    /// 记录一次由内核 OOM Killer 执行的终止
+    #[cfg(feature = "kmsg")]
+    pub fn record_kernel_kill(&mut self, record: crate::linux::kmsg::KernelOomRecord) {
+        self.push_history(KillRecord {
+            pid: record.pid,
+            name: record.comm.clone(),
+            memory_freed: record.total_rss(),
+            timestamp: SystemTime::now(),
+            source: KillSource::Kernel,
+            partial: false,
+        });
+    }
+
+    /// 在后台线程中监视 /dev/kmsg，运行标志清除后线程在一个读取超时内退出
+    #[cfg(feature = "kmsg")]
+    fn spawn_kmsg_watcher(&mut self) {
+        use crate::linux::kmsg::KmsgWatcher;
+
+        let mut watcher = match KmsgWatcher::open() {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                log::warn!("Failed to open /dev/kmsg (requires CAP_SYSLOG): {:?}", e);
+                return;
+            }
+        };
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let running = Arc::clone(&self.running);
+        let spawned = thread::Builder::new()
+            .name("oom-kmsg".to_string())
+            .spawn(move || {
+                while running.load(Ordering::SeqCst) {
+                    match watcher.next_record() {
+                        Ok(Some(record)) => {
+                            if tx.send(record).is_err() {
+                                break;
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            log::warn!("Failed to read /dev/kmsg: {:?}", e);
+                            break;
+                        }
+                    }
+                }
+            });
+
+        match spawned {
+            Ok(watcher) => {
+                self.kernel_records = Some(rx);
+                self.kmsg_watcher = Some(watcher);
+            }
+            Err(e) => log::warn!("Failed to spawn kmsg watcher: {:?}", e),
+        }
+    }
+
+    /// 将 kmsg 线程收到的内核终止记录并入历史
+    #[cfg(feature = "kmsg")]
+    fn drain_kernel_records(&mut self) {
+        let records: Vec<_> = match &self.kernel_records {
+            Some(rx) => rx.try_iter().collect(),
+            None => return,
+        };
+        for record in records {
+            self.record_kernel_kill(record);
+        }
+    }
+
+    /// 获取当前状态
+    pub fn get_status(&self) -> KillerStatus {
+        let effective_interval = self.effective_interval();
+        let stats = self.stats();
+        KillerStatus {
+            last_kill_time: stats.last_kill_time,
+            total_kills: stats.total_kills,
+            estimated_reclaimed: stats.estimated_reclaimed,
+            confirmed_reclaimed: stats.confirmed_reclaimed,
+            running_since: stats.running_since,
+            last_cycle_duration: stats.latency.last_cycle(),
+            average_cycle_duration: stats.latency.cycle_mean(),
+            latency: stats.latency.summary(),
+            effective_interval,
+            error_count: self.error_count.load(Ordering::Relaxed),
+            events_dropped: self.events.as_ref().map_or(0, |events| events.dropped()),
+        }
+    }
+
+    /// 可序列化的运行状态，时刻转换为RFC3339字符串
+    pub fn status_report(&self) -> KillerStatusReport {
+        KillerStatusReport::new(&self.get_status())
+    }
+}
+
+/// 按配置创建选择器，从 `proc_root` 读取进程和内存信息
+fn build_selector(config: &KillerConfig, proc_root: &Path) -> ProcessSelector {
+    let pressure_detector = PressureDetector::new(Some(config.pressure.clone())).with_proc_root(proc_root);
+    let pressure_detector = match config.container_cgroup.clone() {
+        Some(cgroup_dir) => pressure_detector.with_cgroup(cgroup_dir),
+        None => pressure_detector,
+    };
+    ProcessSelector::new(
+        Some(config.selector.clone()),
+        OOMScorer::new().with_proc_root(proc_root),
+        pressure_detector,
+    )
+    .with_proc_root(proc_root)
+}
+
+/// 已终止的进程是否确认在退出：已经消失、pid被复用，或 `count_zombie` 时处于Z/X状态
+fn process_exited(proc_root: &Path, pid: ProcessId, start_time: u64, count_zombie: bool) -> bool {
+    match ProcessStat::from_pid_in(proc_root, pid) {
+        Ok(stat) => {
+            stat.start_time != start_time || (count_zombie && matches!(stat.state, 'Z' | 'X'))
+        }
+        Err(SystemError::ProcessNotFound) => true,
+        // 读取到一半时进程退出等情况，下一轮再确认
+        Err(_) => false,
+    }
+}
+
+/// drop时停止监控线程并等待它退出，即使没有调用 `stop`
+///
+/// 线程在当前一轮（最长约一个 `check_interval` 加上一轮的耗时）结束后退出，
+/// 启用 `watch_kmsg` 时还要等kmsg监视线程在读取超时后退出，drop会阻塞到那时
+impl Drop for OOMKiller {
+    fn drop(&mut self) {
+        self.stop();
+        // 有监控线程时由线程中的实例在退出时写入状态文件
+        if !self.join_monitor() {
+            self.maybe_save_state(true);
+        }
+        #[cfg(feature = "kmsg")]
+        if let Some(watcher) = self.kmsg_watcher.take() {
+            if watcher.join().is_err() {
+                log::warn!("kmsg watcher thread panicked");
+            }
+        }
+    }
+}
+
+/// 用于测试的模拟进程终止器
+#[cfg(test)]
+pub struct MockKiller {
+    killed_processes: Vec<ProcessId>,
+}
+
+#[cfg(test)]
+impl MockKiller {
+    pub fn new() -> Self {
+        Self {
+            killed_processes: Vec::new(),
+        }
+    }
+
+    pub fn kill(&mut self, pid: ProcessId) -> Result<()> {
+        self.killed_processes.push(pid);
+        Ok(())
+    }
+
+    pub fn get_killed_processes(&self) -> &[ProcessId] {
+        &self.killed_processes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    use crate::test_util::{FakeProc, MemInfoSpec, ProcFixture, ProcessGroup, RssDistribution};
+
+    #[test]
+    fn test_oom_killer_lifecycle() {
+        let mut killer = OOMKiller::new(None);
+        
+        // 测试启动
+        assert!(killer.start().is_ok());
+        assert!(killer.running.load(Ordering::SeqCst));
+
+        // 等待一段时间
+        thread::sleep(Duration::from_secs(1));
+
+        // 测试停止
+        killer.stop();
+        assert!(!killer.running.load(Ordering::SeqCst));
+
+        // 验证状态
+        let status = killer.get_status();
+        assert!(status.running_since <= Instant::now());
+    }
+
+    /// 不对应任何真实进程的受害者
+    fn planned_victim(rss_kb: u64) -> PlannedVictim {
+        use crate::linux::proc_stat::ProcessKey;
+        use crate::oom::critical::Comm;
+
+        PlannedVictim {
+            key: ProcessKey { pid: ProcessId::new(i32::MAX).unwrap(), start_time: 0 },
+            comm: Comm::new("worker"),
+            app: Comm::new("worker"),
+            rss_kb,
+            score: 1.0,
+            partial: false,
+        }
+    }
+
+    #[test]
+    fn test_kill_interval_scales_with_freed_memory() {
+        let mut killer = OOMKiller::new(Some(KillerConfig {
+            min_kill_interval: Duration::from_secs(2),
+            kill_interval_per_gib: Duration::from_secs(4),
+            max_kill_interval: Duration::from_secs(20),
+            ..Default::default()
+        }));
+        assert_eq!(killer.effective_kill_interval(), Duration::from_secs(2));
+
+        // 释放256MiB：2s + 4s * 0.25
+        killer.record_kill(planned_victim(256 * 1024));
+        let small = killer.effective_kill_interval();
+        assert_eq!(small, Duration::from_secs(3));
+
+        // 释放2GiB：2s + 4s * 2
+        killer.record_kill(planned_victim(2 * 1024 * 1024));
+        let large = killer.effective_kill_interval();
+        assert_eq!(large, Duration::from_secs(10));
+        assert!(large > small);
+
+        // 不超过上限
+        killer.record_kill(planned_victim(64 * 1024 * 1024));
+        assert_eq!(killer.effective_kill_interval(), Duration::from_secs(20));
+
+        // 间隔内不会再终止
+        killer.check_and_kill().unwrap();
+        assert_eq!(killer.get_status().total_kills, 3);
+
+        // 不按释放量延长时间隔固定
+        killer.config.kill_interval_per_gib = Duration::ZERO;
+        assert_eq!(killer.effective_kill_interval(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_spawn_failure_leaves_killer_stopped() {
+        let mut killer = OOMKiller::new(Some(KillerConfig {
+            spawn_retries: 2,
+            spawn_backoff: Duration::from_millis(1),
+            ..KillerConfig::default()
+        }));
+        killer.set_error_handler(|_| {});
+
+        // 重试用尽后返回描述性的错误，running保持为false，回调留给下一次启动
+        killer.spawn_failures = 3;
+        let error = killer.start().unwrap_err();
+        assert!(matches!(error, SystemError::ThreadSpawnFailed { attempts: 3, .. }));
+        assert!(error.to_string().contains("Failed to spawn monitor thread after 3 attempt(s)"));
+        assert!(!killer.running.load(Ordering::SeqCst));
+        assert!(killer.error_handler.is_some());
+
+        // 重试次数之内的失败不影响启动
+        killer.spawn_failures = 2;
+        assert!(killer.start().is_ok());
+        assert!(killer.running.load(Ordering::SeqCst));
+        killer.stop();
+    }
+
+    #[test]
+    fn test_drop_stops_monitor_thread() {
+        let mut killer = OOMKiller::new(None);
+        assert!(killer.start().is_ok());
+        let running = Arc::clone(&killer.running);
+        assert!(running.load(Ordering::SeqCst));
+        // 监控线程持有另一份引用
+        assert_eq!(Arc::strong_count(&running), 3);
+
+        // drop之后线程已经退出，不再持有引用
+        let dropped = Instant::now();
+        drop(killer);
+        assert!(!running.load(Ordering::SeqCst));
+        assert_eq!(Arc::strong_count(&running), 1);
+        assert!(dropped.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    #[cfg(feature = "kmsg")]
+    fn test_drop_joins_kmsg_watcher() {
+        let mut killer = OOMKiller::new(Some(KillerConfig { watch_kmsg: true, ..KillerConfig::default() }));
+        let running = Arc::clone(&killer.running);
+        assert!(killer.start().is_ok());
+        // 等监控线程启动kmsg监视（没有权限打开 /dev/kmsg 时不会启动）
+        thread::sleep(Duration::from_millis(50));
+
+        // kmsg监视线程阻塞在读取中，停止后在读取超时内退出并被等待
+        let dropped = Instant::now();
+        drop(killer);
+        assert_eq!(Arc::strong_count(&running), 1);
+        assert!(dropped.elapsed() < Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_restart_joins_previous_monitor() {
+        let mut killer = OOMKiller::new(None);
+        let running = Arc::clone(&killer.running);
+        assert!(killer.start().is_ok());
+        killer.stop();
+
+        // 重新启动前上一个线程已经退出，只有新线程持有引用
+        assert!(killer.start().is_ok());
+        assert!(running.load(Ordering::SeqCst));
+        assert_eq!(Arc::strong_count(&running), 3);
+
+        drop(killer);
+        assert_eq!(Arc::strong_count(&running), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "statsd")]
+    fn test_sinks_started_only_by_monitor_thread() {
+        use crate::oom::statsd::StatsdConfig;
+
+        let mut killer = OOMKiller::new(Some(KillerConfig {
+            statsd: Some(StatsdConfig::new("127.0.0.1:8125")),
+            ..Default::default()
+        }));
+        // 句柄本身不发送指标，否则和监控线程中的实例重复发送同一份全局计数
+        assert!(killer._statsd.is_none());
+        assert!(killer.start().is_ok());
+        assert!(killer._statsd.is_none());
+        killer.stop();
+    }
+
+    #[test]
+    fn test_state_file_restores_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_file = dir.path().join("state.json");
+        let config = KillerConfig { state_file: Some(state_file.clone()), ..Default::default() };
+
+        let mut killer = OOMKiller::new(Some(config.clone()));
+        killer.record_kill(planned_victim(1024));
+        killer.record_kill(planned_victim(2048));
+        killer.account_confirmed(1024 * 1024);
+        // drop时写入状态文件
+        drop(killer);
+        assert!(state_file.exists());
+
+        let mut killer = OOMKiller::new(Some(config.clone()));
+        let status = killer.get_status();
+        assert_eq!(status.total_kills, 2);
+        assert_eq!(status.estimated_reclaimed, 3072 * 1024);
+        assert_eq!(status.confirmed_reclaimed, 1024 * 1024);
+        assert_eq!(killer.top_offenders(5), vec![("worker".to_string(), OffenderStats { kills: 2, reclaimed: 3072 * 1024 })]);
+
+        // 新的终止累加到读取的统计上，按间隔写入
+        killer.record_kill(planned_victim(1024));
+        killer.maybe_save_state(false);
+        let saved = PersistedStats::load(&state_file).unwrap().unwrap();
+        assert_eq!(saved.total_kills, 3);
+        assert_eq!(saved.estimated_reclaimed, 4096 * 1024);
+        assert_eq!(saved.offenders["worker"].kills, 3);
+        drop(killer);
+
+        // 没有状态文件时从零开始
+        let killer = OOMKiller::new(Some(KillerConfig {
+            state_file: Some(dir.path().join("missing.json")),
+            ..Default::default()
+        }));
+        assert_eq!(killer.get_status().total_kills, 0);
+    }
+
+    #[test]
+    fn test_state_file_restored_once_when_running() {
+        use std::os::unix::process::ExitStatusExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let state_file = dir.path().join("state.json");
+        let mut previous = PersistedStats { total_kills: 2, estimated_reclaimed: 3072, ..Default::default() };
+        previous.offenders.insert("leaky".to_string(), OffenderStats { kills: 2, reclaimed: 3072 });
+        previous.save(&state_file).unwrap();
+
+        let (mut child, root) = leaky_child();
+        let mut killer = OOMKiller::new(Some(KillerConfig {
+            state_file: Some(state_file.clone()),
+            ..eager_config()
+        }))
+        .with_proc_root(root.path());
+        assert!(killer.start().is_ok());
+
+        // 线程中的终止累加到只读取过一次的统计上，并且在句柄中可见
+        wait_for_kills(&killer, 3);
+        assert_eq!(child.wait().unwrap().signal(), Some(libc::SIGKILL));
+        let reclaimed = 3072 + LEAKY_RSS_KB * 1024;
+        assert_eq!(killer.get_status().total_kills, 3);
+        assert_eq!(killer.top_offenders(1), vec![("leaky".to_string(), OffenderStats { kills: 3, reclaimed })]);
+
+        // 退出时写入的文件没有重复计入之前的统计
+        drop(killer);
+        let saved = PersistedStats::load(&state_file).unwrap().unwrap();
+        assert_eq!(saved.total_kills, 3);
+        assert_eq!(saved.estimated_reclaimed, reclaimed);
+        assert_eq!(saved.offenders["leaky"].kills, 3);
+    }
+
+    #[test]
+    fn test_event_subscription_drops_oldest() {
+        use crate::oom::audit::AuditEvent;
+        use crate::oom::events::EventStreamConfig;
+
+        let mut killer = OOMKiller::new(None);
+        assert_eq!(killer.get_status().events_dropped, 0);
+        let receiver = killer.subscribe_events(Some(EventStreamConfig { capacity: 2, ..Default::default() }));
+        for remaining_ms in 1..=4 {
+            killer.audit(AuditEvent::RateLimited { remaining_ms });
+        }
+
+        // 默认丢弃最旧的事件，审计不会阻塞
+        assert_eq!(killer.get_status().events_dropped, 2);
+        assert_eq!(killer.status_report().events_dropped, 2);
+        let received: Vec<_> = std::iter::from_fn(|| receiver.try_recv())
+            .map(|record| match record.event {
+                AuditEvent::RateLimited { remaining_ms } => remaining_ms,
+                other => panic!("unexpected event {:?}", other),
+            })
+            .collect();
+        assert_eq!(received, vec![3, 4]);
+    }
+
+    const LEAKY_RSS_KB: u64 = 4194304;
+
+    /// 父进程不是init、RSS为 `LEAKY_RSS_KB` 的进程，在压力下成为候选
+    fn candidate(name: &str) -> ProcessGroup {
+        ProcessGroup::new(1)
+            .name(name)
+            .rss(RssDistribution::Constant(LEAKY_RSS_KB))
+            .oom_scores(0, 0)
+            .ppid(100)
+    }
+
+    /// 可用内存只剩1MiB的meminfo
+    fn pressured() -> MemInfoSpec {
+        MemInfoSpec { available_kb: 1024, ..MemInfoSpec::default() }
+    }
+
+    /// 真实的子进程和只包含它的假proc目录，处于内存压力下，`LEAKY_RSS_KB` 使它成为候选
+    fn leaky_child() -> (std::process::Child, ProcFixture) {
+        let child = std::process::Command::new("sleep").arg("30").spawn().expect("Failed to spawn child");
+        let root = FakeProc::new()
+            .meminfo(pressured())
+            .process(child.id() as i32, candidate("leaky"))
+            .build()
+            .unwrap();
+        (child, root)
+    }
+
+    /// 每10ms检查一次、压力出现即终止的配置
+    fn eager_config() -> KillerConfig {
+        use crate::oom::pressure::PressureThresholds;
+
+        KillerConfig {
+            selector: SelectorConfig { min_candidates: 1, ..SelectorConfig::default() },
+            pressure: PressureThresholds {
+                pressure_duration: Duration::ZERO,
+                ..PressureThresholds::default()
+            },
+            check_interval: Duration::from_millis(10),
+            ..Default::default()
+        }
+    }
+
+    /// 等待监控线程完成 `kills` 次终止
+    fn wait_for_kills(killer: &OOMKiller, kills: u64) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while killer.get_status().total_kills < kills {
+            assert!(Instant::now() < deadline, "monitor thread did not kill the child");
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_reset_stats_while_running() {
+        use std::os::unix::process::ExitStatusExt;
+
+        // 假的proc目录中的进程对应真实的子进程，由监控线程终止
+        let (mut child, root) = leaky_child();
+        let mut killer = OOMKiller::new(Some(eager_config())).with_proc_root(root.path());
+        assert!(killer.start().is_ok());
+
+        // 监控线程中的终止在句柄的状态中可见
+        wait_for_kills(&killer, 1);
+        assert_eq!(child.wait().unwrap().signal(), Some(libc::SIGKILL));
+        killer.error_count.fetch_add(1, Ordering::Relaxed);
+        let status = killer.get_status();
+        assert_eq!(status.total_kills, 1);
+        assert_eq!(status.estimated_reclaimed, LEAKY_RSS_KB * 1024);
+        assert_eq!(status.error_count, 1);
+        assert_eq!(killer.kill_history().len(), 1);
+        let started = status.running_since;
+
+        // 线程运行时清零，立即作用于线程使用的统计
+        killer.reset_stats();
+        let status = killer.get_status();
+        assert_eq!(status.total_kills, 0);
+        assert_eq!(status.estimated_reclaimed, 0);
+        assert_eq!(status.confirmed_reclaimed, 0);
+        assert_eq!(status.error_count, 0);
+        assert!(status.running_since > started);
+        // 终止间隔和终止历史不因清零统计而改变
+        assert!(status.last_kill_time.is_some());
+        assert_eq!(killer.kill_history().len(), 1);
+
+        // 之后的轮次在清零后的统计上继续，循环保持运行
+        thread::sleep(Duration::from_millis(100));
+        assert_eq!(killer.get_status().total_kills, 0);
+        assert!(killer.running.load(Ordering::SeqCst));
+        killer.stop();
+    }
+
+    #[test]
+    fn test_kill_interval() {
+        let config = KillerConfig {
+            min_kill_interval: Duration::from_millis(100),
+            ..Default::default()
+        };
+
+        let mut killer = OOMKiller::new(Some(config));
+        
+        // 第一次检查应该可以执行
+        assert!(killer.check_and_kill().is_ok());
+
+        // 立即再次检查应该被间隔限制
+        if let Some(last_time) = killer.get_status().last_kill_time {
+            assert!(last_time.elapsed() < killer.config.min_kill_interval);
+        }
+    }
+
+    #[test]
+    fn test_run_cycle_records_latency() {
+        let mut killer = OOMKiller::new(None);
+        let pause = killer.run_cycle();
+        assert!(pause <= killer.effective_interval());
+
+        let status = killer.get_status();
+        assert_eq!(status.latency.cycle.samples, 1);
+        assert_eq!(status.latency.pressure_check.samples, 1);
+        let last = status.last_cycle_duration.unwrap();
+        assert!(last > Duration::ZERO);
+        assert_eq!(status.average_cycle_duration, last);
+
+        // 启动后监控线程中每一轮的耗时同样出现在句柄的状态和报告中
+        let mut killer = OOMKiller::new(Some(KillerConfig {
+            check_interval: Duration::from_millis(10),
+            ..Default::default()
+        }));
+        assert_eq!(killer.get_status().last_cycle_duration, None);
+        assert!(killer.start().is_ok());
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while killer.get_status().latency.cycle.samples < 2 {
+            assert!(Instant::now() < deadline, "monitor thread did not complete two cycles");
+            thread::sleep(Duration::from_millis(10));
+        }
+        let status = killer.get_status();
+        assert!(status.last_cycle_duration.is_some());
+        assert!(status.average_cycle_duration > Duration::ZERO);
+        assert!(status.latency.pressure_check.samples >= 2);
+        assert!(killer.status_report().last_cycle_ms.is_some());
+        killer.stop();
+    }
+
+    #[test]
+    fn test_error_handler_called_on_cycle_error() {
+        use std::sync::Mutex;
+
+        // proc根目录中没有meminfo，每一轮的压力检查都会失败
+        let root = tempfile::tempdir().unwrap();
+        let mut killer = OOMKiller::new(None).with_proc_root(root.path());
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let seen = Arc::clone(&errors);
+        killer.set_error_handler(move |e| seen.lock().unwrap().push(format!("{:?}", e)));
+
+        killer.run_cycle();
+        killer.run_cycle();
+
+        assert_eq!(killer.get_status().error_count, 2);
+        let errors = errors.lock().unwrap();
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].starts_with("SyscallError"));
+    }
+
+    #[test]
+    fn test_decision_logged_without_kill() {
+        let mut killer = OOMKiller::new(None);
+        killer.check_and_kill().unwrap();
+        assert_eq!(killer.last_decision(), None);
+
+        let mut killer = OOMKiller::new(Some(KillerConfig {
+            log_every_decision: true,
+            decision_log_level: log::Level::Debug,
+            // 永远达不到的压力阈值，本轮不会终止任何进程
+            pressure: PressureThresholds {
+                min_free_ratio: 0.0,
+                max_swap_ratio: 2.0,
+                ..PressureThresholds::default()
+            },
+            ..Default::default()
+        }));
+        killer.check_and_kill().unwrap();
+        let decision = killer.last_decision().unwrap();
+        assert!(!decision.sustained);
+        assert_eq!(decision.outcome, DecisionOutcome::NoPressure);
+        assert_eq!(decision.candidate, None);
+
+        // 终止间隔内的轮次同样记录
+        killer.stats().last_kill_time = Some(Instant::now());
+        killer.last_decision = None;
+        killer.check_and_kill().unwrap();
+        assert_eq!(killer.last_decision().unwrap().outcome, DecisionOutcome::NoPressure);
+    }
+
+    #[test]
+    fn test_stream_decisions_to_writer() {
+        use std::io::{self, Write};
+        use std::sync::Mutex;
+
+        #[derive(Clone, Default)]
+        struct SharedBuf(Arc<Mutex<Vec<u8>>>);
+
+        impl Write for SharedBuf {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        struct BrokenPipe;
+
+        impl Write for BrokenPipe {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        // 不需要启用 log_every_decision
+        let mut killer = OOMKiller::new(Some(KillerConfig {
+            pressure: PressureThresholds {
+                min_free_ratio: 0.0,
+                max_swap_ratio: 2.0,
+                ..PressureThresholds::default()
+            },
+            ..Default::default()
+        }));
+        let buf = SharedBuf::default();
+        killer.stream_decisions_to(buf.clone());
+        for _ in 0..3 {
+            killer.check_and_kill().unwrap();
+        }
+
+        let content = String::from_utf8(buf.0.lock().unwrap().clone()).unwrap();
+        let decisions: Vec<Decision> =
+            content.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(decisions.len(), 3);
+        assert!(decisions.iter().all(|d| d.outcome == DecisionOutcome::NoPressure));
+        assert_eq!(killer.last_decision(), decisions.last());
+
+        // 写入失败不影响监控循环
+        killer.stream_decisions_to(BrokenPipe);
+        killer.check_and_kill().unwrap();
+        killer.check_and_kill().unwrap();
+        assert!(killer.decision_write_failed);
+        assert_eq!(killer.get_status().error_count, 0);
+    }
+
+    #[test]
+    fn test_periodic_top_consumers() {
+        let worker = |rss_kb: u64| ProcessGroup::new(1).name("worker").rss(RssDistribution::Constant(rss_kb)).ppid(100);
+        let root = FakeProc::new()
+            .process(1001, worker(4096))
+            .process(1002, worker(1024))
+            .build()
+            .unwrap();
+
+        let interval = Duration::from_millis(500);
+        let mut killer = OOMKiller::new(Some(KillerConfig {
+            // 记录间隔与检查间隔无关
+            check_interval: Duration::from_secs(60),
+            periodic_snapshot_interval: Some(interval),
+            periodic_snapshot_top: 1,
+            ..Default::default()
+        }))
+        .with_proc_root(root.path());
+        assert_eq!(killer.selector.top_consumers(1).unwrap()[0].pid, 1001);
+
+        // 第一轮立即记录，间隔内的轮次不再记录
+        killer.run_cycle();
+        let first = killer.last_top_consumers.unwrap();
+        killer.run_cycle();
+        killer.run_cycle();
+        assert_eq!(killer.last_top_consumers, Some(first));
+
+        // 到了间隔再次记录，终止间隔内同样记录
+        killer.stats().last_kill_time = Some(Instant::now());
+        killer.last_top_consumers = first.checked_sub(interval);
+        killer.run_cycle();
+        assert!(killer.last_top_consumers.unwrap() > first);
+        assert_eq!(killer.get_status().error_count, 0);
+
+        // 未启用时不记录
+        let mut killer = OOMKiller::new(None);
+        killer.run_cycle();
+        assert!(killer.last_top_consumers.is_none());
+    }
+
+    #[test]
+    fn test_slow_cycle_warns() {
+        let mut killer = OOMKiller::new(Some(KillerConfig {
+            check_interval: Duration::from_millis(100),
+            ..Default::default()
+        }));
+        assert_eq!(killer.get_status().last_cycle_duration, None);
+
+        assert!(!killer.finish_cycle(Duration::from_millis(20)));
+        // 扫描比检查间隔慢：第一轮警告，同一次连续超时中不重复警告
+        assert!(killer.finish_cycle(Duration::from_millis(500)));
+        assert!(!killer.finish_cycle(Duration::from_millis(500)));
+
+        let status = killer.get_status();
+        assert_eq!(status.last_cycle_duration, Some(Duration::from_millis(500)));
+        assert_eq!(status.average_cycle_duration, Duration::from_millis(340));
+
+        // 恢复后再次变慢时重新警告
+        assert!(!killer.finish_cycle(Duration::from_millis(20)));
+        assert!(killer.finish_cycle(Duration::from_millis(500)));
+    }
+
+    #[test]
+    fn test_loop_overrun_alert() {
+        let interval = Duration::from_millis(100);
+        let slow = Duration::from_millis(800);
+        let config = KillerConfig {
+            check_interval: interval,
+            overrun_alert_after: 2,
+            ..Default::default()
+        };
+        let mut killer = OOMKiller::new(Some(config));
+        assert_eq!(killer.effective_interval(), interval);
+
+        killer.finish_cycle(slow);
+        killer.finish_cycle(slow);
+        assert!(!killer.overrun_reported);
+        killer.finish_cycle(slow);
+        assert!(killer.overrun_reported);
+
+        // 循环做不到配置的间隔，按测得的耗时安排下一轮
+        assert_eq!(killer.effective_interval(), slow);
+        let status = killer.get_status();
+        assert_eq!(status.effective_interval, slow);
+        assert_eq!(status.latency.overruns, 3);
+        assert_eq!(status.latency.consecutive_overruns, 3);
+
+        // 一轮按时完成后重新开始计数
+        killer.finish_cycle(Duration::from_millis(10));
+        assert!(!killer.overrun_reported);
+        assert_eq!(killer.get_status().latency.consecutive_overruns, 0);
+    }
+
+    #[test]
+    fn test_reclaim_skips_kill_when_pressure_clears() {
+        use crate::test_util::write_meminfo;
+        use std::path::Path;
+        use std::sync::Mutex;
+
+        // 大于内核pid上限，即使真的发送信号也不会命中任何进程
+        const PID: i32 = 4194305;
+
+        let root = FakeProc::new().meminfo(pressured()).process(PID, candidate("leaky")).build().unwrap();
+        let reclaim = ReclaimConfig {
+            cgroup: Some(root.path().join("cgroup")),
+            ..ReclaimConfig::default()
+        };
+        let killer_for = |relieves: bool, writes: Arc<Mutex<Vec<String>>>| {
+            let mut killer = OOMKiller::new(Some(KillerConfig {
+                log_every_decision: true,
+                attempt_reclaim_first: Some(reclaim.clone()),
+                ..eager_config()
+            }))
+            .with_proc_root(root.path());
+            let proc_root = root.path().to_path_buf();
+            killer.reclaimer = Some(Reclaimer::new(reclaim.clone()).with_writer(move |path: &Path, contents: &str| {
+                writes.lock().unwrap().push(format!("{}={}", path.file_name().unwrap().to_string_lossy(), contents));
+                if relieves {
+                    write_meminfo(&proc_root, &MemInfoSpec::default()).unwrap();
+                }
+                Ok(())
+            }));
+            killer
+        };
+
+        // 回收之后压力消除，不终止
+        let writes = Arc::new(Mutex::new(Vec::new()));
+        let mut killer = killer_for(true, Arc::clone(&writes));
+        killer.check_and_kill().unwrap();
+        assert_eq!(*writes.lock().unwrap(), vec![format!("memory.reclaim={}", reclaim.amount)]);
+        assert_eq!(killer.last_decision().unwrap().outcome, DecisionOutcome::Reclaimed);
+        assert_eq!(killer.get_status().total_kills, 0);
+        assert!(killer.stats().last_kill_time.is_none());
+
+        // 回收没有消除压力时继续选择受害者；候选已经退出，本轮没有可终止的进程
+        std::fs::remove_dir_all(root.path().join(PID.to_string())).unwrap();
+        write_meminfo(root.path(), &pressured()).unwrap();
+        let writes = Arc::new(Mutex::new(Vec::new()));
+        let mut killer = killer_for(false, Arc::clone(&writes));
+        killer.check_and_kill().unwrap();
+        assert_eq!(writes.lock().unwrap().len(), 1);
+        let decision = killer.last_decision().unwrap();
+        assert!(decision.sustained);
+        assert_eq!(decision.outcome, DecisionOutcome::NoCandidate);
+    }
+
+    #[test]
+    fn test_episode_reclaim_cap() {
+        use crate::test_util::write_meminfo;
+
+        // 假的proc目录中的进程对应真实的子进程，终止时确实发送信号
+        let mut children: Vec<std::process::Child> = (0..2)
+            .map(|_| std::process::Command::new("sleep").arg("30").spawn().expect("Failed to spawn child"))
+            .collect();
+        let root = children
+            .iter()
+            .fold(FakeProc::new().meminfo(pressured()), |fake, child| fake.process(child.id() as i32, candidate("leaky")))
+            .build()
+            .unwrap();
+
+        let mut killer = OOMKiller::new(Some(KillerConfig {
+            min_kill_interval: Duration::ZERO,
+            max_reclaim_per_episode: Some(LEAKY_RSS_KB * 1024),
+            log_every_decision: true,
+            ..eager_config()
+        }))
+        .with_proc_root(root.path());
+
+        // 第一次终止达到上限，压力持续时不再终止
+        killer.check_and_kill().unwrap();
+        assert_eq!(killer.last_decision().unwrap().outcome, DecisionOutcome::Killed);
+        let first = killer.last_decision().unwrap().candidate.as_ref().unwrap().pid;
+        std::fs::remove_dir_all(root.path().join(first.to_string())).unwrap();
+        for _ in 0..3 {
+            killer.check_and_kill().unwrap();
+            let decision = killer.last_decision().unwrap();
+            assert!(decision.sustained);
+            assert_eq!(decision.outcome, DecisionOutcome::EpisodeCapped);
+        }
+        assert_eq!(killer.get_status().total_kills, 1);
+
+        // 压力消除后重新计数，下一次压力过程可以继续终止
+        write_meminfo(root.path(), &MemInfoSpec::default()).unwrap();
+        killer.check_and_kill().unwrap();
+        write_meminfo(root.path(), &pressured()).unwrap();
+        killer.check_and_kill().unwrap();
+        assert_eq!(killer.last_decision().unwrap().outcome, DecisionOutcome::Killed);
+        assert_eq!(killer.get_status().total_kills, 2);
+
+        for child in &mut children {
+            let _ = child.kill();
+            child.wait().unwrap();
+        }
+    }
+
+    #[test]
+    fn test_target_gone_before_kill_is_benign() {
+        // 假的proc目录中仍保留已经退出的进程的记录；pid大于内核pid上限，发送信号时总是返回ESRCH，
+        // 不会因为pid被复用而命中其他进程
+        let root = FakeProc::new().meminfo(pressured()).process(4194305, candidate("gone")).build().unwrap();
+        let mut killer = OOMKiller::new(Some(KillerConfig {
+            min_kill_interval: Duration::ZERO,
+            log_every_decision: true,
+            ..eager_config()
+        }))
+        .with_proc_root(root.path());
+
+        // 不返回错误，不计为终止，退出的受害者从计划中移除
+        killer.check_and_kill().unwrap();
+        let status = killer.get_status();
+        assert_eq!(status.total_kills, 0);
+        assert_eq!(status.error_count, 0);
+        assert!(status.last_kill_time.is_none());
+        assert!(killer.kill_history().is_empty());
+        assert_eq!(killer.critical.plan.len(), 0);
+        assert!(killer.vanished.is_none());
+
+        // 重新检查后压力仍然持续，但已经没有其他候选
+        let decision = killer.last_decision().unwrap();
+        assert!(decision.sustained);
+        assert_eq!(decision.outcome, DecisionOutcome::NoCandidate);
+    }
+
+    #[test]
+    fn test_graceful_escalates_after_grace() {
+        use crate::test_util::write_meminfo;
+        use std::os::unix::process::ExitStatusExt;
+
+        // 忽略SIGTERM的子进程，信号处理方式在exec之后保持不变
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", "trap '' TERM; sleep 30"])
+            .spawn()
+            .expect("Failed to spawn child");
+        // 等待shell设置好trap
+        std::thread::sleep(Duration::from_millis(200));
+        let root = FakeProc::new()
+            .meminfo(pressured())
+            .process(child.id() as i32, candidate("stubborn"))
+            .build()
+            .unwrap();
+
+        let grace = Duration::from_millis(300);
+        let mut killer = OOMKiller::new(Some(KillerConfig {
+            min_kill_interval: Duration::ZERO,
+            kill_mode: KillMode::Graceful { grace },
+            ..eager_config()
+        }))
+        .with_proc_root(root.path());
+
+        // 第一轮只发送SIGTERM，不等待进程退出
+        let start = Instant::now();
+        killer.check_and_kill().unwrap();
+        assert!(start.elapsed() < grace);
+        assert_eq!(killer.get_status().total_kills, 1);
+        assert_eq!(killer.terminating.len(), 1);
+
+        // 宽限期内进程仍在运行，不发送SIGKILL
+        write_meminfo(root.path(), &MemInfoSpec::default()).unwrap();
+        killer.check_and_kill().unwrap();
+        assert!(child.try_wait().unwrap().is_none());
+        assert_eq!(killer.terminating.len(), 1);
+
+        // 过了宽限期之后的一轮发送SIGKILL
+        std::thread::sleep(grace);
+        killer.check_and_kill().unwrap();
+        assert!(killer.terminating.is_empty());
+        let status = child.wait().unwrap();
+        assert_eq!(status.signal(), Some(libc::SIGKILL));
+        assert_eq!(killer.get_status().total_kills, 1);
+    }
+
+    #[test]
+    fn test_victim_acted_on_once_per_episode() {
+        use crate::test_util::write_meminfo;
+
+        // 忽略SIGTERM的子进程在整个压力过程中一直是得分最高的候选
+        let mut child = std::process::Command::new("sh")
+            .args(["-c", "trap '' TERM; sleep 30"])
+            .spawn()
+            .expect("Failed to spawn child");
+        std::thread::sleep(Duration::from_millis(200));
+        let root = FakeProc::new()
+            .meminfo(pressured())
+            .process(child.id() as i32, candidate("stubborn"))
+            .build()
+            .unwrap();
+
+        let mut killer = OOMKiller::new(Some(KillerConfig {
+            min_kill_interval: Duration::ZERO,
+            kill_mode: KillMode::Graceful { grace: Duration::from_secs(30) },
+            log_every_decision: true,
+            ..eager_config()
+        }))
+        .with_proc_root(root.path());
+
+        // 连续两轮持续压力，同一个进程只终止和计数一次
+        killer.check_and_kill().unwrap();
+        killer.check_and_kill().unwrap();
+        let status = killer.get_status();
+        assert_eq!(status.total_kills, 1);
+        assert_eq!(status.estimated_reclaimed, LEAKY_RSS_KB * 1024);
+        assert_eq!(killer.kill_history().len(), 1);
+        assert_eq!(killer.last_decision().unwrap().outcome, DecisionOutcome::NoCandidate);
+
+        // 压力消除后结束本次压力过程，记录被清空
+        write_meminfo(root.path(), &MemInfoSpec::default()).unwrap();
+        killer.check_and_kill().unwrap();
+        assert!(killer.episode_victims.is_empty());
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_confirm_top_across_scans() {
+        use crate::test_util::write_process;
+
+        let spawn = || std::process::Command::new("sleep").arg("30").spawn().expect("Failed to spawn child");
+        let with_rss = |name: &str, rss_kb: u64| candidate(name).rss(RssDistribution::Constant(rss_kb));
+        let mut spiky = spawn();
+        let mut steady = spawn();
+        let root = FakeProc::new()
+            .meminfo(pressured())
+            .process(spiky.id() as i32, with_rss("spiky", 4194304))
+            .process(steady.id() as i32, with_rss("steady", 2097152))
+            .build()
+            .unwrap();
+
+        let mut killer = OOMKiller::new(Some(KillerConfig {
+            min_kill_interval: Duration::ZERO,
+            selector: SelectorConfig { confirm_scans: Some(2), ..eager_config().selector },
+            ..eager_config()
+        }))
+        .with_proc_root(root.path());
+
+        // 短暂的峰值只在一轮扫描中排在第一位，不终止
+        killer.check_and_kill().unwrap();
+        assert_eq!(killer.get_status().total_kills, 0);
+        write_process(root.path(), spiky.id() as i32, &with_rss("spiky", 1572864)).unwrap();
+        killer.check_and_kill().unwrap();
+        assert_eq!(killer.get_status().total_kills, 0);
+
+        // 连续第二轮排在第一位后终止
+        killer.check_and_kill().unwrap();
+        assert_eq!(killer.get_status().total_kills, 1);
+        assert_eq!(killer.kill_history()[0].pid.as_raw(), steady.id() as i32);
+        assert!(spiky.try_wait().unwrap().is_none());
+
+        spiky.kill().unwrap();
+        spiky.wait().unwrap();
+        let _ = steady.kill();
+        steady.wait().unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "desktop-session")]
+    fn test_graphical_session_victim_warned() {
+        use crate::linux::session::{DesktopSessionConfig, GraphicalSession, SessionDetector};
+        use std::os::unix::process::ExitStatusExt;
+
+        struct MockDetector(ProcessId);
+        impl SessionDetector for MockDetector {
+            fn graphical_session(&self, key: ProcessKey) -> Option<GraphicalSession> {
+                (key.pid == self.0).then(|| GraphicalSession { id: Some("2".to_string()), session_type: "wayland".to_string() })
+            }
+        }
+
+        let mut child = std::process::Command::new("sleep").arg("30").spawn().expect("Failed to spawn child");
+        let pid = child.id();
+        let root = FakeProc::new().meminfo(pressured()).process(pid as i32, candidate("editor")).build().unwrap();
+
+        let grace = Duration::from_secs(30);
+        let mut killer = OOMKiller::new(Some(KillerConfig {
+            min_kill_interval: Duration::ZERO,
+            desktop_session: Some(DesktopSessionConfig { grace: Some(grace) }),
+            ..eager_config()
+        }))
+        .with_proc_root(root.path());
+        let pid = ProcessId::new(pid as i32).unwrap();
+        killer.session_detector = Some(Box::new(MockDetector(pid)));
+
+        // 图形会话中的应用走警告路径：先收到SIGTERM，宽限期后再升级为SIGKILL
+        killer.check_and_kill().unwrap();
+        assert_eq!(killer.graphical_victims.len(), 1);
+        assert_eq!(killer.graphical_victims[0].pid, pid);
+        assert_eq!(killer.get_status().total_kills, 1);
+        assert_eq!(killer.terminating.len(), 1);
+        assert_eq!(killer.terminating[0].pid, pid);
+        let status = child.wait().unwrap();
+        assert_eq!(status.signal(), Some(libc::SIGTERM));
+    }
+
+    #[test]
+    fn test_mock_killer() {
+        let mut mock = MockKiller::new();
+        let pid = ProcessId::new(1234).unwrap();
+
+        assert!(mock.kill(pid).is_ok());
+        assert_eq!(mock.get_killed_processes(), &[pid]);
+    }
+
+    #[test]
+    fn test_reclaim_accounting() {
+        const GB: u64 = 1024 * 1024 * 1024;
+        let mut killer = OOMKiller::new(None);
+
+        // 模拟两次终止：估算3GB，实际确认2GB；估算1GB，实际确认512MB
+        killer.account_estimated(3 * GB);
+        killer.account_confirmed(2 * GB);
+        killer.account_estimated(GB);
+        killer.account_confirmed(GB / 2);
+
+        let status = killer.get_status();
+        assert_eq!(status.estimated_reclaimed, 4 * GB);
+        assert_eq!(status.confirmed_reclaimed, 2 * GB + GB / 2);
+
+        // 溢出时饱和而不是回绕
+        killer.account_estimated(u64::MAX);
+        killer.account_confirmed(u64::MAX);
+        let status = killer.get_status();
+        assert_eq!(status.estimated_reclaimed, u64::MAX);
+        assert_eq!(status.confirmed_reclaimed, u64::MAX);
+    }
+
+    #[test]
+    fn test_confirm_pending_kill() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .expect("Failed to spawn child");
+        let pid = ProcessId::new(child.id() as i32).unwrap();
+
+        let mut killer = OOMKiller::new(None);
+        let estimated = 4096;
+        killer.pending_confirmations.push(PendingConfirmation {
+            pid,
+            start_time: ProcessStat::from_pid(pid).unwrap().start_time,
+            estimated,
+            available_before: 0,
+            killed_at: Instant::now(),
+        });
+
+        child.kill().unwrap();
+        child.wait().unwrap();
+
+        killer.confirm_pending_kills();
+        assert!(killer.pending_confirmations.is_empty());
+        // 确认值不会超过估算值
+        assert_eq!(killer.get_status().confirmed_reclaimed, estimated);
+    }
+
+    #[test]
+    fn test_zombie_confirms_kill() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("30")
+            .spawn()
+            .expect("Failed to spawn child");
+        let pid = ProcessId::new(child.id() as i32).unwrap();
+        let start_time = ProcessStat::from_pid(pid).unwrap().start_time;
+        let pending = |killer: &mut OOMKiller| {
+            killer.pending_confirmations.push(PendingConfirmation {
+                pid,
+                start_time,
+                estimated: 4096,
+                available_before: 0,
+                killed_at: Instant::now(),
+            });
+        };
+
+        // 终止但不回收，子进程变为僵尸
+        child.kill().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while ProcessStat::from_pid(pid).unwrap().state != 'Z' {
+            assert!(Instant::now() < deadline, "child did not become a zombie");
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        // 只接受消失时，僵尸进程仍在等待确认
+        let mut killer = OOMKiller::new(Some(KillerConfig {
+            confirm_zombie: false,
+            ..Default::default()
+        }));
+        pending(&mut killer);
+        killer.confirm_pending_kills();
+        assert_eq!(killer.pending_confirmations.len(), 1);
+
+        let mut killer = OOMKiller::new(None);
+        pending(&mut killer);
+        killer.confirm_pending_kills();
+        assert!(killer.pending_confirmations.is_empty());
+        assert_eq!(killer.get_status().confirmed_reclaimed, 4096);
+
+        child.wait().unwrap();
+    }
+
+    #[test]
+    fn test_victims_exiting_together_share_one_delta() {
+        use crate::test_util::FakeProc;
+
+        const MIB: u64 = 1024 * 1024;
+
+        // 两个受害者都不在proc目录中，本轮同时确认退出；可用内存为4MiB
+        let root = FakeProc::new().available_kb(4 * 1024).build().unwrap();
+        let mut killer = OOMKiller::new(None).with_proc_root(root.path());
+        let killed_at = Instant::now();
+        for (pid, available_before, killed_at) in [
+            (4194305, MIB, killed_at),
+            (4194306, 2 * MIB, killed_at + Duration::from_millis(1)),
+        ] {
+            killer.pending_confirmations.push(PendingConfirmation {
+                pid: ProcessId::new(pid).unwrap(),
+                start_time: 100,
+                estimated: 2 * MIB,
+                available_before,
+                killed_at,
+            });
+        }
+
+        // 增量从最早的基线算起只计一次：3MiB，而不是各自按自己的基线计入的2MiB + 2MiB
+        killer.confirm_pending_kills();
+        assert!(killer.pending_confirmations.is_empty());
+        assert_eq!(killer.get_status().confirmed_reclaimed, 3 * MIB);
+
+        // 不超过同时退出的受害者的估算值之和
+        killer.pending_confirmations.push(PendingConfirmation {
+            pid: ProcessId::new(4194305).unwrap(),
+            start_time: 100,
+            estimated: MIB,
+            available_before: 0,
+            killed_at,
+        });
+        killer.confirm_pending_kills();
+        assert_eq!(killer.get_status().confirmed_reclaimed, 4 * MIB);
+    }
+
+    /// 假proc目录中的进程当前的oom_score_adj
+    fn fixture_adj(root: &Path, pid: ProcessId) -> i32 {
+        crate::ffi::ProcessDir::open(root, pid).unwrap().read_oom_score_adj().unwrap()
+    }
+
+    #[test]
+    fn test_kernel_delegate_sets_and_reverts_adj() {
+        use crate::test_util::{FakeProc, ProcessGroup, FIRST_PID};
+
+        let root = FakeProc::new()
+            .process(FIRST_PID, ProcessGroup::new(1).start_time(100).oom_scores(0, 200))
+            .build()
+            .unwrap();
+        let key = ProcessKey { pid: ProcessId::new(FIRST_PID).unwrap(), start_time: 100 };
+        let revert_after = Duration::from_millis(50);
+        let mut killer = OOMKiller::new(Some(KillerConfig {
+            kill_mode: KillMode::KernelDelegate { revert_after },
+            ..Default::default()
+        }))
+        .with_proc_root(root.path());
+
+        // 选中后oom_score_adj应被设为最大值
+        killer.delegate_to_kernel(key, revert_after).unwrap();
+        assert_eq!(fixture_adj(root.path(), key.pid), OOM_SCORE_ADJ_MAX);
+
+        // 窗口内不应恢复
+        killer.revert_expired_delegations();
+        assert_eq!(fixture_adj(root.path(), key.pid), OOM_SCORE_ADJ_MAX);
+
+        // 窗口结束后进程仍然存活，应恢复原值
+        thread::sleep(revert_after * 2);
+        killer.revert_expired_delegations();
+        assert_eq!(fixture_adj(root.path(), key.pid), 200);
+        assert!(killer.delegated.is_empty());
+    }
+
+    #[test]
+    fn test_kernel_delegate_skips_reused_pid() {
+        use crate::test_util::{write_process, FakeProc, ProcessGroup, FIRST_PID};
+
+        let reused = ProcessGroup::new(1).start_time(200).oom_scores(0, 300);
+        let root = FakeProc::new()
+            .process(FIRST_PID, ProcessGroup::new(1).start_time(100).oom_scores(0, 0))
+            .build()
+            .unwrap();
+        let key = ProcessKey { pid: ProcessId::new(FIRST_PID).unwrap(), start_time: 100 };
+        let revert_after = Duration::from_millis(10);
+        let mut killer = OOMKiller::new(Some(KillerConfig {
+            kill_mode: KillMode::KernelDelegate { revert_after },
+            ..Default::default()
+        }))
+        .with_proc_root(root.path());
+        killer.delegate_to_kernel(key, revert_after).unwrap();
+        assert_eq!(fixture_adj(root.path(), key.pid), OOM_SCORE_ADJ_MAX);
+
+        // 窗口结束前pid被另一个进程复用，不把原值写给它
+        write_process(root.path(), FIRST_PID, &reused).unwrap();
+        thread::sleep(revert_after * 2);
+        killer.revert_expired_delegations();
+        assert!(killer.delegated.is_empty());
+        assert_eq!(fixture_adj(root.path(), key.pid), 300);
+
+        // 委托时pid已被复用，不标记无关的进程
+        assert!(matches!(killer.delegate_to_kernel(key, revert_after), Err(SystemError::ProcessNotFound)));
+        assert!(killer.delegated.is_empty());
+        assert_eq!(fixture_adj(root.path(), key.pid), 300);
+    }
+
+    #[cfg(feature = "kmsg")]
+    #[test]
+    fn test_record_kernel_kill() {
+        use crate::linux::kmsg::KmsgParser;
+
+        let mut parser = KmsgParser::new();
+        let record = parser
+            .feed_line("[ 9876.543520] Out of memory: Killed process 1777 (python3) total-vm:6543208kB, anon-rss:6123456kB, file-rss:2048kB, shmem-rss:0kB, UID:1000 pgtables:12344kB oom_score_adj:0")
+            .unwrap();
+
+        // 监控线程中的实例与句柄共享终止历史
+        let killer = OOMKiller::new(None);
+        let mut monitor = OOMKiller::new(None);
+        monitor.stats = Arc::clone(&killer.stats);
+        monitor.record_kernel_kill(record);
+
+        let history = killer.kill_history();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].source, KillSource::Kernel);
+        assert_eq!(history[0].pid.as_raw(), 1777);
+        assert_eq!(history[0].memory_freed, (6_123_456 + 2048) * 1024);
     }
 } 
\ No newline at end of file
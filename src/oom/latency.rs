@@ -0,0 +1,293 @@
+use std::time::Duration;
+
+/// 每个阶段保留的最近采样数
+pub const LATENCY_WINDOW: usize = 128;
+
+/// 监控循环中分别计时的阶段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// 读取meminfo并判断压力
+    PressureCheck,
+    /// 枚举进程并过滤候选
+    Enumeration,
+    /// 对候选评分
+    Scoring,
+    /// 终止受害者（发送信号或委托给内核）
+    Kill,
+}
+
+/// 阶段数
+pub(crate) const PHASES: usize = 4;
+
+impl Phase {
+    /// 阶段在按声明顺序排列的数组中的下标
+    pub(crate) fn index(self) -> usize {
+        match self {
+            Phase::PressureCheck => 0,
+            Phase::Enumeration => 1,
+            Phase::Scoring => 2,
+            Phase::Kill => 3,
+        }
+    }
+}
+
+/// 一组采样的百分位数，没有采样时全为0
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Percentiles {
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+    /// 参与统计的采样数
+    pub samples: usize,
+}
+
+/// 固定容量的采样环，写满后覆盖最旧的采样，记录时不分配内存
+#[derive(Debug, Clone)]
+struct Samples {
+    values: [Duration; LATENCY_WINDOW],
+    len: usize,
+    next: usize,
+}
+
+impl Samples {
+    const fn new() -> Self {
+        Self {
+            values: [Duration::ZERO; LATENCY_WINDOW],
+            len: 0,
+            next: 0,
+        }
+    }
+
+    fn push(&mut self, value: Duration) {
+        self.values[self.next] = value;
+        self.next = (self.next + 1) % LATENCY_WINDOW;
+        self.len = (self.len + 1).min(LATENCY_WINDOW);
+    }
+
+    /// 窗口内采样的平均值，没有采样时为0
+    fn mean(&self) -> Duration {
+        if self.len == 0 {
+            return Duration::ZERO;
+        }
+        let total: Duration = self.values[..self.len].iter().sum();
+        total / self.len as u32
+    }
+
+    /// 在栈上排序一份副本计算百分位数（最近秩法）
+    fn percentiles(&self) -> Percentiles {
+        if self.len == 0 {
+            return Percentiles::default();
+        }
+
+        let mut sorted = self.values;
+        let sorted = &mut sorted[..self.len];
+        sorted.sort_unstable();
+        let rank = |p: usize| sorted[(self.len * p).div_ceil(100).max(1) - 1];
+
+        Percentiles {
+            p50: rank(50),
+            p90: rank(90),
+            p99: rank(99),
+            max: sorted[self.len - 1],
+            samples: self.len,
+        }
+    }
+}
+
+/// 最近的监控循环耗时统计
+#[derive(Debug, Clone, Default)]
+pub struct LatencySummary {
+    /// 整轮耗时
+    pub cycle: Percentiles,
+    pub pressure_check: Percentiles,
+    pub enumeration: Percentiles,
+    pub scoring: Percentiles,
+    pub kill: Percentiles,
+    /// 超过检查间隔的累计轮数
+    pub overruns: u64,
+    /// 当前连续超过检查间隔的轮数
+    pub consecutive_overruns: u32,
+}
+
+/// 监控循环的耗时记录
+///
+/// 整轮耗时每轮记录一次；阶段只在本轮实际运行时记录，
+/// 没有选择的轮次不会把枚举和评分的百分位数拉低
+#[derive(Debug, Clone)]
+pub struct LoopLatency {
+    cycle: Samples,
+    phases: [Samples; PHASES],
+    // 本轮各阶段累计的耗时，没有运行的阶段为None
+    current: [Option<Duration>; PHASES],
+    last_cycle: Option<Duration>,
+    overruns: u64,
+    consecutive_overruns: u32,
+}
+
+impl LoopLatency {
+    /// 创建空的记录
+    pub fn new() -> Self {
+        Self {
+            cycle: Samples::new(),
+            phases: [Samples::new(), Samples::new(), Samples::new(), Samples::new()],
+            current: [None; PHASES],
+            last_cycle: None,
+            overruns: 0,
+            consecutive_overruns: 0,
+        }
+    }
+
+    /// 记录本轮一个阶段的耗时，同一阶段在一轮中多次运行时累加
+    pub fn record(&mut self, phase: Phase, duration: Duration) {
+        let current = &mut self.current[phase.index()];
+        *current = Some(current.unwrap_or(Duration::ZERO) + duration);
+    }
+
+    /// 结束一轮：记录整轮和本轮运行过的阶段的耗时，返回这一轮是否超过 `interval`
+    pub fn finish_cycle(&mut self, elapsed: Duration, interval: Duration) -> bool {
+        self.cycle.push(elapsed);
+        self.last_cycle = Some(elapsed);
+        for (samples, current) in self.phases.iter_mut().zip(self.current.iter_mut()) {
+            if let Some(duration) = current.take() {
+                samples.push(duration);
+            }
+        }
+
+        let overran = elapsed > interval;
+        if overran {
+            self.overruns += 1;
+            self.consecutive_overruns += 1;
+        } else {
+            self.consecutive_overruns = 0;
+        }
+        overran
+    }
+
+    /// 当前连续超过检查间隔的轮数
+    pub fn consecutive_overruns(&self) -> u32 {
+        self.consecutive_overruns
+    }
+
+    /// 最近一轮的耗时，还没有完成任何一轮时为None
+    pub fn last_cycle(&self) -> Option<Duration> {
+        self.last_cycle
+    }
+
+    /// 最近 `LATENCY_WINDOW` 轮的平均耗时
+    pub fn cycle_mean(&self) -> Duration {
+        self.cycle.mean()
+    }
+
+    /// 最近整轮耗时的百分位数
+    pub fn cycle_percentiles(&self) -> Percentiles {
+        self.cycle.percentiles()
+    }
+
+    /// 汇总所有百分位数
+    pub fn summary(&self) -> LatencySummary {
+        let phase = |phase: Phase| self.phases[phase.index()].percentiles();
+
+        LatencySummary {
+            cycle: self.cycle.percentiles(),
+            pressure_check: phase(Phase::PressureCheck),
+            enumeration: phase(Phase::Enumeration),
+            scoring: phase(Phase::Scoring),
+            kill: phase(Phase::Kill),
+            overruns: self.overruns,
+            consecutive_overruns: self.consecutive_overruns,
+        }
+    }
+}
+
+impl Default for LoopLatency {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(value: u64) -> Duration {
+        Duration::from_millis(value)
+    }
+
+    #[test]
+    fn test_percentiles_over_window() {
+        let mut latency = LoopLatency::new();
+        assert_eq!(latency.cycle_percentiles(), Percentiles::default());
+
+        // 1..=100ms，p50=50ms，p90=90ms，p99=99ms
+        for value in 1..=100 {
+            latency.finish_cycle(ms(value), ms(1000));
+        }
+        let cycle = latency.cycle_percentiles();
+        assert_eq!((cycle.p50, cycle.p90, cycle.p99, cycle.max), (ms(50), ms(90), ms(99), ms(100)));
+
+        // 写满后最旧的采样被覆盖，只保留最近的 LATENCY_WINDOW 个
+        for _ in 0..LATENCY_WINDOW {
+            latency.finish_cycle(ms(5), ms(1000));
+        }
+        let cycle = latency.cycle_percentiles();
+        assert_eq!(cycle.samples, LATENCY_WINDOW);
+        assert_eq!(cycle.max, ms(5));
+    }
+
+    #[test]
+    fn test_last_cycle_and_mean() {
+        let mut latency = LoopLatency::new();
+        assert_eq!(latency.last_cycle(), None);
+        assert_eq!(latency.cycle_mean(), Duration::ZERO);
+
+        for value in [10, 20, 60] {
+            latency.finish_cycle(ms(value), ms(100));
+        }
+        assert_eq!(latency.last_cycle(), Some(ms(60)));
+        assert_eq!(latency.cycle_mean(), ms(30));
+
+        // 平均值只覆盖窗口内的采样
+        for _ in 0..LATENCY_WINDOW {
+            latency.finish_cycle(ms(4), ms(100));
+        }
+        assert_eq!(latency.cycle_mean(), ms(4));
+    }
+
+    #[test]
+    fn test_phases_recorded_only_when_run() {
+        let mut latency = LoopLatency::new();
+        latency.record(Phase::PressureCheck, ms(1));
+        latency.record(Phase::Enumeration, ms(30));
+        latency.record(Phase::Scoring, ms(10));
+        latency.finish_cycle(ms(45), ms(100));
+
+        // 只检查压力的轮次；同一阶段两次运行时累加
+        latency.record(Phase::PressureCheck, ms(1));
+        latency.record(Phase::PressureCheck, ms(2));
+        latency.finish_cycle(ms(3), ms(100));
+
+        let summary = latency.summary();
+        assert_eq!(summary.cycle.samples, 2);
+        assert_eq!(summary.pressure_check.max, ms(3));
+        assert_eq!(summary.enumeration.samples, 1);
+        assert_eq!(summary.enumeration.p50, ms(30));
+        assert_eq!(summary.kill.samples, 0);
+    }
+
+    #[test]
+    fn test_consecutive_overruns() {
+        let mut latency = LoopLatency::new();
+        assert!(latency.finish_cycle(ms(150), ms(100)));
+        assert!(latency.finish_cycle(ms(800), ms(100)));
+        assert_eq!(latency.consecutive_overruns(), 2);
+
+        assert!(!latency.finish_cycle(ms(100), ms(100)));
+        assert_eq!(latency.consecutive_overruns(), 0);
+        assert!(latency.finish_cycle(ms(101), ms(100)));
+
+        let summary = latency.summary();
+        assert_eq!(summary.overruns, 3);
+        assert_eq!(summary.consecutive_overruns, 1);
+    }
+}
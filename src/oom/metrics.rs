@@ -0,0 +1,195 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use crate::oom::latency::{Phase, PHASES};
+use crate::oom::pressure::PressureLevel;
+
+/// 指标类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricKind {
+    /// 单调递增的累计值
+    Counter,
+    /// 当前值
+    Gauge,
+    /// 耗时（微秒）
+    Timing,
+}
+
+/// 某一时刻的指标值
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MetricSample {
+    pub name: &'static str,
+    pub kind: MetricKind,
+    pub value: u64,
+}
+
+/// 进程内共享的指标注册表
+///
+/// 埋点只写这里，各种导出方式（statsd 等）从这里读取，
+/// 这样同一个指标只需要埋点一次。
+#[derive(Debug)]
+pub struct MetricsRegistry {
+    kills: AtomicU64,
+    memory_reclaimed_bytes: AtomicU64,
+    pressure_level: AtomicU64,
+    available_bytes: AtomicU64,
+    selection_duration_us: AtomicU64,
+    cycle_duration_us: AtomicU64,
+    cycle_p99_us: AtomicU64,
+    loop_overruns: AtomicU64,
+    // 按 Phase 的顺序：压力检查、枚举、评分、终止
+    phase_duration_us: [AtomicU64; PHASES],
+}
+
+static REGISTRY: MetricsRegistry = MetricsRegistry::new();
+
+/// 获取全局指标注册表
+pub fn registry() -> &'static MetricsRegistry {
+    &REGISTRY
+}
+
+impl MetricsRegistry {
+    /// 创建空的注册表
+    pub const fn new() -> Self {
+        Self {
+            kills: AtomicU64::new(0),
+            memory_reclaimed_bytes: AtomicU64::new(0),
+            pressure_level: AtomicU64::new(0),
+            available_bytes: AtomicU64::new(0),
+            selection_duration_us: AtomicU64::new(0),
+            cycle_duration_us: AtomicU64::new(0),
+            cycle_p99_us: AtomicU64::new(0),
+            loop_overruns: AtomicU64::new(0),
+            phase_duration_us: [AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0), AtomicU64::new(0)],
+        }
+    }
+
+    /// 记录一次终止及其估算的回收量
+    pub fn record_kill(&self, memory_freed: u64) {
+        self.kills.fetch_add(1, Ordering::Relaxed);
+        self.memory_reclaimed_bytes.fetch_add(memory_freed, Ordering::Relaxed);
+    }
+
+    /// 记录当前的压力级别（Normal=0, Warning=1, Critical=2）
+    pub fn set_pressure_level(&self, level: PressureLevel) {
+        let value = match level {
+            PressureLevel::Normal => 0,
+            PressureLevel::Warning => 1,
+            PressureLevel::Critical => 2,
+        };
+        self.pressure_level.store(value, Ordering::Relaxed);
+    }
+
+    /// 记录当前可用内存（字节）
+    pub fn set_available_bytes(&self, bytes: u64) {
+        self.available_bytes.store(bytes, Ordering::Relaxed);
+    }
+
+    /// 记录一次进程选择的耗时
+    pub fn record_selection(&self, duration: Duration) {
+        self.selection_duration_us.store(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// 记录一轮监控循环的耗时及其是否超过检查间隔
+    pub fn record_cycle(&self, duration: Duration, overran: bool) {
+        self.cycle_duration_us.store(duration.as_micros() as u64, Ordering::Relaxed);
+        if overran {
+            self.loop_overruns.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// 记录最近整轮耗时的p99
+    pub fn set_cycle_p99(&self, duration: Duration) {
+        self.cycle_p99_us.store(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// 记录一个阶段最近一次的耗时
+    pub fn record_phase(&self, phase: Phase, duration: Duration) {
+        self.phase_duration_us[phase.index()].store(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// 读取所有指标的当前值
+    pub fn samples(&self) -> Vec<MetricSample> {
+        let load = |value: &AtomicU64| value.load(Ordering::Relaxed);
+
+        vec![
+            MetricSample { name: "kills", kind: MetricKind::Counter, value: load(&self.kills) },
+            MetricSample {
+                name: "memory_reclaimed_bytes",
+                kind: MetricKind::Counter,
+                value: load(&self.memory_reclaimed_bytes),
+            },
+            MetricSample { name: "pressure_level", kind: MetricKind::Gauge, value: load(&self.pressure_level) },
+            MetricSample { name: "available_bytes", kind: MetricKind::Gauge, value: load(&self.available_bytes) },
+            MetricSample {
+                name: "selection_duration",
+                kind: MetricKind::Timing,
+                value: load(&self.selection_duration_us),
+            },
+            MetricSample { name: "cycle_duration", kind: MetricKind::Timing, value: load(&self.cycle_duration_us) },
+            MetricSample { name: "cycle_duration_p99_us", kind: MetricKind::Gauge, value: load(&self.cycle_p99_us) },
+            MetricSample { name: "loop_overruns", kind: MetricKind::Counter, value: load(&self.loop_overruns) },
+            MetricSample {
+                name: "pressure_check_duration",
+                kind: MetricKind::Timing,
+                value: load(&self.phase_duration_us[0]),
+            },
+            MetricSample {
+                name: "enumeration_duration",
+                kind: MetricKind::Timing,
+                value: load(&self.phase_duration_us[1]),
+            },
+            MetricSample {
+                name: "scoring_duration",
+                kind: MetricKind::Timing,
+                value: load(&self.phase_duration_us[2]),
+            },
+            MetricSample { name: "kill_duration", kind: MetricKind::Timing, value: load(&self.phase_duration_us[3]) },
+        ]
+    }
+}
+
+impl Default for MetricsRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value_of(registry: &MetricsRegistry, name: &str) -> u64 {
+        registry.samples().into_iter().find(|s| s.name == name).unwrap().value
+    }
+
+    #[test]
+    fn test_registry_records() {
+        let registry = MetricsRegistry::new();
+        registry.record_kill(1024);
+        registry.record_kill(2048);
+        registry.set_pressure_level(PressureLevel::Critical);
+        registry.set_available_bytes(4096);
+        registry.record_selection(Duration::from_millis(3));
+
+        assert_eq!(value_of(&registry, "kills"), 2);
+        assert_eq!(value_of(&registry, "memory_reclaimed_bytes"), 3072);
+        assert_eq!(value_of(&registry, "pressure_level"), 2);
+        assert_eq!(value_of(&registry, "available_bytes"), 4096);
+        assert_eq!(value_of(&registry, "selection_duration"), 3000);
+    }
+
+    #[test]
+    fn test_loop_latency_metrics() {
+        let registry = MetricsRegistry::new();
+        registry.record_cycle(Duration::from_millis(120), true);
+        registry.record_cycle(Duration::from_millis(40), false);
+        registry.set_cycle_p99(Duration::from_millis(120));
+        registry.record_phase(Phase::Enumeration, Duration::from_millis(25));
+
+        assert_eq!(value_of(&registry, "cycle_duration"), 40_000);
+        assert_eq!(value_of(&registry, "cycle_duration_p99_us"), 120_000);
+        assert_eq!(value_of(&registry, "loop_overruns"), 1);
+        assert_eq!(value_of(&registry, "enumeration_duration"), 25_000);
+        assert_eq!(value_of(&registry, "kill_duration"), 0);
+    }
+}
@@ -1,8 +1,15 @@
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use crate::ffi::safe_wrapper::SystemInterface;
 use crate::ffi::types::{SystemError, Result};
-use crate::linux::proc::ProcessInfo;
-use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use crate::linux::cgroup::{self, CgroupMemStat};
+use crate::linux::loadavg::SystemLoad;
+use crate::linux::proc::{MemoryProfile, ProcessInfo, DEFAULT_PROC_ROOT};
+use crate::oom::metrics;
 
 /// 内存压力阈值配置
 #[derive(Debug, Clone)]
@@ -11,8 +18,26 @@ pub struct PressureThresholds {
     pub min_free_ratio: f64,
     /// swap使用率的最大比例（0-1）
     pub max_swap_ratio: f64,
+    /// swap使用率超过 `max_swap_ratio` 进入压力后，低于该比例才解除，`None` 表示按 `max_swap_ratio` 解除
+    ///
+    /// swap使用率在阈值附近来回波动时，避免压力状态反复切换。大于 `max_swap_ratio` 时按 `max_swap_ratio` 处理
+    pub swap_exit_ratio: Option<f64>,
+    /// `Committed_AS / CommitLimit` 超过该比例时视为压力，`None` 表示不检查
+    ///
+    /// 在 `vm.overcommit_memory=2` 的系统上，真正的分配上限是CommitLimit，
+    /// 空闲内存还很多时分配也可能失败。meminfo中没有CommitLimit时不检查
+    pub max_commit_ratio: Option<f64>,
     /// 内存压力持续时间阈值
     pub pressure_duration: Duration,
+    /// 连续多少次无压力采样后才重置压力计时（至少为1）
+    ///
+    /// 持续压力期间偶尔出现的一次正常读数不会让计时从头开始
+    pub reset_tolerance: u32,
+    /// 每个CPU的1分钟平均负载低于该值时，压力需要持续 `pressure_duration` 的两倍才触发终止
+    ///
+    /// 空闲系统上的内存压力往往是短暂的（例如一次性的大分配），而高负载下的压力
+    /// 更可能是持续的抖动。`None` 表示不读取 /proc/loadavg
+    pub low_load_per_cpu: Option<f64>,
 }
 
 impl Default for PressureThresholds {
@@ -20,28 +45,291 @@ impl Default for PressureThresholds {
         Self {
             min_free_ratio: 0.05,  // 5%可用内存
             max_swap_ratio: 0.80,  // 80% swap使用率
+            swap_exit_ratio: None,
+            max_commit_ratio: None,
             pressure_duration: Duration::from_secs(5),
+            reset_tolerance: 3,
+            low_load_per_cpu: None,
         }
     }
 }
 
+impl PressureThresholds {
+    /// 一次采样是否越过阈值（不考虑持续时间）
+    pub fn is_under_pressure(&self, stats: &MemoryStats) -> bool {
+        stats.available_ratio() < self.min_free_ratio
+            || self.is_swap_pressure(stats, false)
+            || self.is_commit_pressure(stats)
+    }
+
+    /// 一次采样对应的压力级别，swap按进入比例判断
+    pub fn level(&self, stats: &MemoryStats) -> PressureLevel {
+        if self.is_under_pressure(stats) {
+            PressureLevel::Critical
+        } else if stats.available_ratio() < self.min_free_ratio * WARNING_MARGIN {
+            PressureLevel::Warning
+        } else {
+            PressureLevel::Normal
+        }
+    }
+
+    /// 已承诺的内存是否接近CommitLimit
+    pub fn is_commit_pressure(&self, stats: &MemoryStats) -> bool {
+        match self.max_commit_ratio {
+            Some(max) if stats.commit_limit > 0 => commit_ratio(stats) > max,
+            _ => false,
+        }
+    }
+
+    /// swap使用率是否构成压力，`triggered` 表示上一次采样时swap已经触发压力
+    pub fn is_swap_pressure(&self, stats: &MemoryStats, triggered: bool) -> bool {
+        let ratio = swap_used_ratio(stats);
+        match self.swap_exit_ratio {
+            Some(exit) if triggered => ratio >= exit.min(self.max_swap_ratio),
+            _ => ratio > self.max_swap_ratio,
+        }
+    }
+}
+
+/// 已承诺的内存占CommitLimit的比例，没有CommitLimit时为0
+fn commit_ratio(stats: &MemoryStats) -> f64 {
+    if stats.commit_limit > 0 {
+        stats.committed_as as f64 / stats.commit_limit as f64
+    } else {
+        0.0
+    }
+}
+
+/// swap使用率，没有swap时为0
+fn swap_used_ratio(stats: &MemoryStats) -> f64 {
+    if stats.total_swap > 0 {
+        stats.total_swap.saturating_sub(stats.free_swap) as f64 / stats.total_swap as f64
+    } else {
+        0.0
+    }
+}
+
+/// 可用内存比例低于阈值的该倍数时进入警告级别
+const WARNING_MARGIN: f64 = 2.0;
+
+/// 低负载时压力持续时间阈值的倍数
+const LOW_LOAD_DURATION_FACTOR: u32 = 2;
+
+/// 压力检查读取meminfo的缓冲区大小
+///
+/// 需要的字段都在文件开头，超出部分不会被读取
+const MEMINFO_BUF_LEN: usize = 4096;
+
+/// 内存压力级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum PressureLevel {
+    /// 内存充足
+    Normal,
+    /// 接近阈值
+    Warning,
+    /// 已越过阈值
+    Critical,
+}
+
 /// 内存压力检测器
 #[derive(Debug)]
 pub struct PressureDetector {
     thresholds: PressureThresholds,
     pressure_start: Option<Instant>,
+    // 压力期间连续的无压力采样次数
+    clean_samples: u32,
     last_pressure_check: Instant,
+    level: PressureLevel,
+    // 最近一次检查是否处于持续压力状态
+    sustained: bool,
+    // 最近一次检查时swap使用率是否触发压力，用于 swap_exit_ratio 的滞回
+    swap_triggered: bool,
+    // 读取meminfo的proc根目录
+    proc_root: PathBuf,
+    meminfo_path: PathBuf,
+    // 每轮检查复用的meminfo读取缓冲区
+    meminfo_buf: Box<[u8; MEMINFO_BUF_LEN]>,
+    last_stats: Option<MemoryStats>,
+    // 容器模式下按该 memory cgroup 的 memory.stat 判断压力
+    cgroup_dir: Option<PathBuf>,
+    // 配置了 low_load_per_cpu 时每轮读取的系统负载
+    loadavg_path: PathBuf,
+    cpus: usize,
+    last_load: Option<SystemLoad>,
 }
 
-/// 内存统计信息
-#[derive(Debug, Clone)]
+/// 内存统计信息（字节）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MemoryStats {
     pub total_memory: u64,
     pub free_memory: u64,
     pub available_memory: u64,
     pub total_swap: u64,
     pub free_swap: u64,
+    /// meminfo中的原始 `Cached`，包含不可回收的Shmem（tmpfs、共享内存）
     pub cached_memory: u64,
+    /// `Shmem`，较老的内核（2.6.32之前）没有这一行，此时为0
+    #[serde(default)]
+    pub shmem: u64,
+    /// `SReclaimable`，可回收的slab，不计入 `Cached`
+    #[serde(default)]
+    pub slab_reclaimable: u64,
+    /// 实际可回收的缓存：`Cached + SReclaimable - Shmem`
+    #[serde(default)]
+    pub reclaimable_cache: u64,
+    /// `CommitLimit`，`vm.overcommit_memory=2` 时的分配上限
+    #[serde(default)]
+    pub commit_limit: u64,
+    /// `Committed_AS`，所有进程已承诺（分配但不一定使用）的内存
+    #[serde(default)]
+    pub committed_as: u64,
+}
+
+impl MemoryStats {
+    /// 可用内存占总内存的比例（0-1）
+    ///
+    /// 读取meminfo时的竞争或个别内核上 `MemAvailable` 可能短暂大于 `MemTotal`，
+    /// 此时按1处理；没有总内存时同样按1处理，不视为压力
+    pub fn available_ratio(&self) -> f64 {
+        if self.available_memory > self.total_memory || self.total_memory == 0 {
+            log::debug!(
+                "Inconsistent memory readings: available {} bytes, total {} bytes",
+                self.available_memory,
+                self.total_memory
+            );
+            return 1.0;
+        }
+        self.available_memory as f64 / self.total_memory as f64
+    }
+
+    /// 解析/proc/meminfo的内容，未出现的字段为0
+    pub fn parse(content: &str) -> Self {
+        let mut stats = MemoryStats::default();
+
+        for line in content.lines() {
+            let mut parts = line.split_whitespace();
+            let (Some(key), Some(value)) = (parts.next(), parts.next()) else {
+                continue;
+            };
+
+            let value = value.parse::<u64>().unwrap_or(0) * 1024; // 转换为字节
+            match key {
+                "MemTotal:" => stats.total_memory = value,
+                "MemFree:" => stats.free_memory = value,
+                "MemAvailable:" => stats.available_memory = value,
+                "SwapTotal:" => stats.total_swap = value,
+                "SwapFree:" => stats.free_swap = value,
+                "Cached:" => stats.cached_memory = value,
+                "Shmem:" => stats.shmem = value,
+                "SReclaimable:" => stats.slab_reclaimable = value,
+                "CommitLimit:" => stats.commit_limit = value,
+                "Committed_AS:" => stats.committed_as = value,
+                _ => {}
+            }
+        }
+        stats.reclaimable_cache = stats.cached_memory
+            .saturating_add(stats.slab_reclaimable)
+            .saturating_sub(stats.shmem);
+
+        stats
+    }
+
+    /// 只解析压力检查需要的字段，全部找到后立即停止
+    ///
+    /// 直接在字节上解析，不做UTF-8校验，也不分配内存；
+    /// 对这些字段的结果与 `parse` 一致，其余字段（包括 `reclaimable_cache`）为0
+    pub fn parse_fast(content: &[u8]) -> Self {
+        const FIELDS: usize = 8;
+        let mut stats = MemoryStats::default();
+        let mut found = 0;
+
+        for line in content.split(|&b| b == b'\n') {
+            let Some(colon) = line.iter().position(|&b| b == b':') else {
+                continue;
+            };
+            let field = match &line[..colon] {
+                b"MemTotal" => &mut stats.total_memory,
+                b"MemFree" => &mut stats.free_memory,
+                b"MemAvailable" => &mut stats.available_memory,
+                b"SwapTotal" => &mut stats.total_swap,
+                b"SwapFree" => &mut stats.free_swap,
+                b"Cached" => &mut stats.cached_memory,
+                b"CommitLimit" => &mut stats.commit_limit,
+                b"Committed_AS" => &mut stats.committed_as,
+                _ => continue,
+            };
+            *field = parse_kb_bytes(&line[colon + 1..]) * 1024; // 转换为字节
+
+            found += 1;
+            if found == FIELDS {
+                break;
+            }
+        }
+
+        stats
+    }
+
+    /// 由 memory cgroup 的上限和 memory.stat 构造统计信息
+    ///
+    /// 可用内存只扣除匿名内存和shmem：页缓存会在达到上限前被回收，
+    /// 不应像 memory.current 那样计入压力。cgroup没有单独的swap统计，swap字段为0
+    pub fn from_cgroup(limit: u64, stat: &CgroupMemStat) -> Self {
+        let unreclaimable = stat.anon.saturating_add(stat.shmem);
+        let charged = stat.anon.saturating_add(stat.file).saturating_add(stat.kernel);
+
+        MemoryStats {
+            total_memory: limit,
+            free_memory: limit.saturating_sub(charged),
+            available_memory: limit.saturating_sub(unreclaimable),
+            total_swap: 0,
+            free_swap: 0,
+            cached_memory: stat.file,
+            shmem: stat.shmem,
+            slab_reclaimable: stat.slab_reclaimable,
+            reclaimable_cache: stat.file
+                .saturating_add(stat.slab_reclaimable)
+                .saturating_sub(stat.shmem),
+            commit_limit: 0,
+            committed_as: 0,
+        }
+    }
+}
+
+/// 解析/proc/meminfo中的所有字段，键不含冒号
+///
+/// 单位为kB的值转换为字节；没有单位的值（例如 `HugePages_Total` 的页数）原样保留。
+/// 无法解析的行被跳过
+pub fn parse_raw_meminfo(content: &str) -> BTreeMap<String, u64> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            let mut parts = value.split_whitespace();
+            let value = parts.next()?.parse::<u64>().ok()?;
+            let value = match parts.next() {
+                Some("kB") => value.checked_mul(1024)?,
+                None => value,
+                Some(_) => return None,
+            };
+            Some((key.trim().to_string(), value))
+        })
+        .collect()
+}
+
+/// 解析 "   1024 kB" 中的数值，无法解析时为0
+fn parse_kb_bytes(value: &[u8]) -> u64 {
+    let digits = value.iter().skip_while(|b| b.is_ascii_whitespace());
+    let mut result: u64 = 0;
+    for &b in digits.take_while(|b| !b.is_ascii_whitespace()) {
+        if !b.is_ascii_digit() {
+            return 0;
+        }
+        result = match result.checked_mul(10).and_then(|r| r.checked_add(u64::from(b - b'0'))) {
+            Some(result) => result,
+            None => return 0,
+        };
+    }
+    result
 }
 
 impl PressureDetector {
@@ -50,85 +338,225 @@ impl PressureDetector {
         Self {
             thresholds: thresholds.unwrap_or_default(),
             pressure_start: None,
+            clean_samples: 0,
             last_pressure_check: Instant::now(),
+            level: PressureLevel::Normal,
+            sustained: false,
+            swap_triggered: false,
+            proc_root: PathBuf::from(DEFAULT_PROC_ROOT),
+            meminfo_path: Path::new(DEFAULT_PROC_ROOT).join("meminfo"),
+            meminfo_buf: Box::new([0; MEMINFO_BUF_LEN]),
+            last_stats: None,
+            cgroup_dir: None,
+            loadavg_path: Path::new(DEFAULT_PROC_ROOT).join("loadavg"),
+            cpus: std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            last_load: None,
         }
     }
 
+    /// 使用指定的proc根目录读取meminfo
+    pub fn with_proc_root(mut self, proc_root: impl Into<PathBuf>) -> Self {
+        self.proc_root = proc_root.into();
+        self.meminfo_path = self.proc_root.join("meminfo");
+        self.loadavg_path = self.proc_root.join("loadavg");
+        self
+    }
+
+    /// 容器模式：按指定 memory cgroup 目录的 memory.stat 和内存上限判断压力
+    ///
+    /// 基于匿名内存而不是总占用，大量可回收的页缓存不会触发终止。
+    /// cgroup没有内存上限时以主机的MemTotal为总量。
+    /// 与读取meminfo不同，这条路径每轮检查都会分配内存
+    pub fn with_cgroup(mut self, cgroup_dir: impl Into<PathBuf>) -> Self {
+        self.cgroup_dir = Some(cgroup_dir.into());
+        self
+    }
+
     /// 检查系统是否处于内存压力状态
     /// 
     /// # 返回值
     /// 
     /// 如果系统处于持续的内存压力状态，返回 true
     pub fn check_pressure(&mut self) -> Result<bool> {
-        let stats = self.get_memory_stats()?;
-        let now = Instant::now();
+        let stats = match &self.cgroup_dir {
+            Some(dir) => {
+                let dir = dir.clone();
+                self.read_cgroup_stats(&dir)?
+            }
+            None => {
+                // 复用读取缓冲区并只解析需要的字段，每轮检查不分配内存
+                let len = self.read_meminfo().map_err(SystemError::SyscallError)?;
+                MemoryStats::parse_fast(&self.meminfo_buf[..len])
+            }
+        };
+        metrics::registry().set_available_bytes(stats.available_memory);
+        if self.thresholds.low_load_per_cpu.is_some() {
+            // 读取失败时按负载未知处理，不放宽也不收紧
+            self.last_load = SystemLoad::read_from(&self.loadavg_path).ok();
+        }
+        let sustained = self.observe(stats, Instant::now());
+        metrics::registry().set_pressure_level(self.level);
+
+        Ok(sustained)
+    }
+
+    /// 用一次不是从meminfo读取的采样更新压力状态，例如回放记录时的采样
+    ///
+    /// `now` 是采样时刻，压力持续时间按它计算；不更新全局指标
+    pub fn observe(&mut self, stats: MemoryStats, now: Instant) -> bool {
+        let sustained = self.update(&stats, now);
+        self.sustained = sustained;
+        self.last_stats = Some(stats);
+        sustained
+    }
 
-        // 计算关键指标
-        let free_ratio = stats.available_memory as f64 / stats.total_memory as f64;
-        let swap_used_ratio = if stats.total_swap > 0 {
-            (stats.total_swap - stats.free_swap) as f64 / stats.total_swap as f64
+    /// 最近一次 `check_pressure` 读取的内存统计信息
+    pub fn last_stats(&self) -> Option<&MemoryStats> {
+        self.last_stats.as_ref()
+    }
+
+    /// 最近一次 `check_pressure` 读取的系统负载，未配置 `low_load_per_cpu` 时为None
+    pub fn last_load(&self) -> Option<&SystemLoad> {
+        self.last_load.as_ref()
+    }
+
+    /// 当前的压力持续时间阈值，系统负载低于 `low_load_per_cpu` 时加倍
+    fn required_duration(&self) -> Duration {
+        let low_load = match (self.thresholds.low_load_per_cpu, &self.last_load) {
+            (Some(threshold), Some(load)) => load.load1_per_cpu(self.cpus) < threshold,
+            _ => false,
+        };
+
+        if low_load {
+            self.thresholds.pressure_duration * LOW_LOAD_DURATION_FACTOR
         } else {
-            0.0
+            self.thresholds.pressure_duration
+        }
+    }
+
+    /// 锁定每轮检查复用的meminfo读取缓冲区，使其常驻内存
+    pub fn lock_buffers(&self) -> Result<()> {
+        SystemInterface::new().lock_memory(&*self.meminfo_buf)
+    }
+
+    /// 将meminfo读入复用的缓冲区，直到文件结束或缓冲区写满，返回读取的字节数
+    fn read_meminfo(&mut self) -> std::io::Result<usize> {
+        let mut file = File::open(&self.meminfo_path)?;
+        let mut len = 0;
+        while len < self.meminfo_buf.len() {
+            match file.read(&mut self.meminfo_buf[len..]) {
+                Ok(0) => break,
+                Ok(read) => len += read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(len)
+    }
+
+    /// 读取容器模式的统计信息，没有内存上限时从meminfo读取总量
+    fn read_cgroup_stats(&mut self, dir: &Path) -> Result<MemoryStats> {
+        let stat = CgroupMemStat::read(dir)?;
+        let limit = match cgroup::read_memory_limit(dir)? {
+            Some(limit) => limit,
+            None => {
+                let len = self.read_meminfo().map_err(SystemError::SyscallError)?;
+                MemoryStats::parse_fast(&self.meminfo_buf[..len]).total_memory
+            }
         };
 
-        // 判断是否处于压力状态
-        let under_pressure = free_ratio < self.thresholds.min_free_ratio || 
-                           swap_used_ratio > self.thresholds.max_swap_ratio;
+        Ok(MemoryStats::from_cgroup(limit, &stat))
+    }
+
+    /// 根据一次采样更新压力级别和计时，返回是否处于持续压力状态
+    fn update(&mut self, stats: &MemoryStats, now: Instant) -> bool {
+        // 判断是否处于压力状态，swap按进入和解除两个比例判断
+        self.swap_triggered = self.thresholds.is_swap_pressure(stats, self.swap_triggered);
+        let under_pressure = stats.available_ratio() < self.thresholds.min_free_ratio
+            || self.swap_triggered
+            || self.thresholds.is_commit_pressure(stats);
+
+        self.level = if under_pressure {
+            PressureLevel::Critical
+        } else if stats.available_ratio() < self.thresholds.min_free_ratio * WARNING_MARGIN {
+            PressureLevel::Warning
+        } else {
+            PressureLevel::Normal
+        };
+        self.last_pressure_check = now;
 
         // 更新压力状态
         if under_pressure {
-            if self.pressure_start.is_none() {
-                self.pressure_start = Some(now);
-            }
-            
+            self.clean_samples = 0;
+            let start = *self.pressure_start.get_or_insert(now);
+
             // 检查压力持续时间
-            if now.duration_since(self.pressure_start.unwrap()) >= self.thresholds.pressure_duration {
-                return Ok(true);
+            return now.duration_since(start) >= self.required_duration();
+        }
+
+        // 连续的无压力采样达到容忍次数后才重置计时
+        if self.pressure_start.is_some() {
+            self.clean_samples += 1;
+            if self.clean_samples >= self.thresholds.reset_tolerance.max(1) {
+                self.pressure_start = None;
+                self.clean_samples = 0;
             }
-        } else {
-            self.pressure_start = None;
         }
 
-        self.last_pressure_check = now;
-        Ok(false)
+        false
     }
 
-    /// 获取当前内存统计信息
-    pub fn get_memory_stats(&self) -> Result<MemoryStats> {
-        let file = File::open("/proc/meminfo").map_err(|e| 
-            SystemError::SyscallError(e)
-        )?;
+    /// 最近一次检查时是否处于持续压力状态
+    pub fn is_sustained(&self) -> bool {
+        self.sustained
+    }
 
-        let reader = BufReader::new(file);
-        let mut stats = MemoryStats {
-            total_memory: 0,
-            free_memory: 0,
-            available_memory: 0,
-            total_swap: 0,
-            free_swap: 0,
-            cached_memory: 0,
-        };
+    /// 最近一次检查时的压力级别
+    pub fn level(&self) -> PressureLevel {
+        self.level
+    }
 
-        for line in reader.lines() {
-            let line = line?;
-            let parts: Vec<&str> = line.split_whitespace().collect();
-            if parts.len() < 2 {
-                continue;
-            }
+    /// 最近一次检查时swap使用率是否触发压力
+    pub fn is_swap_triggered(&self) -> bool {
+        self.swap_triggered
+    }
 
-            let value = parts[1].parse::<u64>().unwrap_or(0) * 1024; // 转换为字节
-            match parts[0] {
-                "MemTotal:" => stats.total_memory = value,
-                "MemFree:" => stats.free_memory = value,
-                "MemAvailable:" => stats.available_memory = value,
-                "SwapTotal:" => stats.total_swap = value,
-                "SwapFree:" => stats.free_swap = value,
-                "Cached:" => stats.cached_memory = value,
-                _ => {}
+    /// 获取当前内存统计信息
+    ///
+    /// 使用完整的解析器，用于报告；压力检查走 `check_pressure` 中的快速路径
+    pub fn get_memory_stats(&self) -> Result<MemoryStats> {
+        let content = std::fs::read_to_string(self.proc_root.join("meminfo"))
+            .map_err(SystemError::SyscallError)?;
+        let host = MemoryStats::parse(&content);
+
+        match &self.cgroup_dir {
+            Some(dir) => {
+                let stat = CgroupMemStat::read(dir)?;
+                let limit = cgroup::read_memory_limit(dir)?.unwrap_or(host.total_memory);
+                Ok(MemoryStats::from_cgroup(limit, &stat))
             }
+            None => Ok(host),
         }
+    }
+
+    /// /proc/meminfo 中的所有字段，`MemoryStats` 没有的字段（例如 `Dirty`、`HugePages_Total`）也包含在内
+    ///
+    /// 单位见 `parse_raw_meminfo`。总是读取主机的meminfo，容器模式下也是如此
+    pub fn raw_meminfo(&self) -> Result<BTreeMap<String, u64>> {
+        let content = std::fs::read_to_string(self.proc_root.join("meminfo"))
+            .map_err(SystemError::SyscallError)?;
+        Ok(parse_raw_meminfo(&content))
+    }
+
+    /// 当前RSS最高的n个进程，按RSS降序排列
+    ///
+    /// 与OOM评分无关，只回答“谁在占用内存”
+    pub fn top_consumers(&self, n: usize) -> Result<Vec<ProcessSummary>> {
+        let mut processes = crate::linux::proc::list_processes_in(&self.proc_root)?;
+        processes.sort_by_key(|p| std::cmp::Reverse(p.mem_info.vm_rss));
+        processes.truncate(n);
 
-        Ok(stats)
+        Ok(processes.iter().map(ProcessSummary::from).collect())
     }
 
     /// 获取系统内存压力的详细信息
@@ -141,16 +569,44 @@ impl PressureDetector {
                 .map(|start| start.elapsed())
                 .unwrap_or_default(),
             last_check: self.last_pressure_check.elapsed(),
+            load: SystemLoad::read_in(&self.proc_root).ok(),
         })
     }
 }
 
+/// 占用内存最多的进程的概要，用于告警
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessSummary {
+    pub pid: i32,
+    pub name: String,
+    /// 物理内存使用量（字节）
+    pub rss: u64,
+    /// swap使用量（字节）
+    pub swap: u64,
+    /// 内存构成的分类
+    pub profile: MemoryProfile,
+}
+
+impl From<&ProcessInfo> for ProcessSummary {
+    fn from(process: &ProcessInfo) -> Self {
+        Self {
+            pid: process.pid.as_raw(),
+            name: process.name.clone(),
+            rss: process.mem_info.vm_rss * 1024,
+            swap: process.mem_info.vm_swap * 1024,
+            profile: process.memory_profile(),
+        }
+    }
+}
+
 /// 内存压力详细信息
 #[derive(Debug)]
 pub struct PressureInfo {
     pub stats: MemoryStats,
     pub pressure_duration: Duration,
     pub last_check: Duration,
+    /// 系统负载，无法读取 /proc/loadavg 时为None
+    pub load: Option<SystemLoad>,
 }
 
 #[cfg(test)]
@@ -169,12 +625,203 @@ mod tests {
         assert!(stats.free_memory <= stats.total_memory);
     }
 
+    #[test]
+    fn test_parse_meminfo_fixture() {
+        let stats = MemoryStats::parse(include_str!("../../tests/fixtures/proc/meminfo"));
+
+        assert_eq!(stats.total_memory, 6158152 * 1024);
+        assert_eq!(stats.free_memory, 2902724 * 1024);
+        assert_eq!(stats.total_swap, 2097148 * 1024);
+        assert_eq!(stats.free_swap, 1835004 * 1024);
+        assert_eq!(stats.commit_limit, 3079076 * 1024);
+        assert_eq!(stats.committed_as, 387284 * 1024);
+    }
+
+    #[test]
+    fn test_reclaimable_cache() {
+        let stats = MemoryStats::parse(include_str!("../../tests/fixtures/proc/meminfo"));
+        assert_eq!(stats.cached_memory, 2773444 * 1024);
+        assert_eq!(stats.shmem, 9048 * 1024);
+        assert_eq!(stats.slab_reclaimable, 108904 * 1024);
+        assert_eq!(stats.reclaimable_cache, (2773444 + 108904 - 9048) * 1024);
+
+        // 大量tmpfs时Shmem可以占去大部分Cached
+        let stats = MemoryStats::parse(
+            "MemTotal: 8000000 kB\nCached: 3000000 kB\nShmem: 2500000 kB\nSReclaimable: 100000 kB\n",
+        );
+        assert_eq!(stats.cached_memory, 3000000 * 1024);
+        assert_eq!(stats.reclaimable_cache, 600000 * 1024);
+
+        // 没有Shmem和SReclaimable的老内核
+        let stats = MemoryStats::parse("MemTotal: 8000000 kB\nCached: 3000000 kB\n");
+        assert_eq!(stats.reclaimable_cache, 3000000 * 1024);
+
+        // 数值异常时饱和为0而不是回绕
+        let stats = MemoryStats::parse("Cached: 100 kB\nShmem: 200 kB\n");
+        assert_eq!(stats.reclaimable_cache, 0);
+    }
+
+    #[test]
+    fn test_fast_parse_matches_full_parser() {
+        let content = include_str!("../../tests/fixtures/proc/meminfo");
+        let full = MemoryStats::parse(content);
+        let fast = MemoryStats::parse_fast(content.as_bytes());
+
+        assert_eq!(fast.total_memory, full.total_memory);
+        assert_eq!(fast.available_memory, full.available_memory);
+        assert_eq!(fast.free_memory, full.free_memory);
+        assert_eq!(fast.total_swap, full.total_swap);
+        assert_eq!(fast.free_swap, full.free_swap);
+        assert_eq!(fast.cached_memory, full.cached_memory);
+        assert_eq!(fast.commit_limit, full.commit_limit);
+        assert_eq!(fast.committed_as, full.committed_as);
+        assert_eq!(fast.reclaimable_cache, 0);
+
+        // 宿主机的 /proc/meminfo
+        let content = std::fs::read_to_string("/proc/meminfo").unwrap();
+        let full = MemoryStats::parse(&content);
+        let fast = MemoryStats::parse_fast(content.as_bytes());
+        assert_eq!(fast.total_memory, full.total_memory);
+        assert_eq!(fast.available_memory, full.available_memory);
+    }
+
+    #[test]
+    fn test_fast_parse_tolerates_malformed_values() {
+        let stats = MemoryStats::parse_fast(b"MemTotal: 12x4 kB\nMemAvailable:\nSwapTotal:   99999999999999999999 kB\nSwapFree: 8 kB");
+        assert_eq!(stats.total_memory, 0);
+        assert_eq!(stats.available_memory, 0);
+        assert_eq!(stats.total_swap, 0);
+        assert_eq!(stats.free_swap, 8 * 1024);
+    }
+
+    #[test]
+    fn test_raw_meminfo() {
+        let raw = parse_raw_meminfo("MemTotal:       16384 kB\nDirty:  12 kB\nHugePages_Total:       4\nBogus: 1 MB\nbroken line\n");
+        assert_eq!(raw["MemTotal"], 16384 * 1024);
+        assert_eq!(raw["Dirty"], 12 * 1024);
+        // 没有单位的是页数，不做转换
+        assert_eq!(raw["HugePages_Total"], 4);
+        assert_eq!(raw.len(), 3);
+
+        // 宿主机的 /proc/meminfo
+        let raw = PressureDetector::new(None).raw_meminfo().unwrap();
+        let stats = MemoryStats::parse(&std::fs::read_to_string("/proc/meminfo").unwrap());
+        assert_eq!(raw["MemTotal"], stats.total_memory);
+        assert!(raw.len() >= 12, "only {} meminfo fields", raw.len());
+    }
+
+    #[test]
+    fn test_check_pressure_reads_fixture() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("meminfo"), include_str!("../../tests/fixtures/proc/meminfo")).unwrap();
+        let mut detector = PressureDetector::new(None).with_proc_root(root.path());
+
+        assert!(!detector.check_pressure().unwrap());
+        let fast = detector.last_stats().unwrap();
+        let full = detector.get_memory_stats().unwrap();
+        assert_eq!(fast.total_memory, full.total_memory);
+        assert_eq!(fast.available_memory, full.available_memory);
+    }
+
+    #[test]
+    fn test_available_ratio_clamped() {
+        let stats = MemoryStats {
+            total_memory: 1024 * 1024 * 1024,
+            available_memory: 1100 * 1024 * 1024,
+            ..MemoryStats::default()
+        };
+        assert_eq!(stats.available_ratio(), 1.0);
+        assert_eq!(sample(0.25).available_ratio(), 0.25);
+        assert_eq!(MemoryStats::default().available_ratio(), 1.0);
+
+        // 不一致的读数按没有压力处理
+        let mut detector = PressureDetector::new(Some(PressureThresholds {
+            pressure_duration: Duration::ZERO,
+            ..PressureThresholds::default()
+        }));
+        assert!(!detector.update(&stats, Instant::now()));
+        assert_eq!(detector.level(), PressureLevel::Normal);
+    }
+
+    #[test]
+    fn test_commit_ratio_triggers_pressure() {
+        let root = tempfile::tempdir().unwrap();
+        // 空闲内存充足，但已承诺的内存达到CommitLimit的95%
+        std::fs::write(
+            root.path().join("meminfo"),
+            "MemTotal:\t8388608 kB\nMemFree:\t4194304 kB\nMemAvailable:\t6291456 kB\n\
+             CommitLimit:\t8388608 kB\nCommitted_AS:\t7969177 kB\n",
+        ).unwrap();
+        let thresholds = PressureThresholds {
+            pressure_duration: Duration::ZERO,
+            ..Default::default()
+        };
+
+        // 不检查CommitLimit时没有压力
+        let mut detector = PressureDetector::new(Some(thresholds.clone())).with_proc_root(root.path());
+        assert!(!detector.check_pressure().unwrap());
+        assert_eq!(detector.level(), PressureLevel::Normal);
+
+        let mut detector = PressureDetector::new(Some(PressureThresholds {
+            max_commit_ratio: Some(0.9),
+            ..thresholds.clone()
+        }))
+        .with_proc_root(root.path());
+        assert!(detector.check_pressure().unwrap());
+        assert_eq!(detector.level(), PressureLevel::Critical);
+        assert_eq!(detector.last_stats().unwrap().committed_as, 7969177 * 1024);
+
+        // 低于阈值时不触发
+        let mut detector = PressureDetector::new(Some(PressureThresholds {
+            max_commit_ratio: Some(0.99),
+            ..thresholds
+        }))
+        .with_proc_root(root.path());
+        assert!(!detector.check_pressure().unwrap());
+    }
+
+    #[test]
+    fn test_cgroup_pressure_uses_anon_memory() {
+        const GIB: u64 = 1024 * 1024 * 1024;
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("meminfo"), include_str!("../../tests/fixtures/proc/meminfo")).unwrap();
+        let cgroup = tempfile::tempdir().unwrap();
+        std::fs::write(cgroup.path().join("memory.max"), format!("{}\n", 4 * GIB)).unwrap();
+        let thresholds = PressureThresholds {
+            min_free_ratio: 0.2,
+            pressure_duration: Duration::ZERO,
+            ..Default::default()
+        };
+        let mut detector = PressureDetector::new(Some(thresholds))
+            .with_proc_root(root.path())
+            .with_cgroup(cgroup.path());
+
+        // 总占用接近上限，但大部分是页缓存
+        std::fs::write(cgroup.path().join("memory.stat"), format!("anon {}\nfile {}\nkernel 0\n", GIB, 3 * GIB - 1)).unwrap();
+        assert!(!detector.check_pressure().unwrap());
+        let stats = detector.last_stats().unwrap();
+        assert_eq!(stats.total_memory, 4 * GIB);
+        assert_eq!(stats.available_memory, 3 * GIB);
+
+        // 匿名内存接近上限
+        std::fs::write(cgroup.path().join("memory.stat"), format!("anon {}\nfile {}\n", 3 * GIB + GIB / 2, GIB / 4)).unwrap();
+        assert!(detector.check_pressure().unwrap());
+
+        // 没有上限时以主机的MemTotal为总量
+        std::fs::write(cgroup.path().join("memory.max"), "max\n").unwrap();
+        detector.check_pressure().unwrap();
+        let host = MemoryStats::parse(include_str!("../../tests/fixtures/proc/meminfo"));
+        assert_eq!(detector.last_stats().unwrap().total_memory, host.total_memory);
+        assert_eq!(detector.get_memory_stats().unwrap().total_memory, host.total_memory);
+    }
+
     #[test]
     fn test_pressure_detection() {
         let mut detector = PressureDetector::new(Some(PressureThresholds {
             min_free_ratio: 0.99, // 设置一个极高的阈值来模拟压力
             max_swap_ratio: 0.0,
             pressure_duration: Duration::from_millis(100),
+            ..PressureThresholds::default()
         }));
 
         // 第一次检查应该开始计时但不触发
@@ -193,6 +840,7 @@ mod tests {
             min_free_ratio: 0.0, // 设置一个极低的阈值
             max_swap_ratio: 1.0,
             pressure_duration: Duration::from_millis(100),
+            ..PressureThresholds::default()
         }));
 
         // 在正常阈值下不应该检测到压力
@@ -200,5 +848,177 @@ mod tests {
         
         // 压力开始时间应该被重置
         assert!(detector.pressure_start.is_none());
+        assert_eq!(detector.level(), PressureLevel::Normal);
+    }
+
+    #[test]
+    fn test_pressure_level_critical() {
+        let mut detector = PressureDetector::new(Some(PressureThresholds {
+            min_free_ratio: 0.99,
+            max_swap_ratio: 0.0,
+            pressure_duration: Duration::from_secs(60),
+            ..PressureThresholds::default()
+        }));
+
+        // 尚未达到持续时间，但级别应立即反映为Critical
+        assert!(!detector.check_pressure().unwrap());
+        assert_eq!(detector.level(), PressureLevel::Critical);
+    }
+
+    fn sample(available_ratio: f64) -> MemoryStats {
+        let total = 1024 * 1024 * 1024;
+        MemoryStats {
+            total_memory: total,
+            free_memory: 0,
+            available_memory: (total as f64 * available_ratio) as u64,
+            total_swap: 0,
+            free_swap: 0,
+            cached_memory: 0,
+            ..MemoryStats::default()
+        }
+    }
+
+    #[test]
+    fn test_single_clean_sample_does_not_reset_timer() {
+        let mut detector = PressureDetector::new(Some(PressureThresholds {
+            pressure_duration: Duration::from_secs(10),
+            reset_tolerance: 2,
+            ..PressureThresholds::default()
+        }));
+        let start = Instant::now();
+
+        assert!(!detector.update(&sample(0.01), start));
+        assert!(!detector.update(&sample(0.01), start + Duration::from_secs(4)));
+        // 压力期间插入一次正常读数
+        assert!(!detector.update(&sample(0.50), start + Duration::from_secs(5)));
+        assert_eq!(detector.pressure_start, Some(start));
+
+        // 计时从最初的开始时间继续，而不是从头开始
+        assert!(detector.update(&sample(0.01), start + Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn test_consecutive_clean_samples_reset_timer() {
+        let mut detector = PressureDetector::new(Some(PressureThresholds {
+            pressure_duration: Duration::from_secs(10),
+            reset_tolerance: 2,
+            ..PressureThresholds::default()
+        }));
+        let start = Instant::now();
+
+        assert!(!detector.update(&sample(0.01), start));
+        assert!(!detector.update(&sample(0.50), start + Duration::from_secs(1)));
+        assert!(!detector.update(&sample(0.50), start + Duration::from_secs(2)));
+        assert!(detector.pressure_start.is_none());
+
+        // 重新进入压力后从新的时间点开始计时
+        assert!(!detector.update(&sample(0.01), start + Duration::from_secs(10)));
+        assert_eq!(detector.pressure_start, Some(start + Duration::from_secs(10)));
+    }
+
+    fn swap_sample(swap_used_ratio: f64) -> MemoryStats {
+        let total_swap = 1024 * 1024 * 1024;
+        MemoryStats {
+            total_swap,
+            free_swap: (total_swap as f64 * (1.0 - swap_used_ratio)) as u64,
+            ..sample(0.50)
+        }
+    }
+
+    #[test]
+    fn test_swap_exit_ratio_prevents_flapping() {
+        let thresholds = PressureThresholds {
+            max_swap_ratio: 0.80,
+            swap_exit_ratio: Some(0.60),
+            pressure_duration: Duration::from_secs(10),
+            reset_tolerance: 1,
+            ..PressureThresholds::default()
+        };
+        let mut detector = PressureDetector::new(Some(thresholds.clone()));
+        let start = Instant::now();
+
+        // 低于进入比例时不触发
+        detector.update(&swap_sample(0.70), start);
+        assert!(!detector.is_swap_triggered());
+
+        // 进入后在两个比例之间来回波动，保持触发且计时不中断
+        let mut now = start;
+        for (i, ratio) in [0.85, 0.70, 0.82, 0.65, 0.79, 0.61].into_iter().enumerate() {
+            now = start + Duration::from_secs(i as u64 + 1);
+            detector.update(&swap_sample(ratio), now);
+            assert!(detector.is_swap_triggered(), "swap trigger cleared at {}", ratio);
+            assert_eq!(detector.level(), PressureLevel::Critical);
+        }
+        assert_eq!(detector.pressure_start, Some(start + Duration::from_secs(1)));
+
+        // 低于解除比例后解除
+        detector.update(&swap_sample(0.55), now + Duration::from_secs(1));
+        assert!(!detector.is_swap_triggered());
+        assert!(detector.pressure_start.is_none());
+        detector.update(&swap_sample(0.70), now + Duration::from_secs(2));
+        assert!(!detector.is_swap_triggered());
+
+        // 没有解除比例时同样的波动会反复切换
+        let mut detector = PressureDetector::new(Some(PressureThresholds {
+            swap_exit_ratio: None,
+            ..thresholds
+        }));
+        let flips = [0.85, 0.70, 0.82, 0.65].into_iter()
+            .map(|ratio| {
+                detector.update(&swap_sample(ratio), start);
+                detector.is_swap_triggered()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(flips, [true, false, true, false]);
+    }
+
+    #[test]
+    fn test_low_load_extends_pressure_duration() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("meminfo"), "MemTotal:\t65536 kB\nMemFree:\t1024 kB\nMemAvailable:\t1024 kB\n").unwrap();
+        std::fs::write(root.path().join("loadavg"), "0.00 0.01 0.05 1/300 4000\n").unwrap();
+        let mut detector = PressureDetector::new(Some(PressureThresholds {
+            pressure_duration: Duration::from_millis(200),
+            low_load_per_cpu: Some(0.5),
+            ..Default::default()
+        }))
+        .with_proc_root(root.path());
+
+        // 空闲系统上压力需要持续两倍的时间
+        assert!(!detector.check_pressure().unwrap());
+        thread::sleep(Duration::from_millis(250));
+        assert!(!detector.check_pressure().unwrap());
+        assert_eq!(detector.last_load().unwrap().runnable, 1);
+
+        // 高负载时使用原来的阈值
+        std::fs::write(root.path().join("loadavg"), "1000.00 900.00 800.00 64/300 4000\n").unwrap();
+        assert!(detector.check_pressure().unwrap());
+
+        let info = detector.get_pressure_info().unwrap();
+        assert_eq!(info.load.unwrap().load1, 1000.0);
+    }
+
+    #[test]
+    fn test_top_consumers() {
+        let root = tempfile::tempdir().unwrap();
+        for (pid, rss_kb) in [(101, 2048), (102, 512), (103, 8192), (104, 1024), (105, 4096)] {
+            let dir = root.path().join(pid.to_string());
+            std::fs::create_dir(&dir).unwrap();
+            std::fs::write(
+                dir.join("status"),
+                format!("Name:\tproc-{}\nState:\tS (sleeping)\nVmRSS:\t{} kB\n", pid, rss_kb),
+            ).unwrap();
+        }
+        let detector = PressureDetector::new(None).with_proc_root(root.path());
+
+        let top = detector.top_consumers(3).unwrap();
+        let pids: Vec<i32> = top.iter().map(|p| p.pid).collect();
+        assert_eq!(pids, vec![103, 105, 101]);
+        assert_eq!(top[0].name, "proc-103");
+        assert_eq!(top[0].rss, 8192 * 1024);
+        assert!(top.windows(2).all(|w| w[0].rss >= w[1].rss));
+
+        // n大于进程数时返回全部进程
+        assert_eq!(detector.top_consumers(10).unwrap().len(), 5);
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,401 @@
+//! 记录运行时的内存状态，并离线回放以调整阈值和权重
+//!
+//! `Recorder` 定期把内存统计、vmstat变化率和RSS最高的一部分进程写成一行JSON，
+//! 追加到记录文件中。`replay` 把记录按原来的时间间隔送入 `PressureDetector`，
+//! 并在每个采样点用任意的配置和评分器做出与 `KillerConfig::evaluate` 相同的选择，
+//! 回答"这份配置在那天会在什么时候、终止哪个进程"
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use serde::{Deserialize, Serialize};
+use crate::ffi::types::{SystemError, Result};
+use crate::linux::proc::{list_processes_in, read_oom_scores_in, DEFAULT_PROC_ROOT};
+use crate::linux::proc_stat::ProcessStat;
+use crate::linux::vmstat::{VmStat, VmStatRates};
+use crate::oom::killer::KillerConfig;
+use crate::oom::pressure::{MemoryStats, PressureDetector, PressureLevel};
+use crate::oom::audit::Decision;
+use crate::oom::scenario::{decide, ScenarioProcess};
+use crate::oom::score::OOMScorer;
+
+/// 记录器的配置
+#[derive(Debug, Clone)]
+pub struct RecorderConfig {
+    /// 追加写入的记录文件
+    pub path: PathBuf,
+    /// 两次记录之间的最小间隔
+    pub interval: Duration,
+    /// 每次记录RSS最高的进程数
+    pub max_processes: usize,
+    /// 记录文件的最大字节数，达到后停止记录
+    pub max_bytes: u64,
+}
+
+impl RecorderConfig {
+    /// 使用默认参数创建写入指定文件的配置
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            interval: Duration::from_secs(10),
+            max_processes: 32,
+            max_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+/// 记录文件中的一行
+///
+/// 进程只包含评分和过滤需要的字段，格式与场景文件中的进程相同，
+/// 任何一帧都可以直接改写成场景
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    pub timestamp_ms: u64,
+    /// 内存统计（字节）
+    pub stats: MemoryStats,
+    /// 与上一帧之间的vmstat变化率，第一帧没有
+    #[serde(default)]
+    pub vmstat_rates: Option<VmStatRates>,
+    /// RSS最高的进程，按RSS降序排列
+    pub processes: Vec<ScenarioProcess>,
+}
+
+/// 定期记录内存状态的记录器
+#[derive(Debug)]
+pub struct Recorder {
+    config: RecorderConfig,
+    proc_root: PathBuf,
+    file: Option<File>,
+    written: u64,
+    full: bool,
+    last_recorded: Option<Instant>,
+    last_vmstat: Option<(Instant, VmStat)>,
+}
+
+impl Recorder {
+    /// 创建新的记录器，记录文件在第一次写入时打开
+    pub fn new(config: RecorderConfig) -> Self {
+        Self {
+            config,
+            proc_root: PathBuf::from(DEFAULT_PROC_ROOT),
+            file: None,
+            written: 0,
+            full: false,
+            last_recorded: None,
+            last_vmstat: None,
+        }
+    }
+
+    /// 使用指定的proc根目录读取vmstat和进程信息
+    pub fn with_proc_root(mut self, proc_root: impl Into<PathBuf>) -> Self {
+        self.proc_root = proc_root.into();
+        self
+    }
+
+    /// 距上次记录是否已经过了配置的间隔，记录文件已满时为false
+    pub fn due(&self) -> bool {
+        !self.full && self.last_recorded
+            .map(|last| last.elapsed() >= self.config.interval)
+            .unwrap_or(true)
+    }
+
+    /// 收集一帧并追加到记录文件
+    ///
+    /// # 返回值
+    ///
+    /// 写入了一帧时返回 true；记录文件已达到 `max_bytes` 时返回 false
+    pub fn record(&mut self, stats: &MemoryStats) -> Result<bool> {
+        if self.full {
+            return Ok(false);
+        }
+        self.last_recorded = Some(Instant::now());
+
+        let frame = self.capture(stats);
+        let mut line = serde_json::to_vec(&frame)
+            .map_err(|e| SystemError::SyscallError(io::Error::new(io::ErrorKind::InvalidData, e)))?;
+        line.push(b'\n');
+
+        let file = match self.file.as_mut() {
+            Some(file) => file,
+            None => {
+                let file = OpenOptions::new().create(true).append(true).open(&self.config.path)?;
+                self.written = file.metadata()?.len();
+                self.file.insert(file)
+            }
+        };
+        if self.written + line.len() as u64 > self.config.max_bytes {
+            log::warn!(
+                "Recording {} reached {} bytes, recording stopped",
+                self.config.path.display(),
+                self.config.max_bytes
+            );
+            self.full = true;
+            return Ok(false);
+        }
+
+        // 一次写入整行，进程在写入中途退出时最多留下一个不完整的末行
+        file.write_all(&line)?;
+        self.written += line.len() as u64;
+
+        Ok(true)
+    }
+
+    /// 收集一帧：vmstat变化率和RSS最高的进程
+    fn capture(&mut self, stats: &MemoryStats) -> RecordedFrame {
+        let vmstat_rates = self.observe_vmstat();
+
+        let mut processes = list_processes_in(&self.proc_root).unwrap_or_default();
+        // 内核线程没有RSS，不会成为候选
+        processes.retain(|process| process.mem_info.vm_rss > 0);
+        processes.sort_by_key(|process| std::cmp::Reverse(process.mem_info.vm_rss));
+        processes.truncate(self.config.max_processes);
+
+        let processes = processes.into_iter()
+            .filter_map(|process| {
+                // 在枚举之后退出的进程直接跳过
+                let (_, oom_score_adj) = read_oom_scores_in(&self.proc_root, process.pid).ok()?;
+                let runtime_secs = ProcessStat::from_pid_in(&self.proc_root, process.pid)
+                    .ok()
+                    .map(|stat| stat.running_time_in(&self.proc_root).as_secs());
+
+                Some(ScenarioProcess {
+                    pid: process.pid.as_raw(),
+                    name: process.name,
+                    state: process.state,
+                    ppid: process.ppid,
+                    uid: process.uid,
+                    rss_kb: process.mem_info.vm_rss,
+                    swap_kb: process.mem_info.vm_swap,
+                    oom_score_adj,
+                    runtime_secs,
+                })
+            })
+            .collect();
+
+        RecordedFrame {
+            timestamp_ms: unix_millis(),
+            stats: stats.clone(),
+            vmstat_rates,
+            processes,
+        }
+    }
+
+    /// 采样vmstat，返回相对于上一次采样的变化率
+    fn observe_vmstat(&mut self) -> Option<VmStatRates> {
+        let content = fs::read_to_string(self.proc_root.join("vmstat")).ok()?;
+        let current = VmStat::parse(&content);
+        let now = Instant::now();
+
+        let rates = self.last_vmstat.as_ref()
+            .map(|(then, previous)| current.rates_since(previous, now.duration_since(*then)));
+        self.last_vmstat = Some((now, current));
+        rates
+    }
+}
+
+/// 读取记录文件
+///
+/// 记录进程在写入中途退出时末行可能不完整，这样的末行被忽略；
+/// 其他无法解析的行返回错误
+pub fn load_recording(path: &Path) -> Result<Vec<RecordedFrame>> {
+    let content = fs::read_to_string(path)?;
+    let lines: Vec<&str> = content.lines().filter(|line| !line.trim().is_empty()).collect();
+
+    let mut frames = Vec::with_capacity(lines.len());
+    for (index, line) in lines.iter().enumerate() {
+        match serde_json::from_str(line) {
+            Ok(frame) => frames.push(frame),
+            Err(_) if index + 1 == lines.len() && !content.ends_with('\n') => {
+                log::debug!("Ignoring incomplete last frame in {}", path.display());
+            }
+            Err(e) => {
+                return Err(SystemError::SyscallError(io::Error::new(io::ErrorKind::InvalidData, e)));
+            }
+        }
+    }
+
+    Ok(frames)
+}
+
+/// 回放中一个采样点的结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReplayPoint {
+    pub timestamp_ms: u64,
+    /// 这一帧的压力级别
+    pub level: PressureLevel,
+    /// 压力在这一帧开始满足持续时间要求，即这份配置会在此时开始终止进程
+    pub triggered: bool,
+    /// 这一帧的决定，压力没有持续足够时间时为 `Decision::no_pressure`
+    pub decision: Decision,
+}
+
+/// 用 `config` 的压力阈值和选择器配置、以及 `scorer` 的权重回放记录
+///
+/// 压力持续时间按帧的时间戳计算，因此回放结果与记录时的检查间隔无关。
+/// 每一帧独立做出选择：回放不模拟终止的效果和 `min_kill_interval`，
+/// 被选中的进程在后续帧中仍然存在
+pub fn replay(frames: &[RecordedFrame], config: &KillerConfig, scorer: &OOMScorer) -> Vec<ReplayPoint> {
+    let mut detector = PressureDetector::new(Some(config.pressure.clone()));
+    let base = Instant::now();
+    let first_ms = frames.first().map(|frame| frame.timestamp_ms).unwrap_or(0);
+    let mut was_sustained = false;
+
+    frames.iter()
+        .map(|frame| {
+            let offset = Duration::from_millis(frame.timestamp_ms.saturating_sub(first_ms));
+            let sustained = detector.observe(frame.stats.clone(), base + offset);
+            let decision = if sustained {
+                decide(&config.selector, scorer, &frame.stats, detector.level(), &frame.processes)
+            } else {
+                Decision::no_pressure(detector.level())
+            };
+            let triggered = sustained && !was_sustained;
+            was_sustained = sustained;
+
+            ReplayPoint {
+                timestamp_ms: frame.timestamp_ms,
+                level: detector.level(),
+                triggered,
+                decision,
+            }
+        })
+        .collect()
+}
+
+/// 读取记录文件并回放
+pub fn replay_file(path: &Path, config: &KillerConfig, scorer: &OOMScorer) -> Result<Vec<ReplayPoint>> {
+    Ok(replay(&load_recording(path)?, config, scorer))
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffi::types::ProcessId;
+    use crate::oom::pressure::PressureThresholds;
+    use crate::oom::selector::SelectorConfig;
+
+    /// 使进程通过严格过滤的RSS（选择器把kB值与字节阈值比较）
+    const LARGE_RSS_KB: u64 = 256 * 1024 * 1024;
+
+    const GIB: u64 = 1024 * 1024 * 1024;
+
+    fn stats(available: u64) -> MemoryStats {
+        MemoryStats {
+            total_memory: 8 * GIB,
+            free_memory: available / 2,
+            available_memory: available,
+            ..MemoryStats::default()
+        }
+    }
+
+    fn process(pid: i32, name: &str, rss_kb: u64, oom_score_adj: i32) -> ScenarioProcess {
+        ScenarioProcess {
+            pid,
+            name: name.to_string(),
+            state: "S (sleeping)".to_string(),
+            ppid: 1,
+            uid: 1000,
+            rss_kb,
+            swap_kb: 0,
+            oom_score_adj,
+            runtime_secs: Some(3600),
+        }
+    }
+
+    /// 每秒一帧：2秒充足、4秒压力、1秒恢复
+    fn frames() -> Vec<RecordedFrame> {
+        let processes = vec![
+            process(1001, "postgres", 4 * LARGE_RSS_KB, -500),
+            process(1002, "batch-job", LARGE_RSS_KB, 0),
+        ];
+        let available = [4 * GIB, 4 * GIB, GIB / 10, GIB / 10, GIB / 10, GIB / 10, 4 * GIB];
+
+        available.iter()
+            .enumerate()
+            .map(|(second, &available)| RecordedFrame {
+                timestamp_ms: 1_700_000_000_000 + second as u64 * 1000,
+                stats: stats(available),
+                vmstat_rates: None,
+                processes: processes.clone(),
+            })
+            .collect()
+    }
+
+    fn config(pressure_duration: Duration) -> KillerConfig {
+        KillerConfig {
+            pressure: PressureThresholds {
+                pressure_duration,
+                ..PressureThresholds::default()
+            },
+            selector: SelectorConfig {
+                min_candidates: 1,
+                ..SelectorConfig::default()
+            },
+            ..KillerConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_replay_reports_trigger_and_victim() {
+        let points = replay(&frames(), &config(Duration::from_secs(2)), &OOMScorer::new());
+        assert_eq!(points.len(), 7);
+
+        // 压力从第2秒开始，按帧的时间戳在第4秒满足2秒的持续时间
+        assert_eq!(points[2].level, PressureLevel::Critical);
+        assert_eq!(points[2].decision, Decision::no_pressure(PressureLevel::Critical));
+        let triggered: Vec<usize> = (0..points.len()).filter(|&i| points[i].triggered).collect();
+        assert_eq!(triggered, vec![4]);
+        assert!(points[5].decision.victim().is_some());
+        assert!(!points[5].triggered);
+        assert_eq!(points[6].level, PressureLevel::Normal);
+        assert_eq!(points[6].decision, Decision::no_pressure(PressureLevel::Normal));
+
+        // 更短的持续时间更早触发
+        let points = replay(&frames(), &config(Duration::ZERO), &OOMScorer::new());
+        assert!(points[2].triggered);
+    }
+
+    #[test]
+    fn test_replay_with_candidate_weights() {
+        let frames = frames();
+        // 以内存为主时数据库占用4倍的内存，尽管有-500的oom_score_adj仍然被选中
+        let memory_heavy = OOMScorer::new().with_weights(1.0, 0.0, 0.0);
+        let points = replay(&frames, &config(Duration::ZERO), &memory_heavy);
+        assert_eq!(points[2].decision.victim(), ProcessId::new(1001));
+
+        // 只看oom_score_adj时选择批处理任务
+        let adj_only = OOMScorer::new().with_weights(0.0, 0.0, 1.0);
+        let points = replay(&frames, &config(Duration::ZERO), &adj_only);
+        assert_eq!(points[2].decision.victim(), ProcessId::new(1002));
+    }
+
+    #[test]
+    fn test_load_recording_ignores_incomplete_last_frame() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("recording.jsonl");
+        let mut content = String::new();
+        for frame in &frames()[..2] {
+            content.push_str(&serde_json::to_string(frame).unwrap());
+            content.push('\n');
+        }
+        let complete = content.clone();
+        content.push_str(r#"{"timestamp_ms": 1700000002000, "stats": {"total_m"#);
+        fs::write(&path, &content).unwrap();
+
+        let frames = load_recording(&path).unwrap();
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[1].processes[0].name, "postgres");
+
+        // 中间损坏的行是错误
+        fs::write(&path, format!("not json\n{}", complete)).unwrap();
+        assert!(matches!(load_recording(&path), Err(SystemError::SyscallError(_))));
+    }
+}
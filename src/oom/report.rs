@@ -0,0 +1,159 @@
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+use crate::oom::killer::KillerStatus;
+
+/// 可序列化的运行状态，时刻以RFC3339（UTC）字符串表示
+///
+/// `KillerStatus` 中的 `Instant` 只在本进程内有意义；JSON、指标和命令行输出使用这个报告
+#[derive(Debug, Clone, Serialize)]
+pub struct KillerStatusReport {
+    /// 最近一次终止的时刻，还没有终止过时为None
+    pub last_kill_time: Option<String>,
+    pub running_since: String,
+    pub uptime_seconds: f64,
+    pub total_kills: u64,
+    pub estimated_reclaimed: u64,
+    pub confirmed_reclaimed: u64,
+    /// 最近一轮检查的耗时（毫秒）
+    pub last_cycle_ms: Option<f64>,
+    pub average_cycle_ms: f64,
+    pub effective_interval_ms: f64,
+    pub error_count: u64,
+    pub events_dropped: u64,
+}
+
+impl KillerStatusReport {
+    /// 以当前时刻为基准转换运行状态
+    pub fn new(status: &KillerStatus) -> Self {
+        Self::at(status, Instant::now(), SystemTime::now())
+    }
+
+    /// 以 `now`（同一时刻的 `Instant` 和 `SystemTime`）为基准转换运行状态
+    pub fn at(status: &KillerStatus, now: Instant, wall_now: SystemTime) -> Self {
+        let to_wall = |instant: Instant| {
+            let wall = wall_now
+                .checked_sub(now.saturating_duration_since(instant))
+                .unwrap_or(UNIX_EPOCH);
+            format_rfc3339(wall)
+        };
+
+        Self {
+            last_kill_time: status.last_kill_time.map(to_wall),
+            running_since: to_wall(status.running_since),
+            uptime_seconds: now.saturating_duration_since(status.running_since).as_secs_f64(),
+            total_kills: status.total_kills,
+            estimated_reclaimed: status.estimated_reclaimed,
+            confirmed_reclaimed: status.confirmed_reclaimed,
+            last_cycle_ms: status.last_cycle_duration.map(millis),
+            average_cycle_ms: millis(status.average_cycle_duration),
+            effective_interval_ms: millis(status.effective_interval),
+            error_count: status.error_count,
+            events_dropped: status.events_dropped,
+        }
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// 格式化为毫秒精度的RFC3339 UTC时间，例如 `2024-03-01T12:00:00.250Z`
+///
+/// 早于1970年的时刻按1970-01-01处理
+pub fn format_rfc3339(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let seconds_of_day = secs % 86400;
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        seconds_of_day / 3600,
+        seconds_of_day % 3600 / 60,
+        seconds_of_day % 60,
+        since_epoch.subsec_millis(),
+    )
+}
+
+/// 1970-01-01起的天数转换为公历日期（Howard Hinnant 的 civil_from_days 算法）
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oom::killer::OOMKiller;
+
+    /// 检查 `YYYY-MM-DDTHH:MM:SS.mmmZ` 格式及各字段的范围
+    fn is_rfc3339(value: &str) -> bool {
+        let bytes = value.as_bytes();
+        let digits = |range: std::ops::Range<usize>| -> Option<u32> {
+            value.get(range.clone())
+                .filter(|s| s.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|s| s.parse().ok())
+        };
+        bytes.len() == 24
+            && bytes[4] == b'-' && bytes[7] == b'-' && bytes[10] == b'T'
+            && bytes[13] == b':' && bytes[16] == b':' && bytes[19] == b'.' && bytes[23] == b'Z'
+            && digits(0..4).is_some()
+            && digits(5..7).is_some_and(|m| (1..=12).contains(&m))
+            && digits(8..10).is_some_and(|d| (1..=31).contains(&d))
+            && digits(11..13).is_some_and(|h| h < 24)
+            && digits(14..16).is_some_and(|m| m < 60)
+            && digits(17..19).is_some_and(|s| s < 60)
+            && digits(20..23).is_some()
+    }
+
+    #[test]
+    fn test_format_rfc3339() {
+        assert_eq!(format_rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00.000Z");
+        // 闰日
+        assert_eq!(
+            format_rfc3339(UNIX_EPOCH + Duration::from_millis(951_827_696_789)),
+            "2000-02-29T12:34:56.789Z"
+        );
+        assert_eq!(
+            format_rfc3339(UNIX_EPOCH + Duration::from_secs(1_735_689_599)),
+            "2024-12-31T23:59:59.000Z"
+        );
+    }
+
+    #[test]
+    fn test_status_report() {
+        let killer = OOMKiller::new(None);
+        std::thread::sleep(Duration::from_millis(10));
+        let report = killer.status_report();
+
+        assert!(is_rfc3339(&report.running_since), "{}", report.running_since);
+        assert!(report.uptime_seconds > 0.0);
+        assert_eq!(report.last_kill_time, None);
+
+        // 固定的基准时刻：10秒前启动，2秒前终止
+        let now = Instant::now();
+        let wall_now = UNIX_EPOCH + Duration::from_secs(1_700_000_010);
+        let mut status = killer.get_status();
+        status.running_since = now - Duration::from_secs(10);
+        status.last_kill_time = Some(now - Duration::from_secs(2));
+        let report = KillerStatusReport::at(&status, now, wall_now);
+        assert_eq!(report.running_since, "2023-11-14T22:13:20.000Z");
+        assert_eq!(report.last_kill_time.as_deref(), Some("2023-11-14T22:13:28.000Z"));
+        assert_eq!(report.uptime_seconds, 10.0);
+
+        let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["running_since"], "2023-11-14T22:13:20.000Z");
+    }
+}
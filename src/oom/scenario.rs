@@ -0,0 +1,316 @@
+//! 用记录的场景测试终止策略
+//!
+//! 场景是某一时刻的内存统计和进程列表，以JSON保存在版本库中。
+//! `KillerConfig::evaluate` 不读取 /proc，用与在线选择相同的压力阈值、
+//! 过滤条件和评分给出决定，CI可以据此断言"配置X在场景Y中终止进程Z"
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use crate::ffi::types::{ProcessId, SystemError, Result};
+use crate::linux::proc::{Completeness, ProcessInfo, ProcessMemInfo};
+use crate::oom::audit::{Decision, DecisionCandidate, DecisionOutcome};
+use crate::oom::killer::KillerConfig;
+use crate::oom::pressure::{MemoryStats, PressureLevel};
+use crate::oom::score::OOMScorer;
+use crate::oom::selector::SelectorConfig;
+
+/// 一个记录的场景
+///
+/// JSON格式：
+///
+/// ```json
+/// {
+///   "stats": {"total_memory": 8589934592, "free_memory": 104857600, "available_memory": 209715200,
+///             "total_swap": 0, "free_swap": 0, "cached_memory": 52428800},
+///   "processes": [{"pid": 1001, "name": "java", "rss_kb": 4194304, "oom_score_adj": 0, "runtime_secs": 3600}],
+///   "expected_victim": 1001
+/// }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+pub struct Scenario {
+    /// 内存统计（字节）
+    pub stats: MemoryStats,
+    /// 场景中的进程
+    pub processes: Vec<ScenarioProcess>,
+    /// 期望终止的进程，`None` 表示期望不终止任何进程
+    #[serde(default)]
+    pub expected_victim: Option<i32>,
+}
+
+/// 场景中的一个进程，未给出的字段使用普通用户进程的值
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScenarioProcess {
+    pub pid: i32,
+    pub name: String,
+    #[serde(default = "default_state")]
+    pub state: String,
+    #[serde(default = "default_ppid")]
+    pub ppid: i32,
+    #[serde(default = "default_uid")]
+    pub uid: u32,
+    #[serde(default)]
+    pub rss_kb: u64,
+    #[serde(default)]
+    pub swap_kb: u64,
+    #[serde(default)]
+    pub oom_score_adj: i32,
+    /// 运行时间（秒），未给出时按无法读取stat处理
+    #[serde(default)]
+    pub runtime_secs: Option<u64>,
+}
+
+fn default_state() -> String {
+    "S (sleeping)".to_string()
+}
+
+fn default_ppid() -> i32 {
+    1
+}
+
+fn default_uid() -> u32 {
+    1000
+}
+
+impl ScenarioProcess {
+    /// 转换为选择器使用的进程信息，pid无效时为None
+    fn to_process_info(&self) -> Option<ProcessInfo> {
+        Some(ProcessInfo {
+            pid: ProcessId::new(self.pid)?,
+            name: self.name.clone(),
+            state: self.state.clone(),
+            ppid: self.ppid,
+            uid: self.uid,
+            mem_info: ProcessMemInfo {
+                vm_rss: self.rss_kb,
+                vm_swap: self.swap_kb,
+                oom_score_adj: self.oom_score_adj,
+                ..ProcessMemInfo::default()
+            },
+            exe_name: None,
+            limits: None,
+            cgroup_memory_low: None,
+            memory_cgroup: None,
+            completeness: Completeness::COMPLETE,
+        })
+    }
+}
+
+impl Scenario {
+    /// 解析JSON格式的场景，所有pid都必须有效
+    pub fn from_json(content: &str) -> Result<Self> {
+        let scenario: Scenario = serde_json::from_str(content)
+            .map_err(|e| SystemError::SyscallError(io::Error::new(io::ErrorKind::InvalidData, e)))?;
+
+        let pids = scenario.processes.iter()
+            .map(|process| process.pid)
+            .chain(scenario.expected_victim);
+        for pid in pids {
+            if ProcessId::new(pid).is_none() {
+                return Err(SystemError::InvalidPid(pid));
+            }
+        }
+
+        Ok(scenario)
+    }
+
+    /// 读取JSON格式的场景文件
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::from_json(&fs::read_to_string(path)?)
+    }
+}
+
+impl KillerConfig {
+    /// 在记录的场景上运行这份配置的压力判断和进程选择
+    ///
+    /// 场景被视为压力已经持续了 `pressure.pressure_duration` 之后的一次采样。
+    /// 评分权重与在线选择一样来自环境变量
+    pub fn evaluate(&self, scenario: &Scenario) -> Decision {
+        let level = self.pressure.level(&scenario.stats);
+        if level != PressureLevel::Critical {
+            return Decision::no_pressure(level);
+        }
+
+        decide(&self.selector, &OOMScorer::new(), &scenario.stats, level, &scenario.processes)
+    }
+}
+
+/// 假定压力已经持续，在给定的进程中做出选择
+pub(crate) fn decide(
+    selector: &SelectorConfig,
+    scorer: &OOMScorer,
+    stats: &MemoryStats,
+    level: PressureLevel,
+    processes: &[ScenarioProcess],
+) -> Decision {
+    let processes: Vec<(ProcessInfo, Option<Duration>)> = processes.iter()
+        .filter_map(|process| {
+            let runtime = process.runtime_secs.map(Duration::from_secs);
+            process.to_process_info().map(|info| (info, runtime))
+        })
+        .collect();
+
+    let selected = selector.select_among(
+        scorer,
+        stats,
+        processes.iter().map(|(info, runtime)| (info, *runtime)),
+    );
+    let candidate = selected.map(|(details, relaxed)| DecisionCandidate {
+        pid: details.process.pid.as_raw(),
+        name: details.process.name,
+        score: details.total_score,
+        relaxed,
+    });
+    Decision {
+        level,
+        sustained: true,
+        outcome: if candidate.is_some() { DecisionOutcome::Killed } else { DecisionOutcome::NoCandidate },
+        candidate,
+    }
+}
+
+/// 一个场景文件的运行结果
+#[derive(Debug, Clone)]
+pub struct ScenarioOutcome {
+    pub path: PathBuf,
+    pub decision: Decision,
+    pub expected_victim: Option<ProcessId>,
+}
+
+impl ScenarioOutcome {
+    /// 决定是否与场景中记录的期望一致
+    pub fn passed(&self) -> bool {
+        self.decision.victim() == self.expected_victim
+    }
+}
+
+/// 读取目录中所有 `.json` 场景文件，按文件名排序
+pub fn load_scenarios(dir: &Path) -> Result<Vec<(PathBuf, Scenario)>> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().map(|ext| ext == "json").unwrap_or(false) {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+
+    paths.into_iter()
+        .map(|path| Scenario::load(&path).map(|scenario| (path, scenario)))
+        .collect()
+}
+
+/// 用 `config` 运行目录中的所有场景
+///
+/// 任何一个文件无法解析时返回错误；是否符合期望由 `ScenarioOutcome::passed` 判断
+pub fn run_scenarios(config: &KillerConfig, dir: &Path) -> Result<Vec<ScenarioOutcome>> {
+    Ok(load_scenarios(dir)?
+        .into_iter()
+        .map(|(path, scenario)| ScenarioOutcome {
+            decision: config.evaluate(&scenario),
+            expected_victim: scenario.expected_victim.and_then(ProcessId::new),
+            path,
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 使进程通过严格过滤的RSS（选择器把kB值与字节阈值比较）
+    const LARGE_RSS_KB: u64 = 256 * 1024 * 1024;
+
+    /// 8GiB内存、可用2%的压力场景
+    fn pressured_stats() -> &'static str {
+        r#"{"total_memory": 8589934592, "free_memory": 85899345, "available_memory": 171798691,
+            "total_swap": 0, "free_swap": 0, "cached_memory": 0}"#
+    }
+
+    fn config() -> KillerConfig {
+        KillerConfig {
+            selector: SelectorConfig {
+                min_candidates: 1,
+                ..SelectorConfig::default()
+            },
+            ..KillerConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_scenarios_select_expected_victims() {
+        let dir = tempfile::tempdir().unwrap();
+        // 数据库占用一半内存、得分最高，但设置了-400的oom_score_adj，配置的下限把它排除在外
+        fs::write(dir.path().join("01-protected-database.json"), format!(r#"{{
+            "stats": {},
+            "processes": [
+                {{"pid": 1001, "name": "postgres", "rss_kb": {}, "oom_score_adj": -400, "runtime_secs": 86400}},
+                {{"pid": 1002, "name": "batch-job", "rss_kb": {}, "runtime_secs": 86400}}
+            ],
+            "expected_victim": 1002
+        }}"#, pressured_stats(), 16 * LARGE_RSS_KB, LARGE_RSS_KB)).unwrap();
+        // 同样的进程，但内存充足
+        fs::write(dir.path().join("02-no-pressure.json"), r#"{
+            "stats": {"total_memory": 8589934592, "free_memory": 4294967296, "available_memory": 4294967296,
+                      "total_swap": 0, "free_swap": 0, "cached_memory": 0},
+            "processes": [{"pid": 1002, "name": "batch-job", "rss_kb": 268435456}]
+        }"#).unwrap();
+        fs::write(dir.path().join("notes.txt"), "not a scenario").unwrap();
+
+        let protective = KillerConfig {
+            selector: SelectorConfig {
+                min_oom_score_adj: -300,
+                ..config().selector
+            },
+            ..config()
+        };
+        let outcomes = run_scenarios(&protective, dir.path()).unwrap();
+        assert_eq!(outcomes.len(), 2);
+        assert!(outcomes.iter().all(ScenarioOutcome::passed), "{:?}", outcomes);
+        assert!(matches!(
+            &outcomes[0].decision.candidate,
+            Some(DecisionCandidate { name, relaxed: false, .. }) if name == "batch-job"
+        ));
+        assert_eq!(outcomes[1].decision, Decision::no_pressure(PressureLevel::Normal));
+
+        // 默认的下限不保护数据库，它成为受害者，场景失败
+        let outcomes = run_scenarios(&config(), dir.path()).unwrap();
+        assert_eq!(outcomes[0].decision.victim(), ProcessId::new(1001));
+        assert!(!outcomes[0].passed());
+    }
+
+    #[test]
+    fn test_relaxed_selection_in_scenario() {
+        // 唯一的进程不到系统内存的1%，只有放宽的阈值能选中它
+        let scenario = Scenario::from_json(&format!(r#"{{
+            "stats": {},
+            "processes": [{{"pid": 2001, "name": "small", "rss_kb": 1048576}}]
+        }}"#, pressured_stats())).unwrap();
+        let decision = config().evaluate(&scenario);
+        assert_eq!(decision.outcome, DecisionOutcome::Killed);
+        assert!(decision.candidate.unwrap().relaxed);
+
+        let strict = KillerConfig {
+            selector: SelectorConfig {
+                relaxed: None,
+                ..config().selector
+            },
+            ..config()
+        };
+        let decision = strict.evaluate(&scenario);
+        assert_eq!(decision.outcome, DecisionOutcome::NoCandidate);
+        assert_eq!(decision.victim(), None);
+    }
+
+    #[test]
+    fn test_invalid_scenarios() {
+        assert!(matches!(
+            Scenario::from_json(r#"{"stats": {}, "processes": []}"#),
+            Err(SystemError::SyscallError(_))
+        ));
+        let invalid_pid = format!(r#"{{"stats": {}, "processes": [{{"pid": 0, "name": "x"}}]}}"#, pressured_stats());
+        assert!(matches!(Scenario::from_json(&invalid_pid), Err(SystemError::InvalidPid(0))));
+    }
+}
@@ -1,7 +1,41 @@
 use std::cmp::Ordering;
-use crate::linux::proc::{ProcessInfo, ProcessMemInfo};
+use std::fmt::{self, Write as _};
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::linux::proc::{ProcessInfo, ProcessMemInfo, DEFAULT_PROC_ROOT};
 use crate::linux::proc_stat::ProcessStat;
 
+/// 无法得知运行时间时使用的中等运行时间分数
+const UNKNOWN_RUNTIME_SCORE: f64 = 0.5;
+
+/// 默认的内存压力权重
+const DEFAULT_MEM_PRESSURE_WEIGHT: f64 = 0.6;
+/// 默认的运行时间权重
+const DEFAULT_RUNTIME_WEIGHT: f64 = 0.2;
+/// 默认的 oom_score_adj 权重
+const DEFAULT_OOM_SCORE_ADJ_WEIGHT: f64 = 0.2;
+
+/// 评分权重的来源
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeightSource {
+    /// 内置的默认值
+    Default,
+    /// 从指定的环境变量读取
+    Env(&'static str),
+    /// 通过 `with_weights` 指定
+    Builder,
+}
+
+impl fmt::Display for WeightSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WeightSource::Default => write!(f, "default"),
+            WeightSource::Env(name) => write!(f, "env {}", name),
+            WeightSource::Builder => write!(f, "with_weights"),
+        }
+    }
+}
+
 /// OOM 评分计算器
 #[derive(Debug)]
 pub struct OOMScorer {
@@ -9,6 +43,10 @@ pub struct OOMScorer {
     mem_pressure_weight: f64,
     runtime_weight: f64,
     oom_score_adj_weight: f64,
+    // 三个权重各自的来源，顺序同上
+    weight_sources: [WeightSource; 3],
+    // 读取进程stat和uptime的proc根目录
+    proc_root: PathBuf,
 }
 
 /// 进程的 OOM 评分详情
@@ -23,30 +61,92 @@ pub struct OOMScoreDetails {
 
 impl OOMScorer {
     /// 创建新的评分器实例
+    ///
+    /// 权重从环境变量 `OOM_MEM_PRESSURE_WEIGHT`、`OOM_RUNTIME_WEIGHT` 和
+    /// `OOM_SCORE_ADJ_WEIGHT` 读取，未设置或无法解析时使用默认值。
+    /// 嵌入到其他程序中、不希望受环境影响时使用 `with_defaults`
     pub fn new() -> Self {
-        // 从环境变量读取权重配置，使用默认值如果未设置
-        let mem_pressure_weight = std::env::var("OOM_MEM_PRESSURE_WEIGHT")
-            .ok()
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(0.6);
-
-        let runtime_weight = std::env::var("OOM_RUNTIME_WEIGHT")
-            .ok()
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(0.2);
-
-        let oom_score_adj_weight = std::env::var("OOM_SCORE_ADJ_WEIGHT")
-            .ok()
-            .and_then(|v| v.parse().ok())
-            .unwrap_or(0.2);
+        let mut scorer = Self::with_defaults();
+        let [memory, runtime, adj] = &mut scorer.weight_sources;
+        env_weight(&mut scorer.mem_pressure_weight, memory, "OOM_MEM_PRESSURE_WEIGHT");
+        env_weight(&mut scorer.runtime_weight, runtime, "OOM_RUNTIME_WEIGHT");
+        env_weight(&mut scorer.oom_score_adj_weight, adj, "OOM_SCORE_ADJ_WEIGHT");
+        scorer
+    }
 
+    /// 使用内置的默认权重创建评分器，不读取任何环境变量
+    pub fn with_defaults() -> Self {
         Self {
-            mem_pressure_weight,
-            runtime_weight,
-            oom_score_adj_weight,
+            mem_pressure_weight: DEFAULT_MEM_PRESSURE_WEIGHT,
+            runtime_weight: DEFAULT_RUNTIME_WEIGHT,
+            oom_score_adj_weight: DEFAULT_OOM_SCORE_ADJ_WEIGHT,
+            weight_sources: [WeightSource::Default; 3],
+            proc_root: PathBuf::from(DEFAULT_PROC_ROOT),
         }
     }
 
+    /// 使用指定的proc根目录读取运行时间信息
+    pub fn with_proc_root(mut self, proc_root: impl Into<PathBuf>) -> Self {
+        self.proc_root = proc_root.into();
+        self
+    }
+
+    /// 使用指定的权重，代替环境变量中的配置（用于离线调整权重）
+    pub fn with_weights(mut self, memory: f64, runtime: f64, oom_score_adj: f64) -> Self {
+        self.mem_pressure_weight = memory;
+        self.runtime_weight = runtime;
+        self.oom_score_adj_weight = oom_score_adj;
+        self.weight_sources = [WeightSource::Builder; 3];
+        self
+    }
+
+    /// 不计算运行时间分数，等同于把运行时间权重设为0
+    ///
+    /// 运行时间权重为0时评分不再读取进程的stat和uptime，所有进程的运行时间分数都按未知处理
+    pub fn without_runtime_scoring(mut self) -> Self {
+        self.runtime_weight = 0.0;
+        self.weight_sources[1] = WeightSource::Builder;
+        self
+    }
+
+    /// 运行时间权重为0时不读取运行时间，它对总分没有影响
+    fn skips_runtime(&self) -> bool {
+        self.runtime_weight == 0.0
+    }
+
+    /// 描述当前使用的评分公式：总分的计算方式、各分项的含义，以及每个权重的值、占权重之和的比例和来源
+    ///
+    /// 用于回答"为什么得分是这样"，与单个进程的评分详情不同，只描述公式本身。
+    /// 总分直接使用配置的权重，不做归一化；比例只用于说明各分项的相对影响
+    pub fn explain(&self) -> String {
+        let weights = [
+            ("memory", self.mem_pressure_weight),
+            ("runtime", self.runtime_weight),
+            ("oom_score_adj", self.oom_score_adj_weight),
+        ];
+        let sum: f64 = weights.iter().map(|(_, weight)| weight).sum();
+
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "total_score = memory_score * {} + runtime_score * {} + adj_score * {}",
+            self.mem_pressure_weight, self.runtime_weight, self.oom_score_adj_weight,
+        );
+        let _ = writeln!(out, "  memory_score  = 0.7 * VmRSS / MemTotal + 0.3 * VmSwap / MemTotal");
+        let _ = writeln!(
+            out,
+            "  runtime_score = 0.8-1.0 under an hour, 0.3-0.8 under a day, 0.0-0.3 after; {} when unknown",
+            UNKNOWN_RUNTIME_SCORE,
+        );
+        let _ = writeln!(out, "  adj_score     = oom_score_adj / 1000 (-1 to 1)");
+        let _ = writeln!(out, "weights (value, share of sum, source):");
+        for ((name, weight), source) in weights.iter().zip(&self.weight_sources) {
+            let share = if sum != 0.0 { weight / sum * 100.0 } else { 0.0 };
+            let _ = writeln!(out, "  {:<13} = {} ({:.1}%, {})", name, weight, share, source);
+        }
+        out
+    }
+
     /// 计算进程的详细评分
     /// 
     /// # 参数
@@ -58,12 +158,37 @@ impl OOMScorer {
     /// 
     /// 返回包含详细评分信息的 OOMScoreDetails
     pub fn calculate_score(&self, process: ProcessInfo, total_memory: u64) -> OOMScoreDetails {
-        // 计算内存压力分数 (0-1)
-        let memory_score = self.calculate_memory_score(&process.mem_info, total_memory);
-        
         // 计算运行时间分数 (0-1)，优先选择新进程
         let runtime_score = self.calculate_runtime_score(&process);
-        
+        self.score_with_runtime(process, runtime_score, total_memory)
+    }
+
+    /// 使用已经读取的启动时间（stat中的starttime）计算评分，不再读取进程的stat
+    ///
+    /// 选择器在同一个进程目录中读取stat和OOM分数后使用，保证评分依据来自同一个进程实例
+    pub fn calculate_score_with_start_time(&self, process: ProcessInfo, start_time: u64, total_memory: u64) -> OOMScoreDetails {
+        if self.skips_runtime() {
+            return self.score_with_runtime(process, UNKNOWN_RUNTIME_SCORE, total_memory);
+        }
+        let runtime = crate::linux::proc_stat::running_time_since(start_time, &self.proc_root);
+        let runtime_score = crate::linux::proc_stat::score_runtime(runtime);
+        self.score_with_runtime(process, runtime_score, total_memory)
+    }
+
+    /// 使用给定的运行时间计算评分，不读取 /proc
+    ///
+    /// `None` 表示运行时间未知，与无法读取stat时一样使用中等分数
+    pub fn calculate_score_with_runtime(&self, process: ProcessInfo, runtime: Option<Duration>, total_memory: u64) -> OOMScoreDetails {
+        let runtime_score = runtime
+            .map(crate::linux::proc_stat::score_runtime)
+            .unwrap_or(UNKNOWN_RUNTIME_SCORE);
+        self.score_with_runtime(process, runtime_score, total_memory)
+    }
+
+    fn score_with_runtime(&self, process: ProcessInfo, runtime_score: f64, total_memory: u64) -> OOMScoreDetails {
+        // 计算内存压力分数 (0-1)
+        let memory_score = self.calculate_memory_score(&process.mem_info, total_memory);
+
         // 计算 oom_score_adj 的影响 (-1 到 1)
         let adj_score = self.calculate_adj_score(process.mem_info.oom_score_adj);
 
@@ -91,14 +216,17 @@ impl OOMScorer {
         0.7 * rss_ratio + 0.3 * swap_ratio
     }
 
-    /// 计算运行时间分数
+    /// 计算运行时间分数，运行时间权重为0时不读取stat
     fn calculate_runtime_score(&self, process: &ProcessInfo) -> f64 {
+        if self.skips_runtime() {
+            return UNKNOWN_RUNTIME_SCORE;
+        }
         // 获取进程统计信息
-        if let Ok(stat) = ProcessStat::from_pid(process.pid) {
-            crate::linux::proc_stat::calculate_runtime_score(&stat)
+        if let Ok(stat) = ProcessStat::from_pid_in(&self.proc_root, process.pid) {
+            crate::linux::proc_stat::score_runtime(stat.running_time_in(&self.proc_root))
         } else {
             // 如果无法获取统计信息，返回中等分数
-            0.5
+            UNKNOWN_RUNTIME_SCORE
         }
     }
 
@@ -109,6 +237,14 @@ impl OOMScorer {
     }
 }
 
+/// 从环境变量读取权重并记录来源，未设置或无法解析时保留原来的值
+fn env_weight(weight: &mut f64, source: &mut WeightSource, name: &'static str) {
+    if let Some(value) = std::env::var(name).ok().and_then(|v| v.parse().ok()) {
+        *weight = value;
+        *source = WeightSource::Env(name);
+    }
+}
+
 /// 为 OOMScoreDetails 实现排序
 impl Ord for OOMScoreDetails {
     fn cmp(&self, other: &Self) -> Ordering {
@@ -135,6 +271,8 @@ impl Eq for OOMScoreDetails {}
 mod tests {
     use super::*;
     use crate::ffi::types::ProcessId;
+    use crate::linux::proc::Completeness;
+    use std::time::Duration;
 
     fn create_test_process(pid: i32, rss: u64, oom_score_adj: i32) -> ProcessInfo {
         ProcessInfo {
@@ -142,14 +280,23 @@ mod tests {
             name: format!("test_process_{}", pid),
             state: "S".to_string(),
             ppid: 1,
+            uid: 0,
             mem_info: ProcessMemInfo {
                 vm_peak: rss * 2,
                 vm_size: rss * 2,
                 vm_rss: rss,
+                rss_anon: rss,
+                rss_file: 0,
+                rss_shmem: 0,
                 vm_swap: 0,
                 oom_score: 0,
                 oom_score_adj,
             },
+            exe_name: None,
+            limits: None,
+            cgroup_memory_low: None,
+            memory_cgroup: None,
+            completeness: Completeness::COMPLETE,
         }
     }
 
@@ -168,6 +315,31 @@ mod tests {
         assert!(score2.total_score > score1.total_score);
     }
 
+    #[test]
+    fn test_score_with_start_time_matches_stat() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("7")).unwrap();
+        std::fs::write(
+            root.path().join("7/stat"),
+            "7 (worker) S 1 7 7 0 -1 0 0 0 0 0 0 0 0 0 20 0 1 0 360000 0 0\n",
+        ).unwrap();
+        std::fs::write(root.path().join("uptime"), "7200.00 14000.00\n").unwrap();
+        let scorer = OOMScorer::new().with_proc_root(root.path());
+        let total_memory = 8 * 1024 * 1024 * 1024;
+
+        let from_stat = scorer.calculate_score(create_test_process(7, 1024 * 1024, 0), total_memory);
+        let from_start_time = scorer.calculate_score_with_start_time(
+            create_test_process(7, 1024 * 1024, 0),
+            360000,
+            total_memory,
+        );
+
+        assert_eq!(from_stat.runtime_score, from_start_time.runtime_score);
+        assert_eq!(from_stat.total_score, from_start_time.total_score);
+        // 启动于3600秒，已运行一小时
+        assert_eq!(from_stat.runtime_score, crate::linux::proc_stat::score_runtime(Duration::from_secs(3600)));
+    }
+
     #[test]
     fn test_oom_score_adj_impact() {
         let scorer = OOMScorer::new();
@@ -182,4 +354,69 @@ mod tests {
         // 有更高 oom_score_adj 的进程应该得分更高
         assert!(score2.total_score > score1.total_score);
     }
-} 
\ No newline at end of file
+
+    #[test]
+    fn test_with_defaults_ignores_environment() {
+        // 只改变运行时间权重，不影响其他并行运行的测试中的比较
+        std::env::set_var("OOM_RUNTIME_WEIGHT", "0.35");
+        let from_env = OOMScorer::new();
+        let defaults = OOMScorer::with_defaults();
+        std::env::remove_var("OOM_RUNTIME_WEIGHT");
+
+        assert_eq!(from_env.runtime_weight, 0.35);
+        assert_eq!(from_env.weight_sources[1], WeightSource::Env("OOM_RUNTIME_WEIGHT"));
+        assert!(from_env.explain().contains("runtime       = 0.35 (30.4%, env OOM_RUNTIME_WEIGHT)"));
+        assert_eq!(defaults.runtime_weight, DEFAULT_RUNTIME_WEIGHT);
+        assert_eq!(defaults.mem_pressure_weight, DEFAULT_MEM_PRESSURE_WEIGHT);
+        assert_eq!(defaults.oom_score_adj_weight, DEFAULT_OOM_SCORE_ADJ_WEIGHT);
+    }
+
+    #[test]
+    fn test_explain_lists_weights_and_sources() {
+        let explanation = OOMScorer::with_defaults().explain();
+        assert!(explanation.starts_with("total_score = memory_score * 0.6 + runtime_score * 0.2 + adj_score * 0.2\n"));
+        assert!(explanation.contains("memory        = 0.6 (60.0%, default)"));
+        assert!(explanation.contains("runtime       = 0.2 (20.0%, default)"));
+        assert!(explanation.contains("oom_score_adj = 0.2 (20.0%, default)"));
+
+        let explanation = OOMScorer::with_defaults().with_weights(3.0, 1.0, 0.0).explain();
+        assert!(explanation.contains("memory_score * 3 + runtime_score * 1 + adj_score * 0"));
+        assert!(explanation.contains("memory        = 3 (75.0%, with_weights)"));
+        assert!(explanation.contains("runtime       = 1 (25.0%, with_weights)"));
+        assert!(explanation.contains("oom_score_adj = 0 (0.0%, with_weights)"));
+    }
+
+    #[test]
+    fn test_zero_runtime_weight_skips_stat() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir(root.path().join("7")).unwrap();
+        std::fs::write(
+            root.path().join("7/stat"),
+            "7 (worker) S 1 7 7 0 -1 0 0 0 0 0 0 0 0 0 20 0 1 0 360000 0 0\n",
+        ).unwrap();
+        std::fs::write(root.path().join("uptime"), "7200.00 14000.00\n").unwrap();
+        let total_memory = 8 * 1024 * 1024 * 1024;
+        let process = || create_test_process(7, 1024 * 1024 * 1024, 500);
+
+        // 权重不为0时读取stat，运行了一小时
+        let with_runtime = OOMScorer::with_defaults().with_proc_root(root.path());
+        let read = with_runtime.calculate_score(process(), total_memory);
+        assert_ne!(read.runtime_score, UNKNOWN_RUNTIME_SCORE);
+
+        // 权重为0时不读取stat和uptime，运行时间分数按未知处理，总分只包含其他两项
+        for scorer in [
+            OOMScorer::with_defaults().with_weights(0.6, 0.0, 0.2),
+            OOMScorer::with_defaults().without_runtime_scoring(),
+        ] {
+            let scorer = scorer.with_proc_root(root.path());
+            for skipped in [
+                scorer.calculate_score(process(), total_memory),
+                scorer.calculate_score_with_start_time(process(), 360000, total_memory),
+            ] {
+                assert_eq!(skipped.runtime_score, UNKNOWN_RUNTIME_SCORE);
+                assert_eq!(skipped.memory_score, read.memory_score);
+                assert_eq!(skipped.total_score, read.memory_score * 0.6 + read.adj_score * 0.2);
+            }
+        }
+    }
+}
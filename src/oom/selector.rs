@@ -1,9 +1,17 @@
-use std::cmp::Ordering;
-use std::collections::BinaryHeap;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use crate::ffi::ProcessDir;
 use crate::ffi::types::{ProcessId, SystemError, Result};
-use crate::linux::proc::ProcessInfo;
+use crate::linux::cgroup::{self, CgroupInfo, CgroupVersion, DEFAULT_CGROUP_ROOT};
+use crate::linux::proc::{exe_basename, OomScoreCache, ProcessInfo, ProcessSource, ResourceLimits, DEFAULT_PROC_ROOT};
+use crate::linux::proc_reader::ProcReader;
+use crate::linux::proc_stat::{ProcessKey, ProcessStat};
+use crate::oom::critical::{Comm, KillPlan, PlannedVictim};
 use crate::oom::score::{OOMScorer, OOMScoreDetails};
-use crate::oom::pressure::{PressureDetector, MemoryStats};
+use crate::oom::pressure::{PressureDetector, PressureLevel, MemoryStats, ProcessSummary};
 
 /// 进程选择器的配置
 #[derive(Debug, Clone)]
@@ -12,10 +20,172 @@ pub struct SelectorConfig {
     pub min_candidates: usize,
     /// 最大可选择进程数
     pub max_candidates: usize,
+    /// 可用内存占总内存的目标比例（0-1），`None` 表示不按缺口调整选择
+    ///
+    /// 可用内存低于目标时，缺口比例 `(目标 - 当前) / 目标` 越大，
+    /// 严格过滤的内存影响下限越低、保留的候选越多，缺口为100%时下限为0、候选数加倍
+    pub headroom_target: Option<f64>,
+    /// 只按内核的 /proc/[pid]/oom_score 排序，不使用本crate的加权评分
+    ///
+    /// 过滤和保护规则不变；候选的 `total_score` 被替换为内核的oom_score（0-2000）
+    pub use_kernel_oom_score_only: bool,
+    /// 只在这些进程中选择，`None` 表示扫描整个 /proc
+    ///
+    /// 用于只管理自己子进程的监督程序：设置后每轮只读取这些进程的文件，不再枚举 /proc
+    pub monitored_pids: Option<Vec<ProcessId>>,
+    /// 设置 `monitored_pids` 时是否同时考虑它们的所有后代进程
+    ///
+    /// 后代通过 /proc/[pid]/task/[tid]/children 查找，需要内核启用 CONFIG_PROC_CHILDREN
+    pub monitor_descendants: bool,
     /// 是否允许选择系统进程
     pub allow_system_processes: bool,
     /// 最小内存阈值（字节），小于此值的进程不会被选择
     pub min_memory_threshold: u64,
+    /// 内存阈值和内存影响按 `vm_rss + vm_swap * swap_weight` 计算，0表示只看RSS
+    ///
+    /// 被完全换出的进程RSS可能接近0，但终止它仍然会释放大量swap
+    pub swap_weight: f64,
+    /// 是否保护与本进程共享控制终端的进程（交互式会话）
+    pub protect_controlling_tty: bool,
+    /// 是否保护使用实时调度策略（SCHED_FIFO/SCHED_RR）的进程
+    ///
+    /// 实时进程（例如音频服务、看门狗）几乎总是关键进程。调度策略从 /proc/[pid]/stat
+    /// 读取，没有该字段的内核上按普通进程处理；外部 `ProcessSource` 提供的进程不读取stat，不受影响
+    pub protect_realtime: bool,
+    /// 是否允许选择与本进程处于同一个 memory cgroup 的进程
+    ///
+    /// 在容器中运行时设为false，避免终止同一容器中的其他进程。
+    /// 本进程的cgroup在创建选择器时从 /proc/self/cgroup 读取
+    pub allow_same_cgroup: bool,
+    /// oom_score_adj低于此值的进程不会被选择
+    ///
+    /// 默认-1000，只排除内核同样不会终止的进程；调高（例如-400）可以保护
+    /// 设置了适度负值的重要服务
+    pub min_oom_score_adj: i32,
+    /// 持续压力下严格过滤没有候选时使用的放宽阈值，`None` 表示不重试
+    pub relaxed: Option<RelaxedSelection>,
+    /// 一轮选择的时间预算（例如50ms），用尽时返回目前为止最好的候选，`None` 表示不限制
+    pub selection_budget: Option<Duration>,
+    /// 是否把终止分散到不同的应用（按进程名区分）
+    ///
+    /// 终止某个应用之后的 `name_cooldown` 内优先终止其他应用，
+    /// 避免同名的多个实例（例如浏览器的渲染进程）被逐个终止而其他大户幸存
+    pub diversify_by_name: bool,
+    /// 按可执行文件名（/proc/[pid]/exe）而不是comm区分应用
+    ///
+    /// comm可以被进程通过 prctl(PR_SET_NAME) 修改。启用后每个候选多读取一次exe链接，
+    /// 结果写入 `ProcessInfo::exe_name`，供分散终止和有效性判断使用；无法读取时退回comm
+    pub match_exe_name: bool,
+    /// 得分与最高分相差不超过该值的候选轮流作为第一个受害者，`None` 表示总是选择最高分
+    ///
+    /// 避免一个只比其他候选略差的重要进程在每次压力过程中都被选中。
+    /// 按 `ProcessKey` 记录每个候选最近一次被选中的顺序，从未被选中或最久之前被选中的优先
+    pub rotate_ties: Option<f64>,
+    /// 候选需要在连续这么多轮扫描中都排在第一位才会被终止，`None` 表示第一次扫描就终止
+    ///
+    /// 避免因为单次扫描中短暂的内存峰值终止进程，代价是终止推迟若干轮检查。
+    /// 按 `ProcessKey` 记录连续排在第一位的轮数，其他进程排在第一位时清零
+    pub confirm_scans: Option<u32>,
+    /// 终止一个应用之后降低其优先级的时间
+    pub name_cooldown: Duration,
+    /// 冷却中的应用得分达到其他应用最高分的这一倍数时仍然终止它
+    pub name_dominance: f64,
+    /// 子进程数比上一轮扫描增加的进程在这段时间内不会被选择，`None` 表示不检测
+    ///
+    /// 刚刚fork的进程（例如启动命令的shell）会短暂地显得占用更多内存，很快恢复正常。
+    /// 子进程数按扫描到的进程的PPid统计，只比较两次完整的扫描
+    pub fork_grace: Option<Duration>,
+    /// RSS已经达到RLIMIT_RSS软限制的进程，总分乘以该系数（0-1），`None` 表示不读取limits
+    ///
+    /// 这样的进程无法继续增长，通常不是压力持续上升的原因。启用后每个候选多读取一次
+    /// /proc/[pid]/limits，结果写入 `ProcessInfo::limits`
+    pub rss_limit_penalty: Option<f64>,
+    /// 所在cgroup设置了非零 memory.low 的进程的处理方式
+    ///
+    /// memory.low 表示管理员希望这部分内存不被回收。只支持cgroup v2，
+    /// 启用后每个候选多读取一次cgroup和 memory.low
+    pub memory_low: MemoryLowPolicy,
+    /// 只在该范围内选择，范围之外的进程无论其他设置如何都不会被选择；`None` 表示不限制
+    ///
+    /// 用于敏感环境：只允许终止明确列出的进程，其余一律不碰
+    pub kill_only: Option<KillScope>,
+    /// 按进程名、cgroup或uid划分的重要性等级，按顺序使用第一条匹配的规则，都不匹配时为 `Normal`
+    ///
+    /// 等级只调整总分，不是保护：`Critical` 的进程在没有其他候选时仍然可能被终止，
+    /// 需要绝对保护时使用 `min_oom_score_adj` 或 `kill_only`
+    pub importance_tiers: Vec<TierRule>,
+}
+
+/// 对所在cgroup设置了 memory.low 的进程的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum MemoryLowPolicy {
+    /// 不读取 memory.low
+    #[default]
+    Ignore,
+    /// 总分乘以该系数（0-1）
+    Deprioritize(f64),
+    /// 不选择这样的进程
+    Exclude,
+}
+
+/// 按进程名、cgroup或uid匹配的进程范围，任意一项匹配即在范围内，用于 `kill_only` 和 `importance_tiers`
+///
+/// 所有列表都为空时不匹配任何进程
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KillScope {
+    /// 进程名，与comm或 /proc/[pid]/exe 的可执行文件名比较
+    pub names: Vec<String>,
+    /// memory cgroup 路径，例如 "/batch.slice"，包括其下的子cgroup
+    pub cgroups: Vec<String>,
+    /// 进程的real uid
+    pub uids: Vec<u32>,
+}
+
+impl KillScope {
+    /// 进程是否在范围内，没有读取 `memory_cgroup` 时不按cgroup匹配
+    pub fn matches(&self, process: &ProcessInfo) -> bool {
+        let name_matches = self.names.iter().any(|name| {
+            *name == process.name || process.exe_name.as_deref() == Some(name.as_str())
+        });
+        let cgroup_matches = process.memory_cgroup.as_deref().is_some_and(|cgroup| {
+            self.cgroups.iter().any(|scope| {
+                let scope = scope.trim_end_matches('/');
+                cgroup.strip_prefix(scope)
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+            })
+        });
+        name_matches || cgroup_matches || self.uids.contains(&process.uid)
+    }
+}
+
+/// 进程的重要性等级，见 `SelectorConfig::importance_tiers`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImportanceTier {
+    /// 重要的进程，总分乘以0.1，很少被选中
+    Critical,
+    /// 总分不变
+    #[default]
+    Normal,
+    /// 可以随时牺牲的进程，总分乘以2，优先被选中
+    BestEffort,
+}
+
+impl ImportanceTier {
+    /// 该等级的总分系数
+    pub fn multiplier(&self) -> f64 {
+        match self {
+            ImportanceTier::Critical => 0.1,
+            ImportanceTier::Normal => 1.0,
+            ImportanceTier::BestEffort => 2.0,
+        }
+    }
+}
+
+/// 把匹配 `scope` 的进程划入 `tier`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TierRule {
+    pub scope: KillScope,
+    pub tier: ImportanceTier,
 }
 
 impl Default for SelectorConfig {
@@ -23,23 +193,365 @@ impl Default for SelectorConfig {
         Self {
             min_candidates: 3,
             max_candidates: 10,
+            headroom_target: None,
+            use_kernel_oom_score_only: false,
+            monitored_pids: None,
+            monitor_descendants: false,
             allow_system_processes: false,
             min_memory_threshold: 1024 * 1024, // 1MB
+            swap_weight: 1.0,
+            protect_controlling_tty: false,
+            protect_realtime: true,
+            allow_same_cgroup: true,
+            min_oom_score_adj: -1000,
+            relaxed: Some(RelaxedSelection::default()),
+            selection_budget: None,
+            diversify_by_name: false,
+            match_exe_name: false,
+            rotate_ties: None,
+            confirm_scans: None,
+            name_cooldown: Duration::from_secs(60),
+            name_dominance: 2.0,
+            fork_grace: None,
+            rss_limit_penalty: None,
+            memory_low: MemoryLowPolicy::Ignore,
+            kill_only: None,
+            importance_tiers: Vec::new(),
+        }
+    }
+}
+
+impl SelectorConfig {
+    /// 进程是否在 `kill_only` 范围内，未设置时总是为true
+    fn in_kill_scope(&self, process: &ProcessInfo) -> bool {
+        match &self.kill_only {
+            Some(scope) => scope.matches(process),
+            None => true,
+        }
+    }
+
+    /// 进程的重要性等级，按顺序使用第一条匹配的规则
+    pub fn importance_tier(&self, process: &ProcessInfo) -> ImportanceTier {
+        self.importance_tiers.iter()
+            .find(|rule| rule.scope.matches(process))
+            .map_or(ImportanceTier::Normal, |rule| rule.tier)
+    }
+
+    /// `kill_only` 或 `importance_tiers` 是否需要按cgroup匹配
+    fn matches_cgroups(&self) -> bool {
+        self.kill_only.iter()
+            .chain(self.importance_tiers.iter().map(|rule| &rule.scope))
+            .any(|scope| !scope.cgroups.is_empty())
+    }
+
+    /// 区分应用使用的名称，启用 `match_exe_name` 且读取到可执行文件名时使用它
+    fn app_name<'a>(&self, process: &'a ProcessInfo) -> &'a str {
+        match &process.exe_name {
+            Some(exe_name) if self.match_exe_name => exe_name,
+            _ => &process.name,
+        }
+    }
+
+    /// 补充OOM分数之后的过滤条件
+    ///
+    /// 没有读到oom_score_adj的进程不会被选择：它可能是被设为-1000保护起来的特权进程。
+    /// 按内核oom_score排序时同样要求读到了oom_score
+    fn is_eligible(&self, process: &ProcessInfo) -> bool {
+        let completeness = process.completeness;
+        if !completeness.oom_adj_ok || (self.use_kernel_oom_score_only && !completeness.oom_score_ok) {
+            return false;
+        }
+        process.is_oomable() && process.mem_info.oom_score_adj >= self.min_oom_score_adj
+    }
+
+    /// 内存阈值使用的内存占用：RSS加上按 `swap_weight` 计入的swap
+    fn memory_footprint(&self, process: &ProcessInfo) -> u64 {
+        let swap = process.mem_info.vm_swap as f64 * self.swap_weight.max(0.0);
+        process.mem_info.vm_rss.saturating_add(swap as u64)
+    }
+
+    /// 不依赖OOM分数的过滤条件，只需要status中的信息
+    fn passes_prefilter(&self, process: &ProcessInfo, memory_stats: &MemoryStats, filter: MemoryFilter) -> bool {
+        // 检查是否是系统进程
+        if !self.allow_system_processes && process.is_system_process() {
+            return false;
+        }
+
+        // 检查内存使用是否达到最小阈值
+        let footprint = self.memory_footprint(process);
+        if footprint < filter.min_rss {
+            return false;
+        }
+
+        // 检查终止该进程是否能显著改善内存状况
+        let memory_impact = footprint as f64 / memory_stats.total_memory as f64;
+        memory_impact >= filter.min_impact // 严格过滤时至少释放1%的系统内存
+    }
+
+    /// 可用内存相对于 `headroom_target` 的缺口比例（0-1），未配置目标或没有缺口时为0
+    pub fn deficit_factor(&self, memory_stats: &MemoryStats) -> f64 {
+        let Some(target) = self.headroom_target.filter(|&target| target > 0.0) else {
+            return 0.0;
+        };
+        let available = memory_stats.available_ratio();
+        ((target - available) / target).clamp(0.0, 1.0)
+    }
+
+    /// 按缺口调整后的严格过滤内存影响下限
+    pub fn effective_memory_impact(&self, memory_stats: &MemoryStats) -> f64 {
+        STRICT_MEMORY_IMPACT * (1.0 - self.deficit_factor(memory_stats))
+    }
+
+    /// 按缺口调整后的最大候选数
+    pub fn effective_max_candidates(&self, memory_stats: &MemoryStats) -> usize {
+        let deficit = self.deficit_factor(memory_stats);
+        (self.max_candidates as f64 * (1.0 + deficit)).round() as usize
+    }
+
+    /// 启用 `use_kernel_oom_score_only` 时用内核的oom_score代替加权总分，
+    /// 再对已达到RSS限制的进程应用 `rss_limit_penalty`、对受 memory.low 保护的进程应用 `memory_low`，
+    /// 最后乘以重要性等级的系数
+    fn ranked(&self, mut details: OOMScoreDetails) -> OOMScoreDetails {
+        if self.use_kernel_oom_score_only {
+            details.total_score = f64::from(details.process.mem_info.oom_score);
+        }
+        if let Some(penalty) = self.rss_limit_penalty {
+            if details.process.at_rss_limit() {
+                details.total_score *= penalty.clamp(0.0, 1.0);
+            }
+        }
+        if let MemoryLowPolicy::Deprioritize(penalty) = self.memory_low {
+            if details.process.cgroup_memory_low.unwrap_or(0) > 0 {
+                details.total_score *= penalty.clamp(0.0, 1.0);
+            }
+        }
+        if !self.importance_tiers.is_empty() {
+            details.total_score *= self.importance_tier(&details.process).multiplier();
+        }
+        details
+    }
+
+    /// 配置中的严格阈值，内存影响下限按缺口调整
+    fn strict_filter(&self, memory_stats: &MemoryStats) -> MemoryFilter {
+        MemoryFilter {
+            min_rss: self.min_memory_threshold,
+            min_impact: self.effective_memory_impact(memory_stats),
+        }
+    }
+
+    /// 放宽后的阈值，未配置时为None
+    fn relaxed_filter(&self) -> Option<MemoryFilter> {
+        self.relaxed.as_ref().map(|relaxed| MemoryFilter {
+            min_rss: relaxed.min_memory_threshold,
+            min_impact: relaxed.min_memory_impact,
+        })
+    }
+
+    /// 在给定的进程中选择得分最高的候选，不读取 /proc
+    ///
+    /// 过滤条件和放宽规则与 `ProcessSelector` 相同，并假定压力已经持续；
+    /// 没有控制终端和cgroup的信息，因此不做终端保护和同cgroup排除。`None` 的运行时间按无法读取stat处理。
+    /// 返回评分详情和是否使用了放宽的阈值
+    pub(crate) fn select_among<'a, I>(
+        &self,
+        scorer: &OOMScorer,
+        memory_stats: &MemoryStats,
+        processes: I,
+    ) -> Option<(OOMScoreDetails, bool)>
+    where
+        I: Iterator<Item = (&'a ProcessInfo, Option<Duration>)> + Clone,
+    {
+        let best = |filter: MemoryFilter| {
+            let mut count = 0;
+            let best = processes.clone()
+                .filter(|(process, _)| {
+                    self.passes_prefilter(process, memory_stats, filter) && self.is_eligible(process)
+                })
+                .inspect(|_| count += 1)
+                .map(|(process, runtime)| {
+                    self.ranked(scorer.calculate_score_with_runtime(process.clone(), runtime, memory_stats.total_memory))
+                })
+                .filter(has_finite_score)
+                .max_by(rank_order);
+            (best, count)
+        };
+
+        let (strict, count) = best(self.strict_filter(memory_stats));
+        if count >= self.min_candidates {
+            return strict.map(|details| (details, false));
+        }
+
+        let (relaxed, _) = best(self.relaxed_filter()?);
+        relaxed.map(|details| (details, true))
+    }
+}
+
+/// 放宽后的过滤阈值
+///
+/// 只放宽内存相关的阈值并忽略 `min_candidates`；系统进程、
+/// oom_score_adj为-1000或低于 `min_oom_score_adj` 的进程和受保护的控制终端仍然不会被选择
+#[derive(Debug, Clone)]
+pub struct RelaxedSelection {
+    /// 最小内存阈值（字节）
+    pub min_memory_threshold: u64,
+    /// 终止后至少释放的系统内存比例（0-1）
+    pub min_memory_impact: f64,
+}
+
+impl Default for RelaxedSelection {
+    fn default() -> Self {
+        Self {
+            min_memory_threshold: 1, // 只要求有常驻内存
+            min_memory_impact: 0.0,
         }
     }
 }
 
+/// 每轮记住的RSS最大的候选数，下一轮最先评估
+const KNOWN_LARGE_PROCESSES: usize = 16;
+
+/// 严格过滤时终止进程至少要释放的系统内存比例
+const STRICT_MEMORY_IMPACT: f64 = 0.01;
+
+/// kthreadd的pid，所有内核线程都是它的后代
+const KTHREADD_PID: i32 = 2;
+
+/// 一轮选择使用的内存阈值
+#[derive(Debug, Clone, Copy)]
+struct MemoryFilter {
+    min_rss: u64,
+    min_impact: f64,
+}
+
+/// 一轮选择中枚举和评分各自的耗时，放宽重试时两次的耗时累加
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SelectionTimings {
+    /// 枚举进程并过滤候选
+    pub enumeration: Duration,
+    /// 对候选评分
+    pub scoring: Duration,
+}
+
+/// `plan_to_free` 估算的终止范围：按选择顺序排列的受害者，以及累计的估算回收量
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReclaimPlan {
+    /// 目标回收量（字节）
+    pub target_bytes: u64,
+    /// 按顺序需要终止的进程，达到目标后不再加入
+    pub victims: Vec<ReclaimStep>,
+    /// 终止所有列出的进程后是否能达到目标，所有候选都不够时为false
+    pub reaches_target: bool,
+}
+
+impl ReclaimPlan {
+    /// 终止所有列出的进程估算回收的内存（字节）
+    pub fn total_bytes(&self) -> u64 {
+        self.victims.last().map_or(0, |step| step.cumulative_bytes)
+    }
+}
+
+/// `ReclaimPlan` 中的一个受害者
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReclaimStep {
+    pub pid: ProcessId,
+    pub name: String,
+    pub score: f64,
+    /// 终止该进程估算回收的内存（字节），按RSS估算，与实际终止时相同
+    pub estimated_bytes: u64,
+    /// 终止到该进程为止累计的估算回收量（字节）
+    pub cumulative_bytes: u64,
+}
+
 /// 进程选择器
 #[derive(Debug)]
 pub struct ProcessSelector {
     config: SelectorConfig,
     scorer: OOMScorer,
     pressure_detector: PressureDetector,
+    // 枚举进程的proc根目录
+    proc_root: PathBuf,
+    // 以下缓冲区在每轮选择中清空并重新填充，稳定状态下不再分配内存
+    reader: ProcReader,
+    // 本轮要评估的进程，上一轮的大进程排在最前
+    pids: Vec<ProcessId>,
+    processes: Vec<ProcessInfo>,
+    // 通过过滤的进程在 processes 中的下标及其唯一标识
+    shortlisted: Vec<(usize, ProcessKey)>,
+    live: HashSet<ProcessKey>,
+    candidates: BinaryHeap<Candidate>,
+    // 通过初步过滤的进程的OOM分数
+    oom_scores: OomScoreCache,
+    // 本进程的控制终端，没有控制终端或未启用保护时为None
+    controlling_tty: Option<i32>,
+    // 本进程的 memory cgroup，允许选择同一cgroup中的进程或无法读取时为None
+    own_cgroup: Option<CgroupInfo>,
+    // cgroup文件系统的挂载点，用于读取候选所在cgroup的 memory.low
+    cgroup_root: PathBuf,
+    // 最近一次选择是否使用了放宽的阈值
+    relaxed: bool,
+    // 上一轮RSS最大的候选，下一轮最先评估
+    known_large: Vec<ProcessId>,
+    // 本轮选择的期限和是否因期限提前结束
+    deadline: Option<Instant>,
+    partial: bool,
+    timings: SelectionTimings,
+    // 最近终止的进程名及终止时间，用于分散终止
+    recently_killed: Vec<(Comm, Instant)>,
+    // 启用 rotate_ties 时每个候选最近一次被选中的序号，以及下一个序号
+    tie_picks: HashMap<ProcessKey, u64>,
+    tie_sequence: u64,
+    // 启用 confirm_scans 时当前排在第一位的候选及其连续排在第一位的轮数
+    top_streaks: HashMap<ProcessKey, u32>,
+    // 用户注册的有效性判断，全部通过的进程才是候选
+    validity_predicates: Vec<ValidityPredicate>,
+    // 启用 fork_grace 时本轮和上一轮完整扫描中每个进程的子进程数（按PPid统计）
+    child_counts: HashMap<i32, u32>,
+    // 本轮扫描到的每个进程的父进程，用于排除内核线程
+    parents: HashMap<i32, i32>,
+    previous_child_counts: Option<HashMap<i32, u32>>,
+    // 子进程数增加的进程及发现的时间
+    recent_forks: HashMap<i32, Instant>,
+    // 测试用：模拟读取每个进程统计信息的延迟
+    #[cfg(test)]
+    slow_stats: Duration,
+    // 测试用：读取status之后、读取stat和OOM分数之前调用，参数是proc根目录
+    #[cfg(test)]
+    after_status: Option<fn(&Path)>,
+    // 测试工具：枚举进程之后、读取进程文件之前调用
+    #[cfg(feature = "test-util")]
+    refresh_hook: Option<crate::test_util::RefreshHook>,
+    // 增量维护的进程表，为空时每次扫描 /proc
+    #[cfg(feature = "proc-connector")]
+    tracker: Option<crate::linux::proc_connector::ProcessTracker>,
+    // 外部提供的进程列表，设置后不再读取 /proc 中的进程文件
+    source: Option<SourceBox>,
+}
+
+/// 外部提供的进程来源
+struct SourceBox(Box<dyn ProcessSource>);
+
+impl fmt::Debug for SourceBox {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProcessSource")
+    }
+}
+
+/// 候选有效性判断的函数类型
+type PredicateFn = dyn Fn(&ProcessInfo, &MemoryStats) -> bool + Send + Sync;
+
+/// 用户注册的候选有效性判断
+struct ValidityPredicate(Box<PredicateFn>);
+
+impl fmt::Debug for ValidityPredicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ValidityPredicate")
+    }
 }
 
 /// 候选进程信息
 #[derive(Debug)]
 pub struct Candidate {
+    pub key: ProcessKey,
     pub score_details: OOMScoreDetails,
     pub memory_saved: u64,
 }
@@ -51,13 +563,74 @@ impl ProcessSelector {
         scorer: OOMScorer,
         pressure_detector: PressureDetector,
     ) -> Self {
+        let config = config.unwrap_or_default();
+        let controlling_tty = if config.protect_controlling_tty {
+            current_controlling_tty()
+        } else {
+            None
+        };
+        let own_cgroup = if config.allow_same_cgroup {
+            None
+        } else {
+            current_cgroup()
+        };
+
         Self {
-            config: config.unwrap_or_default(),
+            config,
             scorer,
             pressure_detector,
+            proc_root: PathBuf::from(DEFAULT_PROC_ROOT),
+            reader: ProcReader::new(DEFAULT_PROC_ROOT),
+            pids: Vec::new(),
+            processes: Vec::new(),
+            shortlisted: Vec::new(),
+            live: HashSet::new(),
+            candidates: BinaryHeap::new(),
+            oom_scores: OomScoreCache::new(),
+            controlling_tty,
+            own_cgroup,
+            cgroup_root: PathBuf::from(DEFAULT_CGROUP_ROOT),
+            relaxed: false,
+            known_large: Vec::new(),
+            deadline: None,
+            partial: false,
+            timings: SelectionTimings::default(),
+            recently_killed: Vec::new(),
+            tie_picks: HashMap::new(),
+            tie_sequence: 0,
+            top_streaks: HashMap::new(),
+            validity_predicates: Vec::new(),
+            child_counts: HashMap::new(),
+            parents: HashMap::new(),
+            previous_child_counts: None,
+            recent_forks: HashMap::new(),
+            #[cfg(test)]
+            slow_stats: Duration::ZERO,
+            #[cfg(test)]
+            after_status: None,
+            #[cfg(feature = "test-util")]
+            refresh_hook: None,
+            #[cfg(feature = "proc-connector")]
+            tracker: None,
+            source: None,
         }
     }
 
+    /// 从指定的proc根目录枚举进程
+    ///
+    /// 只影响进程枚举，评分器和压力检测器需要各自设置
+    pub fn with_proc_root(mut self, proc_root: impl Into<PathBuf>) -> Self {
+        self.proc_root = proc_root.into();
+        self.reader = ProcReader::new(self.proc_root.clone());
+        self
+    }
+
+    /// 使用指定的cgroup挂载点读取 memory.low
+    pub fn with_cgroup_root(mut self, cgroup_root: impl Into<PathBuf>) -> Self {
+        self.cgroup_root = cgroup_root.into();
+        self
+    }
+
     /// 选择最适合终止的进程
     pub fn select_process(&mut self) -> Result<Option<ProcessId>> {
         // 检查系统是否真的处于内存压力状态
@@ -65,170 +638,2010 @@ impl ProcessSelector {
             return Ok(None);
         }
 
-        // 获取内存统计信息
-        let memory_stats = self.pressure_detector.get_memory_stats()?;
-        
-        // 获取并评分所有可能的候选进程
-        let candidates = self.get_candidates(&memory_stats)?;
+        // 获取内存统计信息（check_pressure 刚刚读取过）
+        let Some(memory_stats) = self.pressure_detector.last_stats().cloned() else {
+            return Ok(None);
+        };
         
-        // 如果没有足够的候选进程，返回None
-        if candidates.len() < self.config.min_candidates {
+        // 获取并评分所有可能的候选进程，没有足够的候选进程时返回None
+        if !self.score_candidates(&memory_stats)? {
+            return Ok(None);
+        }
+
+        // 选择得分最高的进程，分散终止时可能让位于其他应用
+        let Some(mut chosen) = best_candidate(self.candidates.iter()) else {
             return Ok(None);
+        };
+        if self.is_cooling(self.config.app_name(&chosen.score_details.process)) {
+            let other = best_candidate(
+                self.candidates.iter().filter(|c| !self.is_cooling(self.config.app_name(&c.score_details.process)))
+            );
+            if let Some(other) = other {
+                if self.yields_to(chosen.score_details.total_score, other.score_details.total_score) {
+                    chosen = other;
+                }
+            }
         }
 
-        // 选择得分最高的进程
-        Ok(candidates.into_iter()
-            .max_by_key(|c| OrderedFloat(c.score_details.total_score))
-            .map(|c| c.score_details.process.pid))
+        let (key, pid) = (chosen.key, chosen.score_details.process.pid);
+        if let Some(required) = self.config.confirm_scans {
+            if self.note_top(key) < required {
+                return Ok(None);
+            }
+        }
+        Ok(Some(pid))
     }
 
-    /// 获取所有候选进程
-    fn get_candidates(&self, memory_stats: &MemoryStats) -> Result<Vec<Candidate>> {
-        let mut candidates = BinaryHeap::new();
-        let processes = crate::linux::proc::get_all_processes()?;
+    /// 记录一次终止，启用分散终止时该应用在 `name_cooldown` 内让位于其他应用
+    pub fn note_kill(&mut self, comm: Comm) {
+        if !self.config.diversify_by_name {
+            return;
+        }
 
-        for process in processes {
-            if self.is_valid_candidate(&process, memory_stats) {
-                let score_details = self.scorer.calculate_score(
-                    process.clone(),
-                    memory_stats.total_memory
-                );
+        let cooldown = self.config.name_cooldown;
+        self.recently_killed.retain(|(name, at)| *name != comm && at.elapsed() < cooldown);
+        self.recently_killed.push((comm, Instant::now()));
+    }
 
-                let memory_saved = process.mem_info.vm_rss;
-                
-                candidates.push(Candidate {
-                    score_details,
-                    memory_saved,
-                });
+    /// 记录被选中的受害者，启用 `rotate_ties` 时下一次在接近最高分的候选中轮换
+    pub fn note_chosen(&mut self, key: ProcessKey) {
+        if self.config.rotate_ties.is_none() {
+            return;
+        }
 
-                // 限制候选进程数量
-                if candidates.len() > self.config.max_candidates {
-                    candidates.pop();
-                }
-            }
+        self.tie_sequence += 1;
+        self.tie_picks.insert(key, self.tie_sequence);
+    }
+
+    /// 启用 `rotate_ties` 时，在得分接近最高分的候选中把最久没有被选中的移到计划的第一位
+    ///
+    /// 得分相同的候选中，从未被选中的按计划中的顺序优先
+    pub fn rotate_ties(&self, plan: &mut KillPlan) {
+        let (Some(epsilon), Some(first)) = (self.config.rotate_ties, plan.first()) else {
+            return;
+        };
+
+        let chosen = plan.victims()
+            .enumerate()
+            .take_while(|(_, victim)| first.score - victim.score <= epsilon)
+            .min_by_key(|(_, victim)| self.tie_picks.get(&victim.key).copied().unwrap_or(0));
+        if let Some((position, _)) = chosen {
+            plan.move_to_front(position);
+        }
+    }
+
+    /// 启用 `confirm_scans` 时记录本轮排在计划第一位的候选，连续轮数不足时清空计划
+    ///
+    /// 确认之后计划只保留这一个候选，其余候选需要在之后的扫描中各自确认
+    pub fn confirm_top(&mut self, plan: &mut KillPlan) {
+        let Some(required) = self.config.confirm_scans else {
+            return;
+        };
+        let Some(first) = plan.first() else {
+            self.top_streaks.clear();
+            return;
+        };
+
+        let streak = self.note_top(first.key);
+        if streak >= required {
+            plan.truncate(1);
+        } else {
+            log::debug!(
+                "Top candidate {} ({}) confirmed in {}/{} scan(s), not eligible yet",
+                first.key.pid.as_raw(),
+                first.comm.as_str(),
+                streak,
+                required
+            );
+            plan.clear();
         }
+    }
 
-        Ok(candidates.into_sorted_vec())
+    /// 记录本轮排在第一位的候选，返回它连续排在第一位的轮数
+    fn note_top(&mut self, key: ProcessKey) -> u32 {
+        self.top_streaks.retain(|top, _| *top == key);
+        let streak = self.top_streaks.entry(key).or_insert(0);
+        *streak += 1;
+        *streak
     }
 
-    /// 检查进程是否是有效的候选者
-    fn is_valid_candidate(&self, process: &ProcessInfo, memory_stats: &MemoryStats) -> bool {
-        // 检查是否是系统进程
-        if !self.config.allow_system_processes && process.is_system_process() {
-            return false;
+    /// 启用分散终止时，把计划中第一个冷却中的应用让位于其他应用中得分最高的候选
+    ///
+    /// 只调整第一位：终止总是从计划的第一个仍然有效的候选开始
+    pub fn diversify_plan(&self, plan: &mut KillPlan) {
+        let Some(first) = plan.first() else {
+            return;
+        };
+        if !self.is_cooling(first.app.as_str()) {
+            return;
         }
 
-        // 检查内存使用是否达到最小阈值
-        if process.mem_info.vm_rss < self.config.min_memory_threshold {
-            return false;
+        let other = plan.victims()
+            .enumerate()
+            .find(|(_, victim)| !self.is_cooling(victim.app.as_str()));
+        if let Some((position, other)) = other {
+            if self.yields_to(first.score, other.score) {
+                plan.move_to_front(position);
+            }
         }
+    }
 
-        // 检查进程是否可以被OOM killer终止
-        if !process.is_oomable() {
+    /// 启用分散终止时，应用是否在终止后的冷却时间内
+    fn is_cooling(&self, name: &str) -> bool {
+        if !self.config.diversify_by_name {
             return false;
         }
 
-        // 检查终止该进程是否能显著改善内存状况
-        let memory_impact = process.mem_info.vm_rss as f64 / memory_stats.total_memory as f64;
-        memory_impact >= 0.01 // 至少释放1%的系统内存
+        let comm = Comm::new(name);
+        self.recently_killed.iter()
+            .any(|(killed, at)| *killed == comm && at.elapsed() < self.config.name_cooldown)
     }
 
-    /// 获取选择器的当前状态信息
-    pub fn get_status(&self) -> Result<SelectorStatus> {
-        let pressure_info = self.pressure_detector.get_pressure_info()?;
-        
-        Ok(SelectorStatus {
-            memory_stats: pressure_info.stats,
-            pressure_duration: pressure_info.pressure_duration,
-            last_check: pressure_info.last_check,
-        })
+    /// 冷却中得分为 `cooling` 的应用是否应该让位于得分为 `other` 的其他应用
+    fn yields_to(&self, cooling: f64, other: f64) -> bool {
+        cooling < other * self.config.name_dominance
     }
-}
 
-/// 用于比较浮点数的包装类型
-#[derive(Debug, Copy, Clone, PartialEq)]
-struct OrderedFloat(f64);
+    /// 按最近一次 `check_pressure` 的内存统计评分，把得分最高的候选写入终止计划
+    ///
+    /// 没有足够的候选进程时计划为空。这一步会分配内存，
+    /// 应在压力上升阶段执行，使持续压力下的终止不再需要评分
+    pub fn plan_victims(&mut self, plan: &mut KillPlan) -> Result<()> {
+        plan.clear();
+        let Some(memory_stats) = self.pressure_detector.last_stats().cloned() else {
+            return Ok(());
+        };
+
+        if !self.score_candidates(&memory_stats)? {
+            return Ok(());
+        }
 
-impl Eq for OrderedFloat {}
+        for candidate in &self.candidates {
+            let process = &candidate.score_details.process;
+            plan.insert(PlannedVictim {
+                key: candidate.key,
+                comm: Comm::new(&process.name),
+                app: Comm::new(self.config.app_name(process)),
+                rss_kb: process.mem_info.vm_rss,
+                score: candidate.score_details.total_score,
+                partial: self.partial,
+            });
+        }
+        self.rotate_ties(plan);
+        self.diversify_plan(plan);
 
-impl PartialOrd for OrderedFloat {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.0.partial_cmp(&other.0)
+        Ok(())
     }
-}
 
-impl Ord for OrderedFloat {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.partial_cmp(other).unwrap_or(Ordering::Equal)
+    /// 注册自定义的候选有效性判断
+    ///
+    /// 进程必须通过内置检查和所有注册的判断（与关系）才是候选，用于实现
+    /// 配置项没有覆盖的策略。判断在读取OOM分数之后、评分之前执行，
+    /// 对每个通过内置检查的进程每轮调用一次，应当足够快且不阻塞
+    pub fn add_validity_predicate(
+        &mut self,
+        predicate: impl Fn(&ProcessInfo, &MemoryStats) -> bool + Send + Sync + 'static,
+    ) {
+        self.validity_predicates.push(ValidityPredicate(Box::new(predicate)));
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::time::Duration;
+    /// 进程是否通过所有注册的有效性判断
+    fn passes_predicates(&self, process: &ProcessInfo, memory_stats: &MemoryStats) -> bool {
+        self.validity_predicates.iter().all(|predicate| (predicate.0)(process, memory_stats))
+    }
 
-    #[test]
-    fn test_process_selection() {
-        let config = SelectorConfig {
-            min_candidates: 1,
-            max_candidates: 5,
-            allow_system_processes: false,
-            min_memory_threshold: 1024 * 1024,
-        };
+    /// 从增量维护的进程表获取进程，而不是每次重新扫描 /proc
+    #[cfg(feature = "proc-connector")]
+    pub fn with_process_tracker(mut self, tracker: crate::linux::proc_connector::ProcessTracker) -> Self {
+        self.tracker = Some(tracker);
+        self
+    }
 
-        let scorer = OOMScorer::new();
-        let pressure_detector = PressureDetector::new(None);
-        let mut selector = ProcessSelector::new(
-            Some(config),
-            scorer,
-            pressure_detector
-        );
+    /// 与 `with_process_tracker` 相同，用于已经创建的选择器
+    #[cfg(feature = "proc-connector")]
+    pub fn set_process_tracker(&mut self, tracker: crate::linux::proc_connector::ProcessTracker) {
+        self.tracker = Some(tracker);
+    }
 
-        // 测试进程选择
-        match selector.select_process() {
-            Ok(Some(pid)) => {
-                // 验证选中的进程
-                let process = ProcessInfo::from_pid(pid).unwrap();
-                assert!(process.mem_info.vm_rss >= 1024 * 1024);
-                assert!(process.is_oomable());
-            }
-            Ok(None) => {
-                // 系统可能没有处于内存压力状态
-                println!("No process selected (system might not be under memory pressure)");
-            }
-            Err(e) => panic!("Process selection failed: {:?}", e),
+    /// 从外部来源获取进程，而不是扫描 /proc
+    ///
+    /// 来源返回的记录直接使用，不再读取进程的stat、cgroup和exe：运行时间按未知处理，
+    /// 控制终端保护、同cgroup排除和 `match_exe_name` 不生效。优先于 `monitored_pids` 和进程表
+    pub fn with_process_source(mut self, source: Box<dyn ProcessSource>) -> Self {
+        self.source = Some(SourceBox(source));
+        self
+    }
+
+    /// 每轮枚举进程之后调用 `hook`，用于模拟在枚举之后退出的进程
+    #[cfg(feature = "test-util")]
+    pub fn with_refresh_hook(mut self, hook: impl FnMut() + Send + 'static) -> Self {
+        self.refresh_hook = Some(crate::test_util::RefreshHook::new(hook));
+        self
+    }
+
+    /// 枚举本轮要评估的进程到 `pids`，上一轮的大进程排在最前
+    ///
+    /// 扫描 /proc 时只读取目录项，进程文件在评估时才读取；
+    /// 使用进程表时 `processes` 与 `pids` 一一对应，包含表中缓存的status；
+    /// 配置了 `monitored_pids` 时只列出这些进程（及其后代），不扫描 /proc
+    fn refresh_processes(&mut self) -> Result<()> {
+        if let Some(monitored) = &self.config.monitored_pids {
+            self.reader.collect_pids(&mut self.pids, monitored, self.config.monitor_descendants);
+            self.pids.sort_by_key(|pid| !self.known_large.contains(pid));
+            return Ok(());
         }
+
+        #[cfg(feature = "proc-connector")]
+        if let Some(tracker) = &self.tracker {
+            self.processes = tracker.processes()?;
+            self.processes.sort_by_key(|p| !self.known_large.contains(&p.pid));
+            self.pids.clear();
+            self.pids.extend(self.processes.iter().map(|p| p.pid));
+            return Ok(());
+        }
+
+        self.reader.scan_pids(&mut self.pids, &self.known_large)
     }
 
-    #[test]
-    fn test_candidate_filtering() {
-        let config = SelectorConfig::default();
-        let scorer = OOMScorer::new();
-        let pressure_detector = PressureDetector::new(None);
-        let selector = ProcessSelector::new(
-            Some(config),
-            scorer,
-            pressure_detector
+    /// `processes` 中是否已经有进程表缓存的status
+    fn preloaded(&self) -> bool {
+        #[cfg(feature = "proc-connector")]
+        if self.tracker.is_some() && self.config.monitored_pids.is_none() {
+            return true;
+        }
+        false
+    }
+
+    /// 本轮选择的时间预算是否已经用尽
+    ///
+    /// 上一轮的大进程总是会被评估
+    fn budget_exhausted(&mut self, position: usize) -> bool {
+        let Some(deadline) = self.deadline else {
+            return false;
+        };
+        if position < self.known_large.len() || Instant::now() < deadline {
+            return false;
+        }
+
+        self.partial = true;
+        true
+    }
+
+    /// 记住本轮RSS最大的候选，下一轮优先评估
+    fn remember_large(&mut self) {
+        let processes = &self.processes;
+        self.shortlisted.sort_unstable_by_key(|&(index, _)| Reverse(processes[index].mem_info.vm_rss));
+
+        self.known_large.clear();
+        self.known_large.extend(
+            self.shortlisted.iter()
+                .take(KNOWN_LARGE_PROCESSES)
+                .map(|&(_, key)| key.pid)
         );
+    }
 
-        let memory_stats = MemoryStats {
-            total_memory: 8 * 1024 * 1024 * 1024, // 8GB
-            free_memory: 4 * 1024 * 1024 * 1024,  // 4GB
-            available_memory: 4 * 1024 * 1024 * 1024,
-            total_swap: 1024 * 1024 * 1024,
-            free_swap: 512 * 1024 * 1024,
-            cached_memory: 1024 * 1024 * 1024,
+    /// 最近一次选择是否因时间预算用尽而提前结束
+    pub fn last_selection_partial(&self) -> bool {
+        self.partial
+    }
+
+    /// 最近一次选择中枚举和评分的耗时
+    pub fn last_selection_timings(&self) -> SelectionTimings {
+        self.timings
+    }
+
+    /// 最近一次选择是否因为严格过滤没有结果而使用了放宽的阈值
+    pub fn last_selection_relaxed(&self) -> bool {
+        self.relaxed
+    }
+
+    /// 评分候选进程，返回是否有足够的候选
+    ///
+    /// 严格过滤的候选数不足 `min_candidates` 且压力已经持续时，
+    /// 按 `relaxed` 阈值重试一次，此时只要有一个候选即可
+    fn score_candidates(&mut self, memory_stats: &MemoryStats) -> Result<bool> {
+        self.relaxed = false;
+        self.partial = false;
+        self.timings = SelectionTimings::default();
+        self.deadline = self.config.selection_budget.map(|budget| Instant::now() + budget);
+
+        self.fill_candidates(memory_stats, self.config.strict_filter(memory_stats))?;
+        if self.candidates.len() >= self.config.min_candidates {
+            self.finish_pass();
+            return Ok(true);
+        }
+
+        let Some(filter) = self.config.relaxed_filter() else {
+            self.finish_pass();
+            return Ok(false);
         };
+        if !self.pressure_detector.is_sustained() {
+            self.finish_pass();
+            return Ok(false);
+        }
 
-        // 创建测试进程
-        let test_process = ProcessInfo::new_test(
-            ProcessId::new(1).unwrap(),
-            "test",
-            2 * 1024 * 1024 * 1024, // 2GB RSS
-            0
+        self.relaxed = true;
+        self.fill_candidates(memory_stats, filter)?;
+        log::warn!(
+            "No candidate passed strict filters under sustained pressure, relaxed selection found {}",
+            self.candidates.len()
         );
+        self.finish_pass();
 
-        assert!(selector.is_valid_candidate(&test_process, &memory_stats));
+        Ok(!self.candidates.is_empty())
     }
-} 
\ No newline at end of file
+
+    /// 一轮选择结束时的收尾
+    fn finish_pass(&mut self) {
+        self.remember_large();
+        self.deadline = None;
+        if self.partial {
+            log::warn!(
+                "Selection budget exhausted, using the best of {} candidates found so far",
+                self.candidates.len()
+            );
+        } else {
+            // 严格过滤之后可能还有放宽的一轮，按最后一轮看到的进程清理轮换记录
+            self.tie_picks.retain(|key, _| self.live.contains(key));
+        }
+    }
+
+    /// 评分所有候选进程，保留在 `candidates` 中
+    fn fill_candidates(&mut self, memory_stats: &MemoryStats, filter: MemoryFilter) -> Result<()> {
+        let enumeration_start = Instant::now();
+        self.shortlist_with(memory_stats, filter)?;
+        let scoring_start = Instant::now();
+        self.timings.enumeration += scoring_start - enumeration_start;
+        self.candidates.clear();
+        let max_candidates = self.config.effective_max_candidates(memory_stats);
+
+        for position in 0..self.shortlisted.len() {
+            if self.budget_exhausted(position) {
+                break;
+            }
+
+            let (index, key) = self.shortlisted[position];
+            let process = &self.processes[index];
+            let score_details = self.score(process, key, memory_stats.total_memory);
+            if !has_finite_score(&score_details) {
+                continue;
+            }
+
+            let memory_saved = process.mem_info.vm_rss;
+            
+            self.candidates.push(Candidate {
+                key,
+                score_details,
+                memory_saved,
+            });
+
+            // 限制候选进程数量
+            if self.candidates.len() > max_candidates {
+                self.candidates.pop();
+            }
+        }
+        self.timings.scoring += scoring_start.elapsed();
+
+        Ok(())
+    }
+
+    /// 枚举进程并记录所有有效候选者在 `processes` 中的下标
+    ///
+    /// 先只读取status，通过初步过滤的进程才补充stat和OOM分数，
+    /// 避免每轮为系统中的每个进程额外打开多个文件
+    fn shortlist(&mut self, memory_stats: &MemoryStats) -> Result<()> {
+        self.shortlist_with(memory_stats, self.config.strict_filter(memory_stats))
+    }
+
+    /// 按指定的内存阈值枚举候选进程
+    ///
+    /// 扫描 /proc 时 `processes` 只保留候选，其余进程的位置被下一个进程复用
+    fn shortlist_with(&mut self, memory_stats: &MemoryStats, filter: MemoryFilter) -> Result<()> {
+        if self.source.is_some() {
+            return self.shortlist_from_source(memory_stats, filter);
+        }
+
+        self.refresh_processes()?;
+        #[cfg(feature = "test-util")]
+        if let Some(hook) = &mut self.refresh_hook {
+            hook.call();
+        }
+
+        self.live.clear();
+        self.shortlisted.clear();
+        self.child_counts.clear();
+        self.parents.clear();
+        let preloaded = self.preloaded();
+
+        for position in 0..self.pids.len() {
+            if self.budget_exhausted(position) {
+                break;
+            }
+
+            let pid = self.pids[position];
+            let index = if preloaded {
+                // 内核线程没有RSS，在这里就会被过滤掉，需要先记录父进程
+                self.parents.insert(pid.as_raw(), self.processes[position].ppid);
+                // 缓存的status可能已经过期，只用来排除明显不合格的进程
+                if !self.config.passes_prefilter(&self.processes[position], memory_stats, filter) {
+                    continue;
+                }
+                position
+            } else {
+                let index = self.shortlisted.len();
+                if index == self.processes.len() {
+                    self.processes.push(ProcessInfo::parse_status(pid, ""));
+                }
+                index
+            };
+
+            // 进程可能在枚举之后已经退出
+            let Some(key) = self.evaluate(index, pid, memory_stats, filter) else {
+                continue;
+            };
+            self.live.insert(key);
+
+            let process = &self.processes[index];
+            if self.config.is_eligible(process) && self.passes_predicates(process, memory_stats) {
+                self.shortlisted.push((index, key));
+            }
+        }
+        // 提前结束时没有看到所有进程，不能据此清理缓存
+        if !self.partial {
+            self.oom_scores.retain(&self.live);
+        }
+        self.exclude_kernel_threads();
+        self.spare_recent_forks();
+
+        Ok(())
+    }
+
+    /// 从外部来源获取进程并过滤，来源的记录已经完整，不读取任何进程文件
+    fn shortlist_from_source(&mut self, memory_stats: &MemoryStats, filter: MemoryFilter) -> Result<()> {
+        let Some(source) = &self.source else {
+            return Ok(());
+        };
+        self.processes = source.0.processes()?;
+        self.processes.sort_by_key(|p| !self.known_large.contains(&p.pid));
+        self.pids.clear();
+        self.pids.extend(self.processes.iter().map(|p| p.pid));
+
+        self.live.clear();
+        self.shortlisted.clear();
+        self.child_counts.clear();
+        self.parents.clear();
+        for (index, process) in self.processes.iter().enumerate() {
+            if self.config.fork_grace.is_some() {
+                *self.child_counts.entry(process.ppid).or_default() += 1;
+            }
+            self.parents.insert(process.pid.as_raw(), process.ppid);
+            // 来源不提供启动时间，按pid区分进程
+            let key = ProcessKey { pid: process.pid, start_time: 0 };
+            self.live.insert(key);
+
+            // 来源不提供cgroup，kill_only只按进程名和uid匹配
+            if self.config.passes_prefilter(process, memory_stats, filter)
+                && self.config.in_kill_scope(process)
+                && self.config.is_eligible(process)
+                && self.passes_predicates(process, memory_stats)
+            {
+                self.shortlisted.push((index, key));
+            }
+        }
+        self.exclude_kernel_threads();
+        self.spare_recent_forks();
+
+        Ok(())
+    }
+
+    /// 对候选评分，来自外部来源的进程没有启动时间，运行时间按未知处理
+    fn score(&self, process: &ProcessInfo, key: ProcessKey, total_memory: u64) -> OOMScoreDetails {
+        let details = if self.source.is_some() {
+            self.scorer.calculate_score_with_runtime(process.clone(), None, total_memory)
+        } else {
+            self.scorer.calculate_score_with_start_time(process.clone(), key.start_time, total_memory)
+        };
+        self.config.ranked(details)
+    }
+
+    /// 从候选中去掉kthreadd及其后代，即内核线程
+    ///
+    /// 按本轮扫描到的ppid逐级向上查找，不依赖进程名；总是生效，与配置无关
+    fn exclude_kernel_threads(&mut self) {
+        let Self { shortlisted, processes, parents, .. } = self;
+        shortlisted.retain(|&(index, _)| {
+            let process = &processes[index];
+            let kernel_thread = descends_from_kthreadd(parents, process.pid.as_raw(), process.ppid);
+            if kernel_thread {
+                log::debug!("Excluding kernel thread {} ({})", process.pid.as_raw(), process.name);
+            }
+            !kernel_thread
+        });
+    }
+
+    /// 启用 `fork_grace` 时，比较两次完整扫描的子进程数，
+    /// 从候选中去掉子进程数刚刚增加的进程
+    fn spare_recent_forks(&mut self) {
+        let Some(grace) = self.config.fork_grace else {
+            return;
+        };
+        let now = Instant::now();
+
+        // 提前结束的扫描没有看到所有子进程，不参与比较
+        if !self.partial {
+            if let Some(previous) = &self.previous_child_counts {
+                for (&ppid, &count) in &self.child_counts {
+                    if count > previous.get(&ppid).copied().unwrap_or(0) {
+                        self.recent_forks.insert(ppid, now);
+                    }
+                }
+            }
+            let previous = self.previous_child_counts.get_or_insert_with(HashMap::new);
+            std::mem::swap(previous, &mut self.child_counts);
+        }
+
+        self.recent_forks.retain(|_, at| now.duration_since(*at) < grace);
+        if self.recent_forks.is_empty() {
+            return;
+        }
+
+        let Self { shortlisted, processes, recent_forks, .. } = self;
+        shortlisted.retain(|&(index, _)| {
+            let process = &processes[index];
+            let forking = recent_forks.contains_key(&process.pid.as_raw());
+            if forking {
+                log::debug!("Sparing {} ({}) after recent fork", process.pid.as_raw(), process.name);
+            }
+            !forking
+        });
+    }
+
+    /// 评估一个进程，把它的status和OOM分数写入 `processes[index]`
+    ///
+    /// /proc/<pid> 目录只打开一次，status、stat 和OOM分数都通过它读取：
+    /// pid在评估期间被复用时，要么全部读到新进程，要么因为原来的进程
+    /// 已经退出而读取失败，不会把两个进程的数据混在一起。
+    /// 返回通过初步过滤的进程的唯一标识
+    fn evaluate(
+        &mut self,
+        index: usize,
+        pid: ProcessId,
+        memory_stats: &MemoryStats,
+        filter: MemoryFilter,
+    ) -> Option<ProcessKey> {
+        let dir = self.reader.read_process(pid, &mut self.processes[index]).ok()?;
+        self.parents.insert(pid.as_raw(), self.processes[index].ppid);
+        if self.config.fork_grace.is_some() {
+            *self.child_counts.entry(self.processes[index].ppid).or_default() += 1;
+        }
+        if !self.config.passes_prefilter(&self.processes[index], memory_stats, filter) {
+            return None;
+        }
+
+        #[cfg(test)]
+        {
+            std::thread::sleep(self.slow_stats);
+            if let Some(after_status) = self.after_status {
+                after_status(&self.proc_root);
+            }
+        }
+
+        let stat = self.reader.read_stat(&dir, pid).ok()?;
+        if self.shares_controlling_tty(&stat) || self.shares_cgroup(&dir) {
+            return None;
+        }
+        if self.config.protect_realtime && stat.is_realtime() {
+            log::debug!("Protecting real-time scheduled process {} ({})", pid.as_raw(), stat.comm);
+            return None;
+        }
+
+        if self.config.match_exe_name {
+            // 没有权限或内核线程时没有可执行文件名，按comm匹配
+            self.processes[index].exe_name = dir.read_link("exe").ok().and_then(|target| exe_basename(&target));
+        }
+
+        if self.config.matches_cgroups() {
+            self.processes[index].memory_cgroup = self.reader.read_cgroup(&dir).ok().map(|cgroup| cgroup.memory_path);
+        }
+        if !self.config.in_kill_scope(&self.processes[index]) {
+            return None;
+        }
+
+        if self.config.memory_low != MemoryLowPolicy::Ignore {
+            let memory_low = self.cgroup_memory_low(&dir);
+            if memory_low > 0 && self.config.memory_low == MemoryLowPolicy::Exclude {
+                return None;
+            }
+            self.processes[index].cgroup_memory_low = Some(memory_low);
+        }
+
+        if self.config.rss_limit_penalty.is_some() {
+            let mut content = String::new();
+            self.processes[index].limits =
+                dir.read_to_string("limits", &mut content).ok().map(|()| ResourceLimits::parse(&content));
+        }
+
+        let key = stat.key();
+        self.oom_scores.enrich(&dir, key, &mut self.processes[index]).ok()?;
+        Some(key)
+    }
+
+    /// 最近一轮通过过滤的候选进程
+    #[cfg(test)]
+    fn shortlisted(&self) -> impl Iterator<Item = &ProcessInfo> {
+        self.shortlisted.iter().map(|&(index, _)| &self.processes[index])
+    }
+
+    /// 对所有有效候选进程评分，按总分降序排列
+    fn score_all_candidates(&mut self) -> Result<Vec<OOMScoreDetails>> {
+        let memory_stats = self.pressure_detector.get_memory_stats()?;
+        self.shortlist(&memory_stats)?;
+
+        let mut scored: Vec<OOMScoreDetails> = self.shortlisted.iter()
+            .map(|&(index, key)| self.score(&self.processes[index], key, memory_stats.total_memory))
+            .filter(has_finite_score)
+            .collect();
+        scored.sort_by(|a, b| rank_order(b, a));
+
+        Ok(scored)
+    }
+
+    /// 估算要回收 `target_bytes` 需要按顺序终止哪些进程，不终止任何进程
+    ///
+    /// 按评分排名依次累加候选的RSS，达到目标即停止。不要求处于内存压力下，
+    /// 使用严格过滤，不做分散终止和轮换的调整，用于在行动之前了解范围
+    pub fn plan_to_free(&mut self, target_bytes: u64) -> Result<ReclaimPlan> {
+        let mut plan = ReclaimPlan { target_bytes, victims: Vec::new(), reaches_target: target_bytes == 0 };
+        if plan.reaches_target {
+            return Ok(plan);
+        }
+
+        let mut cumulative_bytes = 0u64;
+        for details in self.score_all_candidates()? {
+            let estimated_bytes = details.process.mem_info.vm_rss.saturating_mul(1024);
+            cumulative_bytes = cumulative_bytes.saturating_add(estimated_bytes);
+            plan.victims.push(ReclaimStep {
+                pid: details.process.pid,
+                name: details.process.name,
+                score: details.total_score,
+                estimated_bytes,
+                cumulative_bytes,
+            });
+            if cumulative_bytes >= target_bytes {
+                plan.reaches_target = true;
+                break;
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// 以CSV格式导出所有候选进程的评分排名，按总分降序
+    ///
+    /// 列：pid,name,rss_bytes,swap_bytes,oom_score_adj,memory_score,runtime_score,adj_score,total_score
+    pub fn rankings_csv(&mut self) -> Result<String> {
+        Ok(format_rankings_csv(&self.score_all_candidates()?))
+    }
+
+    /// 检查进程是否是有效的候选者
+    #[cfg(test)]
+    fn is_valid_candidate(&self, process: &ProcessInfo, memory_stats: &MemoryStats) -> bool {
+        // 检查进程是否可以被OOM killer终止
+        self.config.passes_prefilter(process, memory_stats, self.config.strict_filter(memory_stats))
+            && self.config.is_eligible(process)
+            && self.passes_predicates(process, memory_stats)
+    }
+
+    /// 进程是否与本进程共享控制终端
+    fn shares_controlling_tty(&self, stat: &ProcessStat) -> bool {
+        self.controlling_tty == Some(stat.tty_nr)
+    }
+
+    /// 进程是否与本进程处于同一个 memory cgroup，无法读取进程的cgroup时视为不同
+    fn shares_cgroup(&mut self, dir: &ProcessDir) -> bool {
+        let Some(own) = &self.own_cgroup else {
+            return false;
+        };
+        match self.reader.read_cgroup(dir) {
+            Ok(cgroup) => cgroup == *own,
+            Err(_) => false,
+        }
+    }
+
+    /// 进程所在cgroup的 memory.low，只支持cgroup v2，无法读取时为0
+    fn cgroup_memory_low(&mut self, dir: &ProcessDir) -> u64 {
+        let Ok(cgroup) = self.reader.read_cgroup(dir) else {
+            return 0;
+        };
+        if cgroup.version != CgroupVersion::V2 {
+            return 0;
+        }
+        let cgroup_dir = self.cgroup_root.join(cgroup.memory_path.trim_start_matches('/'));
+        cgroup::read_memory_low(&cgroup_dir).unwrap_or(0)
+    }
+
+    /// 只检查内存压力，不扫描进程
+    pub fn check_pressure(&mut self) -> Result<bool> {
+        self.pressure_detector.check_pressure()
+    }
+
+    /// 最近一次 `check_pressure` 读取的内存统计信息
+    pub fn last_memory_stats(&self) -> Option<&MemoryStats> {
+        self.pressure_detector.last_stats()
+    }
+
+    /// 当前RSS最高的n个进程，见 `PressureDetector::top_consumers`
+    pub fn top_consumers(&self, n: usize) -> Result<Vec<ProcessSummary>> {
+        self.pressure_detector.top_consumers(n)
+    }
+
+    /// 枚举进程使用的proc根目录
+    pub fn proc_root(&self) -> &Path {
+        &self.proc_root
+    }
+
+    /// 锁定压力检查复用的缓冲区
+    pub fn lock_buffers(&self) -> Result<()> {
+        self.pressure_detector.lock_buffers()
+    }
+
+    /// 最近一次检查时的内存压力级别
+    pub fn pressure_level(&self) -> PressureLevel {
+        self.pressure_detector.level()
+    }
+
+    /// 选择器使用的评分器
+    pub fn scorer(&self) -> &OOMScorer {
+        &self.scorer
+    }
+
+    /// 选择器使用的压力检测器
+    pub fn pressure_detector(&self) -> &PressureDetector {
+        &self.pressure_detector
+    }
+
+    /// 获取选择器的当前状态信息
+    pub fn get_status(&self) -> Result<SelectorStatus> {
+        let pressure_info = self.pressure_detector.get_pressure_info()?;
+        
+        Ok(SelectorStatus {
+            memory_stats: pressure_info.stats,
+            pressure_duration: pressure_info.pressure_duration,
+            last_check: pressure_info.last_check,
+        })
+    }
+}
+
+/// 本进程的控制终端，没有控制终端时返回None
+fn current_controlling_tty() -> Option<i32> {
+    let pid = ProcessId::new(std::process::id() as i32)?;
+    ProcessStat::from_pid(pid)
+        .ok()
+        .map(|stat| stat.tty_nr)
+        .filter(|&tty| tty != 0)
+}
+
+/// 本进程的 memory cgroup，无法读取时记录警告并返回None
+fn current_cgroup() -> Option<CgroupInfo> {
+    CgroupInfo::current()
+        .map_err(|e| log::warn!("Failed to read own cgroup, same-cgroup processes are not excluded: {:?}", e))
+        .ok()
+}
+
+/// CSV表头
+const RANKINGS_CSV_HEADER: &str =
+    "pid,name,rss_bytes,swap_bytes,oom_score_adj,memory_score,runtime_score,adj_score,total_score";
+
+/// 将已排序的评分结果格式化为CSV
+fn format_rankings_csv(scored: &[OOMScoreDetails]) -> String {
+    let mut csv = String::from(RANKINGS_CSV_HEADER);
+    csv.push('\n');
+
+    for details in scored {
+        let process = &details.process;
+        csv.push_str(&format!(
+            "{},{},{},{},{},{:.6},{:.6},{:.6},{:.6}\n",
+            process.pid.as_raw(),
+            escape_csv_field(&process.name),
+            process.mem_info.vm_rss.saturating_mul(1024),
+            process.mem_info.vm_swap.saturating_mul(1024),
+            process.mem_info.oom_score_adj,
+            details.memory_score,
+            details.runtime_score,
+            details.adj_score,
+            details.total_score,
+        ));
+    }
+
+    csv
+}
+
+/// 按RFC 4180转义CSV字段：包含逗号、引号或换行时加引号，内部引号加倍
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// 评分是否是有限值，NaN或无穷大的评分来自上游的异常数据，记录警告后丢弃
+fn has_finite_score(details: &OOMScoreDetails) -> bool {
+    if details.total_score.is_finite() {
+        return true;
+    }
+
+    log::warn!(
+        "Discarding candidate {} ({}) with non-finite score {}",
+        details.process.pid.as_raw(),
+        details.process.name,
+        details.total_score
+    );
+    false
+}
+
+/// 候选的排序：总分高者在后；总分相同时RSS大者在后，再相同时pid小者在后
+///
+/// 只用于有限的评分。结果不依赖候选的遍历顺序，相同输入总是选出同一个进程
+fn rank_order(a: &OOMScoreDetails, b: &OOMScoreDetails) -> Ordering {
+    a.total_score.total_cmp(&b.total_score)
+        .then(a.process.mem_info.vm_rss.cmp(&b.process.mem_info.vm_rss))
+        .then(b.process.pid.as_raw().cmp(&a.process.pid.as_raw()))
+}
+
+/// 进程是否是kthreadd或它的后代
+///
+/// 祖先没有被扫描到（例如只监视部分进程）时无法继续向上，只检查到那里为止
+fn descends_from_kthreadd(parents: &HashMap<i32, i32>, pid: i32, ppid: i32) -> bool {
+    if pid == KTHREADD_PID {
+        return true;
+    }
+    let mut ancestor = ppid;
+    // 扫描期间pid被复用可能形成环，最多查找扫描到的进程数那么多层
+    for _ in 0..=parents.len() {
+        match ancestor {
+            KTHREADD_PID => return true,
+            0 | 1 => return false,
+            _ => match parents.get(&ancestor) {
+                Some(&parent) => ancestor = parent,
+                None => return false,
+            },
+        }
+    }
+    false
+}
+
+/// 评分最高的候选，跳过评分不是有限值的候选
+fn best_candidate<'a>(candidates: impl Iterator<Item = &'a Candidate>) -> Option<&'a Candidate> {
+    candidates
+        .filter(|candidate| has_finite_score(&candidate.score_details))
+        .max_by(|a, b| rank_order(&a.score_details, &b.score_details))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Duration;
+    use crate::oom::pressure::PressureThresholds;
+    use crate::linux::proc::Completeness;
+    use crate::test_util::{write_process, FakeProc, MemInfoSpec, ProcessGroup, RssDistribution};
+
+    #[test]
+    fn test_process_selection() {
+        let config = SelectorConfig {
+            min_candidates: 1,
+            max_candidates: 5,
+            headroom_target: None,
+            use_kernel_oom_score_only: false,
+            monitored_pids: None,
+            monitor_descendants: false,
+            allow_system_processes: false,
+            min_memory_threshold: 1024 * 1024,
+            swap_weight: 1.0,
+            protect_controlling_tty: false,
+            protect_realtime: true,
+            allow_same_cgroup: true,
+            min_oom_score_adj: -1000,
+            relaxed: None,
+            selection_budget: None,
+            diversify_by_name: false,
+            match_exe_name: false,
+            rotate_ties: None,
+            confirm_scans: None,
+            name_cooldown: Duration::from_secs(60),
+            name_dominance: 2.0,
+            fork_grace: None,
+            rss_limit_penalty: None,
+            memory_low: MemoryLowPolicy::Ignore,
+            kill_only: None,
+            importance_tiers: Vec::new(),
+        };
+
+        let scorer = OOMScorer::new();
+        let pressure_detector = PressureDetector::new(None);
+        let mut selector = ProcessSelector::new(
+            Some(config),
+            scorer,
+            pressure_detector
+        );
+
+        // 测试进程选择
+        match selector.select_process() {
+            Ok(Some(pid)) => {
+                // 验证选中的进程
+                let process = ProcessInfo::from_pid(pid).unwrap();
+                assert!(process.mem_info.vm_rss >= 1024 * 1024);
+                assert!(process.is_oomable());
+            }
+            Ok(None) => {
+                // 系统可能没有处于内存压力状态
+                println!("No process selected (system might not be under memory pressure)");
+            }
+            Err(e) => panic!("Process selection failed: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_candidate_filtering() {
+        let config = SelectorConfig::default();
+        let scorer = OOMScorer::new();
+        let pressure_detector = PressureDetector::new(None);
+        let selector = ProcessSelector::new(
+            Some(config),
+            scorer,
+            pressure_detector
+        );
+
+        let memory_stats = MemoryStats {
+            total_memory: 8 * 1024 * 1024 * 1024, // 8GB
+            free_memory: 4 * 1024 * 1024 * 1024,  // 4GB
+            available_memory: 4 * 1024 * 1024 * 1024,
+            total_swap: 1024 * 1024 * 1024,
+            free_swap: 512 * 1024 * 1024,
+            cached_memory: 1024 * 1024 * 1024,
+            ..MemoryStats::default()
+        };
+
+        // 创建测试进程
+        let test_process = ProcessInfo::new_test(
+            ProcessId::new(1).unwrap(),
+            "test",
+            2 * 1024 * 1024 * 1024, // 2GB RSS
+            0
+        );
+
+        assert!(selector.is_valid_candidate(&test_process, &memory_stats));
+    }
+
+    #[test]
+    fn test_swapped_out_process_is_candidate() {
+        let memory_stats = MemoryStats {
+            total_memory: 8 * 1024 * 1024 * 1024,
+            available_memory: 4 * 1024 * 1024 * 1024,
+            ..MemoryStats::default()
+        };
+        // 完全换出的进程：没有常驻内存，swap很大
+        let mut process = ProcessInfo::new_test(ProcessId::new(5000).unwrap(), "swapped", 0, 0);
+        process.uid = 1000;
+        process.mem_info.vm_swap = 2 * 1024 * 1024 * 1024;
+
+        let selector_with = |swap_weight: f64| ProcessSelector::new(
+            Some(SelectorConfig { swap_weight, ..SelectorConfig::default() }),
+            OOMScorer::new(),
+            PressureDetector::new(None),
+        );
+
+        assert!(selector_with(1.0).is_valid_candidate(&process, &memory_stats));
+        // 只看RSS时被排除
+        assert!(!selector_with(0.0).is_valid_candidate(&process, &memory_stats));
+    }
+
+    #[test]
+    fn test_incomplete_records_not_selected() {
+        let memory_stats = MemoryStats {
+            total_memory: 8 * 1024 * 1024 * 1024,
+            available_memory: 4 * 1024 * 1024 * 1024,
+            ..MemoryStats::default()
+        };
+        let mut process = ProcessInfo::new_test(ProcessId::new(5000).unwrap(), "daemon", 2 * 1024 * 1024 * 1024, 0);
+        let selector_with = |use_kernel_oom_score_only: bool| ProcessSelector::new(
+            Some(SelectorConfig { use_kernel_oom_score_only, ..SelectorConfig::default() }),
+            OOMScorer::new(),
+            PressureDetector::new(None),
+        );
+        assert!(selector_with(false).is_valid_candidate(&process, &memory_stats));
+
+        // 没有读到oom_score只影响按内核分数排序
+        process.completeness.oom_score_ok = false;
+        assert!(selector_with(false).is_valid_candidate(&process, &memory_stats));
+        assert!(!selector_with(true).is_valid_candidate(&process, &memory_stats));
+
+        // 没有读到oom_score_adj时不知道进程是否受保护
+        process.completeness = Completeness::STATUS_ONLY;
+        assert!(!selector_with(false).is_valid_candidate(&process, &memory_stats));
+    }
+
+    #[test]
+    fn test_rankings_csv_format() {
+        let scorer = OOMScorer::new();
+        let total_memory = 8 * 1024 * 1024 * 1024;
+
+        let plain = scorer.calculate_score(
+            ProcessInfo::new_test(ProcessId::new(10).unwrap(), "plain", 1024 * 1024, 0),
+            total_memory,
+        );
+        let tricky = scorer.calculate_score(
+            ProcessInfo::new_test(ProcessId::new(11).unwrap(), "worker,\"beta\"", 2 * 1024 * 1024, 0),
+            total_memory,
+        );
+
+        let mut scored = vec![plain, tricky];
+        scored.sort_by(|a, b| b.cmp(a));
+        let csv = format_rankings_csv(&scored);
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(
+            lines[0],
+            "pid,name,rss_bytes,swap_bytes,oom_score_adj,memory_score,runtime_score,adj_score,total_score"
+        );
+        assert_eq!(lines.len(), 3);
+        // 包含逗号的进程名应被加引号，内部引号加倍
+        assert!(csv.contains("11,\"worker,\"\"beta\"\"\",2147483648,0,0,"));
+        assert!(csv.contains("10,plain,1073741824,0,0,"));
+    }
+
+    #[test]
+    fn test_non_finite_scores_never_selected() {
+        let candidate = |pid: i32, rss: u64, score: f64| {
+            let pid = ProcessId::new(pid).unwrap();
+            Candidate {
+                key: ProcessKey { pid, start_time: 0 },
+                score_details: OOMScoreDetails {
+                    total_score: score,
+                    memory_score: 0.0,
+                    runtime_score: 0.0,
+                    adj_score: 0.0,
+                    process: ProcessInfo::new_test(pid, "worker", rss, 0),
+                },
+                memory_saved: rss,
+            }
+        };
+        let mut candidates = vec![
+            candidate(1, 64 * 1024 * 1024, f64::NAN),
+            candidate(2, 1024, 0.3),
+            candidate(3, 1024, 0.3),
+            candidate(4, 2048, 0.2),
+            candidate(5, 4096, f64::INFINITY),
+        ];
+
+        // 平分时pid小者胜出，与遍历顺序无关
+        assert_eq!(best_candidate(candidates.iter()).unwrap().key.pid.as_raw(), 2);
+        candidates.reverse();
+        assert_eq!(best_candidate(candidates.iter()).unwrap().key.pid.as_raw(), 2);
+
+        candidates.retain(|c| !c.score_details.total_score.is_finite());
+        assert!(best_candidate(candidates.iter()).is_none());
+
+        // 评分全部为NaN时不选择任何进程
+        let processes = [ProcessInfo::new_test(ProcessId::new(6).unwrap(), "worker", 64 * 1024 * 1024, 0)];
+        let config = SelectorConfig { min_candidates: 1, ..SelectorConfig::default() };
+        let scorer = OOMScorer::with_defaults().with_weights(f64::NAN, 0.2, 0.2);
+        let selected = config.select_among(
+            &scorer,
+            &fake_memory_stats(),
+            processes.iter().map(|process| (process, None)),
+        );
+        assert!(selected.is_none());
+    }
+
+    #[test]
+    fn test_rankings_csv_live() {
+        let mut selector = ProcessSelector::new(None, OOMScorer::new(), PressureDetector::new(None));
+        let csv = selector.rankings_csv().unwrap();
+        assert!(csv.starts_with(RANKINGS_CSV_HEADER));
+    }
+
+    /// 父进程为100、内核oom_score为0的进程
+    fn named_process(name: &str, rss_kb: u64, oom_score_adj: i32, start_time: u64) -> ProcessGroup {
+        ProcessGroup::new(1)
+            .name(name)
+            .rss(RssDistribution::Constant(rss_kb))
+            .oom_scores(0, oom_score_adj)
+            .start_time(start_time)
+            .ppid(100)
+    }
+
+    fn worker(rss_kb: u64, oom_score_adj: i32, start_time: u64) -> ProcessGroup {
+        named_process("worker", rss_kb, oom_score_adj, start_time)
+    }
+
+    fn fake_memory_stats() -> MemoryStats {
+        MemoryStats {
+            total_memory: 64 * 1024 * 1024,
+            free_memory: 0,
+            available_memory: 0,
+            total_swap: 0,
+            free_swap: 0,
+            cached_memory: 0,
+            ..MemoryStats::default()
+        }
+    }
+
+    #[test]
+    fn test_shortlist_matches_full_scan() {
+        let fixture = FakeProc::new()
+            .process(1001, worker(4 * 1024 * 1024, 0, 10))
+            .process(1002, worker(2 * 1024 * 1024, 500, 20))
+            .process(1003, worker(2 * 1024 * 1024, -1000, 30))
+            .process(1004, worker(16, 0, 40))
+            .build()
+            .unwrap();
+
+        let mut selector = ProcessSelector::new(None, OOMScorer::new(), PressureDetector::new(None))
+            .with_proc_root(fixture.path());
+        let memory_stats = fake_memory_stats();
+
+        let mut expected: Vec<(i32, i32)> = crate::linux::proc::get_all_processes_in(fixture.path())
+            .unwrap()
+            .into_iter()
+            .filter(|p| selector.is_valid_candidate(p, &memory_stats))
+            .map(|p| (p.pid.as_raw(), p.mem_info.oom_score_adj))
+            .collect();
+        selector.shortlist(&memory_stats).unwrap();
+        let mut shortlisted: Vec<(i32, i32)> = selector.shortlisted()
+            .map(|p| (p.pid.as_raw(), p.mem_info.oom_score_adj))
+            .collect();
+        expected.sort();
+        shortlisted.sort();
+
+        assert_eq!(shortlisted, vec![(1001, 0), (1002, 500)]);
+        assert_eq!(shortlisted, expected);
+    }
+
+    #[test]
+    fn test_min_oom_score_adj_floor() {
+        let fixture = FakeProc::new()
+            .process(1001, worker(4 * 1024 * 1024, 0, 10))
+            .process(1002, worker(4 * 1024 * 1024, -500, 20))
+            .build()
+            .unwrap();
+
+        let shortlisted = |min_oom_score_adj| {
+            let config = SelectorConfig {
+                min_oom_score_adj,
+                ..SelectorConfig::default()
+            };
+            let mut selector = ProcessSelector::new(Some(config), OOMScorer::new(), PressureDetector::new(None))
+                .with_proc_root(fixture.path());
+            selector.shortlist(&fake_memory_stats()).unwrap();
+            let mut pids: Vec<i32> = selector.shortlisted().map(|p| p.pid.as_raw()).collect();
+            pids.sort();
+            pids
+        };
+
+        assert_eq!(shortlisted(-400), vec![1001]);
+        assert_eq!(shortlisted(-1000), vec![1001, 1002]);
+    }
+
+    #[test]
+    fn test_recycled_pid_does_not_mix_processes() {
+        let fixture = FakeProc::new()
+            .process(1001, worker(4 * 1024 * 1024, 0, 10))
+            .build()
+            .unwrap();
+        let mut selector = ProcessSelector::new(None, OOMScorer::new(), PressureDetector::new(None))
+            .with_proc_root(fixture.path());
+
+        // 读取原来进程的status之后，进程退出，pid被内存更小、oom_score_adj不同的新进程复用
+        selector.after_status = Some(|root| {
+            fs::remove_dir_all(root.join("1001")).unwrap();
+            write_process(root, 1001, &worker(2 * 1024 * 1024, 800, 99)).unwrap();
+        });
+        selector.shortlist(&fake_memory_stats()).unwrap();
+        // 原来的进程已经退出，不会用新进程的stat和OOM分数补全它
+        assert!(selector.shortlisted.is_empty());
+
+        selector.after_status = None;
+        selector.shortlist(&fake_memory_stats()).unwrap();
+        let (index, key) = selector.shortlisted[0];
+        let process = &selector.processes[index];
+        // 内存、OOM分数和启动时间都属于新进程
+        assert_eq!(key.start_time, 99);
+        assert_eq!(process.mem_info.vm_rss, 2 * 1024 * 1024);
+        assert_eq!(process.mem_info.oom_score_adj, 800);
+    }
+
+    #[test]
+    fn test_oom_scores_read_only_for_shortlisted() {
+        let fixture = FakeProc::new()
+            // 两个大进程通过初步过滤，其余都因内存太小被排除
+            .process(1001, worker(4 * 1024 * 1024, 0, 10))
+            .process(1002, worker(2 * 1024 * 1024, 500, 20))
+            .build()
+            .unwrap();
+        for pid in 2000..2020 {
+            write_process(fixture.path(), pid, &worker(16, 0, 50)).unwrap();
+        }
+
+        let mut selector = ProcessSelector::new(None, OOMScorer::new(), PressureDetector::new(None))
+            .with_proc_root(fixture.path());
+        let memory_stats = fake_memory_stats();
+
+        selector.shortlist(&memory_stats).unwrap();
+        assert_eq!(selector.shortlisted().count(), 2);
+        assert_eq!(selector.oom_scores.reads(), 2);
+
+        // 第二轮全部命中缓存
+        selector.shortlist(&memory_stats).unwrap();
+        assert_eq!(selector.shortlisted().count(), 2);
+        assert_eq!(selector.oom_scores.reads(), 2);
+
+        // pid被复用（start_time变化）时重新读取
+        write_process(fixture.path(), 1002, &worker(2 * 1024 * 1024, 800, 99)).unwrap();
+        selector.shortlist(&memory_stats).unwrap();
+        assert_eq!(selector.oom_scores.reads(), 3);
+        assert_eq!(selector.oom_scores.len(), 2);
+        let reused = selector.shortlisted().find(|p| p.pid.as_raw() == 1002).unwrap();
+        assert_eq!(reused.mem_info.oom_score_adj, 800);
+    }
+
+    #[test]
+    fn test_protect_controlling_tty() {
+        // /dev/pts/0 的设备号
+        let tty = 34816;
+        let fixture = FakeProc::new()
+            .process(1001, worker(4 * 1024 * 1024, 0, 10).tty_nr(tty))
+            .process(1002, worker(4 * 1024 * 1024, 0, 20))
+            .build()
+            .unwrap();
+
+        let config = SelectorConfig {
+            protect_controlling_tty: true,
+            ..SelectorConfig::default()
+        };
+        let mut selector = ProcessSelector::new(Some(config), OOMScorer::new(), PressureDetector::new(None))
+            .with_proc_root(fixture.path());
+        // 测试环境通常没有控制终端，这里模拟本进程运行在同一个终端上
+        selector.controlling_tty = Some(tty);
+
+        selector.shortlist(&fake_memory_stats()).unwrap();
+        let pids: Vec<i32> = selector.shortlisted()
+            .map(|p| p.pid.as_raw())
+            .collect();
+        assert_eq!(pids, vec![1002]);
+
+        // 没有控制终端时不保护任何进程
+        selector.controlling_tty = None;
+        selector.shortlist(&fake_memory_stats()).unwrap();
+        assert_eq!(selector.shortlisted().count(), 2);
+    }
+
+    #[test]
+    fn test_protect_realtime() {
+        let fixture = FakeProc::new()
+            .process(1001, worker(4 * 1024 * 1024, 0, 10))
+            .process(1002, worker(4 * 1024 * 1024, 0, 20))
+            .build()
+            .unwrap();
+        // 完整的stat行，rt_priority 50，policy 1（SCHED_FIFO）
+        fs::write(
+            fixture.path().join("1001").join("stat"),
+            "1001 (worker-1001) S 100 0 0 0 -1 0 0 0 0 0 0 0 0 0 -51 0 1 0 10 0 0 \
+             18446744073709551615 1 1 0 0 0 0 0 0 0 0 0 0 17 0 50 1 0 0 0\n",
+        ).unwrap();
+
+        let mut selector = ProcessSelector::new(None, OOMScorer::new(), PressureDetector::new(None))
+            .with_proc_root(fixture.path());
+        selector.shortlist(&fake_memory_stats()).unwrap();
+        let pids: Vec<i32> = selector.shortlisted()
+            .map(|p| p.pid.as_raw())
+            .collect();
+        assert_eq!(pids, vec![1002]);
+
+        let config = SelectorConfig {
+            protect_realtime: false,
+            ..SelectorConfig::default()
+        };
+        let mut selector = ProcessSelector::new(Some(config), OOMScorer::new(), PressureDetector::new(None))
+            .with_proc_root(fixture.path());
+        selector.shortlist(&fake_memory_stats()).unwrap();
+        assert_eq!(selector.shortlisted().count(), 2);
+    }
+
+    #[test]
+    fn test_deficit_widens_selection() {
+        let config = SelectorConfig {
+            headroom_target: Some(0.2),
+            allow_system_processes: true,
+            min_memory_threshold: 0,
+            ..SelectorConfig::default()
+        };
+        let stats = |available_ratio: f64| MemoryStats {
+            total_memory: 1000,
+            available_memory: (1000.0 * available_ratio) as u64,
+            ..MemoryStats::default()
+        };
+
+        // 高于目标时与未配置目标相同
+        assert_eq!(config.deficit_factor(&stats(0.3)), 0.0);
+        assert_eq!(config.effective_memory_impact(&stats(0.3)), STRICT_MEMORY_IMPACT);
+        assert_eq!(config.effective_max_candidates(&stats(0.3)), 10);
+        assert_eq!(SelectorConfig::default().deficit_factor(&stats(0.0)), 0.0);
+
+        let shallow = stats(0.15);
+        let deep = stats(0.05);
+        assert!((config.deficit_factor(&shallow) - 0.25).abs() < 1e-9);
+        assert!((config.deficit_factor(&deep) - 0.75).abs() < 1e-9);
+        assert!(config.effective_memory_impact(&deep) < config.effective_memory_impact(&shallow));
+        assert!(config.effective_memory_impact(&shallow) < STRICT_MEMORY_IMPACT);
+        assert_eq!(config.effective_max_candidates(&shallow), 13);
+        assert_eq!(config.effective_max_candidates(&deep), 18);
+        assert_eq!(config.effective_max_candidates(&stats(0.0)), 20);
+
+        // 只占0.5%内存的进程在缺口足够大时才成为候选
+        let process = ProcessInfo::new_test(ProcessId::new(1001).unwrap(), "worker", 5, 0);
+        assert!(!config.passes_prefilter(&process, &shallow, config.strict_filter(&shallow)));
+        assert!(config.passes_prefilter(&process, &deep, config.strict_filter(&deep)));
+    }
+
+    #[test]
+    fn test_kernel_oom_score_only() {
+        let fixture = pressured_proc()
+            // 1001的RSS更大，加权评分更高；内核给1002的oom_score更高
+            .process(1001, worker(8 * 1024 * 1024, 0, 10))
+            .process(1002, worker(2 * 1024 * 1024, 0, 20))
+            .build()
+            .unwrap();
+        fs::write(fixture.path().join("1001/oom_score"), "150\n").unwrap();
+        fs::write(fixture.path().join("1002/oom_score"), "900\n").unwrap();
+        let config = SelectorConfig {
+            min_candidates: 1,
+            ..SelectorConfig::default()
+        };
+
+        let mut weighted = pressured_selector(fixture.path(), config.clone());
+        assert_eq!(weighted.select_process().unwrap().map(|pid| pid.as_raw()), Some(1001));
+
+        let mut kernel = pressured_selector(fixture.path(), SelectorConfig {
+            use_kernel_oom_score_only: true,
+            ..config
+        });
+        assert_eq!(kernel.select_process().unwrap().map(|pid| pid.as_raw()), Some(1002));
+        let mut plan = KillPlan::new();
+        kernel.plan_victims(&mut plan).unwrap();
+        let scores: Vec<f64> = plan.victims().map(|v| v.score).collect();
+        assert_eq!(scores, vec![900.0, 150.0]);
+    }
+
+    #[test]
+    fn test_exclude_same_cgroup() {
+        let fixture = FakeProc::new()
+            .process(1001, worker(4 * 1024 * 1024, 0, 10))
+            .process(1002, worker(4 * 1024 * 1024, 0, 20))
+            // 1003没有cgroup文件，视为不同的cgroup
+            .process(1003, worker(4 * 1024 * 1024, 0, 30))
+            .build()
+            .unwrap();
+        fs::write(fixture.path().join("1001/cgroup"), "0::/kubepods/pod-a/room\n").unwrap();
+        fs::write(fixture.path().join("1002/cgroup"), "0::/kubepods/pod-b/app\n").unwrap();
+
+        let config = SelectorConfig {
+            allow_same_cgroup: false,
+            ..SelectorConfig::default()
+        };
+        let mut selector = ProcessSelector::new(Some(config), OOMScorer::new(), PressureDetector::new(None))
+            .with_proc_root(fixture.path());
+        // 模拟本进程与1001运行在同一个容器中
+        selector.own_cgroup = Some(CgroupInfo::parse("0::/kubepods/pod-a/room\n").unwrap());
+
+        selector.shortlist(&fake_memory_stats()).unwrap();
+        let mut pids: Vec<i32> = selector.shortlisted().map(|p| p.pid.as_raw()).collect();
+        pids.sort();
+        assert_eq!(pids, vec![1002, 1003]);
+
+        // 默认允许选择同一cgroup中的进程
+        let mut selector = ProcessSelector::new(None, OOMScorer::new(), PressureDetector::new(None))
+            .with_proc_root(fixture.path());
+        assert!(selector.own_cgroup.is_none());
+        selector.shortlist(&fake_memory_stats()).unwrap();
+        assert_eq!(selector.shortlisted().count(), 3);
+    }
+
+    #[test]
+    fn test_fork_grace_spares_new_parent() {
+        let fixture = FakeProc::new()
+            .process(1001, worker(4 * 1024 * 1024, 0, 10))
+            .process(1003, worker(4 * 1024 * 1024, 0, 30))
+            // 1003从一开始就有子进程，不算新的fork
+            .process(1004, worker(16, 0, 50).ppid(1003))
+            .build()
+            .unwrap();
+
+        let config = SelectorConfig {
+            fork_grace: Some(Duration::from_millis(300)),
+            ..SelectorConfig::default()
+        };
+        let mut selector = ProcessSelector::new(Some(config), OOMScorer::new(), PressureDetector::new(None))
+            .with_proc_root(fixture.path());
+        let shortlisted = |selector: &mut ProcessSelector| {
+            selector.shortlist(&fake_memory_stats()).unwrap();
+            let mut pids: Vec<i32> = selector.shortlisted().map(|p| p.pid.as_raw()).collect();
+            pids.sort();
+            pids
+        };
+
+        assert_eq!(shortlisted(&mut selector), vec![1001, 1003]);
+
+        // 两次扫描之间1001创建了子进程
+        write_process(fixture.path(), 1002, &worker(16, 0, 50).ppid(1001)).unwrap();
+        assert_eq!(shortlisted(&mut selector), vec![1003]);
+        assert_eq!(shortlisted(&mut selector), vec![1003]);
+
+        // 宽限期过后子进程数不再增加，1001重新成为候选
+        std::thread::sleep(Duration::from_millis(350));
+        assert_eq!(shortlisted(&mut selector), vec![1001, 1003]);
+    }
+
+    #[test]
+    fn test_in_memory_process_source() {
+        use crate::linux::proc::ProcfsSource;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        struct StaticSource {
+            processes: Vec<ProcessInfo>,
+            calls: Arc<AtomicUsize>,
+        }
+
+        impl ProcessSource for StaticSource {
+            fn processes(&self) -> Result<Vec<ProcessInfo>> {
+                self.calls.fetch_add(1, Ordering::SeqCst);
+                Ok(self.processes.clone())
+            }
+        }
+
+        // proc根目录中只有meminfo，没有任何进程目录
+        let fixture = pressured_proc().build().unwrap();
+        let process = |pid, name, rss_kb| ProcessInfo::new_test(ProcessId::new(pid).unwrap(), name, rss_kb, 0);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let source = StaticSource {
+            processes: vec![
+                process(3001, "cache", 2 * 1024 * 1024),
+                process(3002, "hog", 6 * 1024 * 1024),
+                process(3003, "worker", 3 * 1024 * 1024),
+            ],
+            calls: Arc::clone(&calls),
+        };
+        let config = SelectorConfig {
+            min_candidates: 1,
+            ..SelectorConfig::default()
+        };
+
+        let mut selector = pressured_selector(fixture.path(), config.clone()).with_process_source(Box::new(source));
+        assert_eq!(selector.select_process().unwrap().map(|pid| pid.as_raw()), Some(3002));
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let mut plan = KillPlan::new();
+        selector.plan_victims(&mut plan).unwrap();
+        let planned: Vec<i32> = plan.victims().map(|victim| victim.key.pid.as_raw()).collect();
+        assert_eq!(planned, vec![3002, 3003, 3001]);
+
+        // 默认的proc来源读取同样的进程文件
+        write_process(fixture.path(), 3101, &named_process("hog", 6 * 1024 * 1024, 0, 10)).unwrap();
+        let mut selector = pressured_selector(fixture.path(), config)
+            .with_process_source(Box::new(ProcfsSource::new(fixture.path())));
+        assert_eq!(selector.select_process().unwrap().map(|pid| pid.as_raw()), Some(3101));
+    }
+
+    #[test]
+    fn test_monitored_pids_only() {
+        let fixture = FakeProc::new()
+            .process(1001, worker(4 * 1024 * 1024, 0, 10))
+            .process(1002, worker(4 * 1024 * 1024, 0, 20))
+            // 不受监控的最大进程
+            .process(1003, worker(16 * 1024 * 1024, 500, 30))
+            .build()
+            .unwrap();
+        fs::create_dir_all(fixture.path().join("1001/task/1001")).unwrap();
+        fs::write(fixture.path().join("1001/task/1001/children"), "1002 ").unwrap();
+        let monitored = vec![ProcessId::new(1001).unwrap(), ProcessId::new(4242).unwrap()];
+
+        let config = SelectorConfig {
+            monitored_pids: Some(monitored.clone()),
+            ..SelectorConfig::default()
+        };
+        let mut selector = ProcessSelector::new(Some(config), OOMScorer::new(), PressureDetector::new(None))
+            .with_proc_root(fixture.path());
+        selector.shortlist(&fake_memory_stats()).unwrap();
+        // 只评估了监控的pid，没有枚举 /proc
+        assert_eq!(selector.pids, monitored);
+        let pids: Vec<i32> = selector.shortlisted().map(|p| p.pid.as_raw()).collect();
+        assert_eq!(pids, vec![1001]);
+
+        let config = SelectorConfig {
+            monitored_pids: Some(monitored),
+            monitor_descendants: true,
+            ..SelectorConfig::default()
+        };
+        let mut selector = ProcessSelector::new(Some(config), OOMScorer::new(), PressureDetector::new(None))
+            .with_proc_root(fixture.path());
+        selector.shortlist(&fake_memory_stats()).unwrap();
+        let mut pids: Vec<i32> = selector.shortlisted().map(|p| p.pid.as_raw()).collect();
+        pids.sort();
+        assert_eq!(pids, vec![1001, 1002]);
+    }
+
+    /// 处于内存压力下的proc目录（可用内存约1.5%）
+    fn pressured_proc() -> FakeProc {
+        FakeProc::new()
+            .meminfo(MemInfoSpec {
+                total_kb: 65536,
+                free_kb: 1024,
+                available_kb: 1024,
+                cached_kb: 0,
+                swap_total_kb: 0,
+                swap_free_kb: 0,
+            })
+            .uptime_secs(1000.0)
+    }
+
+    fn pressured_selector(root: &Path, config: SelectorConfig) -> ProcessSelector {
+        let thresholds = PressureThresholds {
+            pressure_duration: Duration::ZERO,
+            ..PressureThresholds::default()
+        };
+        ProcessSelector::new(
+            Some(config),
+            OOMScorer::new().with_proc_root(root),
+            PressureDetector::new(Some(thresholds)).with_proc_root(root),
+        )
+        .with_proc_root(root)
+    }
+
+    #[test]
+    fn test_relaxed_selection_honors_protections() {
+        let tty = 34816;
+        let fixture = pressured_proc()
+            // 所有进程都低于严格的内存阈值；更大的两个分别受oom_score_adj和控制终端保护
+            .process(1001, worker(512, 0, 10))
+            .process(1002, worker(4096, -1000, 20))
+            .process(1003, worker(4096, 0, 30).tty_nr(tty))
+            .build()
+            .unwrap();
+
+        let config = SelectorConfig {
+            protect_controlling_tty: true,
+            ..SelectorConfig::default()
+        };
+        let mut selector = pressured_selector(fixture.path(), config.clone());
+        selector.controlling_tty = Some(tty);
+
+        let victim = selector.select_process().unwrap();
+        assert_eq!(victim.map(|pid| pid.as_raw()), Some(1001));
+        assert!(selector.last_selection_relaxed());
+
+        // 禁用放宽时严格过滤没有结果
+        let mut strict = pressured_selector(fixture.path(), SelectorConfig {
+            relaxed: None,
+            ..config
+        });
+        strict.controlling_tty = Some(tty);
+        assert_eq!(strict.select_process().unwrap(), None);
+        assert!(!strict.last_selection_relaxed());
+    }
+
+    #[test]
+    fn test_budget_returns_best_known_candidate() {
+        let fixture = pressured_proc()
+            // 1001是最大且得分最高的进程，其余进程都通过过滤
+            .process(1001, worker(8 * 1024 * 1024, 500, 10))
+            .build()
+            .unwrap();
+        for pid in 2000..2100 {
+            write_process(fixture.path(), pid, &worker(2 * 1024 * 1024, 0, 20)).unwrap();
+        }
+
+        let config = SelectorConfig {
+            min_candidates: 1,
+            max_candidates: 200,
+            ..SelectorConfig::default()
+        };
+        let mut selector = pressured_selector(fixture.path(), config);
+
+        // 第一轮没有预算限制，完整评估后记住最大的进程
+        assert_eq!(selector.select_process().unwrap().map(|pid| pid.as_raw()), Some(1001));
+        assert!(!selector.last_selection_partial());
+
+        // 模拟缓慢的统计信息读取，预算远小于完整评估所需的时间
+        selector.slow_stats = Duration::from_millis(2);
+        selector.config.selection_budget = Some(Duration::from_millis(10));
+        let start = std::time::Instant::now();
+        let victim = selector.select_process().unwrap();
+        let elapsed = start.elapsed();
+
+        assert!(selector.last_selection_partial());
+        assert_eq!(victim.map(|pid| pid.as_raw()), Some(1001));
+        // 只评估了上一轮的大进程和期限之前的少数进程，而不是全部101个
+        assert!(elapsed < Duration::from_millis(2 * 101), "selection took {:?}", elapsed);
+    }
+
+    #[test]
+    fn test_diversify_kills_across_applications() {
+        // 三个同名的浏览器进程得分最高，数据库稍小
+        let chrome = named_process("chrome", 4 * 1024 * 1024, 0, 10);
+        let fixture = pressured_proc()
+            .process(1001, chrome.clone())
+            .process(1002, chrome.clone())
+            .process(1003, chrome)
+            .process(2001, named_process("postgres", 3 * 1024 * 1024, 0, 10))
+            .build()
+            .unwrap();
+
+        let config = SelectorConfig {
+            diversify_by_name: true,
+            max_candidates: 100,
+            ..SelectorConfig::default()
+        };
+        let kill_twice = |config: SelectorConfig| {
+            let mut selector = pressured_selector(fixture.path(), config);
+            let first = selector.select_process().unwrap().unwrap();
+            selector.note_kill(Comm::new("chrome"));
+            // 模拟第一次终止：进程退出
+            fs::rename(fixture.path().join(first.as_raw().to_string()), fixture.path().join("killed")).unwrap();
+            let second = selector.select_process().unwrap().unwrap();
+            fs::rename(fixture.path().join("killed"), fixture.path().join(first.as_raw().to_string())).unwrap();
+            (first.as_raw(), second.as_raw())
+        };
+
+        let (first, second) = kill_twice(config.clone());
+        assert!((1001..1004).contains(&first));
+        assert_eq!(second, 2001);
+
+        // 不分散时第二次仍然终止浏览器
+        let (_, second) = kill_twice(SelectorConfig {
+            diversify_by_name: false,
+            ..config.clone()
+        });
+        assert!((1001..1004).contains(&second));
+
+        // 冷却中的应用明显更差（这里只要求不低于其他应用）时仍然终止它
+        let (_, second) = kill_twice(SelectorConfig {
+            name_dominance: 1.0,
+            ..config.clone()
+        });
+        assert!((1001..1004).contains(&second));
+
+        // 预先计划的受害者同样让位
+        let mut selector = pressured_selector(fixture.path(), config);
+        selector.check_pressure().unwrap();
+        let mut plan = KillPlan::new();
+        selector.plan_victims(&mut plan).unwrap();
+        assert_eq!(plan.first().unwrap().comm.as_str(), "chrome");
+        selector.note_kill(Comm::new("chrome"));
+        selector.diversify_plan(&mut plan);
+        assert_eq!(plan.first().unwrap().comm.as_str(), "postgres");
+        assert_eq!(plan.len(), 4);
+    }
+
+    #[test]
+    fn test_diversify_by_exe_name() {
+        let fixture = pressured_proc()
+            // 浏览器的子进程用 PR_SET_NAME 改了comm，可执行文件相同
+            .process(1001, named_process("Web Content", 4 * 1024 * 1024, 0, 10))
+            .process(1002, named_process("firefox", 4 * 1024 * 1024, 0, 10))
+            .process(2001, named_process("postgres", 3 * 1024 * 1024, 0, 10))
+            .build()
+            .unwrap();
+        for pid in [1001, 1002] {
+            std::os::unix::fs::symlink("/usr/lib/firefox/firefox", fixture.path().join(format!("{}/exe", pid))).unwrap();
+        }
+
+        let config = SelectorConfig {
+            diversify_by_name: true,
+            match_exe_name: true,
+            max_candidates: 100,
+            ..SelectorConfig::default()
+        };
+        let mut selector = pressured_selector(fixture.path(), config.clone());
+        selector.check_pressure().unwrap();
+        let mut plan = KillPlan::new();
+        selector.plan_victims(&mut plan).unwrap();
+        let web_content = plan.victims().find(|victim| victim.comm.as_str() == "Web Content").unwrap();
+        assert_eq!(web_content.app.as_str(), "firefox");
+        // 没有exe链接时退回comm
+        let postgres = plan.victims().find(|victim| victim.comm.as_str() == "postgres").unwrap();
+        assert_eq!(postgres.app.as_str(), "postgres");
+
+        selector.note_kill(Comm::new("firefox"));
+        selector.diversify_plan(&mut plan);
+        assert_eq!(plan.first().unwrap().comm.as_str(), "postgres");
+
+        // 不启用时按comm匹配，comm不同的子进程不受冷却影响
+        let mut selector = pressured_selector(fixture.path(), SelectorConfig {
+            match_exe_name: false,
+            ..config
+        });
+        selector.check_pressure().unwrap();
+        let mut plan = KillPlan::new();
+        selector.plan_victims(&mut plan).unwrap();
+        assert!(plan.victims().all(|victim| victim.app == victim.comm));
+        selector.note_kill(Comm::new("firefox"));
+        selector.diversify_plan(&mut plan);
+        assert_eq!(plan.first().unwrap().comm.as_str(), "Web Content");
+    }
+
+    #[test]
+    fn test_rotate_near_tied_candidates() {
+        // 三个得分几乎相同的候选，以及一个明显较小的进程
+        let fixture = pressured_proc()
+            .process(1001, named_process("worker-1001", 512 * 1024, 0, 10))
+            .process(1002, named_process("worker-1002", 512 * 1024 - 1, 0, 10))
+            .process(1003, named_process("worker-1003", 512 * 1024 - 2, 0, 10))
+            .process(1004, named_process("worker-1004", 128 * 1024, 0, 10))
+            .build()
+            .unwrap();
+
+        let episode = |selector: &mut ProcessSelector| {
+            selector.check_pressure().unwrap();
+            let mut plan = KillPlan::new();
+            selector.plan_victims(&mut plan).unwrap();
+            let first = plan.first().unwrap();
+            selector.note_chosen(first.key);
+            first.key.pid.as_raw()
+        };
+
+        // 每个接近最高分的候选被选中一次之后才重复
+        let mut selector = pressured_selector(fixture.path(), SelectorConfig {
+            rotate_ties: Some(0.001),
+            ..SelectorConfig::default()
+        });
+        let chosen: Vec<i32> = (0..4).map(|_| episode(&mut selector)).collect();
+        assert_eq!(chosen, [1001, 1002, 1003, 1001]);
+
+        // 未启用时总是选择最高分
+        let mut selector = pressured_selector(fixture.path(), SelectorConfig::default());
+        let chosen: Vec<i32> = (0..3).map(|_| episode(&mut selector)).collect();
+        assert_eq!(chosen, [1001, 1001, 1001]);
+    }
+
+    #[test]
+    fn test_kill_only_scope() {
+        let fixture = pressured_proc()
+            // database占用最多，但不在允许的范围内
+            .process(1001, named_process("database", 8 * 1024 * 1024, 500, 10))
+            .process(1002, named_process("batch", 2 * 1024 * 1024, 0, 10))
+            .process(1003, named_process("worker", 2 * 1024 * 1024, 0, 10))
+            .process(1004, named_process("web", 4 * 1024 * 1024, 0, 10))
+            .build()
+            .unwrap();
+        fs::write(fixture.path().join("1003/cgroup"), "0::/jobs.slice/worker.service\n").unwrap();
+        fs::write(fixture.path().join("1004/cgroup"), "0::/jobs.slice-web\n").unwrap();
+
+        let candidate_names = |kill_only: Option<KillScope>| {
+            let mut selector = pressured_selector(fixture.path(), SelectorConfig {
+                min_candidates: 1,
+                kill_only,
+                ..SelectorConfig::default()
+            });
+            selector.check_pressure().unwrap();
+            let mut plan = KillPlan::new();
+            selector.plan_victims(&mut plan).unwrap();
+            let mut names: Vec<String> = selector.shortlisted().map(|process| process.name.clone()).collect();
+            names.sort();
+            names
+        };
+        assert_eq!(candidate_names(None), ["batch", "database", "web", "worker"]);
+
+        // 只有名称在列表中的进程会成为候选
+        let names = KillScope { names: vec!["batch".to_string()], ..KillScope::default() };
+        assert_eq!(candidate_names(Some(names)), ["batch"]);
+
+        // cgroup包括子cgroup，但不匹配只有前缀相同的兄弟cgroup
+        let cgroups = KillScope { cgroups: vec!["/jobs.slice/".to_string()], ..KillScope::default() };
+        assert_eq!(candidate_names(Some(cgroups)), ["worker"]);
+
+        // 空的范围不匹配任何进程
+        assert!(candidate_names(Some(KillScope::default())).is_empty());
+    }
+
+    #[test]
+    fn test_importance_tiers() {
+        let fixture = pressured_proc()
+            // 原始得分相同的两个进程，以及一个更大的数据库
+            .process(1001, named_process("service", 2 * 1024 * 1024, 0, 10))
+            .process(1002, named_process("batch", 2 * 1024 * 1024, 0, 10))
+            .process(1003, named_process("database", 4 * 1024 * 1024, 0, 10))
+            .build()
+            .unwrap();
+        fs::write(fixture.path().join("1002/cgroup"), "0::/best-effort.slice/batch.service\n").unwrap();
+
+        let ranking = |importance_tiers: Vec<TierRule>| {
+            let mut selector = pressured_selector(fixture.path(), SelectorConfig {
+                importance_tiers,
+                ..SelectorConfig::default()
+            });
+            selector.check_pressure().unwrap();
+            selector.score_all_candidates().unwrap()
+                .iter()
+                .map(|details| details.process.name.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ranking(Vec::new()), ["database", "service", "batch"]);
+
+        let tiers = vec![
+            TierRule {
+                scope: KillScope { names: vec!["database".to_string()], ..KillScope::default() },
+                tier: ImportanceTier::Critical,
+            },
+            TierRule {
+                scope: KillScope { cgroups: vec!["/best-effort.slice".to_string()], ..KillScope::default() },
+                tier: ImportanceTier::BestEffort,
+            },
+        ];
+        // best-effort排在原始得分相同的普通进程之前，critical排在最后但仍然是候选
+        assert_eq!(ranking(tiers), ["batch", "service", "database"]);
+    }
+
+    #[test]
+    fn test_plan_to_free() {
+        const GIB: u64 = 1 << 30;
+        let fixture = pressured_proc()
+            .process(1001, named_process("large", 4 * 1024 * 1024, 0, 10))
+            .process(1002, named_process("medium", 2 * 1024 * 1024, 0, 10))
+            .process(1003, named_process("small", 1024 * 1024, 0, 10))
+            .build()
+            .unwrap();
+        let mut selector = pressured_selector(fixture.path(), SelectorConfig::default());
+
+        // 5GB需要终止最大的两个进程，累计6GB
+        let plan = selector.plan_to_free(5 * GIB).unwrap();
+        let names: Vec<&str> = plan.victims.iter().map(|step| step.name.as_str()).collect();
+        assert_eq!(names, ["large", "medium"]);
+        assert_eq!(plan.victims[0].cumulative_bytes, 4 * GIB);
+        assert_eq!(plan.total_bytes(), 6 * GIB);
+        assert!(plan.total_bytes() >= plan.target_bytes);
+        assert!(plan.reaches_target);
+
+        // 正好等于第一个进程的RSS时只需要它
+        let plan = selector.plan_to_free(4 * GIB).unwrap();
+        assert_eq!(plan.victims.len(), 1);
+
+        // 所有候选都不够时列出全部并报告达不到目标
+        let plan = selector.plan_to_free(16 * GIB).unwrap();
+        assert_eq!(plan.victims.len(), 3);
+        assert_eq!(plan.total_bytes(), 7 * GIB);
+        assert!(!plan.reaches_target);
+
+        // 不需要回收时为空
+        let plan = selector.plan_to_free(0).unwrap();
+        assert!(plan.victims.is_empty() && plan.reaches_target);
+    }
+
+    #[test]
+    fn test_exclude_kthreadd_descendants() {
+        // kthreadd和它的子线程没有RSS；1001的名称不以'['开头，但祖先是kthreadd
+        let fixture = pressured_proc()
+            .process(2, named_process("kthreadd", 0, 0, 1).ppid(0))
+            .process(300, named_process("kworker/0:1", 0, 0, 1).ppid(2))
+            .process(1001, named_process("helper", 4 * 1024 * 1024, 0, 10).ppid(300))
+            .process(1002, named_process("service", 2 * 1024 * 1024, 0, 10))
+            .build()
+            .unwrap();
+
+        let mut selector = pressured_selector(fixture.path(), SelectorConfig::default());
+        selector.check_pressure().unwrap();
+        let mut plan = KillPlan::new();
+        selector.plan_victims(&mut plan).unwrap();
+        let names: Vec<&str> = selector.shortlisted().map(|process| process.name.as_str()).collect();
+        assert_eq!(names, ["service"]);
+        assert_eq!(plan.first().unwrap().comm.as_str(), "service");
+    }
+
+    #[test]
+    fn test_memory_low_policy() {
+        let fixture = pressured_proc()
+            // database所在的cgroup设置了 memory.low，RSS更大
+            .process(1001, named_process("database", 512 * 1024, 0, 10))
+            .process(1002, named_process("batch", 384 * 1024, 0, 10))
+            .build()
+            .unwrap();
+        let cgroups = tempfile::tempdir().unwrap();
+        fs::write(fixture.path().join("1001/cgroup"), "0::/protected.slice/db.service\n").unwrap();
+        fs::write(fixture.path().join("1002/cgroup"), "0::/batch.slice\n").unwrap();
+        for (path, low) in [("protected.slice/db.service", "1073741824"), ("batch.slice", "0")] {
+            fs::create_dir_all(cgroups.path().join(path)).unwrap();
+            fs::write(cgroups.path().join(path).join("memory.low"), format!("{}\n", low)).unwrap();
+        }
+
+        let plan_names = |memory_low: MemoryLowPolicy| {
+            let mut selector = pressured_selector(fixture.path(), SelectorConfig {
+                memory_low,
+                ..SelectorConfig::default()
+            })
+            .with_cgroup_root(cgroups.path());
+            selector.check_pressure().unwrap();
+            let mut plan = KillPlan::new();
+            selector.plan_victims(&mut plan).unwrap();
+            plan.victims().map(|victim| victim.comm.as_str().to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(plan_names(MemoryLowPolicy::Ignore), ["database", "batch"]);
+        assert_eq!(plan_names(MemoryLowPolicy::Deprioritize(0.5)), ["batch", "database"]);
+        assert_eq!(plan_names(MemoryLowPolicy::Exclude), ["batch"]);
+    }
+
+    #[test]
+    fn test_rss_limit_penalty() {
+        let fixture = pressured_proc()
+            // capped的RSS更大，但已经达到512MB的RLIMIT_RSS，无法继续增长
+            .process(1001, named_process("capped", 512 * 1024, 0, 10))
+            .process(1002, named_process("growing", 384 * 1024, 0, 10))
+            .build()
+            .unwrap();
+        fs::write(
+            fixture.path().join("1001/limits"),
+            "Limit                     Soft Limit           Hard Limit           Units     \n\
+             Max resident set          536870912            unlimited            bytes     \n\
+             Max locked memory         65536                65536                bytes     \n",
+        )
+        .unwrap();
+
+        let plan_first = |config: SelectorConfig| {
+            let mut selector = pressured_selector(fixture.path(), config);
+            selector.check_pressure().unwrap();
+            let mut plan = KillPlan::new();
+            selector.plan_victims(&mut plan).unwrap();
+            plan.first().unwrap().comm.as_str().to_string()
+        };
+        assert_eq!(plan_first(SelectorConfig::default()), "capped");
+        assert_eq!(
+            plan_first(SelectorConfig {
+                rss_limit_penalty: Some(0.5),
+                ..SelectorConfig::default()
+            }),
+            "growing"
+        );
+    }
+
+    #[test]
+    fn test_validity_predicates() {
+        let fixture = FakeProc::new()
+            .process(1001, named_process("worker", 4 * 1024 * 1024, 0, 10))
+            .process(1002, named_process("safe-db", 4 * 1024 * 1024, 0, 10))
+            .process(1003, named_process("unsafe-cache", 4 * 1024 * 1024, 0, 10))
+            // 内置检查排除的进程：内存太小、oom_score_adj为-1000
+            .process(1004, named_process("tiny", 16, 0, 10))
+            .process(1005, named_process("unkillable", 4 * 1024 * 1024, -1000, 10))
+            .build()
+            .unwrap();
+
+        let mut selector = ProcessSelector::new(None, OOMScorer::new(), PressureDetector::new(None))
+            .with_proc_root(fixture.path());
+        let shortlisted = |selector: &mut ProcessSelector| {
+            selector.shortlist(&fake_memory_stats()).unwrap();
+            let mut pids: Vec<i32> = selector.shortlisted().map(|p| p.pid.as_raw()).collect();
+            pids.sort();
+            pids
+        };
+        assert_eq!(shortlisted(&mut selector), vec![1001, 1002, 1003]);
+
+        selector.add_validity_predicate(|process, _| !process.name.contains("safe"));
+        assert_eq!(shortlisted(&mut selector), vec![1001]);
+
+        // 所有判断都必须通过，判断能看到OOM分数和内存统计
+        selector.add_validity_predicate(|process, stats| {
+            process.mem_info.oom_score_adj == 0 && stats.total_memory > 0
+        });
+        assert_eq!(shortlisted(&mut selector), vec![1001]);
+        selector.add_validity_predicate(|process, _| process.pid.as_raw() != 1001);
+        assert!(shortlisted(&mut selector).is_empty());
+
+        let memory_stats = fake_memory_stats();
+        let worker = crate::linux::proc::get_all_processes_in(fixture.path())
+            .unwrap()
+            .into_iter()
+            .find(|p| p.pid.as_raw() == 1001)
+            .unwrap();
+        assert!(!selector.is_valid_candidate(&worker, &memory_stats));
+    }
+}
@@ -0,0 +1,407 @@
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+use crate::ffi::safe_wrapper::SystemInterface;
+use crate::ffi::types::{SystemError, Result};
+use crate::linux::cgroup::CgroupInfo;
+use crate::linux::proc::ProcessInfo;
+use crate::linux::vmstat::{VmStat, VmStatRates};
+use crate::oom::pressure::{MemoryStats, PressureLevel};
+use crate::oom::score::OOMScorer;
+
+/// 快照文件名前缀
+const SNAPSHOT_PREFIX: &str = "room-snapshot-";
+
+/// 读取 cmdline 的最大字节数
+const MAX_CMDLINE_BYTES: u64 = 4096;
+
+/// 诊断快照的配置
+#[derive(Debug, Clone)]
+pub struct SnapshotConfig {
+    /// 快照写入的目录
+    pub directory: PathBuf,
+    /// 两次快照之间的最小间隔
+    pub min_interval: Duration,
+    /// 记录内存占用最高的进程数
+    pub top_n: usize,
+    /// 保留最近的快照数
+    pub retain: usize,
+    /// 单个快照文件的最大字节数
+    pub max_bytes: usize,
+    /// 收集快照的时间预算
+    pub time_budget: Duration,
+}
+
+impl SnapshotConfig {
+    /// 使用默认参数创建写入指定目录的配置
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            min_interval: Duration::from_secs(300),
+            top_n: 20,
+            retain: 10,
+            max_bytes: 64 * 1024,
+            time_budget: Duration::from_millis(200),
+        }
+    }
+}
+
+/// 单个进程在快照中的信息
+#[derive(Debug, Clone, Serialize)]
+pub struct ProcessSnapshot {
+    pub pid: i32,
+    pub comm: String,
+    pub cmdline: String,
+    pub uid: u32,
+    pub cgroup: String,
+    pub rss_kb: u64,
+    pub swap_kb: u64,
+    pub score: f64,
+}
+
+/// 压力进入Critical时写入磁盘的诊断快照
+#[derive(Debug, Clone, Serialize)]
+pub struct DiagnosticSnapshot {
+    pub timestamp_ms: u64,
+    pub level: PressureLevel,
+    pub memory: MemoryStats,
+    pub vmstat_rates: Option<VmStatRates>,
+    pub processes: Vec<ProcessSnapshot>,
+    /// 因时间或大小限制而省略了部分进程
+    pub truncated: bool,
+}
+
+/// 诊断快照写入器
+///
+/// # 为什么要严格限制大小和时间？
+///
+/// 快照只在系统已经处于严重内存压力时写入，此时任何额外的IO和内存分配
+/// 都可能让情况更糟，所以宁可写一个不完整的快照，也不能拖慢终止流程。
+#[derive(Debug)]
+pub struct SnapshotWriter {
+    config: SnapshotConfig,
+    last_written: Option<Instant>,
+    last_level: PressureLevel,
+    last_vmstat: Option<(Instant, VmStat)>,
+    vmstat_rates: Option<VmStatRates>,
+}
+
+impl SnapshotWriter {
+    /// 创建新的快照写入器
+    pub fn new(config: SnapshotConfig) -> Self {
+        Self {
+            config,
+            last_written: None,
+            last_level: PressureLevel::Normal,
+            last_vmstat: None,
+            vmstat_rates: None,
+        }
+    }
+
+    /// 采样 /proc/vmstat，用于计算快照中的回收速率
+    pub fn observe_vmstat(&mut self) {
+        let Ok(current) = VmStat::read() else {
+            return;
+        };
+        let now = Instant::now();
+
+        if let Some((then, previous)) = &self.last_vmstat {
+            self.vmstat_rates = Some(current.rates_since(previous, now.duration_since(*then)));
+        }
+        self.last_vmstat = Some((now, current));
+    }
+
+    /// 在压力进入Critical级别时写入快照
+    ///
+    /// # 返回值
+    ///
+    /// 写入了快照时返回文件路径；未触发、被限流或目标文件系统不可用时返回 `None`
+    pub fn maybe_write(
+        &mut self,
+        level: PressureLevel,
+        stats: &MemoryStats,
+        scorer: &OOMScorer,
+    ) -> Result<Option<PathBuf>> {
+        let crossed = level == PressureLevel::Critical && self.last_level != PressureLevel::Critical;
+        self.last_level = level;
+        if !crossed {
+            return Ok(None);
+        }
+
+        if let Some(last) = self.last_written {
+            if last.elapsed() < self.config.min_interval {
+                return Ok(None);
+            }
+        }
+
+        if !self.target_available() {
+            log::debug!(
+                "Snapshot directory {} unavailable, skipping",
+                self.config.directory.display()
+            );
+            return Ok(None);
+        }
+
+        let snapshot = self.collect(level, stats, scorer);
+        let path = self.write(snapshot)?;
+        self.last_written = Some(Instant::now());
+        self.enforce_retention();
+
+        Ok(path)
+    }
+
+    /// 检查目标目录存在且有足够空间
+    fn target_available(&self) -> bool {
+        let is_dir = fs::metadata(&self.config.directory)
+            .map(|m| m.is_dir())
+            .unwrap_or(false);
+        if !is_dir {
+            return false;
+        }
+
+        SystemInterface::new()
+            .available_space(&self.config.directory)
+            .map(|space| space >= self.config.max_bytes as u64)
+            .unwrap_or(false)
+    }
+
+    /// 在时间预算内收集快照内容
+    fn collect(&self, level: PressureLevel, stats: &MemoryStats, scorer: &OOMScorer) -> DiagnosticSnapshot {
+        let deadline = Instant::now() + self.config.time_budget;
+        let mut truncated = false;
+
+        let mut processes = crate::linux::proc::get_all_processes().unwrap_or_default();
+        processes.sort_by_key(|p| std::cmp::Reverse(p.mem_info.vm_rss));
+        if processes.len() > self.config.top_n {
+            processes.truncate(self.config.top_n);
+            truncated = true;
+        }
+
+        let mut entries = Vec::with_capacity(processes.len());
+        for process in processes {
+            if Instant::now() >= deadline {
+                truncated = true;
+                break;
+            }
+            entries.push(describe_process(process, stats.total_memory, scorer));
+        }
+
+        DiagnosticSnapshot {
+            timestamp_ms: unix_millis(),
+            level,
+            memory: stats.clone(),
+            vmstat_rates: self.vmstat_rates.clone(),
+            processes: entries,
+            truncated,
+        }
+    }
+
+    /// 序列化并原子地写入快照，超出大小限制时逐个省略排名靠后的进程
+    fn write(&self, mut snapshot: DiagnosticSnapshot) -> Result<Option<PathBuf>> {
+        let mut data = serialize(&snapshot)?;
+        while data.len() > self.config.max_bytes {
+            if snapshot.processes.pop().is_none() {
+                log::warn!("Snapshot exceeds {} bytes even without processes, skipping", self.config.max_bytes);
+                return Ok(None);
+            }
+            snapshot.truncated = true;
+            data = serialize(&snapshot)?;
+        }
+
+        let name = format!("{}{:016}.json", SNAPSHOT_PREFIX, snapshot.timestamp_ms);
+        let path = self.config.directory.join(&name);
+        let tmp_path = self.config.directory.join(format!(".{}.tmp", name));
+
+        fs::write(&tmp_path, &data)?;
+        fs::rename(&tmp_path, &path)?;
+
+        Ok(Some(path))
+    }
+
+    /// 删除超出保留数量的旧快照
+    fn enforce_retention(&self) {
+        let mut snapshots = list_snapshots(&self.config.directory);
+        if snapshots.len() <= self.config.retain {
+            return;
+        }
+
+        // 文件名中的时间戳是定宽的，按名称排序即按时间排序
+        snapshots.sort();
+        let excess = snapshots.len() - self.config.retain;
+        for path in &snapshots[..excess] {
+            if let Err(e) = fs::remove_file(path) {
+                log::debug!("Failed to remove old snapshot {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+/// 列出目录中的快照文件
+fn list_snapshots(directory: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .map(|n| n.starts_with(SNAPSHOT_PREFIX) && n.ends_with(".json"))
+                .unwrap_or(false)
+        })
+        .collect()
+}
+
+/// 收集单个进程的快照信息
+fn describe_process(process: ProcessInfo, total_memory: u64, scorer: &OOMScorer) -> ProcessSnapshot {
+    let pid = process.pid.as_raw();
+    let cmdline = read_cmdline(pid);
+    let cgroup = CgroupInfo::from_pid(process.pid)
+        .map(|info| info.memory_path)
+        .unwrap_or_default();
+    let comm = process.name.clone();
+    let uid = process.uid;
+    let rss_kb = process.mem_info.vm_rss;
+    let swap_kb = process.mem_info.vm_swap;
+    let score = scorer.calculate_score(process, total_memory).total_score;
+
+    ProcessSnapshot {
+        pid,
+        comm,
+        cmdline,
+        uid,
+        cgroup,
+        rss_kb,
+        swap_kb,
+        score,
+    }
+}
+
+/// 读取进程的命令行，参数之间的NUL替换为空格
+fn read_cmdline(pid: i32) -> String {
+    let mut buf = Vec::new();
+    let read = fs::File::open(format!("/proc/{}/cmdline", pid))
+        .and_then(|file| file.take(MAX_CMDLINE_BYTES).read_to_end(&mut buf));
+    if read.is_err() {
+        return String::new();
+    }
+
+    String::from_utf8_lossy(&buf)
+        .trim_end_matches('\0')
+        .replace('\0', " ")
+}
+
+fn serialize(snapshot: &DiagnosticSnapshot) -> Result<Vec<u8>> {
+    serde_json::to_vec(snapshot).map_err(|e| {
+        SystemError::SyscallError(std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    })
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_stats() -> MemoryStats {
+        MemoryStats {
+            total_memory: 8 * 1024 * 1024 * 1024,
+            free_memory: 100 * 1024 * 1024,
+            available_memory: 200 * 1024 * 1024,
+            total_swap: 0,
+            free_swap: 0,
+            cached_memory: 50 * 1024 * 1024,
+            ..MemoryStats::default()
+        }
+    }
+
+    fn test_config(dir: &Path) -> SnapshotConfig {
+        SnapshotConfig {
+            min_interval: Duration::from_secs(0),
+            top_n: 5,
+            retain: 2,
+            ..SnapshotConfig::new(dir)
+        }
+    }
+
+    #[test]
+    fn test_snapshot_written_on_critical() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = SnapshotWriter::new(test_config(dir.path()));
+        let scorer = OOMScorer::new();
+
+        // Normal和Warning不应触发
+        assert!(writer.maybe_write(PressureLevel::Warning, &test_stats(), &scorer).unwrap().is_none());
+
+        let path = writer.maybe_write(PressureLevel::Critical, &test_stats(), &scorer)
+            .unwrap()
+            .expect("snapshot should be written");
+        let content: serde_json::Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        assert_eq!(content["level"], "Critical");
+        assert!(content["processes"].as_array().unwrap().len() <= 5);
+
+        // 持续处于Critical不应重复写入
+        assert!(writer.maybe_write(PressureLevel::Critical, &test_stats(), &scorer).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_snapshot_rate_limited() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = SnapshotWriter::new(SnapshotConfig {
+            min_interval: Duration::from_secs(3600),
+            ..test_config(dir.path())
+        });
+        let scorer = OOMScorer::new();
+
+        assert!(writer.maybe_write(PressureLevel::Critical, &test_stats(), &scorer).unwrap().is_some());
+        writer.maybe_write(PressureLevel::Normal, &test_stats(), &scorer).unwrap();
+        assert!(writer.maybe_write(PressureLevel::Critical, &test_stats(), &scorer).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_snapshot_retention() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = SnapshotWriter::new(test_config(dir.path()));
+        let scorer = OOMScorer::new();
+
+        for _ in 0..4 {
+            writer.maybe_write(PressureLevel::Critical, &test_stats(), &scorer).unwrap();
+            writer.maybe_write(PressureLevel::Normal, &test_stats(), &scorer).unwrap();
+            std::thread::sleep(Duration::from_millis(2));
+        }
+
+        assert_eq!(list_snapshots(dir.path()).len(), 2);
+    }
+
+    #[test]
+    fn test_snapshot_size_bounded() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut writer = SnapshotWriter::new(SnapshotConfig {
+            max_bytes: 1024,
+            top_n: 50,
+            ..test_config(dir.path())
+        });
+        let scorer = OOMScorer::new();
+
+        if let Some(path) = writer.maybe_write(PressureLevel::Critical, &test_stats(), &scorer).unwrap() {
+            assert!(fs::metadata(path).unwrap().len() <= 1024);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_skipped_when_directory_missing() {
+        let mut writer = SnapshotWriter::new(test_config(Path::new("/nonexistent/room-snapshots")));
+        let scorer = OOMScorer::new();
+
+        assert!(writer.maybe_write(PressureLevel::Critical, &test_stats(), &scorer).unwrap().is_none());
+    }
+}
@@ -0,0 +1,127 @@
+//! 跨重启保留的终止统计
+//!
+//! 设置 `KillerConfig::state_file` 后，终止次数、回收量和按进程名统计的终止排行
+//! 定期以JSON写入状态文件，启动时读取并累加到新的统计中
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use crate::ffi::types::{SystemError, Result};
+
+/// 一个进程名的累计终止统计
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OffenderStats {
+    pub kills: u64,
+    /// 按终止前RSS估算的回收量（字节）
+    pub reclaimed: u64,
+}
+
+/// 状态文件的内容
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PersistedStats {
+    pub total_kills: u64,
+    /// 按终止前RSS估算的回收量（字节）
+    pub estimated_reclaimed: u64,
+    /// 终止后实际观察到的回收量（字节）
+    pub confirmed_reclaimed: u64,
+    /// 按进程名统计的终止次数和回收量
+    #[serde(default)]
+    pub offenders: BTreeMap<String, OffenderStats>,
+}
+
+impl PersistedStats {
+    /// 读取状态文件，文件不存在时返回None
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(SystemError::SyscallError(e)),
+        };
+        serde_json::from_str(&content)
+            .map(Some)
+            .map_err(|e| SystemError::SyscallError(io::Error::new(io::ErrorKind::InvalidData, e)))
+    }
+
+    /// 先写入同一目录下的临时文件再重命名，读取方不会看到写了一半的文件
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let data = serde_json::to_vec(self)
+            .map_err(|e| SystemError::SyscallError(io::Error::new(io::ErrorKind::InvalidData, e)))?;
+        let name = path.file_name().map(|name| name.to_string_lossy()).unwrap_or_default();
+        let tmp_path = path.with_file_name(format!(".{}.tmp", name));
+
+        fs::write(&tmp_path, &data)?;
+        fs::rename(&tmp_path, path)?;
+        Ok(())
+    }
+
+    /// 把 `other` 的计数累加到这里
+    pub fn merge(&mut self, other: &PersistedStats) {
+        self.total_kills = self.total_kills.saturating_add(other.total_kills);
+        self.estimated_reclaimed = self.estimated_reclaimed.saturating_add(other.estimated_reclaimed);
+        self.confirmed_reclaimed = self.confirmed_reclaimed.saturating_add(other.confirmed_reclaimed);
+        for (name, stats) in &other.offenders {
+            let entry = self.offenders.entry(name.clone()).or_default();
+            entry.kills = entry.kills.saturating_add(stats.kills);
+            entry.reclaimed = entry.reclaimed.saturating_add(stats.reclaimed);
+        }
+    }
+}
+
+/// 按终止次数降序（次数相同时按回收量降序）排列的前 `n` 个进程名
+pub fn top_offenders(offenders: &BTreeMap<String, OffenderStats>, n: usize) -> Vec<(String, OffenderStats)> {
+    let mut ranked: Vec<(String, OffenderStats)> =
+        offenders.iter().map(|(name, stats)| (name.clone(), *stats)).collect();
+    ranked.sort_by(|(_, a), (_, b)| b.kills.cmp(&a.kills).then(b.reclaimed.cmp(&a.reclaimed)));
+    ranked.truncate(n);
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offender(kills: u64, reclaimed: u64) -> OffenderStats {
+        OffenderStats { kills, reclaimed }
+    }
+
+    #[test]
+    fn test_save_load_and_merge() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.json");
+        assert_eq!(PersistedStats::load(&path).unwrap(), None);
+
+        let mut stats = PersistedStats {
+            total_kills: 3,
+            estimated_reclaimed: 3 << 30,
+            confirmed_reclaimed: 2 << 30,
+            offenders: BTreeMap::from([
+                ("chrome".to_string(), offender(2, 2 << 30)),
+                ("java".to_string(), offender(1, 1 << 30)),
+            ]),
+        };
+        stats.save(&path).unwrap();
+        assert_eq!(PersistedStats::load(&path).unwrap(), Some(stats.clone()));
+        // 没有留下临时文件
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+
+        let session = PersistedStats {
+            total_kills: 1,
+            estimated_reclaimed: 1 << 30,
+            confirmed_reclaimed: 0,
+            offenders: BTreeMap::from([("java".to_string(), offender(1, 3 << 30))]),
+        };
+        stats.merge(&session);
+        assert_eq!(stats.total_kills, 4);
+        assert_eq!(stats.estimated_reclaimed, 4 << 30);
+        assert_eq!(stats.confirmed_reclaimed, 2 << 30);
+        assert_eq!(
+            top_offenders(&stats.offenders, 1),
+            vec![("java".to_string(), offender(2, 4 << 30))]
+        );
+
+        fs::write(&path, "not json").unwrap();
+        assert!(PersistedStats::load(&path).is_err());
+    }
+}
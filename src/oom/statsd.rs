@@ -0,0 +1,193 @@
+#![cfg(feature = "statsd")]
+
+use std::collections::HashMap;
+use std::net::UdpSocket;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use crate::ffi::types::{SystemError, Result};
+use crate::oom::metrics::{MetricKind, MetricSample, MetricsRegistry};
+
+/// StatsD 配置
+#[derive(Debug, Clone)]
+pub struct StatsdConfig {
+    /// statsd 服务地址，形如 "127.0.0.1:8125"
+    pub address: String,
+    /// 指标名前缀
+    pub prefix: String,
+    /// 发送间隔
+    pub flush_interval: Duration,
+}
+
+impl StatsdConfig {
+    /// 使用默认前缀和间隔创建配置
+    pub fn new(address: impl Into<String>) -> Self {
+        Self {
+            address: address.into(),
+            prefix: "room".to_string(),
+            flush_interval: Duration::from_secs(10),
+        }
+    }
+}
+
+/// 把注册表中的指标格式化为 statsd 行协议并通过 UDP 发送
+struct StatsdEmitter {
+    socket: UdpSocket,
+    address: String,
+    prefix: String,
+    last_counters: HashMap<&'static str, u64>,
+    reported_failure: bool,
+}
+
+impl StatsdEmitter {
+    fn new(config: &StatsdConfig) -> Result<Self> {
+        let socket = UdpSocket::bind("0.0.0.0:0").map_err(SystemError::SyscallError)?;
+        Ok(Self {
+            socket,
+            address: config.address.clone(),
+            prefix: config.prefix.clone(),
+            last_counters: HashMap::new(),
+            reported_failure: false,
+        })
+    }
+
+    /// 格式化一批指标，计数器发送自上次以来的增量
+    fn format(&mut self, samples: &[MetricSample]) -> String {
+        let mut payload = String::new();
+
+        for sample in samples {
+            let line = match sample.kind {
+                MetricKind::Counter => {
+                    let last = self.last_counters.insert(sample.name, sample.value).unwrap_or(0);
+                    format!("{}.{}:{}|c", self.prefix, sample.name, sample.value.saturating_sub(last))
+                }
+                MetricKind::Gauge => format!("{}.{}:{}|g", self.prefix, sample.name, sample.value),
+                MetricKind::Timing => format!(
+                    "{}.{}:{:.3}|ms",
+                    self.prefix,
+                    sample.name,
+                    sample.value as f64 / 1000.0
+                ),
+            };
+            if !payload.is_empty() {
+                payload.push('\n');
+            }
+            payload.push_str(&line);
+        }
+
+        payload
+    }
+
+    /// 发送一次当前指标，UDP 错误被忽略
+    fn flush(&mut self, registry: &MetricsRegistry) {
+        let payload = self.format(&registry.samples());
+
+        if let Err(e) = self.socket.send_to(payload.as_bytes(), &self.address) {
+            // statsd 本身就是尽力而为，只在第一次失败时留下线索以便排查配置错误
+            if !self.reported_failure {
+                self.reported_failure = true;
+                log::debug!("Failed to send statsd metrics to {}: {}", self.address, e);
+            }
+        }
+    }
+}
+
+/// 定期向 statsd 发送指标的后台线程
+pub struct StatsdSink {
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl StatsdSink {
+    /// 创建发送端并启动发送线程
+    pub fn new(config: StatsdConfig, registry: &'static MetricsRegistry) -> Result<Self> {
+        let mut emitter = StatsdEmitter::new(&config)?;
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let worker_stop = Arc::clone(&stop);
+        let worker = thread::Builder::new()
+            .name("oom-statsd".to_string())
+            .spawn(move || {
+                while !worker_stop.load(Ordering::SeqCst) {
+                    thread::park_timeout(config.flush_interval);
+                    emitter.flush(registry);
+                }
+            })
+            .map_err(SystemError::SyscallError)?;
+
+        Ok(Self {
+            stop,
+            worker: Some(worker),
+        })
+    }
+}
+
+impl Drop for StatsdSink {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(worker) = self.worker.take() {
+            worker.thread().unpark();
+            let _ = worker.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::oom::pressure::PressureLevel;
+
+    #[test]
+    fn test_emits_statsd_lines() {
+        let server = UdpSocket::bind("127.0.0.1:0").unwrap();
+        server.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+        let registry = MetricsRegistry::new();
+        let mut emitter = StatsdEmitter::new(&StatsdConfig {
+            prefix: "test.room".to_string(),
+            ..StatsdConfig::new(server.local_addr().unwrap().to_string())
+        }).unwrap();
+
+        registry.record_kill(4096);
+        registry.set_pressure_level(PressureLevel::Critical);
+        registry.set_available_bytes(1024);
+        registry.record_selection(Duration::from_micros(1500));
+        emitter.flush(&registry);
+
+        let mut buf = [0u8; 1024];
+        let n = server.recv(&mut buf).unwrap();
+        let payload = String::from_utf8_lossy(&buf[..n]);
+        let lines: Vec<&str> = payload.lines().collect();
+
+        assert!(lines.contains(&"test.room.kills:1|c"));
+        assert!(lines.contains(&"test.room.memory_reclaimed_bytes:4096|c"));
+        assert!(lines.contains(&"test.room.pressure_level:2|g"));
+        assert!(lines.contains(&"test.room.available_bytes:1024|g"));
+        assert!(lines.contains(&"test.room.selection_duration:1.500|ms"));
+    }
+
+    #[test]
+    fn test_counters_sent_as_deltas() {
+        let registry = MetricsRegistry::new();
+        let mut emitter = StatsdEmitter::new(&StatsdConfig::new("127.0.0.1:9")).unwrap();
+
+        registry.record_kill(0);
+        registry.record_kill(0);
+        assert!(emitter.format(&registry.samples()).contains("room.kills:2|c"));
+
+        registry.record_kill(0);
+        assert!(emitter.format(&registry.samples()).contains("room.kills:1|c"));
+    }
+
+    #[test]
+    fn test_send_failure_is_silent() {
+        let registry = MetricsRegistry::new();
+        // 缺少端口的地址，发送必然失败
+        let mut emitter = StatsdEmitter::new(&StatsdConfig::new("missing-port")).unwrap();
+
+        emitter.flush(&registry);
+        emitter.flush(&registry);
+        assert!(emitter.reported_failure);
+    }
+}
@@ -0,0 +1,378 @@
+#![cfg(feature = "webhook")]
+
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use crate::ffi::types::{SystemError, Result};
+use crate::oom::audit::AuditRecord;
+
+/// 签名头名称，值为 "sha256=<hex>"
+pub const SIGNATURE_HEADER: &str = "X-Room-Signature";
+
+/// Webhook 配置
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    /// 目标地址，仅支持 http://host[:port]/path
+    pub url: String,
+    /// 单次请求（连接、写入、读取）的超时时间
+    pub timeout: Duration,
+    /// 失败后的最大重试次数
+    pub max_retries: u32,
+    /// 第一次重试前的等待时间，之后每次翻倍
+    pub initial_backoff: Duration,
+    /// HMAC-SHA256 签名密钥，`None` 表示不签名
+    pub secret: Option<Vec<u8>>,
+    /// 待发送队列容量，满时丢弃最旧的记录
+    pub queue_capacity: usize,
+}
+
+impl WebhookConfig {
+    /// 使用默认参数创建指向指定地址的配置
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            timeout: Duration::from_secs(2),
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(200),
+            secret: None,
+            queue_capacity: 64,
+        }
+    }
+}
+
+/// 解析后的 http 地址
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Endpoint {
+    host: String,
+    port: u16,
+    path: String,
+}
+
+impl Endpoint {
+    fn parse(url: &str) -> Result<Self> {
+        let invalid = |msg: &str| {
+            SystemError::SyscallError(io::Error::new(io::ErrorKind::InvalidInput, msg.to_string()))
+        };
+
+        let rest = url.strip_prefix("http://")
+            .ok_or_else(|| invalid("Only http:// webhook URLs are supported"))?;
+        let (authority, path) = match rest.find('/') {
+            Some(idx) => (&rest[..idx], &rest[idx..]),
+            None => (rest, "/"),
+        };
+        let (host, port) = match authority.rsplit_once(':') {
+            Some((host, port)) => (host, port.parse().map_err(|_| invalid("Invalid webhook port"))?),
+            None => (authority, 80),
+        };
+        if host.is_empty() {
+            return Err(invalid("Missing webhook host"));
+        }
+
+        Ok(Self {
+            host: host.to_string(),
+            port,
+            path: path.to_string(),
+        })
+    }
+}
+
+/// 有界的丢弃最旧队列
+#[derive(Debug)]
+struct DeliveryQueue {
+    items: Mutex<VecDeque<Vec<u8>>>,
+    ready: Condvar,
+    capacity: usize,
+    dropped: AtomicU64,
+    closed: AtomicBool,
+}
+
+impl DeliveryQueue {
+    fn new(capacity: usize) -> Self {
+        Self {
+            items: Mutex::new(VecDeque::with_capacity(capacity)),
+            ready: Condvar::new(),
+            capacity: capacity.max(1),
+            dropped: AtomicU64::new(0),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    /// 入队，队列已满时丢弃最旧的记录
+    fn push(&self, body: Vec<u8>) {
+        let mut items = self.items.lock().unwrap_or_else(|e| e.into_inner());
+        if items.len() >= self.capacity {
+            items.pop_front();
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        items.push_back(body);
+        self.ready.notify_one();
+    }
+
+    /// 阻塞等待下一条记录，队列关闭后返回 `None`
+    fn pop(&self) -> Option<Vec<u8>> {
+        let mut items = self.items.lock().unwrap_or_else(|e| e.into_inner());
+        loop {
+            if self.closed.load(Ordering::SeqCst) {
+                return None;
+            }
+            if let Some(body) = items.pop_front() {
+                return Some(body);
+            }
+            items = self.ready.wait(items).unwrap_or_else(|e| e.into_inner());
+        }
+    }
+
+    fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        let _guard = self.items.lock().unwrap_or_else(|e| e.into_inner());
+        self.ready.notify_all();
+    }
+}
+
+/// 将审计记录异步 POST 到 webhook 的发送端
+///
+/// # 为什么使用有界队列？
+///
+/// 终止流程绝不能因为 webhook 接收端宕机而被阻塞，所以 `send` 只负责入队，
+/// 实际发送在独立线程中进行；接收端不可用时丢弃最旧的记录并计数。
+pub struct WebhookSink {
+    queue: Arc<DeliveryQueue>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl WebhookSink {
+    /// 创建发送端并启动发送线程
+    pub fn new(config: WebhookConfig) -> Result<Self> {
+        let endpoint = Endpoint::parse(&config.url)?;
+        let queue = Arc::new(DeliveryQueue::new(config.queue_capacity));
+
+        let worker_queue = Arc::clone(&queue);
+        let worker = thread::Builder::new()
+            .name("oom-webhook".to_string())
+            .spawn(move || {
+                while let Some(body) = worker_queue.pop() {
+                    deliver_with_retry(&endpoint, &config, &body, &worker_queue.closed);
+                }
+            })
+            .map_err(SystemError::SyscallError)?;
+
+        Ok(Self {
+            queue,
+            worker: Some(worker),
+        })
+    }
+
+    /// 将审计记录加入发送队列，不会阻塞
+    pub fn send(&self, record: &AuditRecord) {
+        self.queue.push(record.to_json().into_bytes());
+    }
+
+    /// 因队列已满而丢弃的记录数
+    pub fn dropped(&self) -> u64 {
+        self.queue.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for WebhookSink {
+    fn drop(&mut self) {
+        self.queue.close();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// 发送一条记录，失败时按指数退避重试
+fn deliver_with_retry(endpoint: &Endpoint, config: &WebhookConfig, body: &[u8], closed: &AtomicBool) {
+    let mut backoff = config.initial_backoff;
+
+    for attempt in 0..=config.max_retries {
+        match post(endpoint, config, body) {
+            Ok(()) => return,
+            Err(e) => {
+                log::debug!("Webhook delivery attempt {} failed: {}", attempt + 1, e);
+            }
+        }
+
+        if attempt == config.max_retries || closed.load(Ordering::SeqCst) {
+            break;
+        }
+        thread::sleep(backoff);
+        backoff = backoff.saturating_mul(2);
+    }
+
+    log::warn!("Dropping webhook record after {} attempts", config.max_retries + 1);
+}
+
+/// 发送一次 HTTP/1.1 POST 请求，2xx 视为成功
+fn post(endpoint: &Endpoint, config: &WebhookConfig, body: &[u8]) -> io::Result<()> {
+    let addr = (endpoint.host.as_str(), endpoint.port)
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Webhook host did not resolve"))?;
+
+    let mut stream = TcpStream::connect_timeout(&addr, config.timeout)?;
+    stream.set_read_timeout(Some(config.timeout))?;
+    stream.set_write_timeout(Some(config.timeout))?;
+
+    let mut request = format!(
+        "POST {} HTTP/1.1\r\nHost: {}:{}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n",
+        endpoint.path,
+        endpoint.host,
+        endpoint.port,
+        body.len()
+    );
+    if let Some(secret) = &config.secret {
+        request.push_str(&format!("{}: sha256={}\r\n", SIGNATURE_HEADER, sign(secret, body)));
+    }
+    request.push_str("\r\n");
+
+    stream.write_all(request.as_bytes())?;
+    stream.write_all(body)?;
+    stream.flush()?;
+
+    // 只需要状态行
+    let mut status_line = String::new();
+    BufReader::new(stream).read_line(&mut status_line)?;
+    let status: u16 = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Malformed HTTP status line"))?;
+
+    if (200..300).contains(&status) {
+        Ok(())
+    } else {
+        Err(io::Error::other(format!("Webhook returned HTTP {}", status)))
+    }
+}
+
+/// 计算 HMAC-SHA256 签名的十六进制表示
+fn sign(secret: &[u8], body: &[u8]) -> String {
+    // HMAC 接受任意长度的密钥
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret).expect("HMAC accepts any key length");
+    mac.update(body);
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use crate::oom::audit::AuditEvent;
+    use crate::oom::pressure::PressureLevel;
+
+    /// 模拟服务端收到的请求头和请求体
+    type ReceivedRequest = (Vec<String>, Vec<u8>);
+
+    /// 简单的 HTTP 服务端：接收一个请求，返回 200，并把请求头和请求体交给测试
+    fn spawn_mock_server() -> (String, mpsc::Receiver<ReceivedRequest>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hooks/room", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut headers = Vec::new();
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end().to_string();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some(len) = line.strip_prefix("Content-Length: ") {
+                    content_length = len.parse().unwrap();
+                }
+                headers.push(line);
+            }
+
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            (&stream).write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\n\r\n").unwrap();
+            tx.send((headers, body)).unwrap();
+        });
+
+        (url, rx)
+    }
+
+    #[test]
+    fn test_parse_endpoint() {
+        let endpoint = Endpoint::parse("http://alerts.local:8080/room").unwrap();
+        assert_eq!(endpoint.host, "alerts.local");
+        assert_eq!(endpoint.port, 8080);
+        assert_eq!(endpoint.path, "/room");
+
+        assert_eq!(Endpoint::parse("http://alerts.local").unwrap().path, "/");
+        assert!(Endpoint::parse("https://alerts.local").is_err());
+    }
+
+    #[test]
+    fn test_delivers_signed_record() {
+        let (url, rx) = spawn_mock_server();
+        let secret = b"s3cret".to_vec();
+        let sink = WebhookSink::new(WebhookConfig {
+            secret: Some(secret.clone()),
+            ..WebhookConfig::new(url)
+        }).unwrap();
+
+        sink.send(&AuditRecord::new(AuditEvent::PressureTransition {
+            from: PressureLevel::Normal,
+            to: PressureLevel::Critical,
+        }));
+
+        let (headers, body) = rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert!(headers[0].starts_with("POST /hooks/room HTTP/1.1"));
+
+        let value: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(value["event"], "pressure_transition");
+
+        let expected = format!("{}: sha256={}", SIGNATURE_HEADER, sign(&secret, &body));
+        assert!(headers.contains(&expected));
+    }
+
+    #[test]
+    fn test_queue_drops_oldest() {
+        let queue = DeliveryQueue::new(2);
+        queue.push(b"1".to_vec());
+        queue.push(b"2".to_vec());
+        queue.push(b"3".to_vec());
+
+        assert_eq!(queue.dropped.load(Ordering::Relaxed), 1);
+        assert_eq!(queue.pop().unwrap(), b"2");
+        assert_eq!(queue.pop().unwrap(), b"3");
+    }
+
+    #[test]
+    fn test_dead_endpoint_never_blocks_sender() {
+        // 绑定后立即释放端口，得到一个无人监听的地址
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let sink = WebhookSink::new(WebhookConfig {
+            queue_capacity: 2,
+            initial_backoff: Duration::from_secs(1),
+            ..WebhookConfig::new(format!("http://127.0.0.1:{}/", port))
+        }).unwrap();
+
+        let start = std::time::Instant::now();
+        for _ in 0..10 {
+            sink.send(&AuditRecord::new(AuditEvent::RateLimited { remaining_ms: 1 }));
+        }
+        assert!(start.elapsed() < Duration::from_millis(100));
+        assert!(sink.dropped() >= 7);
+    }
+}
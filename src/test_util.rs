@@ -0,0 +1,731 @@
+//! 测试工具：在临时目录中生成合成的proc目录
+//!
+//! 库内的单元测试直接使用，集成测试和基准测试需要启用 `test-util` feature。`FixtureBuilder` 按声明式的进程组描述生成
+//! meminfo、uptime 和每个pid的 status/stat/statm/oom_score/oom_score_adj，
+//! 可以按比例写入损坏或不完整的文件，或让进程在第二次读取时消失，
+//! 用于在大规模和异常输入下驱动选择器与压力检测器。
+//! `FakeProc` 逐个描述指定pid的进程，用于用少量进程端到端地测试策略
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+use crate::ffi::types::{ProcessId, SystemError, Result};
+
+/// 合成进程的默认起始pid，避开pid 1等系统进程
+pub const FIRST_PID: i32 = 1000;
+
+/// 构建器的默认伪随机种子
+const DEFAULT_SEED: u64 = 0x5eed;
+
+/// 进程组中每个进程的RSS分布
+#[derive(Debug, Clone, Copy)]
+pub enum RssDistribution {
+    /// 所有进程相同（kB）
+    Constant(u64),
+    /// 从 `start_kb` 开始，每个进程递增 `step_kb`
+    Linear { start_kb: u64, step_kb: u64 },
+    /// `[min_kb, max_kb)` 内的伪随机值，由构建器的种子决定
+    Uniform { min_kb: u64, max_kb: u64 },
+}
+
+/// 一组属性相同的进程
+#[derive(Debug, Clone)]
+pub struct ProcessGroup {
+    count: usize,
+    name: String,
+    rss: RssDistribution,
+    swap_kb: u64,
+    state: String,
+    ppid: i32,
+    uid: u32,
+    oom_score: i32,
+    oom_score_adj: i32,
+    start_time: u64,
+    tty_nr: i32,
+    corrupt_fraction: f64,
+    vanishing_fraction: f64,
+}
+
+impl ProcessGroup {
+    /// 创建包含 `count` 个进程的组，默认为1MB RSS的睡眠进程
+    pub fn new(count: usize) -> Self {
+        Self {
+            count,
+            name: "worker-{}".to_string(),
+            rss: RssDistribution::Constant(1024),
+            swap_kb: 0,
+            state: "S (sleeping)".to_string(),
+            ppid: 1,
+            uid: 1000,
+            oom_score: 100,
+            oom_score_adj: 0,
+            start_time: 4821,
+            tty_nr: 0,
+            corrupt_fraction: 0.0,
+            vanishing_fraction: 0.0,
+        }
+    }
+
+    /// 进程名模式，`{}` 被替换为进程在组内的序号
+    pub fn name(mut self, pattern: &str) -> Self {
+        self.name = pattern.to_string();
+        self
+    }
+
+    /// RSS分布，为0时不写入Vm*行（与内核线程一致）
+    pub fn rss(mut self, rss: RssDistribution) -> Self {
+        self.rss = rss;
+        self
+    }
+
+    /// swap使用量（kB）
+    pub fn swap_kb(mut self, swap_kb: u64) -> Self {
+        self.swap_kb = swap_kb;
+        self
+    }
+
+    /// status中的State字段，例如 "Z (zombie)"
+    pub fn state(mut self, state: &str) -> Self {
+        self.state = state.to_string();
+        self
+    }
+
+    /// 父进程pid，内核线程为2
+    pub fn ppid(mut self, ppid: i32) -> Self {
+        self.ppid = ppid;
+        self
+    }
+
+    /// real uid
+    pub fn uid(mut self, uid: u32) -> Self {
+        self.uid = uid;
+        self
+    }
+
+    /// oom_score 和 oom_score_adj
+    pub fn oom_scores(mut self, oom_score: i32, oom_score_adj: i32) -> Self {
+        self.oom_score = oom_score;
+        self.oom_score_adj = oom_score_adj;
+        self
+    }
+
+    /// stat中的starttime（时钟滴答），组内进程依次加1
+    pub fn start_time(mut self, start_time: u64) -> Self {
+        self.start_time = start_time;
+        self
+    }
+
+    /// stat中控制终端的设备号，0表示没有控制终端
+    pub fn tty_nr(mut self, tty_nr: i32) -> Self {
+        self.tty_nr = tty_nr;
+        self
+    }
+
+    /// 文件损坏或不完整的进程比例（0-1）
+    pub fn corrupt_fraction(mut self, fraction: f64) -> Self {
+        self.corrupt_fraction = fraction;
+        self
+    }
+
+    /// 枚举之后、读取之前消失的进程比例（0-1），见 `ProcFixture::vanish_hook`
+    pub fn vanishing_fraction(mut self, fraction: f64) -> Self {
+        self.vanishing_fraction = fraction;
+        self
+    }
+}
+
+/// /proc/meminfo 中的字段（kB）
+#[derive(Debug, Clone, Copy)]
+pub struct MemInfoSpec {
+    pub total_kb: u64,
+    pub free_kb: u64,
+    pub available_kb: u64,
+    pub cached_kb: u64,
+    pub swap_total_kb: u64,
+    pub swap_free_kb: u64,
+}
+
+impl Default for MemInfoSpec {
+    /// 与仓库中的meminfo夹具一致，没有内存压力
+    fn default() -> Self {
+        Self {
+            total_kb: 6158152,
+            free_kb: 2902724,
+            available_kb: 5582016,
+            cached_kb: 2773444,
+            swap_total_kb: 2097148,
+            swap_free_kb: 1835004,
+        }
+    }
+}
+
+/// 合成proc目录的构建器
+#[derive(Debug, Clone)]
+pub struct FixtureBuilder {
+    meminfo: MemInfoSpec,
+    meminfo_raw: Option<String>,
+    uptime_secs: f64,
+    groups: Vec<ProcessGroup>,
+    first_pid: i32,
+    seed: u64,
+}
+
+impl Default for FixtureBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FixtureBuilder {
+    /// 创建空的构建器：没有进程，系统已运行一天
+    pub fn new() -> Self {
+        Self {
+            meminfo: MemInfoSpec::default(),
+            meminfo_raw: None,
+            uptime_secs: 86400.25,
+            groups: Vec::new(),
+            first_pid: FIRST_PID,
+            seed: DEFAULT_SEED,
+        }
+    }
+
+    /// meminfo的字段
+    pub fn meminfo(mut self, meminfo: MemInfoSpec) -> Self {
+        self.meminfo = meminfo;
+        self
+    }
+
+    /// 只修改可用内存，其余字段保持不变
+    pub fn available_kb(mut self, available_kb: u64) -> Self {
+        self.meminfo.available_kb = available_kb;
+        self
+    }
+
+    /// 原样写入meminfo的内容，用于构造格式错误的文件
+    pub fn meminfo_raw(mut self, content: &str) -> Self {
+        self.meminfo_raw = Some(content.to_string());
+        self
+    }
+
+    /// 系统运行时间（秒）
+    pub fn uptime_secs(mut self, uptime_secs: f64) -> Self {
+        self.uptime_secs = uptime_secs;
+        self
+    }
+
+    /// 添加一组进程，pid紧接在上一组之后
+    pub fn processes(mut self, group: ProcessGroup) -> Self {
+        self.groups.push(group);
+        self
+    }
+
+    /// 第一个进程的pid
+    pub fn first_pid(mut self, pid: i32) -> Self {
+        self.first_pid = pid;
+        self
+    }
+
+    /// 伪随机RSS、损坏和消失进程的种子，相同的种子生成相同的目录
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// 在新的临时目录中写入proc目录
+    pub fn build(&self) -> Result<ProcFixture> {
+        let dir = TempDir::new()?;
+        let mut rng = XorShift::new(self.seed);
+
+        let meminfo = match &self.meminfo_raw {
+            Some(content) => content.clone(),
+            None => format_meminfo(&self.meminfo),
+        };
+        write_system_files(dir.path(), &meminfo, self.uptime_secs)?;
+
+        let mut fixture = ProcFixture {
+            dir,
+            groups: Vec::with_capacity(self.groups.len()),
+            corrupt: Vec::new(),
+            vanishing: Vec::new(),
+        };
+        let mut pid = self.first_pid;
+        for group in &self.groups {
+            let mut pids = Vec::with_capacity(group.count);
+            for index in 0..group.count {
+                let process_id = ProcessId::new(pid).expect("synthetic pids are positive");
+                let spec = ProcessSpec::new(group, index, pid, &mut rng);
+
+                if rng.chance(group.corrupt_fraction) {
+                    let corruption = Corruption::ALL[rng.below(Corruption::ALL.len() as u64) as usize];
+                    spec.write(fixture.path(), Some(corruption))?;
+                    fixture.corrupt.push(process_id);
+                } else {
+                    spec.write(fixture.path(), None)?;
+                }
+                if rng.chance(group.vanishing_fraction) {
+                    fixture.vanishing.push(process_id);
+                }
+
+                pids.push(process_id);
+                pid += 1;
+            }
+            fixture.groups.push(pids);
+        }
+
+        Ok(fixture)
+    }
+}
+
+/// 逐个描述进程的合成proc目录构建器
+///
+/// 与按进程组批量生成的 `FixtureBuilder` 不同，每个进程使用指定的pid，
+/// 生成的目录可以直接交给选择器、压力检测器和评分器的 `with_proc_root`
+#[derive(Debug, Clone)]
+pub struct FakeProc {
+    meminfo: MemInfoSpec,
+    uptime_secs: f64,
+    processes: Vec<(i32, ProcessGroup)>,
+}
+
+impl Default for FakeProc {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FakeProc {
+    /// 创建空的描述：没有进程，没有内存压力，系统已运行一天
+    pub fn new() -> Self {
+        Self {
+            meminfo: MemInfoSpec::default(),
+            uptime_secs: 86400.25,
+            processes: Vec::new(),
+        }
+    }
+
+    /// meminfo的字段
+    pub fn meminfo(mut self, meminfo: MemInfoSpec) -> Self {
+        self.meminfo = meminfo;
+        self
+    }
+
+    /// 只修改可用内存，其余字段保持不变
+    pub fn available_kb(mut self, available_kb: u64) -> Self {
+        self.meminfo.available_kb = available_kb;
+        self
+    }
+
+    /// 系统运行时间（秒）
+    pub fn uptime_secs(mut self, uptime_secs: f64) -> Self {
+        self.uptime_secs = uptime_secs;
+        self
+    }
+
+    /// 添加pid为 `pid` 的进程，属性取自 `process`，组中的进程数被忽略
+    ///
+    /// 进程名中的 `{}` 被替换为0
+    pub fn process(mut self, pid: i32, process: ProcessGroup) -> Self {
+        self.processes.push((pid, process));
+        self
+    }
+
+    /// 在新的临时目录中写入proc目录，`ProcFixture::group(i)` 为第i个添加的进程
+    ///
+    /// pid不是正数或重复时返回错误
+    pub fn build(&self) -> Result<ProcFixture> {
+        let dir = TempDir::new()?;
+        write_system_files(dir.path(), &format_meminfo(&self.meminfo), self.uptime_secs)?;
+
+        let mut groups = Vec::with_capacity(self.processes.len());
+        for (pid, process) in &self.processes {
+            let process_id = ProcessId::new(*pid).ok_or(SystemError::InvalidPid(*pid))?;
+            if dir.path().join(pid.to_string()).exists() {
+                return Err(SystemError::InvalidPid(*pid));
+            }
+            write_process(dir.path(), *pid, process)?;
+            groups.push(vec![process_id]);
+        }
+
+        Ok(ProcFixture {
+            dir,
+            groups,
+            corrupt: Vec::new(),
+            vanishing: Vec::new(),
+        })
+    }
+}
+
+/// 在proc根目录 `root` 中写入pid为 `pid` 的进程，属性取自 `process`
+///
+/// 与 `FakeProc::process` 相同，组中的进程数被忽略。pid目录已经存在时先删除，
+/// 用于在构建之后添加进程或模拟pid被新进程复用
+pub fn write_process(root: &Path, pid: i32, process: &ProcessGroup) -> Result<()> {
+    let dir = root.join(pid.to_string());
+    if dir.exists() {
+        fs::remove_dir_all(&dir)?;
+    }
+    ProcessSpec::new(process, 0, pid, &mut XorShift::new(DEFAULT_SEED)).write(root, None)
+}
+
+/// 覆盖proc根目录 `root` 中的meminfo，用于在构建之后模拟内存压力出现或消除
+pub fn write_meminfo(root: &Path, meminfo: &MemInfoSpec) -> Result<()> {
+    fs::write(root.join("meminfo"), format_meminfo(meminfo))?;
+    Ok(())
+}
+
+/// 写入meminfo和uptime
+fn write_system_files(root: &Path, meminfo: &str, uptime_secs: f64) -> Result<()> {
+    fs::write(root.join("meminfo"), meminfo)?;
+    fs::write(
+        root.join("uptime"),
+        format!("{:.2} {:.2}\n", uptime_secs, uptime_secs * 2.0),
+    )?;
+    Ok(())
+}
+
+/// 生成的proc目录，被丢弃时删除
+#[derive(Debug)]
+pub struct ProcFixture {
+    dir: TempDir,
+    groups: Vec<Vec<ProcessId>>,
+    corrupt: Vec<ProcessId>,
+    vanishing: Vec<ProcessId>,
+}
+
+impl ProcFixture {
+    /// proc根目录，传给各组件的 `with_proc_root`
+    pub fn path(&self) -> &Path {
+        self.dir.path()
+    }
+
+    /// 第 `index` 个进程组（按添加顺序）的pid
+    pub fn group(&self, index: usize) -> &[ProcessId] {
+        &self.groups[index]
+    }
+
+    /// 写入了损坏文件的进程
+    pub fn corrupt(&self) -> &[ProcessId] {
+        &self.corrupt
+    }
+
+    /// 第二次读取时消失的进程
+    pub fn vanishing(&self) -> &[ProcessId] {
+        &self.vanishing
+    }
+
+    /// 立即删除所有会消失的进程的目录
+    pub fn vanish(&self) {
+        remove_pids(self.path(), &self.vanishing);
+    }
+
+    /// 删除会消失的进程的刷新钩子
+    ///
+    /// 交给 `ProcessSelector::with_refresh_hook` 后，钩子在枚举进程之后、
+    /// 读取进程文件之前执行，这些进程在枚举时可见，读取时已经不存在
+    pub fn vanish_hook(&self) -> impl FnMut() + Send + 'static {
+        let root = self.path().to_path_buf();
+        let vanishing = self.vanishing.clone();
+        move || remove_pids(&root, &vanishing)
+    }
+}
+
+/// 选择器在枚举进程之后调用的钩子
+pub struct RefreshHook(Box<dyn FnMut() + Send>);
+
+impl RefreshHook {
+    /// 包装闭包
+    pub fn new(hook: impl FnMut() + Send + 'static) -> Self {
+        Self(Box::new(hook))
+    }
+
+    /// 执行钩子
+    pub fn call(&mut self) {
+        (self.0)()
+    }
+}
+
+impl fmt::Debug for RefreshHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RefreshHook")
+    }
+}
+
+fn remove_pids(root: &Path, pids: &[ProcessId]) {
+    for pid in pids {
+        // 已经删除过时忽略错误
+        let _ = fs::remove_dir_all(root.join(pid.as_raw().to_string()));
+    }
+}
+
+/// 损坏文件的方式
+#[derive(Debug, Clone, Copy)]
+enum Corruption {
+    /// status在行中间被截断
+    TruncatedStatus,
+    /// stat不是合法格式
+    GarbageStat,
+    /// oom_score_adj不是数字
+    InvalidOomScoreAdj,
+    /// 没有status文件
+    MissingStatus,
+    /// 所有文件都为空
+    EmptyFiles,
+}
+
+impl Corruption {
+    const ALL: [Corruption; 5] = [
+        Corruption::TruncatedStatus,
+        Corruption::GarbageStat,
+        Corruption::InvalidOomScoreAdj,
+        Corruption::MissingStatus,
+        Corruption::EmptyFiles,
+    ];
+}
+
+/// 单个进程的全部字段
+struct ProcessSpec<'a> {
+    group: &'a ProcessGroup,
+    pid: i32,
+    name: String,
+    rss_kb: u64,
+    start_time: u64,
+}
+
+impl<'a> ProcessSpec<'a> {
+    fn new(group: &'a ProcessGroup, index: usize, pid: i32, rng: &mut XorShift) -> Self {
+        let rss_kb = match group.rss {
+            RssDistribution::Constant(kb) => kb,
+            RssDistribution::Linear { start_kb, step_kb } => start_kb + step_kb * index as u64,
+            RssDistribution::Uniform { min_kb, max_kb } => min_kb + rng.below(max_kb.saturating_sub(min_kb).max(1)),
+        };
+
+        Self {
+            group,
+            pid,
+            name: group.name.replace("{}", &index.to_string()),
+            rss_kb,
+            start_time: group.start_time + index as u64,
+        }
+    }
+
+    fn write(&self, root: &Path, corruption: Option<Corruption>) -> Result<()> {
+        let dir = root.join(self.pid.to_string());
+        fs::create_dir(&dir)?;
+
+        let mut status = self.status();
+        let mut stat = self.stat();
+        let mut statm = self.statm();
+        let mut oom_score = format!("{}\n", self.group.oom_score);
+        let mut oom_score_adj = format!("{}\n", self.group.oom_score_adj);
+        match corruption {
+            None => {}
+            Some(Corruption::TruncatedStatus) => {
+                // 截断在Vm*行之前的某一行中间
+                let cut = status.find("PPid:").map(|at| at + 3).unwrap_or(status.len() / 2);
+                status.truncate(cut);
+            }
+            Some(Corruption::GarbageStat) => stat = format!("{} garbage\n", self.pid),
+            Some(Corruption::InvalidOomScoreAdj) => oom_score_adj = "not-a-number\n".to_string(),
+            Some(Corruption::MissingStatus) => {}
+            Some(Corruption::EmptyFiles) => {
+                status.clear();
+                stat.clear();
+                statm.clear();
+                oom_score.clear();
+                oom_score_adj.clear();
+            }
+        }
+
+        if !matches!(corruption, Some(Corruption::MissingStatus)) {
+            fs::write(dir.join("status"), status)?;
+        }
+        fs::write(dir.join("stat"), stat)?;
+        fs::write(dir.join("statm"), statm)?;
+        fs::write(dir.join("oom_score"), oom_score)?;
+        fs::write(dir.join("oom_score_adj"), oom_score_adj)?;
+
+        Ok(())
+    }
+
+    fn status(&self) -> String {
+        let uid = self.group.uid;
+        let mut status = format!(
+            "Name:\t{}\nUmask:\t0022\nState:\t{}\nTgid:\t{}\nNgid:\t0\nPid:\t{}\nPPid:\t{}\n\
+             TracerPid:\t0\nUid:\t{}\t{}\t{}\t{}\nGid:\t{}\t{}\t{}\t{}\n",
+            self.name, self.group.state, self.pid, self.pid, self.group.ppid,
+            uid, uid, uid, uid, uid, uid, uid, uid,
+        );
+        // 内核线程的status中没有Vm*行
+        if self.rss_kb > 0 {
+            status.push_str(&format!(
+                "VmPeak:\t{:>8} kB\nVmSize:\t{:>8} kB\nVmRSS:\t{:>8} kB\nVmSwap:\t{:>8} kB\n",
+                self.vm_size_kb(), self.vm_size_kb(), self.rss_kb, self.group.swap_kb,
+            ));
+        }
+        status.push_str("Threads:\t1\n");
+        status
+    }
+
+    fn stat(&self) -> String {
+        // 内核截断comm到15个字节
+        let mut comm_len = self.name.len().min(15);
+        while !self.name.is_char_boundary(comm_len) {
+            comm_len -= 1;
+        }
+        let state = self.group.state.chars().next().unwrap_or('S');
+
+        format!(
+            "{pid} ({comm}) {state} {ppid} {pid} {pid} {tty_nr} -1 4194560 9623 1203 12 0 431 207 5 3 20 0 1 0 \
+             {start_time} {vsize} {rss} 18446744073709551615 0 0 0 0 0 0 0 0 0 0 0 0 17 1 0 0 0 0 0 0 0 0 0 0 0 0 0\n",
+            pid = self.pid,
+            comm = &self.name[..comm_len],
+            state = state,
+            ppid = self.group.ppid,
+            tty_nr = self.group.tty_nr,
+            start_time = self.start_time,
+            vsize = self.vm_size_kb() * 1024,
+            rss = self.rss_kb / 4,
+        )
+    }
+
+    fn statm(&self) -> String {
+        // 以4kB页为单位：size resident shared text lib data dt
+        format!("{} {} 0 0 0 0 0\n", self.vm_size_kb() / 4, self.rss_kb / 4)
+    }
+
+    fn vm_size_kb(&self) -> u64 {
+        self.rss_kb * 2
+    }
+}
+
+fn format_meminfo(spec: &MemInfoSpec) -> String {
+    [
+        ("MemTotal:", spec.total_kb),
+        ("MemFree:", spec.free_kb),
+        ("MemAvailable:", spec.available_kb),
+        ("Buffers:", 0),
+        ("Cached:", spec.cached_kb),
+        ("SwapCached:", 0),
+        ("SwapTotal:", spec.swap_total_kb),
+        ("SwapFree:", spec.swap_free_kb),
+    ]
+    .iter()
+    .map(|(key, kb)| format!("{:<16}{:>8} kB\n", key, kb))
+    .collect()
+}
+
+/// 确定性的伪随机数生成器（xorshift64*），避免引入依赖
+#[derive(Debug)]
+struct XorShift(u64);
+
+impl XorShift {
+    fn new(seed: u64) -> Self {
+        // 状态不能为0
+        Self(seed | 1)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// `[0, bound)` 内的值
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+
+    /// 以 `probability` 的概率返回true
+    fn chance(&mut self, probability: f64) -> bool {
+        // 取高53位得到 [0, 1) 内均匀分布的浮点数
+        let sample = (self.next() >> 11) as f64 / (1u64 << 53) as f64;
+        probability > 0.0 && sample < probability
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::linux::proc::ProcessInfo;
+    use crate::linux::proc_stat::ProcessStat;
+    use crate::oom::pressure::MemoryStats;
+
+    #[test]
+    fn test_generated_files_parse() {
+        let fixture = FixtureBuilder::new()
+            .available_kb(300000)
+            .processes(ProcessGroup::new(3).name("db-{}").rss(RssDistribution::Linear { start_kb: 1000, step_kb: 10 }))
+            .build()
+            .unwrap();
+
+        let meminfo = fs::read_to_string(fixture.path().join("meminfo")).unwrap();
+        let stats = MemoryStats::parse(&meminfo);
+        assert_eq!(stats.total_memory, 6158152 * 1024);
+        assert_eq!(stats.available_memory, 300000 * 1024);
+
+        let pid = fixture.group(0)[2];
+        assert_eq!(pid.as_raw(), FIRST_PID + 2);
+        let info = ProcessInfo::from_pid_in(fixture.path(), pid).unwrap();
+        assert_eq!(info.name, "db-2");
+        assert_eq!(info.mem_info.vm_rss, 1020);
+        assert_eq!(info.mem_info.oom_score, 100);
+
+        let stat = ProcessStat::from_pid_in(fixture.path(), pid).unwrap();
+        assert_eq!(stat.comm, "db-2");
+        assert_eq!(stat.start_time, 4823);
+    }
+
+    #[test]
+    fn test_fake_proc_uses_given_pids() {
+        let fixture = FakeProc::new()
+            .process(42, ProcessGroup::new(1).name("nginx").rss(RssDistribution::Constant(2048)))
+            .process(7, ProcessGroup::new(5).name("sshd").oom_scores(0, -1000))
+            .build()
+            .unwrap();
+
+        assert_eq!(fixture.group(0), &[ProcessId::new(42).unwrap()]);
+        assert_eq!(fixture.group(1), &[ProcessId::new(7).unwrap()]);
+        let info = ProcessInfo::from_pid_in(fixture.path(), fixture.group(1)[0]).unwrap();
+        assert_eq!(info.name, "sshd");
+        assert_eq!(info.mem_info.oom_score_adj, -1000);
+        assert!(!fixture.path().join("8").exists());
+
+        // 重复的pid
+        assert!(FakeProc::new()
+            .process(42, ProcessGroup::new(1))
+            .process(42, ProcessGroup::new(1))
+            .build()
+            .is_err());
+    }
+
+    #[test]
+    fn test_same_seed_same_fixture() {
+        let builder = FixtureBuilder::new()
+            .seed(7)
+            .processes(ProcessGroup::new(200).corrupt_fraction(0.3).vanishing_fraction(0.2));
+        let first = builder.build().unwrap();
+        let second = builder.build().unwrap();
+
+        assert!(!first.corrupt().is_empty());
+        assert!(!first.vanishing().is_empty());
+        assert_eq!(first.corrupt(), second.corrupt());
+        assert_eq!(first.vanishing(), second.vanishing());
+    }
+
+    #[test]
+    fn test_vanish_hook_removes_pids() {
+        let fixture = FixtureBuilder::new()
+            .processes(ProcessGroup::new(50).vanishing_fraction(0.5))
+            .build()
+            .unwrap();
+        let vanished = fixture.vanishing()[0];
+        assert!(fixture.path().join(vanished.as_raw().to_string()).exists());
+
+        let mut hook = fixture.vanish_hook();
+        hook();
+        hook();
+        assert!(!fixture.path().join(vanished.as_raw().to_string()).exists());
+    }
+}
@@ -0,0 +1,116 @@
+//! 关键路径不分配堆内存
+//!
+//! 全局分配器在关键路径执行期间处于“武装”状态，此时任何分配都会使测试进程
+//! 中止。关键路径与 `OOMKiller::check_and_kill` 中的一致：检查内存压力，
+//! 然后终止计划中的第一个受害者。受害者是真实的子进程，合成proc目录中
+//! 记录的是它们的pid
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::os::unix::process::ExitStatusExt;
+use std::process::{Child, Command};
+use std::time::Duration;
+use room::oom::critical::CriticalState;
+use room::oom::pressure::PressureThresholds;
+use room::oom::selector::SelectorConfig;
+use room::test_util::{FakeProc, ProcessGroup, RssDistribution};
+use room::{OOMScorer, PressureDetector, ProcessId, ProcessSelector, SystemError};
+
+struct ArmedAllocator;
+
+thread_local! {
+    static ARMED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// 先解除武装再报告，报告本身的分配不会递归触发
+fn forbidden_allocation(layout: Layout) -> ! {
+    eprintln!("heap allocation of {} bytes on the critical path", layout.size());
+    // 分配器中不能展开，因此中止而不是 panic
+    std::process::abort()
+}
+
+unsafe impl GlobalAlloc for ArmedAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        if ARMED.try_with(|armed| armed.replace(false)).unwrap_or(false) {
+            forbidden_allocation(layout);
+        }
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        if ARMED.try_with(|armed| armed.replace(false)).unwrap_or(false) {
+            forbidden_allocation(layout);
+        }
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: ArmedAllocator = ArmedAllocator;
+
+fn spawn_sleeper() -> Child {
+    Command::new("sleep").arg("30").spawn().expect("Failed to spawn child")
+}
+
+fn kill(pid: ProcessId) -> room::Result<()> {
+    match unsafe { libc::kill(pid.as_raw(), libc::SIGKILL) } {
+        0 => Ok(()),
+        _ => Err(SystemError::ProcessNotFound),
+    }
+}
+
+#[test]
+fn test_critical_path_does_not_allocate() {
+    // 三个真实的子进程作为候选，RSS最大的一个应被终止
+    let mut children: Vec<Child> = (0..3).map(|_| spawn_sleeper()).collect();
+    let mut proc = FakeProc::new().available_kb(300000);
+    for (i, child) in children.iter().enumerate() {
+        let rss_kb = 64 * 1024 * 1024 + i as u64 * 1024 * 1024;
+        proc = proc.process(child.id() as i32, ProcessGroup::new(1).rss(RssDistribution::Constant(rss_kb)));
+    }
+    let root = proc.build().unwrap();
+    let expected = children[2].id() as i32;
+
+    let thresholds = PressureThresholds {
+        pressure_duration: Duration::ZERO,
+        ..PressureThresholds::default()
+    };
+    let config = SelectorConfig {
+        min_candidates: 1,
+        ..SelectorConfig::default()
+    };
+    let mut selector = ProcessSelector::new(
+        Some(config),
+        OOMScorer::new().with_proc_root(root.path()),
+        PressureDetector::new(Some(thresholds)).with_proc_root(root.path()),
+    )
+    .with_proc_root(root.path());
+
+    // 压力上升阶段：准备计划（允许分配）
+    let mut state = CriticalState::new();
+    selector.check_pressure().unwrap();
+    selector.plan_victims(&mut state.plan).unwrap();
+    assert_eq!(state.plan.len(), 3);
+
+    ARMED.with(|armed| armed.set(true));
+    let sustained = selector.check_pressure();
+    let victim = state.kill_first(selector.proc_root(), kill);
+    ARMED.with(|armed| armed.set(false));
+
+    assert!(sustained.unwrap());
+    let victim = victim.unwrap().expect("a planned victim should be killed");
+    assert_eq!(victim.key.pid.as_raw(), expected);
+
+    let status = children[2].wait().unwrap();
+    assert_eq!(status.signal(), Some(libc::SIGKILL));
+    assert_eq!(state.plan.len(), 2);
+
+    for child in &mut children[..2] {
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
+}
@@ -0,0 +1,128 @@
+//! 稳定状态下选择流程的内存分配次数
+//!
+//! 通过计数的全局分配器统计一轮 `select_process` 的分配次数，
+//! 确认缓冲区在多轮之间被复用：分配次数只与候选进程数有关，
+//! 与系统中的进程总数无关
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::time::Duration;
+use room::linux::proc::ProcessInfo;
+use room::oom::pressure::PressureThresholds;
+use room::oom::selector::SelectorConfig;
+use room::test_util::{FakeProc, ProcFixture, ProcessGroup, RssDistribution};
+use room::{OOMScorer, PressureDetector, ProcessId, ProcessSelector};
+
+struct CountingAllocator;
+
+thread_local! {
+    // 只统计测试线程自己的分配，不受并行运行的其他测试影响
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|count| count.set(count.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// 每轮允许的分配次数，覆盖少量候选进程的评分开销
+const MAX_ALLOCATIONS_PER_CYCLE: usize = 64;
+
+/// 构造处于内存压力下的proc目录：3个大进程和 `small` 个被过滤掉的小进程
+fn synthetic_proc(small: usize) -> ProcFixture {
+    let mut proc = FakeProc::new().available_kb(300000);
+    for i in 0..3 {
+        let rss_kb = 64 * 1024 * 1024 + i as u64 * 1024;
+        proc = proc.process(1000 + i, ProcessGroup::new(1).rss(RssDistribution::Constant(rss_kb)));
+    }
+    for i in 0..small {
+        proc = proc.process(5000 + i as i32, ProcessGroup::new(1).rss(RssDistribution::Constant(16)));
+    }
+
+    proc.build().unwrap()
+}
+
+/// 预热后测量一轮选择的分配次数
+fn allocations_per_cycle(small: usize) -> usize {
+    let root = synthetic_proc(small);
+    let thresholds = PressureThresholds {
+        pressure_duration: Duration::ZERO,
+        ..PressureThresholds::default()
+    };
+    let config = SelectorConfig {
+        min_candidates: 1,
+        ..SelectorConfig::default()
+    };
+    let mut selector = ProcessSelector::new(
+        Some(config),
+        OOMScorer::new().with_proc_root(root.path()),
+        PressureDetector::new(Some(thresholds)).with_proc_root(root.path()),
+    )
+    .with_proc_root(root.path());
+
+    // 第一轮填充缓冲区和OOM分数缓存
+    for _ in 0..3 {
+        assert!(selector.select_process().unwrap().is_some());
+    }
+
+    let before = ALLOCATIONS.with(Cell::get);
+    assert!(selector.select_process().unwrap().is_some());
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn test_steady_state_allocations_bounded() {
+    let few = allocations_per_cycle(200);
+    let many = allocations_per_cycle(2000);
+
+    assert!(few <= MAX_ALLOCATIONS_PER_CYCLE, "{} allocations with 200 processes", few);
+    assert!(many <= MAX_ALLOCATIONS_PER_CYCLE, "{} allocations with 2000 processes", many);
+    // 分配次数不随进程总数增长
+    assert_eq!(few, many);
+}
+
+/// 读取 `pids` 中的每个进程，返回结果（Debug格式）和分配次数
+fn read_each(pids: &[ProcessId], mut read: impl FnMut(ProcessId) -> ProcessInfo) -> (Vec<String>, usize) {
+    let mut infos = Vec::with_capacity(pids.len());
+    let before = ALLOCATIONS.with(Cell::get);
+    for &pid in pids {
+        infos.push(read(pid));
+    }
+    let allocations = ALLOCATIONS.with(Cell::get) - before;
+    (infos.iter().map(|info| format!("{:?}", info)).collect(), allocations)
+}
+
+#[test]
+fn test_shared_buffer_scan_matches_allocating_reads() {
+    let root = synthetic_proc(200);
+    let pids: Vec<ProcessId> = (1000..1003).chain(5000..5200).filter_map(ProcessId::new).collect();
+
+    let (allocating, allocating_count) =
+        read_each(&pids, |pid| ProcessInfo::from_pid_in(root.path(), pid).unwrap());
+    let mut buf = String::new();
+    let (buffered, buffered_count) =
+        read_each(&pids, |pid| ProcessInfo::from_pid_with(root.path(), pid, &mut buf).unwrap());
+
+    assert_eq!(allocating, buffered);
+    // 每个进程至少省去status、oom_score和oom_score_adj三个缓冲区
+    assert!(
+        buffered_count + 3 * pids.len() <= allocating_count,
+        "{} allocations with a shared buffer, {} without",
+        buffered_count,
+        allocating_count
+    );
+}
@@ -0,0 +1,256 @@
+//! 在合成的proc目录上驱动完整的选择器和压力检测器
+//!
+//! 覆盖一万个进程的规模，以及损坏的文件、在枚举之后退出的进程、
+//! 僵尸进程和内核线程等异常输入。需要 `test-util` feature：
+//! `cargo test --features test-util --test synthetic_proc`
+
+use std::time::{Duration, Instant};
+use room::oom::killer::KillerConfig;
+use room::oom::pressure::{PressureLevel, PressureThresholds};
+use room::oom::replay::{load_recording, replay_file, Recorder, RecorderConfig};
+use room::oom::selector::SelectorConfig;
+use room::oom::critical::KillPlan;
+use room::test_util::{FakeProc, FixtureBuilder, ProcFixture, ProcessGroup, RssDistribution};
+use room::{OOMScorer, PressureDetector, ProcessSelector};
+
+/// 可用内存约5%，使压力检测器判定为压力状态
+const PRESSURED_AVAILABLE_KB: u64 = 300000;
+
+/// 使普通进程通过严格过滤的RSS（选择器把kB值与字节阈值比较）
+const LARGE_RSS_KB: u64 = 64 * 1024 * 1024;
+
+/// 一万个进程的一轮选择在调试构建下的时间上限
+const MAX_PASS_DURATION: Duration = Duration::from_secs(30);
+
+fn selector(fixture: &ProcFixture, selection_budget: Option<Duration>) -> ProcessSelector {
+    let thresholds = PressureThresholds {
+        pressure_duration: Duration::ZERO,
+        ..PressureThresholds::default()
+    };
+    let config = SelectorConfig {
+        min_candidates: 1,
+        // 保留所有候选，得分最高的进程不会因为超出上限被丢弃
+        max_candidates: 20_000,
+        selection_budget,
+        ..SelectorConfig::default()
+    };
+
+    ProcessSelector::new(
+        Some(config),
+        OOMScorer::new().with_proc_root(fixture.path()),
+        PressureDetector::new(Some(thresholds)).with_proc_root(fixture.path()),
+    )
+    .with_proc_root(fixture.path())
+}
+
+/// 一个明显应被终止的进程：RSS最大且oom_score_adj为500
+fn victim_group() -> ProcessGroup {
+    ProcessGroup::new(1)
+        .name("leaky-cache")
+        .rss(RssDistribution::Constant(LARGE_RSS_KB * 2))
+        .oom_scores(900, 500)
+}
+
+fn workers(count: usize) -> ProcessGroup {
+    ProcessGroup::new(count).rss(RssDistribution::Uniform {
+        min_kb: LARGE_RSS_KB,
+        max_kb: LARGE_RSS_KB + 1024 * 1024,
+    })
+}
+
+#[test]
+fn test_ten_thousand_processes() {
+    let fixture = FixtureBuilder::new()
+        .available_kb(PRESSURED_AVAILABLE_KB)
+        .processes(workers(10_000))
+        .processes(victim_group())
+        .build()
+        .unwrap();
+    let victim = fixture.group(1)[0];
+    let mut selector = selector(&fixture, None);
+
+    let start = Instant::now();
+    let selected = selector.select_process().unwrap();
+    let elapsed = start.elapsed();
+
+    assert_eq!(selected, Some(victim));
+    assert!(!selector.last_selection_partial());
+    assert!(elapsed < MAX_PASS_DURATION, "selection took {:?}", elapsed);
+}
+
+#[test]
+fn test_budget_bounds_pass_duration() {
+    let fixture = FixtureBuilder::new()
+        .available_kb(PRESSURED_AVAILABLE_KB)
+        .processes(workers(10_000))
+        .build()
+        .unwrap();
+    let budget = Duration::from_millis(20);
+    let mut selector = selector(&fixture, Some(budget));
+
+    for _ in 0..3 {
+        let start = Instant::now();
+        let selected = selector.select_process().unwrap();
+        let elapsed = start.elapsed();
+
+        // 期限在每个进程之间检查，超出的部分不超过读取一个进程的时间
+        assert!(elapsed < budget + Duration::from_secs(1), "selection took {:?}", elapsed);
+        assert!(selector.last_selection_partial());
+        // 期限可能在枚举完成之前就已用尽，此时没有候选
+        if let Some(pid) = selected {
+            assert!(fixture.group(0).contains(&pid));
+        }
+    }
+}
+
+#[test]
+fn test_adversarial_fixture() {
+    let fixture = FixtureBuilder::new()
+        .available_kb(PRESSURED_AVAILABLE_KB)
+        .seed(1218)
+        .processes(victim_group())
+        // 比受害者更大，但在枚举之后、读取进程文件之前退出
+        .processes(
+            ProcessGroup::new(5)
+                .name("short-lived-{}")
+                .rss(RssDistribution::Constant(LARGE_RSS_KB * 4))
+                .oom_scores(1000, 1000)
+                .vanishing_fraction(1.0),
+        )
+        .processes(workers(2_000).corrupt_fraction(0.2).vanishing_fraction(0.1))
+        .processes(ProcessGroup::new(50).name("defunct-{}").state("Z (zombie)").rss(RssDistribution::Constant(0)))
+        .processes(ProcessGroup::new(20).name("kworker/{}:0").ppid(2).uid(0).rss(RssDistribution::Constant(0)))
+        .processes(
+            ProcessGroup::new(20)
+                .name("odd) (name {}")
+                .state("D (disk sleep)")
+                .rss(RssDistribution::Linear { start_kb: LARGE_RSS_KB, step_kb: 1 }),
+        )
+        .build()
+        .unwrap();
+    let victim = fixture.group(0)[0];
+    let short_lived = fixture.group(1);
+    assert!(!fixture.corrupt().is_empty());
+
+    let mut selector = selector(&fixture, None).with_refresh_hook(fixture.vanish_hook());
+    let selected = selector.select_process().unwrap();
+    assert_eq!(selected, Some(victim));
+    assert!(!short_lived.contains(&selected.unwrap()));
+
+    // 消失的进程不会在后续的轮次中留下任何状态
+    assert_eq!(selector.select_process().unwrap(), Some(victim));
+}
+
+#[test]
+fn test_pressure_detector_on_malformed_meminfo() {
+    for meminfo in [
+        "",
+        "MemTotal: garbage\n",
+        "MemTotal:        6158152 kB\nMemAvail",
+        "MemTotal:        6158152 kB\nMemAvailable:    99999999999999999999999 kB\n",
+        "\0\0\0\n:::\n",
+    ] {
+        let fixture = FixtureBuilder::new().meminfo_raw(meminfo).build().unwrap();
+        let mut detector = PressureDetector::new(None).with_proc_root(fixture.path());
+
+        // 不完整的meminfo不应被当作内存压力
+        assert!(!detector.check_pressure().unwrap(), "meminfo: {:?}", meminfo);
+        assert!(detector.get_memory_stats().is_ok());
+    }
+}
+
+#[test]
+fn test_pressure_detector_on_pressured_fixture() {
+    let fixture = FixtureBuilder::new().available_kb(PRESSURED_AVAILABLE_KB).build().unwrap();
+    let mut detector = PressureDetector::new(Some(PressureThresholds {
+        pressure_duration: Duration::ZERO,
+        ..PressureThresholds::default()
+    }))
+    .with_proc_root(fixture.path());
+
+    assert!(detector.check_pressure().unwrap());
+    assert_eq!(detector.level(), PressureLevel::Critical);
+}
+
+#[test]
+fn test_record_and_replay_fixture() {
+    let fixture = FixtureBuilder::new()
+        .available_kb(PRESSURED_AVAILABLE_KB)
+        .processes(workers(50))
+        .processes(victim_group())
+        .processes(ProcessGroup::new(20).name("kworker/{}").rss(RssDistribution::Constant(0)).ppid(2))
+        .build()
+        .unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("recording.jsonl");
+
+    let mut recorder = Recorder::new(RecorderConfig {
+        interval: Duration::ZERO,
+        max_processes: 10,
+        ..RecorderConfig::new(&path)
+    })
+    .with_proc_root(fixture.path());
+    let stats = PressureDetector::new(None).with_proc_root(fixture.path()).get_memory_stats().unwrap();
+    assert!(recorder.record(&stats).unwrap());
+    assert!(recorder.record(&stats).unwrap());
+
+    let frames = load_recording(&path).unwrap();
+    assert_eq!(frames.len(), 2);
+    // 只记录RSS最高的进程，内核线程不在其中
+    assert_eq!(frames[0].processes.len(), 10);
+    assert_eq!(frames[0].processes[0].name, "leaky-cache");
+    assert_eq!(frames[0].processes[0].oom_score_adj, 500);
+    assert!(frames[0].processes[0].runtime_secs.is_some());
+
+    let config = KillerConfig {
+        pressure: PressureThresholds {
+            pressure_duration: Duration::ZERO,
+            ..PressureThresholds::default()
+        },
+        selector: SelectorConfig {
+            min_candidates: 1,
+            ..SelectorConfig::default()
+        },
+        ..KillerConfig::default()
+    };
+    let points = replay_file(&path, &config, &OOMScorer::new()).unwrap();
+    assert!(points[0].triggered);
+    assert_eq!(points[0].decision.victim(), Some(fixture.group(1)[0]));
+    assert_eq!(points[1].decision.victim(), Some(fixture.group(1)[0]));
+
+    // 记录文件达到上限后停止记录
+    let mut full = Recorder::new(RecorderConfig {
+        interval: Duration::ZERO,
+        max_bytes: 1,
+        ..RecorderConfig::new(dir.path().join("full.jsonl"))
+    })
+    .with_proc_root(fixture.path());
+    assert!(!full.record(&stats).unwrap());
+    assert!(!full.due());
+}
+
+#[test]
+fn test_fake_proc_end_to_end() {
+    let fake = FakeProc::new()
+        .available_kb(PRESSURED_AVAILABLE_KB)
+        .process(812, ProcessGroup::new(1).name("sshd").rss(RssDistribution::Constant(LARGE_RSS_KB * 3)).oom_scores(0, -1000))
+        .process(2301, ProcessGroup::new(1).name("postgres").rss(RssDistribution::Constant(LARGE_RSS_KB)))
+        .process(4410, victim_group());
+    let fixture = fake.build().unwrap();
+    let mut pipeline = selector(&fixture, None);
+
+    // 压力 -> 枚举 -> 评分 -> 选择
+    assert!(pipeline.check_pressure().unwrap());
+    assert_eq!(pipeline.select_process().unwrap(), Some(fixture.group(2)[0]));
+    assert_eq!(fixture.group(2)[0].as_raw(), 4410);
+
+    // oom_score_adj为-1000的进程即使RSS最大也不在计划中
+    let mut plan = KillPlan::new();
+    pipeline.plan_victims(&mut plan).unwrap();
+    let planned: Vec<i32> = plan.victims().map(|v| v.key.pid.as_raw()).collect();
+    assert_eq!(planned, vec![4410, 2301]);
+
+    // 没有压力时不选择任何进程
+    let relaxed = fake.available_kb(5582016).build().unwrap();
+    assert_eq!(selector(&relaxed, None).select_process().unwrap(), None);
+}